@@ -0,0 +1,35 @@
+use std::time::Instant;
+
+use pong_rs::batch::{BatchEnv, Opponent};
+use pong_rs::sim::Rng;
+
+const DT: f32 = 1.0 / 60.0;
+const STEPS: usize = 500;
+
+fn run(len: usize, threads: usize) {
+    let mut env = BatchEnv::new(len, DT, Opponent::BuiltinAi, 42).with_threads(threads);
+    let mut rng = Rng::new(7);
+    let actions: Vec<u8> = (0..len * 2).map(|_| (rng.next_u64() % 3) as u8).collect();
+
+    let start = Instant::now();
+    for _ in 0..STEPS {
+        env.step(&actions);
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+
+    let steps_per_sec = (len * STEPS) as f64 / elapsed;
+    println!(
+        "{:>6} matches  {:>2} threads  {:>12.0} steps/s",
+        len, threads, steps_per_sec
+    );
+}
+
+fn main() {
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    for len in [1024, 8192, 65536] {
+        run(len, 1);
+        if cores > 1 {
+            run(len, cores);
+        }
+    }
+}
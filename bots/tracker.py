@@ -0,0 +1,27 @@
+#!/usr/bin/env python3
+"""Example bot for `cargo run -- --bot "python3 bots/tracker.py"`.
+
+Reads one JSON message per line on stdin and answers every state with a
+move on stdout. Keeps the paddle centered on the ball.
+"""
+import json
+import sys
+
+paddle_height = 100
+
+for line in sys.stdin:
+    msg = json.loads(line)
+    if msg["type"] == "hello":
+        paddle_height = msg["paddle_height"]
+    elif msg["type"] == "state":
+        center = msg["you"]["y"] + paddle_height / 2
+        target = msg["ball"]["y"]
+        if target < center - 10:
+            move = "up"
+        elif target > center + 10:
+            move = "down"
+        else:
+            move = "stay"
+        print(json.dumps({"tick": msg["tick"], "move": move}), flush=True)
+    elif msg["type"] == "end":
+        break
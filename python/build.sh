@@ -0,0 +1,7 @@
+#!/bin/bash
+# Build the pong_rs Python extension next to this script
+set -e
+cd "$(dirname "$0")/.."
+cargo rustc --release --lib --features python --crate-type cdylib
+cp target/release/libpong_rs.so python/pong_rs.so
+echo "Built python/pong_rs.so"
@@ -0,0 +1,48 @@
+"""Plays a full headless match through the Rust simulation.
+
+Run after ./python/build.sh with: python3 python/tests/test_match.py
+"""
+import os
+import sys
+import tempfile
+
+sys.path.insert(0, os.path.join(os.path.dirname(__file__), ".."))
+
+import pong_rs  # noqa: E402
+
+
+def tracking_action(obs):
+    # Move the left paddle's center towards the ball
+    ball_y, paddle_y = obs[1], obs[4]
+    center = paddle_y + 50.0 / 600.0
+    if ball_y < center - 0.02:
+        return 1
+    if ball_y > center + 0.02:
+        return 2
+    return 0
+
+
+def test_full_match():
+    game = pong_rs.Match(seed=1234)
+    obs = game.reset()
+    assert len(obs) == pong_rs.OBS_DIM
+
+    done = False
+    total_reward = 0.0
+    while not done:
+        obs, reward, done = game.step(tracking_action(obs))
+        total_reward += reward
+        assert game.tick < 60 * 60 * 30, "match never finished"
+
+    assert max(game.left_score, game.right_score) == pong_rs.WINNING_SCORE
+    assert total_reward == game.left_score - game.right_score
+
+    with tempfile.TemporaryDirectory() as tmp:
+        path = os.path.join(tmp, "match.replay")
+        game.save_replay(path)
+        assert pong_rs.replay_scores(path) == (game.left_score, game.right_score)
+
+
+if __name__ == "__main__":
+    test_full_match()
+    print("ok")
@@ -0,0 +1,212 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::constants::*;
+use crate::sim::{Ball, Match, Paddle, Side};
+
+// Tunable knobs of the built-in AI
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AiParams {
+    pub speed: f32,         // Paddle speed while chasing the target
+    pub prediction: f32,    // How far ahead the ball is extrapolated (0 = none, 1 = full)
+    pub error: f32,         // Maximum random offset added to the target
+    pub reaction_time: f32, // Seconds between target updates (0 = every frame)
+}
+
+impl AiParams {
+    // The original hand-tuned AI from `constants.rs`
+    pub const fn classic() -> Self {
+        Self {
+            speed: AI_SPEED,
+            prediction: AI_PREDICTION,
+            error: AI_ERROR,
+            reaction_time: 0.0,
+        }
+    }
+
+    // Predicted height of `ball` when it reaches `side`'s paddle
+    pub fn predict(&self, ball: &Ball, side: Side) -> f32 {
+        let (paddle_x, facing) = match side {
+            Side::Left => (LEFT_PADDLE_X + PADDLE_WIDTH, -1.0),
+            Side::Right => (RIGHT_PADDLE_X, 1.0),
+        };
+        if ball.vel_x * facing > 0.0 {
+            // Ball moving towards AI paddle
+            let time_to_reach = facing * (paddle_x - ball.x) / ball.vel_x.abs();
+            ball.y + ball.vel_y * time_to_reach * self.prediction
+        } else {
+            ball.y
+        }
+    }
+
+    // Move the paddle center towards `target_y`
+    pub fn steer(&self, paddle: &mut Paddle, target_y: f32) {
+        let paddle_center = paddle.y + paddle.height / 2.0;
+        let diff = target_y - paddle_center;
+        if diff.abs() > 10.0 {
+            if diff < 0.0 {
+                paddle.vel = -self.speed;
+            } else {
+                paddle.vel = self.speed;
+            }
+        } else {
+            paddle.vel *= 0.9; // Slow down when close to target
+        }
+    }
+}
+
+impl Default for AiParams {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
+// AI player with its own reaction delay: the target is only refreshed every
+// `reaction_time` seconds, in between the paddle keeps chasing the old one.
+#[derive(Clone, Copy, Debug)]
+pub struct AiController {
+    pub params: AiParams,
+    pub side: Side,
+    target: f32,
+    cooldown: f32,
+}
+
+impl AiController {
+    pub fn new(params: AiParams, side: Side) -> Self {
+        Self {
+            params,
+            side,
+            target: SCREEN_HEIGHT / 2.0,
+            cooldown: 0.0,
+        }
+    }
+
+    // Steer our paddle in a regular match
+    pub fn drive(&mut self, game: &mut Match, dt: f32, noise: f32) {
+        let (ball, side) = (game.ball, self.side);
+        self.drive_paddle(game.paddle_mut(side), dt, noise, |params| params.predict(&ball, side));
+    }
+
+    // Steer `paddle` for one frame of any mode, which only supplies where it
+    // expects the ball; `predict` runs when the reaction delay is up.
+    // `noise` is a random value in [-1, 1] scaled by the error parameter.
+    pub fn drive_paddle(&mut self, paddle: &mut Paddle, dt: f32, noise: f32, predict: impl FnOnce(&AiParams) -> f32) {
+        self.cooldown -= dt;
+        if self.cooldown <= 0.0 {
+            self.target = predict(&self.params) + noise * self.params.error;
+            self.cooldown = self.params.reaction_time;
+        }
+        self.params.steer(paddle, self.target);
+    }
+}
+
+// A named parameter set, e.g. one difficulty level
+#[derive(Clone, Debug, PartialEq)]
+pub struct AiProfile {
+    pub name: String,
+    pub params: AiParams,
+    pub win_rate: Option<f32>, // Measured win rate against the reference bots, if tuned
+}
+
+// Difficulty levels used when no tuned profile file exists
+pub fn default_profiles() -> Vec<AiProfile> {
+    let classic = AiParams::classic();
+    vec![
+        AiProfile {
+            name: "easy".to_string(),
+            params: AiParams {
+                speed: classic.speed * 0.75,
+                error: classic.error * 3.0,
+                reaction_time: 0.15,
+                ..classic
+            },
+            win_rate: None,
+        },
+        AiProfile {
+            name: "medium".to_string(),
+            params: classic,
+            win_rate: None,
+        },
+        AiProfile {
+            name: "hard".to_string(),
+            params: AiParams {
+                speed: classic.speed * 1.25,
+                prediction: 0.8,
+                error: classic.error * 0.5,
+                ..classic
+            },
+            win_rate: None,
+        },
+    ]
+}
+
+// Profiles file format:
+//
+//   [medium]
+//   speed = 210
+//   prediction = 0.3
+//   error = 10
+//   reaction_time = 0
+//   win_rate = 0.5      # optional, written by the tuner
+pub fn profiles_to_text(profiles: &[AiProfile]) -> String {
+    let mut out = String::new();
+    for profile in profiles {
+        let p = &profile.params;
+        let _ = writeln!(out, "[{}]", profile.name);
+        let _ = writeln!(out, "speed = {}", p.speed);
+        let _ = writeln!(out, "prediction = {}", p.prediction);
+        let _ = writeln!(out, "error = {}", p.error);
+        let _ = writeln!(out, "reaction_time = {}", p.reaction_time);
+        if let Some(win_rate) = profile.win_rate {
+            let _ = writeln!(out, "win_rate = {}", win_rate);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+pub fn parse_profiles(text: &str) -> Result<Vec<AiProfile>, String> {
+    let mut profiles: Vec<AiProfile> = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            profiles.push(AiProfile {
+                name: name.trim().to_string(),
+                params: AiParams::classic(),
+                win_rate: None,
+            });
+            continue;
+        }
+
+        let error = || format!("line {}: expected `key = value`", number + 1);
+        let (key, value) = line.split_once('=').ok_or_else(error)?;
+        let value: f32 = value.trim().parse().map_err(|_| error())?;
+        let profile = profiles
+            .last_mut()
+            .ok_or(format!("line {}: value outside of a [section]", number + 1))?;
+        match key.trim() {
+            "speed" => profile.params.speed = value,
+            "prediction" => profile.params.prediction = value,
+            "error" => profile.params.error = value,
+            "reaction_time" => profile.params.reaction_time = value,
+            "win_rate" => profile.win_rate = Some(value),
+            other => return Err(format!("line {}: unknown key {}", number + 1, other)),
+        }
+    }
+    Ok(profiles)
+}
+
+pub fn load_profiles(path: impl AsRef<Path>) -> io::Result<Vec<AiProfile>> {
+    let text = fs::read_to_string(path)?;
+    parse_profiles(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub fn save_profiles(path: impl AsRef<Path>, profiles: &[AiProfile]) -> io::Result<()> {
+    let header = "# AI difficulty profiles, generated by `pong_rs tune`\n\n";
+    fs::write(path, format!("{}{}", header, profiles_to_text(profiles)))
+}
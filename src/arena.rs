@@ -0,0 +1,440 @@
+// Arenas: obstacles between the paddles of a local match. Blocks and
+// bumpers never move, bricks break after a number of hits, and deflectors
+// are angled bars that send the ball off somewhere new.
+//
+// Obstacles act on the ball after `Match::step`, the same way power-ups do.
+// Every collision is a circle against the obstacle's shape: find the point
+// of the shape closest to the ball's center, and if it's within the ball's
+// radius, reflect the velocity about the normal from that point and push
+// the ball back out.
+//
+// Some layouts can trap the ball between obstacles where no paddle can
+// reach it, so a ball nobody has touched for `STALL_TIMEOUT` is served
+// again.
+//
+// An arena can also narrow the goals (the end line outside the opening
+// is a wall) and move the serve to spawn points, used in turn.
+//
+// Arena file format (plain text, `#` starts a comment, one item per line,
+// coordinates in pixels of the 800x600 field):
+//
+//   pong-arena 1
+//   name Bumpers
+//   goal left 150 450           # top and bottom of the opening
+//   goal right 0 600
+//   spawn 400 300               # x y, any number
+//   block 390 0 20 90           # x y width height
+//   brick 300 100 20 60 2       # x y width height hits
+//   bumper 400 140 30           # x y radius
+//   deflector 200 120 280 170   # x1 y1 x2 y2
+//
+// Goals default to the full height and the serve to the center.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::constants::*;
+use crate::sim::{Ball, Match, Side, StepEvents};
+
+pub const DEFLECTOR_THICKNESS: f32 = 8.0;
+const BUMPER_BOOST: f32 = 1.1; // Speed multiplier from a bumper kick
+const MIN_ANGLE: f32 = 0.3; // Least horizontal speed after a bounce, so the ball can't get stuck going up and down
+const STALL_TIMEOUT: f32 = 8.0; // Seconds without a paddle touch before the ball is served again
+pub const MIN_GOAL_SIZE: f32 = 60.0;
+const HEADER: &str = "pong-arena 1";
+
+// Built-in arenas, bundled into the binary so the web build has them too
+const BUNDLED: [&str; 6] = [
+    include_str!("../arenas/empty.arena"),
+    include_str!("../arenas/bricks.arena"),
+    include_str!("../arenas/bumpers.arena"),
+    include_str!("../arenas/deflectors.arena"),
+    include_str!("../arenas/mixed.arena"),
+    include_str!("../arenas/fortress.arena"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Obstacle {
+    Block { x: f32, y: f32, w: f32, h: f32 },            // Static rectangle
+    Brick { x: f32, y: f32, w: f32, h: f32, hits: u32 }, // Breaks after `hits` more hits
+    Bumper { x: f32, y: f32, radius: f32 },              // Round, kicks the ball away faster
+    Deflector { x1: f32, y1: f32, x2: f32, y2: f32 },    // Angled bar from one end to the other
+}
+
+impl Obstacle {
+    // Point of the obstacle's surface closest to (`px`, `py`) and the
+    // distance the ball's center must keep from it
+    fn closest(&self, px: f32, py: f32) -> (f32, f32, f32) {
+        match *self {
+            Obstacle::Block { x, y, w, h } | Obstacle::Brick { x, y, w, h, .. } => {
+                (px.clamp(x, x + w), py.clamp(y, y + h), BALL_SIZE)
+            }
+            Obstacle::Bumper { x, y, radius } => (x, y, BALL_SIZE + radius),
+            Obstacle::Deflector { x1, y1, x2, y2 } => {
+                let (dx, dy) = (x2 - x1, y2 - y1);
+                let length = dx * dx + dy * dy;
+                let t = if length > 0.0 {
+                    (((px - x1) * dx + (py - y1) * dy) / length).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                (x1 + dx * t, y1 + dy * t, BALL_SIZE + DEFLECTOR_THICKNESS / 2.0)
+            }
+        }
+    }
+
+    // Bounce `ball` off this obstacle if they overlap
+    fn collide(&self, ball: &mut Ball) -> bool {
+        let (cx, cy, reach) = self.closest(ball.x, ball.y);
+        let (mut nx, mut ny) = (ball.x - cx, ball.y - cy);
+        let distance = (nx * nx + ny * ny).sqrt();
+        if distance >= reach {
+            return false;
+        }
+
+        if distance > 0.0 {
+            nx /= distance;
+            ny /= distance;
+        } else if let Obstacle::Block { x, y, w, h } | Obstacle::Brick { x, y, w, h, .. } = *self {
+            // Center inside the rectangle: leave through the nearest side
+            let exits = [(ball.x - x, -1.0, 0.0), (x + w - ball.x, 1.0, 0.0), (ball.y - y, 0.0, -1.0), (y + h - ball.y, 0.0, 1.0)];
+            let (_, ex, ey) = exits.into_iter().fold((f32::MAX, 0.0, 0.0), |best, exit| if exit.0 < best.0 { exit } else { best });
+            nx = ex;
+            ny = ey;
+        } else {
+            // Dead center on a point: send it back where it came from
+            let speed = ball.vel_x.hypot(ball.vel_y).max(f32::EPSILON);
+            nx = -ball.vel_x / speed;
+            ny = -ball.vel_y / speed;
+        }
+
+        // Push out, then reflect if still moving into the surface
+        let depth = if distance > 0.0 { reach - distance } else { reach + rect_depth(self, ball) };
+        ball.x += nx * depth;
+        ball.y += ny * depth;
+        let along = ball.vel_x * nx + ball.vel_y * ny;
+        if along < 0.0 {
+            ball.vel_x -= 2.0 * along * nx;
+            ball.vel_y -= 2.0 * along * ny;
+        }
+
+        keep_moving_across(ball);
+        if let Obstacle::Bumper { .. } = self {
+            let speed = (ball.speed * BUMPER_BOOST).min(MAX_BALL_SPEED);
+            ball.vel_x *= speed / ball.speed;
+            ball.vel_y *= speed / ball.speed;
+            ball.speed = speed;
+        }
+        true
+    }
+
+    // Whether (`x`, `y`) is on the obstacle or a few pixels from it, e.g.
+    // under the mouse
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        let (cx, cy, reach) = self.closest(x, y);
+        (x - cx).hypot(y - cy) <= reach - BALL_SIZE / 2.0
+    }
+
+    // Center, for effects
+    pub fn center(&self) -> (f32, f32) {
+        match *self {
+            Obstacle::Block { x, y, w, h } | Obstacle::Brick { x, y, w, h, .. } => (x + w / 2.0, y + h / 2.0),
+            Obstacle::Bumper { x, y, .. } => (x, y),
+            Obstacle::Deflector { x1, y1, x2, y2 } => ((x1 + x2) / 2.0, (y1 + y2) / 2.0),
+        }
+    }
+}
+
+// How far the ball's center sits inside a rectangle, zero for other shapes
+fn rect_depth(obstacle: &Obstacle, ball: &Ball) -> f32 {
+    match *obstacle {
+        Obstacle::Block { x, y, w, h } | Obstacle::Brick { x, y, w, h, .. } => {
+            (ball.x - x).min(x + w - ball.x).min(ball.y - y).min(y + h - ball.y).max(0.0)
+        }
+        _ => 0.0,
+    }
+}
+
+// Make sure at least `MIN_ANGLE` of the ball's velocity goes across the
+// field, keeping its overall speed
+fn keep_moving_across(ball: &mut Ball) {
+    let length = ball.vel_x.hypot(ball.vel_y);
+    let least = length * MIN_ANGLE;
+    if ball.vel_x.abs() < least {
+        ball.vel_x = if ball.vel_x < 0.0 { -least } else { least };
+        let rest = (length * length - least * least).sqrt();
+        ball.vel_y = if ball.vel_y < 0.0 { -rest } else { rest };
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Arena {
+    pub name: String,
+    pub obstacles: Vec<Obstacle>,
+    pub goals: [(f32, f32); 2], // Top and bottom of the left and right goal openings
+    pub spawns: Vec<(f32, f32)>, // Serve spots, used in turn; the center if empty
+}
+
+impl Arena {
+    pub fn empty() -> Self {
+        Self {
+            name: "Empty".to_string(),
+            obstacles: Vec::new(),
+            goals: [(0.0, SCREEN_HEIGHT); 2],
+            spawns: Vec::new(),
+        }
+    }
+
+    pub fn goal(&self, side: Side) -> (f32, f32) {
+        match side {
+            Side::Left => self.goals[0],
+            Side::Right => self.goals[1],
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(number, line)| (number + 1, line.split('#').next().unwrap_or("").trim()))
+            .filter(|(_, line)| !line.is_empty());
+        if lines.next().map(|(_, line)| line) != Some(HEADER) {
+            return Err(format!("expected header \"{}\"", HEADER));
+        }
+
+        let mut arena = Arena::empty();
+        for (number, line) in lines {
+            let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            if keyword == "name" {
+                arena.name = rest.trim().to_string();
+                continue;
+            }
+            let mut words: Vec<&str> = rest.split_whitespace().collect();
+            let side = if keyword == "goal" && !words.is_empty() {
+                Some(words.remove(0))
+            } else {
+                None
+            };
+            let values = words
+                .iter()
+                .map(|word| word.parse::<f32>().ok().filter(|value| value.is_finite()))
+                .collect::<Option<Vec<f32>>>()
+                .ok_or(format!("line {}: expected numbers after {}", number, keyword))?;
+            let expected = match keyword {
+                "goal" => 2,
+                "spawn" => 2,
+                "block" => 4,
+                "brick" => 5,
+                "bumper" => 3,
+                "deflector" => 4,
+                other => return Err(format!("line {}: unknown item {}", number, other)),
+            };
+            if values.len() != expected {
+                return Err(format!("line {}: {} takes {} numbers", number, keyword, expected));
+            }
+            let v = &values;
+            match keyword {
+                "goal" => {
+                    let (top, bottom) = (v[0].max(0.0), v[1].min(SCREEN_HEIGHT));
+                    if bottom - top < MIN_GOAL_SIZE {
+                        return Err(format!("line {}: goals must be at least {} high", number, MIN_GOAL_SIZE));
+                    }
+                    match side {
+                        Some("left") => arena.goals[0] = (top, bottom),
+                        Some("right") => arena.goals[1] = (top, bottom),
+                        _ => return Err(format!("line {}: goal must be left or right", number)),
+                    }
+                }
+                "spawn" => arena.spawns.push((v[0], v[1])),
+                "block" => arena.obstacles.push(Obstacle::Block { x: v[0], y: v[1], w: v[2], h: v[3] }),
+                "brick" => {
+                    let hits = v[4] as u32;
+                    if hits == 0 {
+                        return Err(format!("line {}: bricks need at least 1 hit", number));
+                    }
+                    arena.obstacles.push(Obstacle::Brick { x: v[0], y: v[1], w: v[2], h: v[3], hits });
+                }
+                "bumper" => arena.obstacles.push(Obstacle::Bumper { x: v[0], y: v[1], radius: v[2] }),
+                _ => arena.obstacles.push(Obstacle::Deflector { x1: v[0], y1: v[1], x2: v[2], y2: v[3] }),
+            }
+        }
+        Ok(arena)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = format!("{}\nname {}\n", HEADER, self.name);
+        for (side, (top, bottom)) in ["left", "right"].iter().zip(self.goals) {
+            out += &format!("goal {} {} {}\n", side, top, bottom);
+        }
+        for (x, y) in &self.spawns {
+            out += &format!("spawn {} {}\n", x, y);
+        }
+        for obstacle in &self.obstacles {
+            out += &match *obstacle {
+                Obstacle::Block { x, y, w, h } => format!("block {} {} {} {}\n", x, y, w, h),
+                Obstacle::Brick { x, y, w, h, hits } => format!("brick {} {} {} {} {}\n", x, y, w, h, hits),
+                Obstacle::Bumper { x, y, radius } => format!("bumper {} {} {}\n", x, y, radius),
+                Obstacle::Deflector { x1, y1, x2, y2 } => format!("deflector {} {} {} {}\n", x1, y1, x2, y2),
+            };
+        }
+        out
+    }
+
+    // Remove the spawn point or obstacle at (`x`, `y`), newest first
+    pub fn remove_at(&mut self, x: f32, y: f32) -> bool {
+        if let Some(index) = self.spawns.iter().rposition(|&(sx, sy)| (x - sx).hypot(y - sy) <= BALL_SIZE) {
+            self.spawns.remove(index);
+            return true;
+        }
+        if let Some(index) = self.obstacles.iter().rposition(|obstacle| obstacle.contains(x, y)) {
+            self.obstacles.remove(index);
+            return true;
+        }
+        false
+    }
+
+    pub fn has_bricks(&self) -> bool {
+        self.obstacles.iter().any(|obstacle| matches!(obstacle, Obstacle::Brick { .. }))
+    }
+
+    // Built-in layouts from `arenas/`, all leaving their serve spots clear
+    pub fn presets() -> Vec<Arena> {
+        BUNDLED
+            .iter()
+            .map(|text| Arena::parse(text).expect("bundled arena files are valid"))
+            .collect()
+    }
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+// Obstacles the ball bounced off or broke, the point a brick earned, and
+// stalled balls served again
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ArenaEvents {
+    pub bounced: Option<Obstacle>,
+    pub broken: Option<Obstacle>,
+    pub brick_point: Option<Side>, // Player credited with breaking a brick
+    pub winner: Option<Side>,      // Set when that point won the match
+    pub stalled: bool,             // The ball was stuck and got served again
+}
+
+// An arena being played: bricks disappear from `arena` as they break
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArenaState {
+    pub arena: Arena,
+    pub brick_points: bool, // A broken brick scores for whoever hit the ball last
+    last_hit: Option<Side>,
+    untouched: f32, // Seconds since a paddle last touched the ball
+    serves: usize,  // Spawn point of the next serve
+}
+
+impl ArenaState {
+    pub fn new(arena: Arena, brick_points: bool) -> Self {
+        Self {
+            arena,
+            brick_points,
+            last_hit: None,
+            untouched: 0.0,
+            serves: 0,
+        }
+    }
+
+    // Move a freshly served ball to the next spawn point, if the arena has
+    // any; call it for the opening serve too
+    pub fn serve(&mut self, ball: &mut Ball) {
+        if self.arena.spawns.is_empty() {
+            return;
+        }
+        let (x, y) = self.arena.spawns[self.serves % self.arena.spawns.len()];
+        self.serves += 1;
+        ball.x = x;
+        ball.y = y;
+    }
+
+    // Run before `Match::step`: the end line outside each goal opening is a
+    // wall, checked ahead so a fast ball can't pass it within one step
+    pub fn guard_goals(&self, game: &mut Match, dt: f32) {
+        let ball = &mut game.ball;
+        let next_x = ball.x + ball.vel_x * dt;
+        let side = if ball.vel_x < 0.0 && next_x <= BALL_SIZE {
+            Side::Left
+        } else if ball.vel_x > 0.0 && next_x >= SCREEN_WIDTH - BALL_SIZE {
+            Side::Right
+        } else {
+            return;
+        };
+        let (top, bottom) = self.arena.goal(side);
+        if ball.y < top || ball.y > bottom {
+            ball.vel_x = -ball.vel_x;
+        }
+    }
+
+    // Run after `Match::step`, with the events it returned
+    pub fn step(&mut self, game: &mut Match, events: &StepEvents, dt: f32) -> ArenaEvents {
+        let mut arena_events = ArenaEvents::default();
+        self.untouched += dt;
+        if let Some((side, _)) = events.paddle_hit {
+            self.last_hit = Some(side);
+            self.untouched = 0.0;
+        }
+        if events.scored.is_some() {
+            self.last_hit = None; // Just served
+            self.untouched = 0.0;
+            self.serve(&mut game.ball);
+            return arena_events;
+        }
+        if self.untouched >= STALL_TIMEOUT {
+            // Towards whoever didn't touch it last
+            let towards = self.last_hit.map_or(Side::Right, Side::opponent);
+            game.ball = Ball::serve(towards);
+            self.serve(&mut game.ball);
+            self.last_hit = None;
+            self.untouched = 0.0;
+            arena_events.stalled = true;
+            return arena_events;
+        }
+
+        let hit = self
+            .arena
+            .obstacles
+            .iter()
+            .position(|obstacle| obstacle.collide(&mut game.ball));
+        let Some(index) = hit else {
+            return arena_events;
+        };
+        let obstacle = &mut self.arena.obstacles[index];
+        arena_events.bounced = Some(*obstacle);
+
+        if let Obstacle::Brick { hits, .. } = obstacle {
+            *hits = hits.saturating_sub(1);
+            if *hits == 0 {
+                arena_events.broken = Some(self.arena.obstacles.remove(index));
+                if let Some(side) = self.last_hit.filter(|_| self.brick_points) {
+                    match side {
+                        Side::Left => game.left_score += 1,
+                        Side::Right => game.right_score += 1,
+                    }
+                    arena_events.brick_point = Some(side);
+                    arena_events.winner = game.winner();
+                }
+            }
+        }
+
+        arena_events
+    }
+}
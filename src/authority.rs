@@ -0,0 +1,782 @@
+// Server-authoritative online play. A headless server (`src/bin/server.rs`)
+// owns the simulation and clients only send inputs. Each client predicts its
+// own paddle, re-applies unacknowledged inputs on top of every snapshot, and
+// draws the opponent paddle and ball interpolated slightly in the past.
+// Further clients can watch as spectators; they get a delayed stream.
+// When a player goes quiet the match pauses until they rejoin (from any
+// address, with the token they were given) or forfeit after a timeout.
+
+use std::collections::hash_map::RandomState;
+use std::collections::VecDeque;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::ai::{AiController, AiParams};
+use crate::constants::*;
+use crate::net::{Packet, Transport};
+use crate::rollback::NET_DT;
+use crate::sim::{Intent, Match, Paddle, Rng, Side, StepEvents};
+
+const JOIN_INTERVAL: f64 = 0.2;
+const WATCH_KEEPALIVE: f64 = 1.0;
+const PLAYER_KEEPALIVE: f64 = 0.25; // Resend inputs while paused so the server knows we're here
+const SPECTATOR_TIMEOUT: f32 = 5.0; // Seconds without a keepalive before a spectator is dropped
+const MAX_INPUTS_PER_PACKET: usize = 64;
+const MAX_INPUT_BUFFER: u32 = 6; // Queued inputs beyond this are dropped to cut latency
+const SNAPSHOT_HISTORY: usize = 32;
+
+// Identifies where a datagram came from; assigned by whoever owns the sockets
+pub type ConnectionId = usize;
+
+struct Player {
+    connection: ConnectionId,
+    name: String,
+    inputs: Vec<u8>, // Every input received, by client tick
+    applied: u32,    // Inputs consumed by the simulation
+    last: u8,        // Repeated while the client's inputs are late
+    ping: f64,       // Latest ping timestamp, echoed back in snapshots
+    rtt_ms: u16,
+    token: u64,      // Secret for taking the seat back after a reconnect
+    last_heard: u32, // Server tick of the last datagram
+}
+
+// Fills an empty side when the players agreed to play against the AI
+struct Bot {
+    name: String,
+    ai: AiController,
+}
+
+struct Watcher {
+    connection: ConnectionId,
+    last_seen: u32, // Server tick of the last keepalive
+}
+
+// The authoritative simulation. Transport-agnostic: the caller routes
+// datagrams in with `receive` and out with `take_outgoing`.
+pub struct AuthorityServer {
+    game: Match,
+    rng: Rng,
+    tick: u32,
+    rally: u32, // Paddle hits since the last point
+    paused_at: Option<u32>, // Tick a player went quiet, while the match waits for them
+    forfeit: Option<Side>,  // Side that didn't come back in time
+    abandoned: bool,        // Both players gone
+    players: [Option<Player>; 2],
+    bots: [Option<Bot>; 2],
+    spectators: Vec<Watcher>,
+    history: VecDeque<(u32, Match, u32)>, // Tick, state and rally for the delayed stream
+    outgoing: Vec<(ConnectionId, Vec<u8>)>,
+}
+
+impl AuthorityServer {
+    pub fn new() -> Self {
+        Self {
+            game: Match::new(),
+            rng: Rng::new(0),
+            tick: 0,
+            rally: 0,
+            paused_at: None,
+            forfeit: None,
+            abandoned: false,
+            players: [None, None],
+            bots: [None, None],
+            spectators: Vec::new(),
+            history: VecDeque::new(),
+            outgoing: Vec::new(),
+        }
+    }
+
+    // Play to `score` points instead of `WINNING_SCORE`
+    pub fn with_winning_score(mut self, score: i32) -> Self {
+        self.game.winning_score = score.max(1);
+        self
+    }
+
+    // Reserve `side` for a connection before it joins, e.g. from the lobby
+    pub fn seat(&mut self, side: Side, connection: ConnectionId, name: &str) {
+        let token = random_token();
+        self.players[side as usize] = Some(Player {
+            connection,
+            name: clean_name(name, side as usize),
+            inputs: Vec::new(),
+            applied: 0,
+            last: 0,
+            ping: 0.0,
+            rtt_ms: 0,
+            token,
+            last_heard: self.tick,
+        });
+    }
+
+    // Let the built-in AI play `side`; joins skip it
+    pub fn fill_with_ai(&mut self, side: Side, name: &str, params: AiParams) {
+        self.bots[side as usize] = Some(Bot {
+            name: name.to_string(),
+            ai: AiController::new(params, side),
+        });
+    }
+
+    pub fn state(&self) -> &Match {
+        &self.game
+    }
+
+    pub fn tick_count(&self) -> u32 {
+        self.tick
+    }
+
+    pub fn rally(&self) -> u32 {
+        self.rally
+    }
+
+    pub fn player_side(&self, connection: ConnectionId) -> Option<Side> {
+        [Side::Left, Side::Right]
+            .into_iter()
+            .find(|&side| self.players[side as usize].as_ref().is_some_and(|p| p.connection == connection))
+    }
+
+    pub fn player_name(&self, side: Side) -> Option<&str> {
+        match (&self.players[side as usize], &self.bots[side as usize]) {
+            (Some(player), _) => Some(&player.name),
+            (None, Some(bot)) => Some(&bot.name),
+            (None, None) => None,
+        }
+    }
+
+    // Whether `connection` is a player or spectator here
+    pub fn knows(&self, connection: ConnectionId) -> bool {
+        self.player_side(connection).is_some() || self.spectators.iter().any(|watcher| watcher.connection == connection)
+    }
+
+    pub fn spectator_count(&self) -> usize {
+        self.spectators.len()
+    }
+
+    // The seat a rejoin token belongs to
+    pub fn token_side(&self, token: u64) -> Option<Side> {
+        [Side::Left, Side::Right]
+            .into_iter()
+            .find(|&side| self.players[side as usize].as_ref().is_some_and(|p| p.token == token))
+    }
+
+    // Side the match is waiting for, if paused
+    pub fn absent(&self) -> Option<Side> {
+        self.paused_at.and(self.quiet_sides().first().copied())
+    }
+
+    // Seconds left before the absent player forfeits
+    pub fn resume_within(&self) -> f32 {
+        self.paused_at.map_or(0.0, |since| {
+            (NET_RECONNECT_TIMEOUT - (self.tick - since) as f32 * NET_DT).max(0.0)
+        })
+    }
+
+    pub fn forfeit(&self) -> Option<Side> {
+        self.forfeit
+    }
+
+    // Won, forfeited or abandoned by both players
+    pub fn is_over(&self) -> bool {
+        self.game.winner().is_some() || self.abandoned
+    }
+
+    fn quiet_sides(&self) -> Vec<Side> {
+        let quiet = (NET_DISCONNECT_TIMEOUT / NET_DT) as u32;
+        [Side::Left, Side::Right]
+            .into_iter()
+            .filter(|&side| {
+                self.players[side as usize]
+                    .as_ref()
+                    .is_some_and(|p| self.tick - p.last_heard > quiet)
+            })
+            .collect()
+    }
+
+    // The match runs once both sides have a player or the AI
+    pub fn is_running(&self) -> bool {
+        self.players.iter().zip(&self.bots).all(|(player, bot)| player.is_some() || bot.is_some())
+    }
+
+    pub fn receive(&mut self, connection: ConnectionId, data: &[u8]) {
+        let packet = Packet::decode(data);
+        if let Some(side) = self.player_side(connection).filter(|_| packet.is_some()) {
+            self.players[side as usize].as_mut().unwrap().last_heard = self.tick;
+        }
+        match packet {
+            Some(Packet::Join { name, token }) => {
+                // Answer every join in case an earlier accept got lost
+                let side = match (self.player_side(connection), self.token_side(token)) {
+                    (Some(side), _) => side,
+                    (None, Some(side)) => {
+                        // Back from a new address: inputs start over from its first tick
+                        let player = self.players[side as usize].as_mut().unwrap();
+                        player.connection = connection;
+                        player.inputs.clear();
+                        player.applied = 0;
+                        player.ping = 0.0;
+                        player.last_heard = self.tick;
+                        side
+                    }
+                    (None, None) => {
+                        let free = |side: &Side| {
+                            self.players[*side as usize].is_none() && self.bots[*side as usize].is_none()
+                        };
+                        let Some(side) = [Side::Left, Side::Right].into_iter().find(free) else {
+                            return; // Full; the client can watch instead
+                        };
+                        self.spectators.retain(|watcher| watcher.connection != connection);
+                        self.seat(side, connection, &name);
+                        side
+                    }
+                };
+                let token = self.players[side as usize].as_ref().unwrap().token;
+                self.outgoing.push((connection, Packet::Accept { side, token }.encode()));
+            }
+            Some(Packet::Commands {
+                start,
+                inputs,
+                ping,
+                rtt_ms,
+            }) => {
+                let Some(side) = self.player_side(connection) else { return };
+                let player = self.players[side as usize].as_mut().unwrap();
+                player.ping = player.ping.max(ping);
+                player.rtt_ms = rtt_ms;
+                for (offset, &input) in inputs.iter().enumerate() {
+                    // Inputs are resent until acknowledged; keep the next contiguous ones
+                    if start as usize + offset == player.inputs.len() {
+                        player.inputs.push(input);
+                    }
+                }
+            }
+            Some(Packet::Watch) if self.player_side(connection).is_none() => {
+                match self.spectators.iter_mut().find(|watcher| watcher.connection == connection) {
+                    Some(watcher) => watcher.last_seen = self.tick,
+                    None => {
+                        self.spectators.push(Watcher {
+                            connection,
+                            last_seen: self.tick,
+                        });
+                        // Late joiners catch up from the current delayed snapshot
+                        let packet = self.spectate_packet();
+                        self.outgoing.push((connection, packet));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Advance the match by one fixed step and queue snapshots
+    pub fn tick(&mut self) -> StepEvents {
+        if !self.is_running() {
+            return StepEvents::default();
+        }
+
+        let mut events = StepEvents::default();
+        let quiet = if self.is_over() { Vec::new() } else { self.quiet_sides() };
+        if !quiet.is_empty() {
+            // Frozen until they're back; the tick keeps counting for the snapshots
+            let since = *self.paused_at.get_or_insert(self.tick);
+            if (self.tick - since) as f32 * NET_DT >= NET_RECONNECT_TIMEOUT {
+                if let [side] = quiet[..] {
+                    let winner = side.opponent();
+                    match winner {
+                        Side::Left => self.game.left_score = self.game.winning_score,
+                        Side::Right => self.game.right_score = self.game.winning_score,
+                    }
+                    self.forfeit = Some(side);
+                    events.winner = Some(winner);
+                } else {
+                    self.abandoned = true;
+                }
+                self.paused_at = None;
+            }
+        } else if !self.is_over() {
+            self.paused_at = None;
+            for side in [Side::Left, Side::Right] {
+                if let Some(player) = self.players[side as usize].as_mut() {
+                    let received = player.inputs.len() as u32;
+                    if received > player.applied + MAX_INPUT_BUFFER {
+                        player.applied = received - MAX_INPUT_BUFFER;
+                    }
+                    if player.applied < received {
+                        player.last = player.inputs[player.applied as usize];
+                        player.applied += 1;
+                    }
+                    self.game.paddle_mut(side).drive(Intent::from_action(player.last), NET_DT);
+                } else if let Some(bot) = self.bots[side as usize].as_mut() {
+                    let noise = self.rng.range(-1.0, 1.0);
+                    bot.ai.drive(&mut self.game, NET_DT, noise);
+                }
+            }
+            events = self.game.step(NET_DT);
+            if events.scored.is_some() {
+                self.rally = 0;
+            } else if events.paddle_hit.is_some() {
+                self.rally += 1;
+            }
+        }
+        self.tick += 1;
+
+        self.history.push_back((self.tick, self.game, self.rally));
+        while self.history.len() as f32 > SPECTATOR_DELAY / NET_DT {
+            self.history.pop_front();
+        }
+        let timeout = (SPECTATOR_TIMEOUT / NET_DT) as u32;
+        let tick = self.tick;
+        self.spectators.retain(|watcher| tick - watcher.last_seen < timeout);
+
+        // Keep sending after the match ends so everyone learns the result
+        if self.tick.is_multiple_of(NET_SNAPSHOT_INTERVAL) || events.scored.is_some() {
+            for (side, player) in [Side::Left, Side::Right].into_iter().zip(&self.players) {
+                let Some(player) = player else { continue };
+                // Tell each player about the other side first
+                let absent = self.forfeit.or_else(|| {
+                    let mut quiet = quiet.clone();
+                    quiet.sort_by_key(|&quiet| quiet == side);
+                    self.paused_at.and(quiet.first().copied())
+                });
+                let snapshot = Packet::Snapshot {
+                    tick: self.tick,
+                    ack: player.applied,
+                    pong: player.ping,
+                    state: self.game,
+                    absent,
+                    resume_within: self.resume_within(),
+                };
+                self.outgoing.push((player.connection, snapshot.encode()));
+            }
+            if !self.spectators.is_empty() {
+                let packet = self.spectate_packet();
+                for watcher in &self.spectators {
+                    self.outgoing.push((watcher.connection, packet.clone()));
+                }
+            }
+        }
+        events
+    }
+
+    // The oldest state still in the history, i.e. `SPECTATOR_DELAY` behind
+    fn spectate_packet(&self) -> Vec<u8> {
+        let (tick, state, rally) = self.history.front().copied().unwrap_or((self.tick, self.game, self.rally));
+        let info = |side: Side| self.players[side as usize].as_ref();
+        let name = |side: Side| info(side).map_or_else(|| "Waiting...".to_string(), |p| p.name.clone());
+        let ping = |side: Side| info(side).map_or(0, |p| p.rtt_ms);
+        Packet::Spectate {
+            tick,
+            state,
+            rally,
+            names: [name(Side::Left), name(Side::Right)],
+            pings: [ping(Side::Left), ping(Side::Right)],
+            spectators: self.spectators.len().min(u16::MAX as usize) as u16,
+        }
+        .encode()
+    }
+
+    pub fn take_outgoing(&mut self) -> Vec<(ConnectionId, Vec<u8>)> {
+        std::mem::take(&mut self.outgoing)
+    }
+}
+
+impl Default for AuthorityServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// A fresh rejoin token that can't be guessed from the other seat's, or
+// from another server's. Each `RandomState` has its own keys from the OS's
+// random source, and the counter and clock are mixed in on top. Never 0,
+// which means "no token".
+fn random_token() -> u64 {
+    static ISSUED: AtomicU64 = AtomicU64::new(0);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(ISSUED.fetch_add(1, Ordering::Relaxed));
+    hasher.write_u128(SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos()));
+    hasher.finish().max(1)
+}
+
+fn clean_name(name: &str, slot: usize) -> String {
+    let name: String = name.chars().filter(|c| !c.is_control()).take(16).collect();
+    if name.trim().is_empty() {
+        format!("Player {}", slot + 1)
+    } else {
+        name.trim().to_string()
+    }
+}
+
+// Snapshots received from the server, sampled in the past for smooth motion
+struct SnapshotBuffer {
+    snapshots: VecDeque<(u32, Match)>,
+    latest_at: f64, // When the newest snapshot arrived
+}
+
+impl SnapshotBuffer {
+    fn new() -> Self {
+        Self {
+            snapshots: VecDeque::new(),
+            latest_at: 0.0,
+        }
+    }
+
+    // False for late or duplicate snapshots
+    fn push(&mut self, tick: u32, state: Match, now: f64) -> bool {
+        if self.snapshots.back().is_some_and(|&(newest, _)| tick <= newest) {
+            return false;
+        }
+        self.snapshots.push_back((tick, state));
+        if self.snapshots.len() > SNAPSHOT_HISTORY {
+            self.snapshots.pop_front();
+        }
+        self.latest_at = now;
+        true
+    }
+
+    fn newest(&self) -> Option<&Match> {
+        self.snapshots.back().map(|(_, state)| state)
+    }
+
+    // The state `NET_INTERP_DELAY` ago with the ball and paddles interpolated
+    fn sample(&self, now: f64) -> Option<Match> {
+        let &(newest, latest) = self.snapshots.back()?;
+        let rate = NET_TICK_RATE as f64;
+        let render_tick = newest as f64 + (now - self.latest_at) * rate - NET_INTERP_DELAY as f64 * rate;
+
+        if render_tick <= self.snapshots[0].0 as f64 {
+            return Some(self.snapshots[0].1);
+        }
+        let pairs = self.snapshots.iter().zip(self.snapshots.iter().skip(1));
+        let Some((&(a_tick, a), &(b_tick, b))) = pairs.into_iter().find(|(_, &(b_tick, _))| render_tick <= b_tick as f64)
+        else {
+            return Some(latest);
+        };
+        let t = ((render_tick - a_tick as f64) / (b_tick - a_tick) as f64) as f32;
+        let lerp = |from: f32, to: f32| from + (to - from) * t;
+        let mut view = b;
+        // Don't sweep the ball across the field when it was served again
+        if a.left_score == b.left_score && a.right_score == b.right_score {
+            view.ball.x = lerp(a.ball.x, b.ball.x);
+            view.ball.y = lerp(a.ball.y, b.ball.y);
+        }
+        view.left.y = lerp(a.left.y, b.left.y);
+        view.right.y = lerp(a.right.y, b.right.y);
+        Some(view)
+    }
+}
+
+// Effects to play for the change between two drawn states
+fn derive_events(previous: &Match, view: &Match) -> StepEvents {
+    let mut events = StepEvents::default();
+    if view.left_score > previous.left_score {
+        events.scored = Some(Side::Left);
+    } else if view.right_score > previous.right_score {
+        events.scored = Some(Side::Right);
+    } else if view.ball.vel_x.signum() != previous.ball.vel_x.signum() {
+        let side = if view.ball.vel_x > 0.0 { Side::Left } else { Side::Right };
+        events.paddle_hit = Some((side, view.ball.y));
+    }
+    events.winner = view.winner();
+    events
+}
+
+fn update_rtt(rtt: &mut Option<f64>, sample: f64) {
+    *rtt = Some(match *rtt {
+        Some(rtt) => rtt * 0.9 + sample * 0.1,
+        None => sample,
+    });
+}
+
+// A player connected to an `AuthorityServer`
+pub struct ServerClient<T: Transport> {
+    transport: T,
+    name: String,
+    side: Option<Side>,
+    inputs: Vec<u8>, // Every local input, by client tick
+    acked: u32,      // Inputs the server has applied
+    paddle: Paddle,  // Own paddle, predicted ahead of the server
+    snapshots: SnapshotBuffer,
+    rtt: Option<f64>,
+    last_send: f64,
+    view: Match,
+    corrections: u32,
+    token: u64,        // Proves the seat is ours when we rejoin; 0 until accepted
+    accepted: bool,    // The server knows our current connection
+    last_heard: f64,   // When the last snapshot arrived
+    absent: Option<Side>,
+    resume_within: f32,
+}
+
+impl<T: Transport> ServerClient<T> {
+    pub fn new(transport: T, name: &str) -> Self {
+        Self {
+            transport,
+            name: name.to_string(),
+            side: None,
+            inputs: Vec::new(),
+            acked: 0,
+            paddle: Paddle::new(),
+            snapshots: SnapshotBuffer::new(),
+            rtt: None,
+            last_send: f64::NEG_INFINITY,
+            view: Match::new(),
+            corrections: 0,
+            token: 0,
+            accepted: false,
+            last_heard: 0.0,
+            absent: None,
+            resume_within: 0.0,
+        }
+    }
+
+    // Assigned by the server when it accepts us
+    pub fn side(&self) -> Option<Side> {
+        self.side
+    }
+
+    // True once the first snapshot arrived, i.e. both players are in
+    pub fn is_running(&self) -> bool {
+        self.side.is_some() && self.snapshots.newest().is_some()
+    }
+
+    pub fn ping_ms(&self) -> Option<f32> {
+        self.rtt.map(|rtt| (rtt * 1000.0) as f32)
+    }
+
+    // How often a snapshot disagreed with our predicted paddle
+    pub fn corrections(&self) -> u32 {
+        self.corrections
+    }
+
+    // Newest state received from the server, without prediction
+    pub fn server_state(&self) -> Option<&Match> {
+        self.snapshots.newest()
+    }
+
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    // Back to the lobby on the same connection after the match
+    pub fn into_transport(self) -> T {
+        self.transport
+    }
+
+    // Seconds since the server was last heard from
+    pub fn silence(&self, now: f64) -> f64 {
+        now - self.last_heard
+    }
+
+    // Take our seat back over a fresh connection, e.g. after the Wi-Fi
+    // dropped. The server restarts our inputs, so we do too.
+    pub fn reconnect(&mut self, transport: T) {
+        self.transport = transport;
+        self.accepted = false;
+        self.inputs.clear();
+        self.acked = 0;
+        self.last_send = f64::NEG_INFINITY;
+    }
+
+    // The side the server is waiting for and the seconds left before it
+    // forfeits, or our own side while the server is out of reach
+    pub fn pause(&self, now: f64) -> Option<(Side, f32)> {
+        let silence = self.silence(now) as f32;
+        match (self.side, self.server_state()) {
+            (Some(side), Some(state)) if state.winner().is_none() && silence > NET_DISCONNECT_TIMEOUT => {
+                Some((side, (NET_RECONNECT_TIMEOUT - silence + NET_DISCONNECT_TIMEOUT).max(0.0)))
+            }
+            (_, Some(state)) if state.winner().is_none() => self.absent.map(|side| (side, self.resume_within)),
+            _ => None,
+        }
+    }
+
+    // The side that lost by not coming back in time
+    pub fn forfeited(&self) -> Option<Side> {
+        self.absent.filter(|_| self.server_state().is_some_and(|state| state.winner().is_some()))
+    }
+
+    // Handle the handshake and incoming snapshots; call every frame
+    pub fn poll(&mut self, now: f64) {
+        if !self.accepted && now - self.last_send >= JOIN_INTERVAL {
+            let join = Packet::Join {
+                name: self.name.clone(),
+                token: self.token,
+            };
+            self.transport.send(&join.encode());
+            self.last_send = now;
+        } else if self.accepted && now - self.last_send >= PLAYER_KEEPALIVE {
+            self.send_inputs(now);
+        }
+
+        while let Some(data) = self.transport.recv() {
+            match Packet::decode(&data) {
+                Some(Packet::Accept { side, token }) => {
+                    self.side = Some(side);
+                    self.token = token;
+                    self.accepted = true;
+                }
+                Some(Packet::Snapshot {
+                    tick,
+                    ack,
+                    pong,
+                    state,
+                    absent,
+                    resume_within,
+                }) => {
+                    if !self.snapshots.push(tick, state, now) {
+                        continue; // Late or duplicate
+                    }
+                    self.last_heard = now;
+                    self.absent = absent;
+                    self.resume_within = resume_within;
+                    if pong > 0.0 {
+                        update_rtt(&mut self.rtt, now - pong);
+                    }
+                    self.reconcile(ack, &state);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Start from the server's paddle and replay the inputs it hasn't seen
+    fn reconcile(&mut self, ack: u32, state: &Match) {
+        let Some(side) = self.side else { return };
+        self.acked = ack.min(self.inputs.len() as u32);
+        let predicted = self.paddle;
+        self.paddle = *state.paddle(side);
+        for &input in &self.inputs[self.acked as usize..] {
+            self.paddle.drive(Intent::from_action(input), NET_DT);
+            self.paddle.integrate(NET_DT);
+        }
+        if (self.paddle.y - predicted.y).abs() > 0.5 {
+            self.corrections += 1;
+        }
+    }
+
+    // Apply one fixed step of local input and send it to the server
+    pub fn advance(&mut self, intent: Intent, now: f64) {
+        // Not while rejoining; the server has forgotten our inputs
+        if !self.is_running() || !self.accepted {
+            return;
+        }
+        self.inputs.push(intent.to_action());
+        self.paddle.drive(intent, NET_DT);
+        self.paddle.integrate(NET_DT);
+        self.send_inputs(now);
+    }
+
+    // Everything the server hasn't acknowledged yet
+    fn send_inputs(&mut self, now: f64) {
+        let start = (self.acked as usize).max(self.inputs.len().saturating_sub(MAX_INPUTS_PER_PACKET));
+        let packet = Packet::Commands {
+            start: start as u32,
+            inputs: self.inputs[start..].to_vec(),
+            ping: now,
+            rtt_ms: self.ping_ms().unwrap_or(0.0).min(u16::MAX as f32) as u16,
+        };
+        self.transport.send(&packet.encode());
+        self.last_send = now;
+    }
+
+    // The state to draw: own paddle predicted, everything else interpolated
+    // between snapshots. Events are derived from the change since last call.
+    pub fn view(&mut self, now: f64) -> (Match, StepEvents) {
+        let Some(mut view) = self.snapshots.sample(now) else {
+            return (self.view, StepEvents::default());
+        };
+        if let Some(side) = self.side {
+            *view.paddle_mut(side) = self.paddle;
+        }
+        let events = derive_events(&self.view, &view);
+        self.view = view;
+        (view, events)
+    }
+}
+
+// A read-only viewer of a server match. The server sends it states that
+// are `SPECTATOR_DELAY` old, so watching can't be used to help a player.
+pub struct Spectator<T: Transport> {
+    transport: T,
+    snapshots: SnapshotBuffer,
+    names: [String; 2],
+    pings: [u16; 2],
+    rally: u32,
+    spectators: u16,
+    last_send: f64,
+    view: Match,
+}
+
+impl<T: Transport> Spectator<T> {
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            snapshots: SnapshotBuffer::new(),
+            names: Default::default(),
+            pings: [0; 2],
+            rally: 0,
+            spectators: 0,
+            last_send: f64::NEG_INFINITY,
+            view: Match::new(),
+        }
+    }
+
+    // True once the server answered with a snapshot
+    pub fn is_running(&self) -> bool {
+        self.snapshots.newest().is_some()
+    }
+
+    pub fn name(&self, side: Side) -> &str {
+        &self.names[side as usize]
+    }
+
+    pub fn ping_ms(&self, side: Side) -> u16 {
+        self.pings[side as usize]
+    }
+
+    pub fn rally(&self) -> u32 {
+        self.rally
+    }
+
+    // Including us
+    pub fn spectators(&self) -> u16 {
+        self.spectators
+    }
+
+    pub fn poll(&mut self, now: f64) {
+        let interval = if self.is_running() { WATCH_KEEPALIVE } else { JOIN_INTERVAL };
+        if now - self.last_send >= interval {
+            self.transport.send(&Packet::Watch.encode());
+            self.last_send = now;
+        }
+
+        while let Some(data) = self.transport.recv() {
+            if let Some(Packet::Spectate {
+                tick,
+                state,
+                rally,
+                names,
+                pings,
+                spectators,
+            }) = Packet::decode(&data)
+            {
+                if self.snapshots.push(tick, state, now) {
+                    self.rally = rally;
+                    self.names = names;
+                    self.pings = pings;
+                    self.spectators = spectators;
+                }
+            }
+        }
+    }
+
+    // The delayed match, interpolated, with effects since the last call
+    pub fn view(&mut self, now: f64) -> (Match, StepEvents) {
+        let Some(view) = self.snapshots.sample(now) else {
+            return (self.view, StepEvents::default());
+        };
+        let events = derive_events(&self.view, &view);
+        self.view = view;
+        (view, events)
+    }
+}
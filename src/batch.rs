@@ -0,0 +1,344 @@
+use crate::constants::{AI_ERROR, WINNING_SCORE};
+use crate::sim::{Ball, Intent, Match, Paddle, Rng, Side, StepEvents, OBS_DIM};
+
+// Who moves the right paddle in a batch
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Opponent {
+    Agent,     // Right action comes from the caller like the left one
+    BuiltinAi, // Right paddle is driven by the in-game AI
+}
+
+// Per-match state stored as one array per field (struct of arrays)
+struct Lanes<'a> {
+    left_y: &'a mut [f32],
+    left_vel: &'a mut [f32],
+    right_y: &'a mut [f32],
+    right_vel: &'a mut [f32],
+    ball_x: &'a mut [f32],
+    ball_y: &'a mut [f32],
+    ball_vel_x: &'a mut [f32],
+    ball_vel_y: &'a mut [f32],
+    ball_speed: &'a mut [f32],
+    left_score: &'a mut [i32],
+    right_score: &'a mut [i32],
+    rng: &'a mut [Rng],
+    obs: &'a mut [f32],
+    rewards: &'a mut [f32],
+    dones: &'a mut [u8],
+}
+
+impl<'a> Lanes<'a> {
+    fn len(&self) -> usize {
+        self.left_y.len()
+    }
+
+    fn split_at(self, mid: usize) -> (Lanes<'a>, Lanes<'a>) {
+        let (left_y, left_y2) = self.left_y.split_at_mut(mid);
+        let (left_vel, left_vel2) = self.left_vel.split_at_mut(mid);
+        let (right_y, right_y2) = self.right_y.split_at_mut(mid);
+        let (right_vel, right_vel2) = self.right_vel.split_at_mut(mid);
+        let (ball_x, ball_x2) = self.ball_x.split_at_mut(mid);
+        let (ball_y, ball_y2) = self.ball_y.split_at_mut(mid);
+        let (ball_vel_x, ball_vel_x2) = self.ball_vel_x.split_at_mut(mid);
+        let (ball_vel_y, ball_vel_y2) = self.ball_vel_y.split_at_mut(mid);
+        let (ball_speed, ball_speed2) = self.ball_speed.split_at_mut(mid);
+        let (left_score, left_score2) = self.left_score.split_at_mut(mid);
+        let (right_score, right_score2) = self.right_score.split_at_mut(mid);
+        let (rng, rng2) = self.rng.split_at_mut(mid);
+        let (obs, obs2) = self.obs.split_at_mut(mid * 2 * OBS_DIM);
+        let (rewards, rewards2) = self.rewards.split_at_mut(mid);
+        let (dones, dones2) = self.dones.split_at_mut(mid);
+        (
+            Lanes {
+                left_y,
+                left_vel,
+                right_y,
+                right_vel,
+                ball_x,
+                ball_y,
+                ball_vel_x,
+                ball_vel_y,
+                ball_speed,
+                left_score,
+                right_score,
+                rng,
+                obs,
+                rewards,
+                dones,
+            },
+            Lanes {
+                left_y: left_y2,
+                left_vel: left_vel2,
+                right_y: right_y2,
+                right_vel: right_vel2,
+                ball_x: ball_x2,
+                ball_y: ball_y2,
+                ball_vel_x: ball_vel_x2,
+                ball_vel_y: ball_vel_y2,
+                ball_speed: ball_speed2,
+                left_score: left_score2,
+                right_score: right_score2,
+                rng: rng2,
+                obs: obs2,
+                rewards: rewards2,
+                dones: dones2,
+            },
+        )
+    }
+
+    fn load(&self, i: usize) -> Match {
+        gather(
+            i,
+            [self.left_y, self.left_vel, self.right_y, self.right_vel],
+            [self.ball_x, self.ball_y, self.ball_vel_x, self.ball_vel_y, self.ball_speed],
+            [self.left_score, self.right_score],
+        )
+    }
+
+    fn store(&mut self, i: usize, m: &Match) {
+        self.left_y[i] = m.left.y;
+        self.left_vel[i] = m.left.vel;
+        self.right_y[i] = m.right.y;
+        self.right_vel[i] = m.right.vel;
+        self.ball_x[i] = m.ball.x;
+        self.ball_y[i] = m.ball.y;
+        self.ball_vel_x[i] = m.ball.vel_x;
+        self.ball_vel_y[i] = m.ball.vel_y;
+        self.ball_speed[i] = m.ball.speed;
+        self.left_score[i] = m.left_score;
+        self.right_score[i] = m.right_score;
+
+        let obs = &mut self.obs[i * 2 * OBS_DIM..(i + 1) * 2 * OBS_DIM];
+        obs[..OBS_DIM].copy_from_slice(&m.observation(Side::Left));
+        obs[OBS_DIM..].copy_from_slice(&m.observation(Side::Right));
+    }
+
+    fn step(&mut self, actions: &[u8], dt: f32, opponent: Opponent) {
+        for i in 0..self.len() {
+            let mut m = self.load(i);
+            let events = step_match(
+                &mut m,
+                &mut self.rng[i],
+                [actions[i * 2], actions[i * 2 + 1]],
+                dt,
+                opponent,
+            );
+
+            // Reward from the left player's point of view
+            self.rewards[i] = match events.scored {
+                Some(Side::Left) => 1.0,
+                Some(Side::Right) => -1.0,
+                None => 0.0,
+            };
+            self.dones[i] = events.winner.is_some() as u8;
+            if events.winner.is_some() {
+                m = Match::new();
+            }
+
+            self.store(i, &m);
+        }
+    }
+}
+
+// Match `i` from the struct-of-arrays fields: paddle y/vel (left, right),
+// ball x/y/vel_x/vel_y/speed, then left/right score
+fn gather(i: usize, paddles: [&[f32]; 4], ball: [&[f32]; 5], scores: [&[i32]; 2]) -> Match {
+    Match {
+        left: Paddle {
+            y: paddles[0][i],
+            vel: paddles[1][i],
+            ..Paddle::new()
+        },
+        right: Paddle {
+            y: paddles[2][i],
+            vel: paddles[3][i],
+            ..Paddle::new()
+        },
+        ball: Ball {
+            x: ball[0][i],
+            y: ball[1][i],
+            vel_x: ball[2][i],
+            vel_y: ball[3][i],
+            speed: ball[4][i],
+        },
+        left_score: scores[0][i],
+        right_score: scores[1][i],
+        winning_score: WINNING_SCORE,
+    }
+}
+
+// Apply one frame of actions (`Intent::from_action` encoding) and step the
+// match. Shared by the batch, replays and bindings so they all agree.
+pub fn step_match(
+    m: &mut Match,
+    rng: &mut Rng,
+    actions: [u8; 2],
+    dt: f32,
+    opponent: Opponent,
+) -> StepEvents {
+    m.left.drive(Intent::from_action(actions[0]), dt);
+    match opponent {
+        Opponent::Agent => m.right.drive(Intent::from_action(actions[1]), dt),
+        Opponent::BuiltinAi => {
+            let jitter = rng.range(-AI_ERROR, AI_ERROR);
+            m.ai_drive(Side::Right, jitter);
+        }
+    }
+    m.step(dt)
+}
+
+// Many independent matches stepped together with a fixed time step.
+// Finished matches are reset automatically; `dones` flags them for one step.
+pub struct BatchEnv {
+    len: usize,
+    dt: f32,
+    threads: usize,
+    opponent: Opponent,
+    left_y: Vec<f32>,
+    left_vel: Vec<f32>,
+    right_y: Vec<f32>,
+    right_vel: Vec<f32>,
+    ball_x: Vec<f32>,
+    ball_y: Vec<f32>,
+    ball_vel_x: Vec<f32>,
+    ball_vel_y: Vec<f32>,
+    ball_speed: Vec<f32>,
+    left_score: Vec<i32>,
+    right_score: Vec<i32>,
+    rng: Vec<Rng>,
+    obs: Vec<f32>,
+    rewards: Vec<f32>,
+    dones: Vec<u8>,
+}
+
+impl BatchEnv {
+    pub fn new(len: usize, dt: f32, opponent: Opponent, seed: u64) -> Self {
+        let mut env = Self {
+            len,
+            dt,
+            threads: 1,
+            opponent,
+            left_y: vec![0.0; len],
+            left_vel: vec![0.0; len],
+            right_y: vec![0.0; len],
+            right_vel: vec![0.0; len],
+            ball_x: vec![0.0; len],
+            ball_y: vec![0.0; len],
+            ball_vel_x: vec![0.0; len],
+            ball_vel_y: vec![0.0; len],
+            ball_speed: vec![0.0; len],
+            left_score: vec![0; len],
+            right_score: vec![0; len],
+            rng: (0..len as u64).map(|i| Rng::new(seed ^ i.wrapping_mul(0x2545_F491_4F6C_DD1D))).collect(),
+            obs: vec![0.0; len * 2 * OBS_DIM],
+            rewards: vec![0.0; len],
+            dones: vec![0; len],
+        };
+        env.reset();
+        env
+    }
+
+    // Number of worker threads used by `step` (1 = run on the caller's thread)
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn reset(&mut self) {
+        let fresh = Match::new();
+        let mut lanes = self.lanes();
+        for i in 0..lanes.len() {
+            lanes.store(i, &fresh);
+        }
+        self.rewards.fill(0.0);
+        self.dones.fill(0);
+    }
+
+    // Step every match once. `actions` holds two entries per match
+    // (left, right) using the `Intent::from_action` encoding; the right
+    // entry is ignored when the opponent is the built-in AI.
+    pub fn step(&mut self, actions: &[u8]) {
+        assert_eq!(actions.len(), self.len * 2, "expected two actions per match");
+        let dt = self.dt;
+        let opponent = self.opponent;
+        let threads = self.threads.min(self.len.max(1));
+        let chunk = self.len.div_ceil(threads);
+        let mut lanes = self.lanes();
+
+        if threads <= 1 {
+            lanes.step(actions, dt, opponent);
+            return;
+        }
+
+        std::thread::scope(|scope| {
+            let mut offset = 0;
+            while lanes.len() > chunk {
+                let (head, tail) = lanes.split_at(chunk);
+                let acts = &actions[offset * 2..(offset + chunk) * 2];
+                scope.spawn(move || {
+                    let mut head = head;
+                    head.step(acts, dt, opponent);
+                });
+                lanes = tail;
+                offset += chunk;
+            }
+            lanes.step(&actions[offset * 2..], dt, opponent);
+        });
+    }
+
+    // Observations, `2 * OBS_DIM` floats per match: left view then right view
+    pub fn observations(&self) -> &[f32] {
+        &self.obs
+    }
+
+    // Reward for the left player per match: +1 point won, -1 point lost
+    pub fn rewards(&self) -> &[f32] {
+        &self.rewards
+    }
+
+    pub fn dones(&self) -> &[u8] {
+        &self.dones
+    }
+
+    pub fn scores(&self, i: usize) -> (i32, i32) {
+        (self.left_score[i], self.right_score[i])
+    }
+
+    // Copy out the full state of one match
+    pub fn get(&self, i: usize) -> Match {
+        gather(
+            i,
+            [&self.left_y, &self.left_vel, &self.right_y, &self.right_vel],
+            [&self.ball_x, &self.ball_y, &self.ball_vel_x, &self.ball_vel_y, &self.ball_speed],
+            [&self.left_score, &self.right_score],
+        )
+    }
+
+    fn lanes(&mut self) -> Lanes<'_> {
+        Lanes {
+            left_y: &mut self.left_y,
+            left_vel: &mut self.left_vel,
+            right_y: &mut self.right_y,
+            right_vel: &mut self.right_vel,
+            ball_x: &mut self.ball_x,
+            ball_y: &mut self.ball_y,
+            ball_vel_x: &mut self.ball_vel_x,
+            ball_vel_y: &mut self.ball_vel_y,
+            ball_speed: &mut self.ball_speed,
+            left_score: &mut self.left_score,
+            right_score: &mut self.right_score,
+            rng: &mut self.rng,
+            obs: &mut self.obs,
+            rewards: &mut self.rewards,
+            dones: &mut self.dones,
+        }
+    }
+}
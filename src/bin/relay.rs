@@ -0,0 +1,256 @@
+// Relay and lobby server for online matches. Serves the web build over HTTP
+// (so it can stand in for the Fly.io deployment locally) and upgrades `/ws`
+// to a WebSocket where clients join rooms by code. Binary messages are
+// forwarded to the other player in the room; see `pong_rs::ws` for the
+// control messages.
+//
+//   cargo run --bin relay -- [--port 8080] [--web web]
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use pong_rs::ws::{
+    accept_key, decode_frame, encode_frame, header, Frame, Reassembler, CLOSE_TOO_BIG, MAX_BUFFERED, OP_BINARY, OP_CLOSE,
+    OP_PING, OP_PONG, OP_TEXT,
+};
+
+// Outgoing frames for one connection: (opcode, payload)
+type Outbox = Sender<(u8, Vec<u8>)>;
+
+#[derive(Default)]
+struct Room {
+    players: [Option<(u64, Outbox)>; 2], // Left, right
+}
+
+type Rooms = Arc<Mutex<HashMap<String, Room>>>;
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let value = |flag: &str| {
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|i| args.get(i + 1).cloned())
+    };
+    let port: u16 = value("--port").and_then(|p| p.parse().ok()).unwrap_or(8080);
+    let web_root = PathBuf::from(value("--web").unwrap_or_else(|| "web".to_string()));
+
+    let listener = match TcpListener::bind(("0.0.0.0", port)) {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("Could not listen on port {}: {}", port, err);
+            std::process::exit(1);
+        }
+    };
+    println!("Relay running at http://localhost:{} (WebSocket at /ws)", port);
+
+    let rooms: Rooms = Arc::default();
+    for (id, stream) in listener.incoming().enumerate() {
+        let Ok(stream) = stream else { continue };
+        let rooms = rooms.clone();
+        let web_root = web_root.clone();
+        thread::spawn(move || {
+            if let Err(err) = handle_connection(stream, id as u64, &rooms, &web_root) {
+                eprintln!("connection {}: {}", id, err);
+            }
+        });
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, id: u64, rooms: &Rooms, web_root: &Path) -> std::io::Result<()> {
+    // Read the HTTP request head
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 2048];
+    let head_end = loop {
+        let read = stream.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(());
+        }
+        request.extend_from_slice(&buffer[..read]);
+        if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+        if request.len() > 16 * 1024 {
+            return Ok(());
+        }
+    };
+    let head = String::from_utf8_lossy(&request[..head_end]).to_string();
+    let path = head.split_whitespace().nth(1).unwrap_or("/").to_string();
+
+    match header(&head, "Sec-WebSocket-Key") {
+        Some(key) if path.starts_with("/ws") => {
+            let response = format!(
+                "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+                 Sec-WebSocket-Accept: {}\r\n\r\n",
+                accept_key(key)
+            );
+            stream.write_all(response.as_bytes())?;
+            run_websocket(stream, request[head_end..].to_vec(), id, rooms)
+        }
+        _ => serve_file(stream, &path, web_root),
+    }
+}
+
+fn serve_file(mut stream: TcpStream, path: &str, web_root: &Path) -> std::io::Result<()> {
+    let path = path.split('?').next().unwrap_or("/");
+    let relative = if path == "/" { "index.html" } else { path.trim_start_matches('/') };
+
+    // Never serve anything outside the web root
+    let safe = Path::new(relative)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    let file = web_root.join(relative);
+
+    match fs::read(&file) {
+        Ok(body) if safe => {
+            let content_type = match file.extension().and_then(|e| e.to_str()) {
+                Some("html") => "text/html; charset=utf-8",
+                Some("js") => "application/javascript",
+                Some("wasm") => "application/wasm",
+                Some("css") => "text/css",
+                Some("png") => "image/png",
+                _ => "application/octet-stream",
+            };
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                content_type,
+                body.len()
+            );
+            stream.write_all(head.as_bytes())?;
+            stream.write_all(&body)
+        }
+        _ => stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 9\r\nConnection: close\r\n\r\nnot found"),
+    }
+}
+
+fn run_websocket(stream: TcpStream, mut incoming: Vec<u8>, id: u64, rooms: &Rooms) -> std::io::Result<()> {
+    // A writer thread owns the sending half so other connections can post to us
+    let (outbox, queue) = mpsc::channel::<(u8, Vec<u8>)>();
+    let mut writer = stream.try_clone()?;
+    thread::spawn(move || {
+        for (opcode, payload) in queue {
+            if writer.write_all(&encode_frame(opcode, &payload, None)).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut reader = stream;
+    let mut membership: Option<(String, usize)> = None;
+    let mut messages = Reassembler::default();
+    let mut buffer = [0u8; 4096];
+    'connection: loop {
+        loop {
+            // Oversized frames and messages close the connection before
+            // anything big is buffered
+            let (frame, used) = match decode_frame(&incoming) {
+                Ok(Some(decoded)) => decoded,
+                Ok(None) if incoming.len() <= MAX_BUFFERED => break,
+                Ok(None) | Err(_) => {
+                    let _ = outbox.send((OP_CLOSE, CLOSE_TOO_BIG.to_be_bytes().to_vec()));
+                    break 'connection;
+                }
+            };
+            incoming.drain(..used);
+            let frame = match messages.push(frame) {
+                Ok(Some(frame)) => frame,
+                Ok(None) => continue,
+                Err(_) => {
+                    let _ = outbox.send((OP_CLOSE, CLOSE_TOO_BIG.to_be_bytes().to_vec()));
+                    break 'connection;
+                }
+            };
+            if !handle_frame(frame, id, &outbox, rooms, &mut membership) {
+                break 'connection;
+            }
+        }
+        match reader.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => incoming.extend_from_slice(&buffer[..read]),
+        }
+    }
+
+    leave(rooms, id, &membership);
+    Ok(())
+}
+
+// Returns false when the connection should close
+fn handle_frame(
+    frame: Frame,
+    id: u64,
+    outbox: &Outbox,
+    rooms: &Rooms,
+    membership: &mut Option<(String, usize)>,
+) -> bool {
+    match frame.opcode {
+        OP_TEXT => {
+            let text = String::from_utf8_lossy(&frame.payload).to_string();
+            if let Some(code) = text.strip_prefix("join ") {
+                if membership.is_some() {
+                    return true;
+                }
+                let code = code.trim().to_uppercase();
+                let mut rooms = rooms.lock().unwrap();
+                let room = rooms.entry(code.clone()).or_default();
+                match room.players.iter().position(Option::is_none) {
+                    Some(slot) => {
+                        room.players[slot] = Some((id, outbox.clone()));
+                        let side = if slot == 0 { "left" } else { "right" };
+                        let _ = outbox.send((OP_TEXT, format!("joined {}", side).into_bytes()));
+                        if let Some((_, other)) = &room.players[1 - slot] {
+                            let _ = other.send((OP_TEXT, b"peer-joined".to_vec()));
+                            let _ = outbox.send((OP_TEXT, b"peer-joined".to_vec()));
+                        }
+                        println!("room {}: player joined on the {}", code, side);
+                        *membership = Some((code, slot));
+                    }
+                    None => {
+                        let _ = outbox.send((OP_TEXT, b"full".to_vec()));
+                    }
+                }
+            }
+            true
+        }
+        OP_BINARY => {
+            // Forward game data to the other player in the room
+            if let Some((code, slot)) = membership {
+                let rooms = rooms.lock().unwrap();
+                if let Some((_, other)) = rooms.get(code).and_then(|room| room.players[1 - *slot].as_ref()) {
+                    let _ = other.send((OP_BINARY, frame.payload));
+                }
+            }
+            true
+        }
+        OP_PING => {
+            let _ = outbox.send((OP_PONG, frame.payload));
+            true
+        }
+        OP_CLOSE => {
+            let _ = outbox.send((OP_CLOSE, Vec::new()));
+            false
+        }
+        _ => true,
+    }
+}
+
+fn leave(rooms: &Rooms, id: u64, membership: &Option<(String, usize)>) {
+    let Some((code, slot)) = membership else { return };
+    let mut rooms = rooms.lock().unwrap();
+    if let Some(room) = rooms.get_mut(code) {
+        if room.players[*slot].as_ref().is_some_and(|(player, _)| *player == id) {
+            room.players[*slot] = None;
+        }
+        if let Some((_, other)) = &room.players[1 - *slot] {
+            let _ = other.send((OP_TEXT, b"peer-left".to_vec()));
+        }
+        if room.players.iter().all(Option::is_none) {
+            rooms.remove(code);
+        }
+        println!("room {}: player left", code);
+    }
+}
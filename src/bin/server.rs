@@ -0,0 +1,96 @@
+// Headless match server with a LAN lobby. Players browse its rooms, agree on
+// the rules and ready up, then each room plays an authoritative match; see
+// `pong_rs::lobby` and `pong_rs::authority`. Clients started with `--server`
+// skip the lobby and take the first free seat, anyone else may watch.
+//
+//   cargo run --bin server -- [--port 7778] [--rooms 4]
+
+use std::net::{SocketAddr, UdpSocket};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use pong_rs::ai;
+use pong_rs::constants::*;
+use pong_rs::lobby::{LobbyEvent, LobbyServer};
+use pong_rs::net::MAX_PACKET;
+use pong_rs::rollback::NET_DT;
+
+const MAX_CONNECTIONS: usize = 1024; // Addresses in use at once, players and spectators
+
+fn arg_value<T: std::str::FromStr>(args: &[String], flag: &str) -> Option<T> {
+    let index = args.iter().position(|arg| arg == flag)?;
+    args.get(index + 1)?.parse().ok()
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let port = arg_value(&args, "--port").unwrap_or(SERVER_DEFAULT_PORT);
+    let rooms = arg_value(&args, "--rooms").unwrap_or(4);
+
+    let socket = match UdpSocket::bind(("0.0.0.0", port)) {
+        Ok(socket) => socket,
+        Err(err) => {
+            eprintln!("Could not listen on port {}: {}", port, err);
+            std::process::exit(1);
+        }
+    };
+    socket.set_nonblocking(true).expect("non-blocking socket");
+    println!("Match server listening on UDP port {} with {} rooms", port, rooms);
+
+    // AI fill-ins use the same difficulty levels as the game
+    let profiles = ai::load_profiles(AI_PROFILE_FILE)
+        .ok()
+        .filter(|profiles| !profiles.is_empty())
+        .unwrap_or_else(ai::default_profiles);
+    let mut lobby = LobbyServer::new(rooms, profiles);
+    let mut connections: Vec<Option<SocketAddr>> = Vec::new(); // Indexed by connection id, None when free
+    let mut buffer = [0u8; MAX_PACKET];
+    let clock = Instant::now();
+    let mut ticks: u32 = 0;
+
+    loop {
+        while let Ok((len, from)) = socket.recv_from(&mut buffer) {
+            let known = connections.iter().position(|&address| address == Some(from));
+            let connection = match known.or_else(|| connections.iter().position(Option::is_none)) {
+                Some(connection) => connection,
+                None if connections.len() < MAX_CONNECTIONS => {
+                    connections.push(None);
+                    connections.len() - 1
+                }
+                None => continue,
+            };
+            connections[connection] = Some(from);
+            lobby.receive(connection, &buffer[..len]);
+        }
+
+        lobby.tick();
+        for (connection, packet) in lobby.take_outgoing() {
+            if let Some(address) = connections[connection] {
+                let _ = socket.send_to(&packet, address);
+            }
+        }
+        // Free the ids of addresses that timed out, left, or whose seat
+        // moved to a new address, so reconnects don't use up the table
+        for (connection, address) in connections.iter_mut().enumerate() {
+            if address.is_some() && !lobby.knows(connection) {
+                *address = None;
+            }
+        }
+        for event in lobby.take_events() {
+            match event {
+                LobbyEvent::Entered { room, name } => println!("{}: {} sat down", lobby.room_name(room), name),
+                LobbyEvent::Left { room, name } => println!("{}: {} left", lobby.room_name(room), name),
+                LobbyEvent::Started { room } => println!("{}: match started", lobby.room_name(room)),
+                LobbyEvent::Finished { room, winner, score } => {
+                    println!("{}: {} wins {}-{}", lobby.room_name(room), winner, score.0, score.1);
+                }
+                LobbyEvent::Abandoned { room } => println!("{}: both players left, match abandoned", lobby.room_name(room)),
+            }
+        }
+
+        // Fixed tick rate against the wall clock
+        ticks += 1;
+        let next = Duration::from_secs_f64(ticks as f64 * NET_DT as f64);
+        thread::sleep(next.saturating_sub(clock.elapsed()));
+    }
+}
@@ -0,0 +1,271 @@
+// External bot protocol: a paddle controlled by another process over
+// line-delimited JSON on its stdin/stdout.
+//
+// Game -> bot, once at start:
+//   {"type":"hello","side":"right","width":800,"height":600,"paddle_height":100,"ball_size":15,"winning_score":6}
+// Game -> bot, every tick (coordinates are screen pixels, Y grows downwards):
+//   {"type":"state","tick":12,"ball":{"x":..,"y":..,"vx":..,"vy":..},
+//    "you":{"x":..,"y":..,"vel":..},"opponent":{"x":..,"y":..,"vel":..},
+//    "score":{"you":0,"opponent":0}}
+// Game -> bot, when the match ends:
+//   {"type":"end","winner":"you"}
+//
+// Bot -> game, one line per state: {"move":"up"} / {"move":"down"} / {"move":"stay"}
+// (a bare `up`/`down`/`stay` line is also accepted). A reply may echo
+// `"tick"`; replies for older ticks than the last one used are discarded.
+// The game never waits for a reply: each tick uses the newest move received
+// so far, and the paddle stays put once the bot has been quiet for the
+// timeout.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError, TrySendError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::constants::*;
+use crate::sim::{Intent, Match, Side};
+
+const SEND_QUEUE: usize = 8; // Messages waiting for a bot that reads slowly; newer ones are dropped
+
+pub struct BotProcess {
+    child: Child,
+    lines: SyncSender<String>, // To the writer thread
+    replies: Receiver<String>,
+    side: Side,
+    timeout: Duration,
+    tick: u64,
+    alive: bool,
+    intent: Intent,      // Newest move received
+    reply_tick: u64,     // Tick that move echoed, if it did
+    replied_at: Instant, // When it arrived
+}
+
+impl BotProcess {
+    // Launch `command` through the shell and greet it
+    pub fn spawn(command: &str, side: Side, timeout: Duration) -> io::Result<Self> {
+        let mut child = shell(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()?;
+        let mut stdin = child.stdin.take().expect("piped stdin");
+        let stdout = child.stdout.take().expect("piped stdout");
+
+        // Write on a separate thread too: a bot that stops reading fills the
+        // pipe, and only the writer gets stuck. States that queued up behind
+        // a slow bot are stale, so only the newest of them is sent.
+        let (lines, queued) = mpsc::sync_channel::<String>(SEND_QUEUE);
+        thread::spawn(move || {
+            while let Ok(first) = queued.recv() {
+                let batch: Vec<String> = std::iter::once(first).chain(queued.try_iter()).collect();
+                let newest_state = batch.iter().rposition(|line| is_state(line));
+                for (index, line) in batch.iter().enumerate() {
+                    if is_state(line) && Some(index) != newest_state {
+                        continue;
+                    }
+                    if writeln!(stdin, "{}", line).and_then(|_| stdin.flush()).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        // Read replies on a separate thread so the game never blocks on the bot
+        let (sender, replies) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => {
+                        if sender.send(line).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                }
+            }
+        });
+
+        let mut bot = Self {
+            child,
+            lines,
+            replies,
+            side,
+            timeout,
+            tick: 0,
+            alive: true,
+            intent: Intent::Stay,
+            reply_tick: 0,
+            replied_at: Instant::now(),
+        };
+        bot.send(&format!(
+            r#"{{"type":"hello","side":"{}","width":{},"height":{},"paddle_height":{},"ball_size":{},"winning_score":{}}}"#,
+            side_name(side),
+            SCREEN_WIDTH,
+            SCREEN_HEIGHT,
+            PADDLE_HEIGHT,
+            BALL_SIZE,
+            WINNING_SCORE
+        ));
+        Ok(bot)
+    }
+
+    pub fn side(&self) -> Side {
+        self.side
+    }
+
+    // False once the bot has exited or closed its pipes
+    pub fn is_alive(&self) -> bool {
+        self.alive
+    }
+
+    // Send the current state and return the newest move the bot has sent,
+    // without waiting for an answer to this one
+    pub fn decide(&mut self, game: &Match) -> Intent {
+        if !self.alive {
+            return Intent::Stay;
+        }
+        self.tick += 1;
+        let message = state_message(game, self.side, self.tick);
+        self.send(&message);
+
+        loop {
+            match self.replies.try_recv() {
+                Ok(line) => match parse_reply(&line) {
+                    Some((tick, _)) if tick.is_some_and(|t| t < self.reply_tick) => {}
+                    Some((tick, intent)) => {
+                        self.intent = intent;
+                        self.reply_tick = tick.unwrap_or(self.reply_tick);
+                        self.replied_at = Instant::now();
+                    }
+                    None => {}
+                },
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.alive = false;
+                    return Intent::Stay;
+                }
+            }
+        }
+        if self.replied_at.elapsed() > self.timeout {
+            Intent::Stay
+        } else {
+            self.intent
+        }
+    }
+
+    // Tell the bot how the match ended
+    pub fn finish(&mut self, winner: Side) {
+        let result = if winner == self.side { "you" } else { "opponent" };
+        self.send(&format!(r#"{{"type":"end","winner":"{}"}}"#, result));
+    }
+
+    // Queue a line for the writer thread; never waits
+    fn send(&mut self, line: &str) {
+        match self.lines.try_send(line.to_string()) {
+            Ok(()) | Err(TrySendError::Full(_)) => {} // Behind anyway; a newer state follows
+            Err(TrySendError::Disconnected(_)) => self.alive = false,
+        }
+    }
+}
+
+impl Drop for BotProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", command]);
+        cmd
+    }
+}
+
+fn is_state(line: &str) -> bool {
+    line.starts_with(r#"{"type":"state""#)
+}
+
+fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::Left => "left",
+        Side::Right => "right",
+    }
+}
+
+pub fn state_message(game: &Match, side: Side, tick: u64) -> String {
+    let (own_x, opp_x) = match side {
+        Side::Left => (LEFT_PADDLE_X, RIGHT_PADDLE_X),
+        Side::Right => (RIGHT_PADDLE_X, LEFT_PADDLE_X),
+    };
+    let own = game.paddle(side);
+    let opp = game.paddle(side.opponent());
+    let (own_score, opp_score) = match side {
+        Side::Left => (game.left_score, game.right_score),
+        Side::Right => (game.right_score, game.left_score),
+    };
+    format!(
+        concat!(
+            r#"{{"type":"state","tick":{},"ball":{{"x":{:.2},"y":{:.2},"vx":{:.2},"vy":{:.2}}},"#,
+            r#""you":{{"x":{:.2},"y":{:.2},"vel":{:.2}}},"opponent":{{"x":{:.2},"y":{:.2},"vel":{:.2}}},"#,
+            r#""score":{{"you":{},"opponent":{}}}}}"#
+        ),
+        tick,
+        game.ball.x,
+        game.ball.y,
+        game.ball.vel_x,
+        game.ball.vel_y,
+        own_x,
+        own.y,
+        own.vel,
+        opp_x,
+        opp.y,
+        opp.vel,
+        own_score,
+        opp_score
+    )
+}
+
+// Parse a bot reply into an optional echoed tick and the requested move
+pub fn parse_reply(line: &str) -> Option<(Option<u64>, Intent)> {
+    let line = line.trim();
+    let word = if line.starts_with('{') {
+        string_field(line, "move")?
+    } else {
+        line
+    };
+    let intent = match word {
+        "up" => Intent::Up,
+        "down" => Intent::Down,
+        "stay" => Intent::Stay,
+        _ => return None,
+    };
+    let tick = number_field(line, "tick").map(|t| t as u64);
+    Some((tick, intent))
+}
+
+// Minimal lookups for flat JSON objects; enough for the bot replies
+fn field_start<'a>(json: &'a str, key: &str) -> Option<&'a str> {
+    let pattern = format!("\"{}\"", key);
+    let rest = &json[json.find(&pattern)? + pattern.len()..];
+    Some(rest.trim_start().strip_prefix(':')?.trim_start())
+}
+
+fn string_field<'a>(json: &'a str, key: &str) -> Option<&'a str> {
+    let rest = field_start(json, key)?.strip_prefix('"')?;
+    Some(&rest[..rest.find('"')?])
+}
+
+fn number_field(json: &str, key: &str) -> Option<f64> {
+    let rest = field_start(json, key)?;
+    let end = rest
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '-' | '.' | 'e' | 'E' | '+')))
+        .unwrap_or(rest.len());
+    rest[..end].parse().ok()
+}
@@ -0,0 +1,540 @@
+// Challenges: short scripted setups with a goal, played on the left paddle
+// against an AI or a wall. A challenge is passed when its goal is reached
+// and failed when one of its limits is exceeded. Passing earns one star,
+// plus one for each star target met at the end, up to `MAX_STARS`; stars
+// earned over all challenges unlock the later ones.
+//
+// Results are counted as the match goes: returns (paddle hits), points,
+// bank shots (points where the ball came off the top or bottom wall after
+// the player's hit), points conceded and time. Goals use the counts that go
+// up, limits the ones that should stay low, and star targets either.
+//
+// Challenge file format (plain text, `#` starts a comment, one item per
+// line):
+//
+//   pong-challenge 1
+//   name Bank Shots
+//   description Score 3 points, only bank shots count
+//   unlock 2                    # stars needed to play it
+//   opponent ai easy            # or `opponent wall`; ai takes an AI profile name
+//   ball_speed 250              # serve speed in pixels per second
+//   paddle 100                  # your paddle's height
+//   opponent_paddle 100
+//   bank_only                   # points without a bank shot don't count
+//   goal banks 3                # returns, points or banks
+//   limit time 120              # conceded or time (seconds)
+//   star time 90                # any result, at most two
+//
+// Everything but the name and the goal is optional. Progress is kept in a
+// small text file, stars then the challenge name:
+//
+//   3 Speed Demon
+//   1 Bank Shots
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::ai::{AiController, AiProfile};
+use crate::constants::*;
+use crate::sim::{Ball, Match, Rng, Side, StepEvents};
+
+pub const MAX_STARS: u32 = 3;
+const MIN_PADDLE: f32 = 20.0;
+const HEADER: &str = "pong-challenge 1";
+
+// Built-in challenges, bundled into the binary so the web build has them too
+const BUNDLED: [&str; 6] = [
+    include_str!("../challenges/warm_up.challenge"),
+    include_str!("../challenges/speed_demon.challenge"),
+    include_str!("../challenges/half_size.challenge"),
+    include_str!("../challenges/bank_shots.challenge"),
+    include_str!("../challenges/sudden_death.challenge"),
+    include_str!("../challenges/giant_killer.challenge"),
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Metric {
+    Returns,
+    Points,
+    Banks,
+    Conceded,
+    Time,
+}
+
+impl Metric {
+    const ALL: [Metric; 5] = [Metric::Returns, Metric::Points, Metric::Banks, Metric::Conceded, Metric::Time];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Metric::Returns => "Returns",
+            Metric::Points => "Points",
+            Metric::Banks => "Bank shots",
+            Metric::Conceded => "Conceded",
+            Metric::Time => "Time",
+        }
+    }
+
+    // Conceded points and time should stay low, the rest go up
+    pub fn lower_is_better(self) -> bool {
+        matches!(self, Metric::Conceded | Metric::Time)
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Metric::Returns => "returns",
+            Metric::Points => "points",
+            Metric::Banks => "banks",
+            Metric::Conceded => "conceded",
+            Metric::Time => "time",
+        }
+    }
+}
+
+// A result to reach (or stay within) for a goal, limit or star
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Target {
+    pub metric: Metric,
+    pub value: f32,
+}
+
+impl Target {
+    pub fn met(&self, stats: &ChallengeStats) -> bool {
+        if self.metric.lower_is_better() {
+            stats.get(self.metric) <= self.value
+        } else {
+            stats.get(self.metric) >= self.value
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self.metric {
+            Metric::Returns => format!("Return {} shots", self.value),
+            Metric::Points => format!("Score {} points", self.value),
+            Metric::Banks => format!("Score {} bank shots", self.value),
+            Metric::Conceded => format!("Concede at most {}", self.value),
+            Metric::Time => format!("Finish within {}s", self.value),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Opponent {
+    Wall,       // Returns everything at the same speed and angle
+    Ai(String), // Name of the AI profile it plays with
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Challenge {
+    pub name: String,
+    pub description: String,
+    pub unlock: u32, // Stars needed to play it
+    pub opponent: Opponent,
+    pub ball_speed: f32,
+    pub paddle: f32,          // Player paddle height
+    pub opponent_paddle: f32, // Opponent paddle height, unused against the wall
+    pub bank_only: bool,
+    pub goal: Target,
+    pub limits: Vec<Target>,
+    pub stars: Vec<Target>,
+}
+
+impl Challenge {
+    // Stars for a finished run: none for a failure
+    pub fn stars_for(&self, stats: &ChallengeStats) -> u32 {
+        if !self.goal.met(stats) || self.limits.iter().any(|limit| !limit.met(stats)) {
+            return 0;
+        }
+        1 + self.stars.iter().filter(|star| star.met(stats)).count() as u32
+    }
+
+    // Fails when the AI opponent's profile isn't among `profiles`
+    pub fn check_profiles(&self, profiles: &[AiProfile]) -> Result<(), String> {
+        match &self.opponent {
+            Opponent::Ai(name) if !profiles.iter().any(|profile| profile.name == *name) => {
+                Err(format!("{} needs the AI profile \"{}\"", self.name, name))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    // Every `.challenge` file in `dir`, in file name order, with an error
+    // for each one that didn't load. A missing directory has none.
+    pub fn load_dir(dir: impl AsRef<Path>) -> (Vec<Challenge>, Vec<String>) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return (Vec::new(), Vec::new());
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "challenge"))
+            .collect();
+        paths.sort();
+        let mut challenges = Vec::new();
+        let mut errors = Vec::new();
+        for path in paths {
+            match Challenge::load(&path) {
+                Ok(challenge) => challenges.push(challenge),
+                Err(err) => errors.push(format!("{}: {}", path.display(), err)),
+            }
+        }
+        (challenges, errors)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(number, line)| (number + 1, line.split('#').next().unwrap_or("").trim()))
+            .filter(|(_, line)| !line.is_empty());
+        if lines.next().map(|(_, line)| line) != Some(HEADER) {
+            return Err(format!("expected header \"{}\"", HEADER));
+        }
+
+        let mut name = None;
+        let mut challenge = Challenge {
+            name: String::new(),
+            description: String::new(),
+            unlock: 0,
+            opponent: Opponent::Ai("medium".to_string()),
+            ball_speed: BALL_SPEED,
+            paddle: PADDLE_HEIGHT,
+            opponent_paddle: PADDLE_HEIGHT,
+            bank_only: false,
+            goal: Target { metric: Metric::Points, value: 0.0 },
+            limits: Vec::new(),
+            stars: Vec::new(),
+        };
+        let mut goal = None;
+        for (number, line) in lines {
+            let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let rest = rest.trim();
+            let number_arg = |low: f32, high: f32| {
+                rest.parse::<f32>()
+                    .ok()
+                    .filter(|value| (low..=high).contains(value))
+                    .ok_or(format!("line {}: {} takes a number from {} to {}", number, keyword, low, high))
+            };
+            let target = || {
+                let mut words = rest.split_whitespace();
+                let metric = words.next().and_then(|key| Metric::ALL.into_iter().find(|metric| metric.key() == key));
+                let value = words.next().and_then(|value| value.parse::<f32>().ok()).filter(|value| *value >= 0.0);
+                match (metric, value, words.next()) {
+                    (Some(metric), Some(value), None) => Ok(Target { metric, value }),
+                    _ => Err(format!("line {}: expected \"{} <result> <value>\"", number, keyword)),
+                }
+            };
+            match keyword {
+                "name" if !rest.is_empty() => name = Some(rest.to_string()),
+                "description" => challenge.description = rest.to_string(),
+                "unlock" => {
+                    challenge.unlock = rest.parse().map_err(|_| format!("line {}: unlock takes a number of stars", number))?;
+                }
+                "opponent" => {
+                    challenge.opponent = match rest.split_once(char::is_whitespace) {
+                        None if rest == "wall" => Opponent::Wall,
+                        Some(("ai", profile)) => Opponent::Ai(profile.trim().to_string()),
+                        _ => return Err(format!("line {}: opponent must be \"wall\" or \"ai <profile>\"", number)),
+                    };
+                }
+                "ball_speed" => challenge.ball_speed = number_arg(BALL_SPEED * 0.5, MAX_BALL_SPEED)?,
+                "paddle" => challenge.paddle = number_arg(MIN_PADDLE, SCREEN_HEIGHT)?,
+                "opponent_paddle" => challenge.opponent_paddle = number_arg(MIN_PADDLE, SCREEN_HEIGHT)?,
+                "bank_only" => challenge.bank_only = true,
+                "goal" => {
+                    let target = target()?;
+                    if target.metric.lower_is_better() {
+                        return Err(format!("line {}: a goal must be returns, points or banks", number));
+                    }
+                    goal = Some(target);
+                }
+                "limit" => {
+                    let target = target()?;
+                    if !target.metric.lower_is_better() {
+                        return Err(format!("line {}: a limit must be conceded or time", number));
+                    }
+                    challenge.limits.push(target);
+                }
+                "star" => {
+                    if challenge.stars.len() as u32 >= MAX_STARS - 1 {
+                        return Err(format!("line {}: at most {} star targets", number, MAX_STARS - 1));
+                    }
+                    challenge.stars.push(target()?);
+                }
+                other => return Err(format!("line {}: unknown item {}", number, other)),
+            }
+        }
+
+        challenge.name = name.ok_or("the challenge needs a name")?;
+        challenge.goal = goal.ok_or("the challenge needs a goal")?;
+        if challenge.bank_only && challenge.goal.metric == Metric::Points {
+            // Only banked points count, so the two are the same
+            challenge.goal.metric = Metric::Banks;
+        }
+        Ok(challenge)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = format!("{}\nname {}\n", HEADER, self.name);
+        if !self.description.is_empty() {
+            out += &format!("description {}\n", self.description);
+        }
+        out += &format!("unlock {}\n", self.unlock);
+        out += &match &self.opponent {
+            Opponent::Wall => "opponent wall\n".to_string(),
+            Opponent::Ai(profile) => format!("opponent ai {}\n", profile),
+        };
+        out += &format!(
+            "ball_speed {}\npaddle {}\nopponent_paddle {}\n",
+            self.ball_speed, self.paddle, self.opponent_paddle
+        );
+        if self.bank_only {
+            out += "bank_only\n";
+        }
+        out += &format!("goal {} {}\n", self.goal.metric.key(), self.goal.value);
+        for limit in &self.limits {
+            out += &format!("limit {} {}\n", limit.metric.key(), limit.value);
+        }
+        for star in &self.stars {
+            out += &format!("star {} {}\n", star.metric.key(), star.value);
+        }
+        out
+    }
+
+    // Built-in challenges from `challenges/`, easiest first
+    pub fn presets() -> Vec<Challenge> {
+        BUNDLED
+            .iter()
+            .map(|text| Challenge::parse(text).expect("bundled challenge files are valid"))
+            .collect()
+    }
+}
+
+// Results so far in a run
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ChallengeStats {
+    pub returns: u32,
+    pub points: u32,
+    pub banks: u32,
+    pub conceded: u32,
+    pub time: f32,
+}
+
+impl ChallengeStats {
+    pub fn get(&self, metric: Metric) -> f32 {
+        match metric {
+            Metric::Returns => self.returns as f32,
+            Metric::Points => self.points as f32,
+            Metric::Banks => self.banks as f32,
+            Metric::Conceded => self.conceded as f32,
+            Metric::Time => self.time,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    Passed(u32), // Stars earned
+    Failed,
+}
+
+// The match's own events plus bank shots, voided points and the outcome
+// once the challenge is decided
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ChallengeEvents {
+    pub step: StepEvents,
+    pub bank: Option<f32>,    // Ball X where a shot of the player's came off the top or bottom wall
+    pub voided: bool,         // The player scored, but not with a bank shot
+    pub outcome: Option<Outcome>,
+}
+
+// A challenge being played. The opponent paddle is driven here; the
+// player's paddle velocity must already reflect this frame's input.
+#[derive(Clone, Debug)]
+pub struct ChallengeRun {
+    pub challenge: Challenge,
+    pub game: Match,
+    pub stats: ChallengeStats,
+    pub outcome: Option<Outcome>,
+    ai: Option<AiController>,
+    rng: Rng,
+    last_hit: Option<Side>,
+    banked: bool, // The player's last shot has come off a wall
+}
+
+impl ChallengeRun {
+    pub fn new(challenge: Challenge, profiles: &[AiProfile], seed: u64) -> Self {
+        let mut game = Match::new();
+        game.winning_score = i32::MAX; // The challenge decides when it's over
+        game.left.height = challenge.paddle;
+        game.left.y = (SCREEN_HEIGHT - challenge.paddle) / 2.0;
+        let ai = match &challenge.opponent {
+            // The wall is a full-height right paddle that reflects without aiming
+            Opponent::Wall => {
+                game.right.height = SCREEN_HEIGHT;
+                game.right.y = 0.0;
+                None
+            }
+            Opponent::Ai(name) => {
+                game.right.height = challenge.opponent_paddle;
+                game.right.y = (SCREEN_HEIGHT - challenge.opponent_paddle) / 2.0;
+                profiles
+                    .iter()
+                    .find(|profile| profile.name == *name)
+                    .map(|profile| AiController::new(profile.params, Side::Right))
+            }
+        };
+        let mut run = Self {
+            challenge,
+            game,
+            stats: ChallengeStats::default(),
+            outcome: None,
+            ai,
+            rng: Rng::new(seed),
+            last_hit: None,
+            banked: false,
+        };
+        run.game.ball = Ball::serve(Side::Left);
+        run.launch();
+        run
+    }
+
+    // Bring a fresh serve up to the challenge's ball speed
+    fn launch(&mut self) {
+        let ball = &mut self.game.ball;
+        let scale = self.challenge.ball_speed / ball.speed;
+        ball.vel_x *= scale;
+        ball.vel_y *= scale;
+        ball.speed = self.challenge.ball_speed;
+    }
+
+    pub fn step(&mut self, dt: f32) -> ChallengeEvents {
+        let mut events = ChallengeEvents::default();
+        if self.outcome.is_some() {
+            return events;
+        }
+
+        if let Some(ai) = self.ai.as_mut() {
+            let noise = self.rng.range(-1.0, 1.0);
+            ai.drive(&mut self.game, dt, noise);
+        }
+        let before = self.game.ball;
+        events.step = self.game.step(dt);
+        self.stats.time += dt;
+
+        match events.step.paddle_hit {
+            Some((Side::Right, _)) if self.challenge.opponent == Opponent::Wall => {
+                // Straight back at the same speed and angle
+                let ball = &mut self.game.ball;
+                ball.vel_x = -before.vel_x;
+                ball.vel_y = before.vel_y;
+                ball.speed = before.speed;
+            }
+            _ => {}
+        }
+        if let Some((side, _)) = events.step.paddle_hit {
+            self.last_hit = Some(side);
+            self.banked = false;
+            if side == Side::Left {
+                self.stats.returns += 1;
+            }
+        } else if events.step.scored.is_none() && before.vel_y * self.game.ball.vel_y < 0.0 && self.last_hit == Some(Side::Left) {
+            self.banked = true;
+            events.bank = Some(self.game.ball.x);
+        }
+
+        if let Some(side) = events.step.scored {
+            if side == Side::Right {
+                self.stats.conceded += 1;
+            } else if self.challenge.bank_only && !self.banked {
+                self.game.left_score -= 1;
+                events.voided = true;
+            } else {
+                self.stats.points += 1;
+                self.stats.banks += self.banked as u32;
+            }
+            self.last_hit = None;
+            self.banked = false;
+            self.launch();
+        }
+
+        if self.challenge.limits.iter().any(|limit| !limit.met(&self.stats)) {
+            self.outcome = Some(Outcome::Failed);
+        } else if self.challenge.goal.met(&self.stats) {
+            self.outcome = Some(Outcome::Passed(self.challenge.stars_for(&self.stats)));
+        }
+        events.outcome = self.outcome;
+        events
+    }
+}
+
+// Best star count for each challenge played, by name
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChallengeProgress {
+    stars: BTreeMap<String, u32>,
+}
+
+impl ChallengeProgress {
+    pub fn stars(&self, name: &str) -> u32 {
+        self.stars.get(name).copied().unwrap_or_default()
+    }
+
+    pub fn total(&self) -> u32 {
+        self.stars.values().sum()
+    }
+
+    pub fn is_unlocked(&self, challenge: &Challenge) -> bool {
+        self.total() >= challenge.unlock
+    }
+
+    // Record a finished run; true if it earned more stars than before
+    pub fn record(&mut self, name: &str, stars: u32) -> bool {
+        let stars = stars.min(MAX_STARS);
+        if stars > self.stars(name) {
+            self.stars.insert(name.to_string(), stars);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut progress = ChallengeProgress::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (stars, name) = line
+                .split_once(char::is_whitespace)
+                .ok_or(format!("line {}: expected stars and a challenge name", number + 1))?;
+            let stars: u32 = stars
+                .parse()
+                .map_err(|_| format!("line {}: bad star count {}", number + 1, stars))?;
+            progress.record(name.trim(), stars);
+        }
+        Ok(progress)
+    }
+
+    pub fn to_text(&self) -> String {
+        self.stars.iter().map(|(name, stars)| format!("{} {}\n", stars, name)).collect()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+}
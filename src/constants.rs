@@ -23,6 +23,7 @@ pub const BALL_START_Y: f32 = SCREEN_HEIGHT / 2.0;
 
 // Game settings
 pub const WINNING_SCORE: i32 = 6;
+pub const FOUR_PLAYER_POINT_LIMIT: i32 = 5; // Goals conceded before a four-player seat is out
 
 // Enhanced physics
 pub const BALL_SPEED_INCREASE: f32 = 1.05; // 5% speed increase per paddle hit
@@ -37,3 +38,35 @@ pub const AI_SPEED: f32 = PADDLE_SPEED * 0.7; // AI paddle speed (slightly slowe
 pub const AI_PREDICTION: f32 = 0.3; // How far ahead AI predicts ball position
 pub const AI_ERROR: f32 = 10.0; // Random error in AI positioning for difficulty
 
+
+// External bot settings
+pub const BOT_TIMEOUT_MS: u64 = 100; // How long a bot's last move holds without a newer one
+pub const AI_PROFILE_FILE: &str = "ai_params.cfg"; // Tuned difficulty levels, see `pong_rs tune`
+
+// Network play
+pub const NET_DEFAULT_PORT: u16 = 7777;
+pub const NET_TICK_RATE: f32 = 60.0; // Fixed simulation steps per second online
+pub const NET_INPUT_DELAY: u32 = 2; // Frames local input is delayed before use
+pub const NET_MAX_PREDICTION: u32 = 8; // Frames we may run ahead of the remote inputs
+pub const SERVER_DEFAULT_PORT: u16 = 7778; // Authoritative server (`cargo run --bin server`)
+pub const NET_SNAPSHOT_INTERVAL: u32 = 2; // Server ticks between state snapshots
+pub const NET_INTERP_DELAY: f32 = 0.1; // Seconds remote entities are drawn in the past
+pub const SPECTATOR_DELAY: f32 = 2.0; // Seconds spectators trail the live match
+pub const NET_DISCONNECT_TIMEOUT: f32 = 1.0; // Seconds of silence before an online match pauses
+pub const NET_RECONNECT_TIMEOUT: f32 = 30.0; // Seconds a dropped player has to return before forfeiting
+pub const RELAY_DEFAULT_URL: &str = "ws://127.0.0.1:8080/ws"; // Desktop default for `--relay`
+
+// Arena editor
+pub const ARENA_DEFAULT_FILE: &str = "custom.arena"; // Where the editor saves unless `--arena` names a file
+pub const EDITOR_GRID: f32 = 10.0; // Placement snaps to this many pixels
+pub const MAX_BRICK_HITS: u32 = 5;
+
+// Practice mode
+pub const PRACTICE_BEST_FILE: &str = "practice_best.cfg"; // Personal best rallies against the wall
+
+// Tournaments
+pub const TOURNAMENT_FILE: &str = "tournament.txt"; // Saved bracket, offered for resuming on the setup screen
+
+// Challenges
+pub const CHALLENGE_DIR: &str = "challenges"; // Extra `.challenge` files are picked up from here
+pub const CHALLENGE_PROGRESS_FILE: &str = "challenges.cfg"; // Best stars earned on each challenge
@@ -0,0 +1,75 @@
+// Desync reports: when two simulations that should be identical produce
+// different checksums (see `Match::checksum`), dump both states at the first
+// divergent tick side by side so the differing fields stand out.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::sim::Match;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DesyncReport {
+    pub frame: u32, // First tick whose resulting state differs
+    pub local: Match,
+    pub local_checksum: u32,
+    pub remote: Option<Match>, // Unknown until the peer's dump arrives, or never for replays
+    pub remote_checksum: u32,
+    pub remote_label: String, // "peer", "recording", ...
+}
+
+impl DesyncReport {
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Desync at tick {}", self.frame);
+        let _ = writeln!(
+            out,
+            "checksums: local {:08x}, {} {:08x}",
+            self.local_checksum, self.remote_label, self.remote_checksum
+        );
+        let _ = writeln!(out);
+
+        let local = fields(&self.local);
+        let remote = self.remote.as_ref().map(fields);
+        let _ = writeln!(out, "{:<14} {:>16} {:>16}", "field", "local", self.remote_label);
+        for (i, (name, value)) in local.iter().enumerate() {
+            let (other, marker) = match &remote {
+                Some(remote) => {
+                    let other = remote[i].1;
+                    let marker = if other.to_bits() != value.to_bits() { "  <--" } else { "" };
+                    (format!("{:?}", other), marker)
+                }
+                None => ("?".to_string(), ""),
+            };
+            let _ = writeln!(out, "{:<14} {:>16} {:>16}{}", name, format!("{:?}", value), other, marker);
+        }
+        if self.remote.is_none() {
+            let _ = writeln!(out, "\n({} state not available)", self.remote_label);
+        }
+        out
+    }
+
+    // Write the report next to the game as `desync-<tick>.txt`
+    pub fn save(&self) -> io::Result<PathBuf> {
+        let path = PathBuf::from(format!("desync-{}.txt", self.frame));
+        fs::write(&path, self.to_text())?;
+        Ok(path)
+    }
+}
+
+fn fields(game: &Match) -> [(&'static str, f32); 11] {
+    [
+        ("left.y", game.left.y),
+        ("left.vel", game.left.vel),
+        ("right.y", game.right.y),
+        ("right.vel", game.right.vel),
+        ("ball.x", game.ball.x),
+        ("ball.y", game.ball.y),
+        ("ball.vel_x", game.ball.vel_x),
+        ("ball.vel_y", game.ball.vel_y),
+        ("ball.speed", game.ball.speed),
+        ("left_score", game.left_score as f32),
+        ("right_score", game.right_score as f32),
+    ]
+}
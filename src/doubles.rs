@@ -0,0 +1,201 @@
+// 2v2 doubles: each side has a back paddle on the usual line and a front
+// paddle further up the field, each played by a different human or AI.
+//
+// Collision rules: a paddle only plays balls heading for its own goal, so
+// the front paddle gets the first chance, the back paddle covers what it
+// misses, and returns pass straight through teammates. Whoever touched the
+// ball last is credited with the point it wins.
+
+use crate::ai::{AiController, AiParams};
+use crate::constants::*;
+use crate::sim::{bounce_off_walls, hit_paddle, Ball, Paddle, Side};
+
+const FRONT_OFFSET: f32 = 150.0; // Front paddles' distance ahead of the back ones
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Seat {
+    LeftBack,
+    LeftFront,
+    RightFront,
+    RightBack,
+}
+
+impl Seat {
+    // Left to right across the field
+    pub const ALL: [Seat; 4] = [Seat::LeftBack, Seat::LeftFront, Seat::RightFront, Seat::RightBack];
+
+    pub fn side(self) -> Side {
+        match self {
+            Seat::LeftBack | Seat::LeftFront => Side::Left,
+            Seat::RightFront | Seat::RightBack => Side::Right,
+        }
+    }
+
+    pub fn is_front(self) -> bool {
+        matches!(self, Seat::LeftFront | Seat::RightFront)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Seat::LeftBack => "Left Back",
+            Seat::LeftFront => "Left Front",
+            Seat::RightFront => "Right Front",
+            Seat::RightBack => "Right Back",
+        }
+    }
+
+    // Paddle X, i.e. the left edge like `LEFT_PADDLE_X`/`RIGHT_PADDLE_X`
+    pub fn x(self) -> f32 {
+        match self {
+            Seat::LeftBack => LEFT_PADDLE_X,
+            Seat::LeftFront => LEFT_PADDLE_X + FRONT_OFFSET,
+            Seat::RightFront => RIGHT_PADDLE_X - FRONT_OFFSET,
+            Seat::RightBack => RIGHT_PADDLE_X,
+        }
+    }
+}
+
+// Which seat returned the ball, and the point and match result if there
+// was one, in one `DoublesMatch::step`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DoublesEvents {
+    pub paddle_hit: Option<(Seat, f32)>, // Seat and ball Y at impact
+    pub scored: Option<Side>,
+    pub winner: Option<Side>,
+}
+
+// Per-player statistics for the end screen
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SeatStats {
+    pub hits: u32,
+    pub points: u32, // Points won with this player's touch last
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DoublesMatch {
+    pub paddles: [Paddle; 4], // By `Seat` order
+    pub ball: Ball,
+    pub left_score: i32,
+    pub right_score: i32,
+    pub winning_score: i32,
+    pub stats: [SeatStats; 4],
+    last_touch: Option<Seat>,
+}
+
+impl DoublesMatch {
+    pub fn new(winning_score: i32) -> Self {
+        Self {
+            paddles: [Paddle::new(); 4],
+            ball: Ball::new(),
+            left_score: 0,
+            right_score: 0,
+            winning_score,
+            stats: [SeatStats::default(); 4],
+            last_touch: None,
+        }
+    }
+
+    pub fn winner(&self) -> Option<Side> {
+        if self.left_score >= self.winning_score {
+            Some(Side::Left)
+        } else if self.right_score >= self.winning_score {
+            Some(Side::Right)
+        } else {
+            None
+        }
+    }
+
+    // Advance paddles and ball by `dt`. Paddle velocities must already
+    // reflect this frame's input (see `Paddle::drive` / `DoublesAi::drive`).
+    pub fn step(&mut self, dt: f32) -> DoublesEvents {
+        let mut events = DoublesEvents::default();
+        if self.winner().is_some() {
+            return events;
+        }
+
+        for paddle in self.paddles.iter_mut() {
+            paddle.integrate(dt);
+        }
+
+        let ball = &mut self.ball;
+        ball.x += ball.vel_x * dt;
+        ball.y += ball.vel_y * dt;
+
+        bounce_off_walls(ball);
+
+        // Front paddles first: they meet an incoming ball before the back ones
+        let order = [Seat::LeftFront, Seat::RightFront, Seat::LeftBack, Seat::RightBack];
+        for seat in order {
+            if hit_paddle(ball, &self.paddles[seat as usize], seat.x(), seat.side()) {
+                self.stats[seat as usize].hits += 1;
+                self.last_touch = Some(seat);
+                events.paddle_hit = Some((seat, ball.y));
+                break;
+            }
+        }
+
+        // Check for scoring and reset ball
+        let scored = if ball.x < -BALL_SIZE {
+            self.right_score += 1;
+            Some(Side::Right)
+        } else if ball.x > SCREEN_WIDTH + BALL_SIZE {
+            self.left_score += 1;
+            Some(Side::Left)
+        } else {
+            None
+        };
+        if let Some(side) = scored {
+            if let Some(seat) = self.last_touch.filter(|seat| seat.side() == side) {
+                self.stats[seat as usize].points += 1;
+            }
+            self.ball = Ball::serve(side);
+            self.last_touch = None;
+            events.scored = Some(side);
+            events.winner = self.winner();
+        }
+
+        events
+    }
+}
+
+impl Default for DoublesMatch {
+    fn default() -> Self {
+        Self::new(WINNING_SCORE)
+    }
+}
+
+// Built-in AI for one seat: an `AiController` aiming at its own paddle's line
+#[derive(Clone, Copy, Debug)]
+pub struct DoublesAi {
+    pub seat: Seat,
+    pub controller: AiController,
+}
+
+impl DoublesAi {
+    pub fn new(params: AiParams, seat: Seat) -> Self {
+        Self {
+            seat,
+            controller: AiController::new(params, seat.side()),
+        }
+    }
+
+    pub fn drive(&mut self, game: &mut DoublesMatch, dt: f32, noise: f32) {
+        // Predicted ball height when it reaches our paddle, while it's
+        // still in front of it
+        let ball = game.ball;
+        let (paddle_x, facing) = match self.seat.side() {
+            Side::Left => (self.seat.x() + PADDLE_WIDTH, -1.0),
+            Side::Right => (self.seat.x(), 1.0),
+        };
+        let paddle = &mut game.paddles[self.seat as usize];
+        self.controller.drive_paddle(paddle, dt, noise, |params| {
+            let ahead = facing * (paddle_x - ball.x) >= 0.0;
+            if ball.vel_x * facing > 0.0 && ahead {
+                let time_to_reach = facing * (paddle_x - ball.x) / ball.vel_x.abs();
+                ball.y + ball.vel_y * time_to_reach * params.prediction
+            } else {
+                ball.y
+            }
+        });
+    }
+}
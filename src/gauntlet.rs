@@ -0,0 +1,114 @@
+// Gauntlet: one player against a line of AI opponents, each sharper than
+// the last. Every match is first to `MATCH_POINTS`. Losing a match costs a
+// life and the same opponent is played again; the run ends when the lives
+// run out or the last opponent is beaten.
+//
+// The final score rewards points won, opponents beaten and speed: every
+// beaten opponent adds a bonus that shrinks with the time the match took.
+
+use crate::ai::AiParams;
+use crate::constants::*;
+
+pub const MATCH_POINTS: i32 = 3;
+pub const LIVES: u32 = 3;
+const POINT_SCORE: u32 = 100; // Per point won
+const STAGE_SCORE: u32 = 500; // Per opponent beaten
+const TIME_PAR: f32 = 60.0; // Seconds a match can take before the time bonus is gone
+const TIME_SCORE: f32 = 10.0; // Bonus per second under par
+
+// Opponents in order; the AI gets faster, reads the ball further ahead,
+// aims better and reacts sooner with each one
+pub const OPPONENTS: [&str; 8] = ["Rookie", "Amateur", "Contender", "Veteran", "Expert", "Master", "Champion", "Legend"];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MatchResult {
+    Advanced, // Beat the opponent, on to the next
+    LifeLost, // Lost, play the same opponent again
+    Victory,  // Beat the last opponent
+    Out,      // Lost the last life
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gauntlet {
+    pub stage: usize, // Index into `OPPONENTS`
+    pub lives: u32,
+    pub points: u32,                 // Points won over the whole run
+    pub elapsed: f32,                // Seconds played over the whole run
+    pub match_time: f32,             // Seconds into the current match
+    pub result: Option<MatchResult>, // Of the last match played
+    time_bonus: u32,
+}
+
+impl Gauntlet {
+    pub fn new() -> Self {
+        Self {
+            stage: 0,
+            lives: LIVES,
+            points: 0,
+            elapsed: 0.0,
+            match_time: 0.0,
+            result: None,
+            time_bonus: 0,
+        }
+    }
+
+    pub fn opponent(&self) -> &'static str {
+        OPPONENTS[self.stage.min(OPPONENTS.len() - 1)]
+    }
+
+    // AI settings for the current opponent, from a slow, sloppy start to
+    // better than the hard difficulty level
+    pub fn opponent_params(&self) -> AiParams {
+        let t = self.stage as f32 / (OPPONENTS.len() - 1) as f32;
+        AiParams {
+            speed: AI_SPEED * (0.7 + 0.7 * t),
+            prediction: 0.1 + 0.9 * t,
+            error: AI_ERROR * (3.0 - 2.7 * t),
+            reaction_time: 0.2 * (1.0 - t),
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        matches!(self.result, Some(MatchResult::Victory | MatchResult::Out))
+    }
+
+    // Count match time; call for every frame of play
+    pub fn tick(&mut self, dt: f32) {
+        self.match_time += dt;
+        self.elapsed += dt;
+    }
+
+    // Record a finished match: the player's points and whether they won
+    pub fn finish_match(&mut self, points: i32, won: bool) -> MatchResult {
+        self.points += points.max(0) as u32;
+        let result = if won {
+            self.time_bonus += ((TIME_PAR - self.match_time).max(0.0) * TIME_SCORE) as u32;
+            self.stage += 1;
+            if self.stage >= OPPONENTS.len() {
+                MatchResult::Victory
+            } else {
+                MatchResult::Advanced
+            }
+        } else {
+            self.lives = self.lives.saturating_sub(1);
+            if self.lives == 0 {
+                MatchResult::Out
+            } else {
+                MatchResult::LifeLost
+            }
+        };
+        self.match_time = 0.0;
+        self.result = Some(result);
+        result
+    }
+
+    pub fn score(&self) -> u32 {
+        self.points * POINT_SCORE + self.stage as u32 * STAGE_SCORE + self.time_bonus
+    }
+}
+
+impl Default for Gauntlet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
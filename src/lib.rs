@@ -0,0 +1,29 @@
+pub mod ai;
+pub mod arena;
+pub mod authority;
+pub mod batch;
+pub mod bot;
+pub mod challenge;
+pub mod constants;
+pub mod desync;
+pub mod doubles;
+pub mod gauntlet;
+pub mod lobby;
+pub mod multiball;
+pub mod net;
+pub mod netsim;
+pub mod nn;
+pub mod powerups;
+pub mod practice;
+pub mod quad;
+pub mod replay;
+pub mod rollback;
+pub mod sim;
+pub mod spin;
+pub mod timed;
+pub mod tournament;
+pub mod tune;
+pub mod ws;
+
+#[cfg(feature = "python")]
+mod python;
@@ -0,0 +1,768 @@
+// LAN lobby hosted by the match server. Players browse the server's rooms,
+// take a seat with a display name and side, agree on the rules, chat and
+// mark themselves ready. When everyone in a room is ready the room plays an
+// authoritative match (see `authority`) over the same connections, then
+// reopens for the next one. A player who drops out mid-match can rejoin the
+// game from a new address with its token; seats stay fixed meanwhile.
+//
+// Every lobby message is a full "this is what I want" state that is resent
+// until the server's room update reflects it, so lost datagrams only delay
+// things. Chat lines are numbered per sender and acknowledged the same way.
+
+use std::collections::VecDeque;
+
+use crate::ai::AiProfile;
+use crate::authority::{AuthorityServer, ConnectionId};
+use crate::constants::*;
+use crate::net::{Packet, Transport};
+use crate::rollback::NET_DT;
+use crate::sim::{Match, Side};
+
+pub const MAX_CHAT_LEN: usize = 100; // Bytes per chat message
+pub const MAX_WINNING_SCORE: i32 = 21;
+const CHAT_HISTORY: usize = 8; // Lines repeated in every room update
+const MAX_CHAT_LINE: usize = 192; // Bytes per line, name included, so a full history fits a packet
+const CHAT_LOG: usize = 50; // Lines a client keeps
+const ROOM_UPDATE_INTERVAL: u32 = 12; // Server ticks between room updates
+const MEMBER_TIMEOUT: f32 = 10.0; // Seconds of silence before a seat is freed
+const RESULT_SECONDS: f32 = 3.0; // Final state broadcast before the room reopens
+const SEAT_INTERVAL: f64 = 0.2;
+const LIST_INTERVAL: f64 = 1.0;
+
+// Match settings the players in a room agree on
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rules {
+    pub winning_score: i32,
+    pub ai_fill: Option<String>, // Difficulty profile playing an empty side, or wait for a second player
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            winning_score: WINNING_SCORE,
+            ai_fill: None,
+        }
+    }
+}
+
+impl Rules {
+    pub fn describe(&self) -> String {
+        let fill = match &self.ai_fill {
+            Some(difficulty) => format!("AI ({}) fills an empty side", difficulty),
+            None => "no AI fill-in".to_string(),
+        };
+        format!("first to {}, {}", self.winning_score, fill)
+    }
+}
+
+// One line of the server's room list
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoomSummary {
+    pub name: String,
+    pub players: Vec<String>,
+    pub playing: bool,
+}
+
+// A seated player as shown to everyone in the room
+#[derive(Clone, Debug, PartialEq)]
+pub struct Member {
+    pub name: String,
+    pub side: Side,
+    pub ready: bool,
+}
+
+// What happened on the server, for its log
+#[derive(Clone, Debug, PartialEq)]
+pub enum LobbyEvent {
+    Entered { room: usize, name: String },
+    Left { room: usize, name: String },
+    Started { room: usize },
+    Finished { room: usize, winner: String, score: (i32, i32) },
+    Abandoned { room: usize }, // Both players left mid-match
+}
+
+struct Occupant {
+    connection: ConnectionId,
+    name: String,
+    side: Side,
+    wants: Option<Side>, // Side this player picked, kept when the other asks for it
+    ready_for: u32,      // Room version this player is ready for, 0 = not ready
+    chat_received: u32,  // Number of this player's chat messages taken
+    quick: bool,         // Joined straight into a match (`--server`), always ready
+    last_seen: u32,
+}
+
+struct Room {
+    name: String,
+    members: Vec<Occupant>,
+    rules: Rules,
+    version: u32, // Bumped whenever rules, sides or members change; readiness refers to it
+    chat: VecDeque<(u32, String)>,
+    chat_count: u32,
+    game: Option<AuthorityServer>,
+    finished_ticks: u32,
+}
+
+impl Room {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            members: Vec::new(),
+            rules: Rules::default(),
+            version: 1,
+            chat: VecDeque::new(),
+            chat_count: 0,
+            game: None,
+            finished_ticks: 0,
+        }
+    }
+
+    fn post(&mut self, line: String) {
+        self.chat_count += 1;
+        self.chat.push_back((self.chat_count, clip(&line, MAX_CHAT_LINE).to_string()));
+        if self.chat.len() > CHAT_HISTORY {
+            self.chat.pop_front();
+        }
+    }
+
+    fn member(&self, connection: ConnectionId) -> Option<usize> {
+        self.members.iter().position(|member| member.connection == connection)
+    }
+
+    // Move a member to the side they asked for, swapping with the other
+    // player unless that player picked their side themselves
+    fn pick_side(&mut self, index: usize, side: Side) {
+        self.members[index].wants = Some(side);
+        if self.members[index].side == side {
+            return;
+        }
+        match self.members.iter().position(|member| member.side == side) {
+            Some(other) if self.members[other].wants == Some(side) => return,
+            Some(other) => self.members[other].side = side.opponent(),
+            None => {}
+        }
+        self.members[index].side = side;
+        self.version += 1;
+    }
+
+    fn ready_to_start(&self) -> bool {
+        let all_ready = self
+            .members
+            .iter()
+            .all(|member| member.quick || member.ready_for == self.version);
+        self.game.is_none() && !self.members.is_empty() && all_ready && (self.members.len() == 2 || self.rules.ai_fill.is_some())
+    }
+}
+
+// The lobby side of the match server. Transport-agnostic like
+// `AuthorityServer`: route datagrams in with `receive`, out with `take_outgoing`.
+pub struct LobbyServer {
+    rooms: Vec<Room>,
+    profiles: Vec<AiProfile>,
+    tick: u32,
+    watching: Vec<(ConnectionId, usize)>, // Spectators and the room they watch
+    outgoing: Vec<(ConnectionId, Vec<u8>)>,
+    events: Vec<LobbyEvent>,
+}
+
+impl LobbyServer {
+    // `profiles` are the difficulties an AI fill-in can be asked for
+    pub fn new(rooms: usize, profiles: Vec<AiProfile>) -> Self {
+        Self {
+            rooms: (1..=rooms.clamp(1, 255)).map(|i| Room::new(format!("Table {}", i))).collect(),
+            profiles,
+            tick: 0,
+            watching: Vec::new(),
+            outgoing: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn room_name(&self, room: usize) -> &str {
+        &self.rooms[room].name
+    }
+
+    // The match being played in `room`, if any
+    pub fn game(&self, room: usize) -> Option<&Match> {
+        self.rooms[room].game.as_ref().map(AuthorityServer::state)
+    }
+
+    // Whether `connection` still has a seat, a match or a match to watch.
+    // Once it hasn't, whoever owns the sockets may reuse its id.
+    pub fn knows(&self, connection: ConnectionId) -> bool {
+        self.find(connection).is_some()
+            || self.rooms.iter().any(|r| r.game.as_ref().is_some_and(|game| game.knows(connection)))
+    }
+
+    fn find(&self, connection: ConnectionId) -> Option<(usize, usize)> {
+        self.rooms
+            .iter()
+            .enumerate()
+            .find_map(|(room, r)| r.member(connection).map(|member| (room, member)))
+    }
+
+    pub fn receive(&mut self, connection: ConnectionId, data: &[u8]) {
+        let Some(packet) = Packet::decode(data) else { return };
+        let seated = self.find(connection);
+        if let Some((room, member)) = seated {
+            self.rooms[room].members[member].last_seen = self.tick;
+        }
+
+        match packet {
+            Packet::ListRooms => {
+                let rooms = self
+                    .rooms
+                    .iter()
+                    .map(|room| RoomSummary {
+                        name: room.name.clone(),
+                        players: room.members.iter().map(|member| member.name.clone()).collect(),
+                        playing: room.game.is_some(),
+                    })
+                    .collect();
+                self.outgoing.push((connection, Packet::Rooms { rooms }.encode()));
+            }
+            Packet::Seat {
+                room,
+                name,
+                side,
+                ready_for,
+                rules_base,
+                rules,
+                chat_seq,
+                chat,
+            } => {
+                let room = room as usize;
+                if room >= self.rooms.len() {
+                    return;
+                }
+                // Still in the lobby screen while the others started: that's not gone
+                if let Some((playing, _)) = seated.filter(|&(playing, _)| self.rooms[playing].game.is_some()) {
+                    if let Some(game) = self.rooms[playing].game.as_mut() {
+                        game.receive(connection, data);
+                    }
+                }
+                if let Some((other, _)) = seated.filter(|&(other, _)| other != room) {
+                    self.leave(other, connection);
+                }
+                self.take_seat(room, connection, &name, side);
+                let r = &mut self.rooms[room];
+                if let Some(index) = r.member(connection).filter(|_| r.game.is_none()) {
+                    if rules_base == r.version && rules != r.rules {
+                        r.rules = Rules {
+                            winning_score: rules.winning_score.clamp(1, MAX_WINNING_SCORE),
+                            ai_fill: rules.ai_fill.filter(|name| self.profiles.iter().any(|p| &p.name == name)),
+                        };
+                        r.version += 1;
+                        let line = format!("* {} set the rules: {}", r.members[index].name, r.rules.describe());
+                        r.post(line);
+                    }
+                    let member = &mut r.members[index];
+                    member.ready_for = ready_for;
+                    let chat = clean_chat(&chat);
+                    if chat_seq == member.chat_received + 1 && !chat.is_empty() {
+                        member.chat_received = chat_seq;
+                        let line = format!("{}: {}", member.name, chat);
+                        r.post(line);
+                    }
+                }
+                // Answer right away so acknowledgements don't wait for the next update
+                let packet = self.room_packet(room, connection);
+                self.outgoing.push((connection, packet));
+            }
+            Packet::Leave => {
+                if let Some((room, _)) = seated.filter(|&(room, _)| self.rooms[room].game.is_none()) {
+                    self.leave(room, connection);
+                }
+            }
+            Packet::Join { name, token } => match seated {
+                Some((room, _)) => {
+                    if let Some(game) = self.rooms[room].game.as_mut() {
+                        game.receive(connection, data);
+                    }
+                }
+                // Back from a new address: move the seat over and let the game take it back
+                None if token != 0 => {
+                    for r in self.rooms.iter_mut() {
+                        let Some(game) = r.game.as_mut() else { continue };
+                        let Some(side) = game.token_side(token) else { continue };
+                        if let Some(member) = r.members.iter_mut().find(|member| member.side == side) {
+                            member.connection = connection;
+                            member.last_seen = self.tick;
+                        }
+                        game.receive(connection, data);
+                    }
+                }
+                // Straight from `--server`: take the first free seat and play by the room's rules
+                None => {
+                    let free = self
+                        .rooms
+                        .iter()
+                        .position(|room| room.game.is_none() && room.members.len() < 2);
+                    if let Some(room) = free {
+                        self.take_seat(room, connection, &name, None);
+                        if let Some(member) = self.rooms[room].member(connection) {
+                            self.rooms[room].members[member].quick = true;
+                        }
+                    }
+                }
+            },
+            Packet::Watch => {
+                let watched = self
+                    .watching
+                    .iter()
+                    .find(|&&(watcher, _)| watcher == connection)
+                    .map(|&(_, room)| room)
+                    .filter(|&room| self.rooms[room].game.is_some());
+                let Some(room) = watched.or_else(|| self.rooms.iter().position(|room| room.game.is_some())) else {
+                    return; // Nothing to watch yet; the spectator keeps asking
+                };
+                self.watching.retain(|&(watcher, _)| watcher != connection);
+                self.watching.push((connection, room));
+                if let Some(game) = self.rooms[room].game.as_mut() {
+                    game.receive(connection, data);
+                }
+            }
+            _ => {
+                // Match traffic
+                if let Some((room, _)) = seated {
+                    if let Some(game) = self.rooms[room].game.as_mut() {
+                        game.receive(connection, data);
+                    }
+                }
+            }
+        }
+    }
+
+    // Seat `connection` in `room` if it isn't yet and there is space, and
+    // move it to the `side` it asks for when that side is free
+    fn take_seat(&mut self, room: usize, connection: ConnectionId, name: &str, side: Option<Side>) {
+        let tick = self.tick;
+        let r = &mut self.rooms[room];
+        let name = clean_name(name);
+        match r.member(connection) {
+            Some(index) => {
+                if r.game.is_some() {
+                    return; // Seats are fixed during a match
+                }
+                if r.members[index].name != name {
+                    let line = format!("* {} is now {}", r.members[index].name, name);
+                    r.members[index].name = name;
+                    r.post(line);
+                }
+                if let Some(side) = side {
+                    r.pick_side(index, side);
+                }
+            }
+            None if r.game.is_none() && r.members.len() < 2 => {
+                let free = match r.members.first() {
+                    Some(other) => other.side.opponent(),
+                    None => Side::Left,
+                };
+                r.post(format!("* {} joined", name));
+                self.events.push(LobbyEvent::Entered {
+                    room,
+                    name: name.clone(),
+                });
+                r.members.push(Occupant {
+                    connection,
+                    name,
+                    side: free,
+                    wants: None,
+                    ready_for: 0,
+                    chat_received: 0,
+                    quick: false,
+                    last_seen: tick,
+                });
+                r.version += 1;
+                if let Some(side) = side {
+                    r.pick_side(r.members.len() - 1, side);
+                }
+            }
+            None => {} // Full or playing; the room update tells the client
+        }
+    }
+
+    fn leave(&mut self, room: usize, connection: ConnectionId) {
+        let r = &mut self.rooms[room];
+        let Some(index) = r.member(connection) else { return };
+        let member = r.members.remove(index);
+        r.version += 1;
+        r.post(format!("* {} left", member.name));
+        self.events.push(LobbyEvent::Left { room, name: member.name });
+    }
+
+    // Advance running matches by one fixed step, start rooms where everyone
+    // is ready and send room updates
+    pub fn tick(&mut self) {
+        self.tick += 1;
+        // Spectators the match has stopped hearing from
+        let rooms = &self.rooms;
+        self.watching
+            .retain(|&(watcher, room)| rooms[room].game.as_ref().is_some_and(|game| game.knows(watcher)));
+        let timeout = (MEMBER_TIMEOUT / NET_DT) as u32;
+        for room in 0..self.rooms.len() {
+            if self.rooms[room].game.is_none() {
+                let tick = self.tick;
+                let gone: Vec<ConnectionId> = self.rooms[room]
+                    .members
+                    .iter()
+                    .filter(|member| tick - member.last_seen > timeout)
+                    .map(|member| member.connection)
+                    .collect();
+                for connection in gone {
+                    self.leave(room, connection);
+                }
+            }
+            if self.rooms[room].ready_to_start() {
+                self.start(room);
+            }
+
+            let r = &mut self.rooms[room];
+            if let Some(game) = r.game.as_mut() {
+                let events = game.tick();
+                self.outgoing.extend(game.take_outgoing());
+                if let Some(winner) = events.winner {
+                    let state = game.state();
+                    self.events.push(LobbyEvent::Finished {
+                        room,
+                        winner: game.player_name(winner).unwrap_or_default().to_string(),
+                        score: (state.left_score, state.right_score),
+                    });
+                }
+                if game.is_over() {
+                    if r.finished_ticks == 0 && game.state().winner().is_none() {
+                        self.events.push(LobbyEvent::Abandoned { room });
+                    }
+                    r.finished_ticks += 1;
+                }
+                if r.finished_ticks as f32 * NET_DT >= RESULT_SECONDS {
+                    // Reopen the room; quick joiners have had their match
+                    r.game = None;
+                    r.finished_ticks = 0;
+                    r.members.retain(|member| !member.quick);
+                    r.version += 1;
+                    self.watching.retain(|&(_, watched)| watched != room);
+                }
+            }
+
+            if self.tick.is_multiple_of(ROOM_UPDATE_INTERVAL) {
+                for member in 0..self.rooms[room].members.len() {
+                    let connection = self.rooms[room].members[member].connection;
+                    let packet = self.room_packet(room, connection);
+                    self.outgoing.push((connection, packet));
+                }
+            }
+        }
+    }
+
+    fn start(&mut self, room: usize) {
+        let r = &mut self.rooms[room];
+        let mut game = AuthorityServer::new().with_winning_score(r.rules.winning_score);
+        for member in &r.members {
+            game.seat(member.side, member.connection, &member.name);
+        }
+        if let (Some(difficulty), [member]) = (&r.rules.ai_fill, r.members.as_slice()) {
+            let profile = self.profiles.iter().find(|profile| &profile.name == difficulty);
+            let params = profile.map(|profile| profile.params).unwrap_or_default();
+            game.fill_with_ai(member.side.opponent(), &format!("AI ({})", difficulty), params);
+        }
+        r.game = Some(game);
+        r.post(format!("* Match started, {}", r.rules.describe()));
+        self.events.push(LobbyEvent::Started { room });
+    }
+
+    // The room as `connection` sees it
+    fn room_packet(&self, room: usize, connection: ConnectionId) -> Vec<u8> {
+        let r = &self.rooms[room];
+        let you = r.member(connection);
+        Packet::Room {
+            room: room as u8,
+            you: you.map(|you| you as u8),
+            members: r
+                .members
+                .iter()
+                .map(|member| Member {
+                    name: member.name.clone(),
+                    side: member.side,
+                    ready: member.quick || member.ready_for == r.version,
+                })
+                .collect(),
+            version: r.version,
+            rules: r.rules.clone(),
+            playing: r.game.is_some(),
+            chat_ack: you.map_or(0, |you| r.members[you].chat_received),
+            chat: r.chat.iter().cloned().collect(),
+        }
+        .encode()
+    }
+
+    pub fn take_outgoing(&mut self) -> Vec<(ConnectionId, Vec<u8>)> {
+        std::mem::take(&mut self.outgoing)
+    }
+
+    pub fn take_events(&mut self) -> Vec<LobbyEvent> {
+        std::mem::take(&mut self.events)
+    }
+}
+
+fn clean_name(name: &str) -> String {
+    let name: String = name.chars().filter(|c| !c.is_control()).take(16).collect();
+    match name.trim() {
+        "" => "Player".to_string(),
+        name => name.to_string(),
+    }
+}
+
+// Chat text without control characters, cut to `MAX_CHAT_LEN` bytes
+fn clean_chat(text: &str) -> String {
+    let text: String = text.chars().filter(|c| !c.is_control()).collect();
+    clip(text.trim(), MAX_CHAT_LEN).trim_end().to_string()
+}
+
+// The longest prefix of `text` within `bytes` that ends on a character boundary
+fn clip(text: &str, bytes: usize) -> &str {
+    let mut end = text.len().min(bytes);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+// The room a `LobbyClient` sits in, as last reported by the server
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoomView {
+    pub index: u8,
+    pub members: Vec<Member>,
+    pub you: Option<usize>, // Our entry in `members`; None when the room was full
+    pub version: u32,
+    pub rules: Rules,
+    pub playing: bool,
+}
+
+// A player in the lobby: browses rooms, then sits in one until its match starts
+pub struct LobbyClient<T: Transport> {
+    transport: T,
+    name: String,
+    rooms: Vec<RoomSummary>,
+    room: Option<u8>, // Room we want to sit in
+    view: Option<RoomView>,
+    waited: bool,       // Saw the room between matches, so `playing` means ours started
+    side: Option<Side>, // Side we asked for
+    ready: bool,
+    rules: Rules, // Our proposal, the room's rules unless we just edited them
+    rules_base: u32,
+    chat: Vec<String>,
+    chat_seen: u32,             // Id of the newest line we have
+    chat_outbox: VecDeque<String>,
+    chat_acked: u32,            // Our messages the server has taken
+    notice: Option<String>,
+    last_send: f64,
+}
+
+impl<T: Transport> LobbyClient<T> {
+    pub fn new(transport: T, name: &str) -> Self {
+        Self {
+            transport,
+            name: name.to_string(),
+            rooms: Vec::new(),
+            room: None,
+            view: None,
+            waited: false,
+            side: None,
+            ready: false,
+            rules: Rules::default(),
+            rules_base: 0,
+            chat: Vec::new(),
+            chat_seen: 0,
+            chat_outbox: VecDeque::new(),
+            chat_acked: 0,
+            notice: None,
+            last_send: f64::NEG_INFINITY,
+        }
+    }
+
+    // Rooms on the server, refreshed while we aren't in one
+    pub fn rooms(&self) -> &[RoomSummary] {
+        &self.rooms
+    }
+
+    pub fn room(&self) -> Option<&RoomView> {
+        self.view.as_ref().filter(|view| view.you.is_some())
+    }
+
+    // Set when the server turned us away, e.g. the room was full
+    pub fn notice(&self) -> Option<&str> {
+        self.notice.as_deref()
+    }
+
+    pub fn chat(&self) -> &[String] {
+        &self.chat
+    }
+
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.ready
+    }
+
+    // True once our room's match is on; hand the transport to a `ServerClient`
+    pub fn match_started(&self) -> bool {
+        self.waited && self.room().is_some_and(|view| view.playing)
+    }
+
+    pub fn into_transport(self) -> T {
+        self.transport
+    }
+
+    pub fn enter(&mut self, room: u8) {
+        self.room = Some(room);
+        self.view = None;
+        self.waited = false;
+        self.side = None;
+        self.ready = false;
+        self.chat.clear();
+        self.chat_seen = 0;
+        self.chat_outbox.clear();
+        self.notice = None;
+        self.last_send = f64::NEG_INFINITY;
+    }
+
+    pub fn leave(&mut self) {
+        if self.room.take().is_some() {
+            // If this gets lost the server frees the seat once we go quiet
+            self.transport.send(&Packet::Leave.encode());
+        }
+        self.view = None;
+        self.last_send = f64::NEG_INFINITY;
+    }
+
+    pub fn set_side(&mut self, side: Side) {
+        self.side = Some(side);
+        self.last_send = f64::NEG_INFINITY;
+    }
+
+    // Propose new rules; everyone's ready flag resets when they change
+    pub fn set_rules(&mut self, rules: Rules) {
+        if let Some(view) = &self.view {
+            self.rules = rules;
+            self.rules_base = view.version;
+            self.last_send = f64::NEG_INFINITY;
+        }
+    }
+
+    pub fn set_ready(&mut self, ready: bool) {
+        self.ready = ready;
+        self.last_send = f64::NEG_INFINITY;
+    }
+
+    pub fn say(&mut self, text: &str) {
+        let text = clean_chat(text);
+        if !text.is_empty() {
+            self.chat_outbox.push_back(text);
+            self.last_send = f64::NEG_INFINITY;
+        }
+    }
+
+    // Send our seat (or ask for the room list) and take in updates; call every frame
+    pub fn poll(&mut self, now: f64) {
+        while let Some(data) = self.transport.recv() {
+            match Packet::decode(&data) {
+                Some(Packet::Rooms { rooms }) => self.rooms = rooms,
+                Some(Packet::Room {
+                    room,
+                    you,
+                    members,
+                    version,
+                    rules,
+                    playing,
+                    chat_ack,
+                    chat,
+                }) if Some(room) == self.room => self.update(
+                    RoomView {
+                        index: room,
+                        members,
+                        you: you.map(usize::from),
+                        version,
+                        rules,
+                        playing,
+                    },
+                    chat_ack,
+                    chat,
+                ),
+                _ => {}
+            }
+        }
+
+        match self.room {
+            Some(room) if now - self.last_send >= SEAT_INTERVAL => {
+                let version = self.view.as_ref().map_or(0, |view| view.version);
+                // Chat waits for the first update, which tells us what the server already has
+                let chat = self.chat_outbox.front().filter(|_| self.view.is_some());
+                let seat = Packet::Seat {
+                    room,
+                    name: self.name.clone(),
+                    side: self.side,
+                    ready_for: if self.ready { version } else { 0 },
+                    rules_base: self.rules_base,
+                    rules: self.rules.clone(),
+                    chat_seq: self.chat_acked + 1,
+                    chat: chat.cloned().unwrap_or_default(),
+                };
+                self.transport.send(&seat.encode());
+                self.last_send = now;
+            }
+            None if now - self.last_send >= LIST_INTERVAL => {
+                self.transport.send(&Packet::ListRooms.encode());
+                self.last_send = now;
+            }
+            _ => {}
+        }
+    }
+
+    fn update(&mut self, view: RoomView, chat_ack: u32, chat: Vec<(u32, String)>) {
+        if view.you.is_none() {
+            self.notice = Some(if view.playing {
+                "That room is playing a match".to_string()
+            } else {
+                "That room is full".to_string()
+            });
+            self.room = None;
+            self.view = None;
+            return;
+        }
+
+        let first = self.view.is_none();
+        if first || view.version != self.rules_base {
+            // Someone changed the room (or our proposal was applied): start from its rules
+            self.rules = view.rules.clone();
+            self.rules_base = view.version;
+        }
+        let changed = self.view.as_ref().is_some_and(|old| old.version != view.version);
+        if changed && !view.playing {
+            self.ready = false; // Readiness was for the old setup
+        }
+
+        if first {
+            self.chat_acked = chat_ack;
+        }
+        while chat_ack > self.chat_acked && !self.chat_outbox.is_empty() {
+            self.chat_outbox.pop_front();
+            self.chat_acked += 1;
+            self.last_send = f64::NEG_INFINITY; // Next message right away
+        }
+        self.chat_acked = self.chat_acked.max(chat_ack);
+        self.waited |= !view.playing;
+        for (id, line) in chat {
+            if id > self.chat_seen {
+                self.chat.push(line);
+                self.chat_seen = id;
+            }
+        }
+        if self.chat.len() > CHAT_LOG {
+            self.chat.drain(..self.chat.len() - CHAT_LOG);
+        }
+        self.view = Some(view);
+    }
+}
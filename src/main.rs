@@ -1,22 +1,317 @@
 use macroquad::prelude::*;
 use macroquad::rand::gen_range;
 
-mod constants;
-use constants::*;
+use std::time::Duration;
+
+use pong_rs::bot::BotProcess;
+use pong_rs::challenge::{Challenge, ChallengeProgress, ChallengeRun, Metric, Opponent, Outcome, MAX_STARS};
+use pong_rs::ai::{self, AiController, AiProfile};
+use pong_rs::arena::{Arena, ArenaEvents, ArenaState, Obstacle, DEFLECTOR_THICKNESS, MIN_GOAL_SIZE};
+use pong_rs::authority::{ServerClient, Spectator};
+use pong_rs::constants::*;
+use pong_rs::desync::DesyncReport;
+use pong_rs::doubles::{DoublesAi, DoublesMatch, Seat};
+use pong_rs::gauntlet::{self, Gauntlet, MatchResult, OPPONENTS};
+use pong_rs::lobby::{LobbyClient, MAX_CHAT_LEN, MAX_WINNING_SCORE};
+use pong_rs::multiball::{BallRule, MultiBallAi, MultiBallMatch};
+use pong_rs::nn::Mlp;
+use pong_rs::powerups::{self, PowerUpEvents, PowerUps};
+use pong_rs::practice::{PersonalBests, Practice, WallKind, WALL_WIDTH};
+use pong_rs::quad::{QuadAi, QuadMatch, Wall, FIELD_SIZE};
+use pong_rs::net::{Transport, UdpTransport};
+use pong_rs::netsim::{NetConditions, NetSimulator};
+use pong_rs::replay::Replay;
+use pong_rs::rollback::{RollbackSession, NET_DT};
+use pong_rs::sim::{Intent, Match, Side, StepEvents};
+use pong_rs::spin::{Spin, SpinEvents};
+use pong_rs::timed::{ClockEvents, MatchClock, MatchRule};
+use pong_rs::tournament::{Bracket, Controller, Entrant, Format, Tournament, MAX_ENTRANTS, MIN_ENTRANTS};
+use pong_rs::ws::{RelayStatus, WsTransport};
 
 #[derive(Clone, Copy, PartialEq)]
 enum GameState {
     Menu,
     ModeSelect,
+    NetSetup,
+    Lobby,
     Playing,
     Paused,
     GameOver,
+    FourPlayer,
+    Doubles,
+    MultiBall,
+    Editor,
+    Practice,
+    TournamentSetup,
+    Bracket,
+    Gauntlet,
+    ChallengeSelect,
+    Challenge,
+}
+
+impl GameState {
+    fn handles_escape(self) -> bool {
+        matches!(
+            self,
+            GameState::ModeSelect
+                | GameState::NetSetup
+                | GameState::Lobby
+                | GameState::Editor
+                | GameState::TournamentSetup
+                | GameState::ChallengeSelect
+        )
+    }
 }
 
 #[derive(Clone, Copy, PartialEq)]
 enum GameMode {
     TwoPlayer,
     VsAI,
+    VsBot,
+    Online,
+    Lobby,
+    FourPlayer,
+    Doubles,
+    MultiBall,
+    Practice,
+    Tournament,
+    Gauntlet,
+    Challenges,
+}
+
+impl GameMode {
+    // Order shown on the mode select screen
+    const ALL: [GameMode; 12] = [
+        GameMode::TwoPlayer,
+        GameMode::VsAI,
+        GameMode::VsBot,
+        GameMode::Online,
+        GameMode::Lobby,
+        GameMode::FourPlayer,
+        GameMode::Doubles,
+        GameMode::MultiBall,
+        GameMode::Practice,
+        GameMode::Tournament,
+        GameMode::Gauntlet,
+        GameMode::Challenges,
+    ];
+    
+    fn label(self) -> &'static str {
+        match self {
+            GameMode::TwoPlayer => "Two Player",
+            GameMode::VsAI => "vs AI",
+            GameMode::VsBot => "vs External Bot",
+            GameMode::Online => "Online",
+            GameMode::Lobby => "LAN Lobby",
+            GameMode::FourPlayer => "Four Player",
+            GameMode::Doubles => "2v2 Doubles",
+            GameMode::MultiBall => "Multi-Ball",
+            GameMode::Practice => "Wall Practice",
+            GameMode::Tournament => "Tournament",
+            GameMode::Gauntlet => "Gauntlet",
+            GameMode::Challenges => "Challenges",
+        }
+    }
+    
+    // Played over the network, so there is no pausing or local restart
+    fn is_online(self) -> bool {
+        matches!(self, GameMode::Online | GameMode::Lobby)
+    }
+    
+    // Local two-paddle matches, which can be played with power-ups and arenas
+    fn has_match_options(self) -> bool {
+        matches!(self, GameMode::TwoPlayer | GameMode::VsAI | GameMode::VsBot | GameMode::Tournament)
+    }
+}
+
+// Ways to start an online match on the setup screen
+#[derive(Clone, Copy, PartialEq)]
+enum NetChoice {
+    Host,
+    Join,
+    Room,
+    Server,
+    Watch,
+}
+
+impl NetChoice {
+    fn next(self) -> Self {
+        match self {
+            NetChoice::Host => NetChoice::Join,
+            NetChoice::Join => NetChoice::Room,
+            NetChoice::Room => NetChoice::Server,
+            NetChoice::Server => NetChoice::Watch,
+            NetChoice::Watch => NetChoice::Host,
+        }
+    }
+    
+    fn previous(self) -> Self {
+        self.next().next().next().next()
+    }
+}
+
+// An online match: peer-to-peer with rollback, against an authoritative
+// server, or watching a server match
+enum NetSession {
+    Rollback(Box<RollbackSession<Box<dyn Transport>>>),
+    Server(ServerClient<Box<dyn Transport>>),
+    Spectate(Spectator<Box<dyn Transport>>),
+}
+
+impl NetSession {
+    fn side(&self) -> Option<Side> {
+        match self {
+            NetSession::Rollback(session) => Some(session.side()),
+            NetSession::Server(client) => client.side(),
+            NetSession::Spectate(_) => None,
+        }
+    }
+    
+    fn ping_ms(&self) -> Option<f32> {
+        match self {
+            NetSession::Rollback(session) => session.ping_ms(),
+            NetSession::Server(client) => client.ping_ms(),
+            NetSession::Spectate(_) => None,
+        }
+    }
+    
+    fn is_running(&self) -> bool {
+        match self {
+            NetSession::Rollback(session) => session.is_running(),
+            NetSession::Server(client) => client.is_running(),
+            NetSession::Spectate(spectator) => spectator.is_running(),
+        }
+    }
+    
+    fn desync(&self) -> Option<&DesyncReport> {
+        match self {
+            NetSession::Rollback(session) => session.desync(),
+            NetSession::Server(_) | NetSession::Spectate(_) => None, // The server's state is the truth
+        }
+    }
+    
+    fn poll(&mut self, now: f64) {
+        match self {
+            NetSession::Rollback(session) => session.poll(now),
+            NetSession::Server(client) => client.poll(now),
+            NetSession::Spectate(spectator) => spectator.poll(now),
+        }
+    }
+    
+    // The side that went quiet and the seconds left before it forfeits, or
+    // before a peer-to-peer match is called off
+    fn pause(&self, now: f64) -> Option<(Side, f32)> {
+        match self {
+            NetSession::Rollback(session) => {
+                // No referee between peers: we only know the other side went quiet
+                let silence = session.silence(now) as f32;
+                let over = session.confirmed_state().winner().is_some();
+                (session.is_running() && !over && silence > NET_DISCONNECT_TIMEOUT).then(|| {
+                    let remaining = NET_RECONNECT_TIMEOUT - (silence - NET_DISCONNECT_TIMEOUT);
+                    (session.side().opponent(), remaining.max(0.0))
+                })
+            }
+            NetSession::Server(client) => client.pause(now),
+            NetSession::Spectate(_) => None, // The delayed stream simply freezes
+        }
+    }
+    
+    // The side that lost by not coming back, as decided by the server
+    fn forfeited(&self) -> Option<Side> {
+        match self {
+            NetSession::Server(client) => client.forfeited(),
+            NetSession::Rollback(_) | NetSession::Spectate(_) => None,
+        }
+    }
+    
+    // One fixed step of local input
+    fn advance(&mut self, intent: Intent, now: f64) -> StepEvents {
+        match self {
+            NetSession::Rollback(session) => session.advance(intent, now).unwrap_or_default(),
+            NetSession::Server(client) => {
+                client.advance(intent, now);
+                StepEvents::default()
+            }
+            NetSession::Spectate(_) => StepEvents::default(), // Read-only
+        }
+    }
+    
+    // The state to draw, with the events of the steps since the last call
+    fn view(&mut self, now: f64, mut events: StepEvents) -> (Match, StepEvents) {
+        match self {
+            NetSession::Rollback(session) => {
+                // Only end the match once both peers agree on the result
+                events.winner = session.confirmed_state().winner();
+                if events.winner.is_some() {
+                    (*session.confirmed_state(), events)
+                } else {
+                    (*session.state(), events)
+                }
+            }
+            NetSession::Server(client) => client.view(now),
+            NetSession::Spectate(spectator) => spectator.view(now),
+        }
+    }
+}
+
+// What the arena editor places with the mouse
+#[derive(Clone, Copy, PartialEq)]
+enum EditorTool {
+    Block,
+    Brick,
+    Bumper,
+    Deflector,
+    Spawn,
+    Goal,
+}
+
+impl EditorTool {
+    const ALL: [EditorTool; 6] = [
+        EditorTool::Block,
+        EditorTool::Brick,
+        EditorTool::Bumper,
+        EditorTool::Deflector,
+        EditorTool::Spawn,
+        EditorTool::Goal,
+    ];
+    
+    fn label(self) -> &'static str {
+        match self {
+            EditorTool::Block => "Wall",
+            EditorTool::Brick => "Brick",
+            EditorTool::Bumper => "Bumper",
+            EditorTool::Deflector => "Deflector",
+            EditorTool::Spawn => "Spawn",
+            EditorTool::Goal => "Goal",
+        }
+    }
+}
+
+// Round to the editor grid
+fn snap(value: f32) -> f32 {
+    (value / EDITOR_GRID).round() * EDITOR_GRID
+}
+
+// The obstacle a drag from `from` to `to` places with `tool`, if it's big
+// enough to keep
+fn editor_shape(tool: EditorTool, from: (f32, f32), to: (f32, f32), hits: u32) -> Option<Obstacle> {
+    let (x, y) = (from.0.min(to.0), from.1.min(to.1));
+    let (w, h) = ((to.0 - from.0).abs(), (to.1 - from.1).abs());
+    let length = w.hypot(h);
+    match tool {
+        EditorTool::Block if w >= EDITOR_GRID && h >= EDITOR_GRID => Some(Obstacle::Block { x, y, w, h }),
+        EditorTool::Brick if w >= EDITOR_GRID && h >= EDITOR_GRID => Some(Obstacle::Brick { x, y, w, h, hits }),
+        EditorTool::Bumper if length >= EDITOR_GRID => Some(Obstacle::Bumper { x: from.0, y: from.1, radius: length }),
+        EditorTool::Deflector if length >= EDITOR_GRID * 2.0 => Some(Obstacle::Deflector { x1: from.0, y1: from.1, x2: to.0, y2: to.1 }),
+        _ => None,
+    }
+}
+
+// Which controller plays the right paddle in GameMode::VsAI
+#[derive(Clone, Copy, PartialEq)]
+enum AiOpponent {
+    Classic,
+    Neural,
 }
 
 #[derive(Clone)]
@@ -28,6 +323,7 @@ struct Particle {
     life: f32,
     max_life: f32,
     size: f32,
+    color: Color,
 }
 
 impl Particle {
@@ -40,9 +336,14 @@ impl Particle {
             life,
             max_life: life,
             size,
+            color: WHITE,
         }
     }
     
+    fn with_color(self, color: Color) -> Self {
+        Self { color, ..self }
+    }
+    
     fn update(&mut self, dt: f32) -> bool {
         self.x += self.vel_x * dt;
         self.y += self.vel_y * dt;
@@ -56,38 +357,492 @@ impl Particle {
     
     fn draw(&self) {
         let alpha = self.life / self.max_life;
-        let color = Color::new(1.0, 1.0, 1.0, alpha);
+        let color = Color::new(self.color.r, self.color.g, self.color.b, alpha);
         draw_circle(self.x, self.y, self.size * alpha, color);
     }
 }
 
-#[macroquad::main("Pong")]
-async fn main() {
-    request_new_screen_size(SCREEN_WIDTH, SCREEN_HEIGHT);
+// Pickup and HUD color of each power-up
+fn powerup_color(kind: powerups::Kind) -> Color {
+    match kind {
+        powerups::Kind::BigPaddle => GREEN,
+        powerups::Kind::SmallPaddle => RED,
+        powerups::Kind::FastBall => ORANGE,
+        powerups::Kind::SlowBall => SKYBLUE,
+        powerups::Kind::Reverse => MAGENTA,
+        powerups::Kind::Shield => GOLD,
+        powerups::Kind::Sticky => LIME,
+    }
+}
+
+// A burst of `color` particles in all directions
+fn burst(particles: &mut Vec<Particle>, x: f32, y: f32, count: usize, color: Color) {
+    for _ in 0..count {
+        let angle = gen_range(0.0, std::f32::consts::TAU);
+        let speed = gen_range(60.0, 220.0);
+        particles.push(
+            Particle::new(x, y, angle.cos() * speed, angle.sin() * speed, gen_range(0.4, 0.9), gen_range(2.0, 5.0))
+                .with_color(color),
+        );
+    }
+}
+
+// Arena obstacles and the walls beside narrowed goals, offset by the
+// screen shake
+fn draw_arena(arena: &Arena, shake_x: f32, shake_y: f32) {
+    for (side, x) in [(Side::Left, 0.0), (Side::Right, SCREEN_WIDTH - 6.0)] {
+        let (top, bottom) = arena.goal(side);
+        draw_rectangle(x + shake_x, shake_y, 6.0, top, GRAY);
+        draw_rectangle(x + shake_x, bottom + shake_y, 6.0, SCREEN_HEIGHT - bottom, GRAY);
+    }
+    for obstacle in &arena.obstacles {
+        draw_obstacle(obstacle, shake_x, shake_y);
+    }
+}
+
+fn draw_obstacle(obstacle: &Obstacle, shake_x: f32, shake_y: f32) {
+    match *obstacle {
+        Obstacle::Block { x, y, w, h } => draw_rectangle(x + shake_x, y + shake_y, w, h, GRAY),
+        Obstacle::Brick { x, y, w, h, hits } => {
+            let color = match hits {
+                1 => ORANGE,
+                2 => RED,
+                _ => MAROON,
+            };
+            draw_rectangle(x + shake_x, y + shake_y, w, h, color);
+            draw_rectangle_lines(x + shake_x, y + shake_y, w, h, 2.0, BLACK);
+        }
+        Obstacle::Bumper { x, y, radius } => {
+            draw_circle(x + shake_x, y + shake_y, radius, PURPLE);
+            draw_circle_lines(x + shake_x, y + shake_y, radius, 3.0, PINK);
+        }
+        Obstacle::Deflector { x1, y1, x2, y2 } => {
+            draw_line(x1 + shake_x, y1 + shake_y, x2 + shake_x, y2 + shake_y, DEFLECTOR_THICKNESS, SKYBLUE);
+        }
+    }
+}
+
+// Seconds as m:ss
+fn clock(seconds: f32) -> String {
+    let seconds = seconds.max(0.0) as u32;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+// Stripe across the ball that turns with its spin, and a faint ring that
+// grows with how hard it's spinning
+fn draw_spin_marker(x: f32, y: f32, spin: &Spin) {
+    let (dx, dy) = (spin.angle.cos() * BALL_SIZE * 0.8, spin.angle.sin() * BALL_SIZE * 0.8);
+    draw_line(x - dx, y - dy, x + dx, y + dy, 3.0, Color::new(0.2, 0.4, 0.9, 1.0));
+    let strength = (spin.spin.abs() / MAX_PADDLE_SPEED).min(1.0);
+    if strength > 0.05 {
+        draw_circle_lines(x, y, BALL_SIZE + 3.0, 2.0, Color::new(0.4, 0.7, 1.0, strength));
+    }
+}
 
-    // Paddle positions (Y coordinates, X is constant)
-    let mut left_paddle_y = PADDLE_START_Y;
-    let mut right_paddle_y = PADDLE_START_Y;
+// Five-pointed star centered on (`x`, `y`), for challenge ratings
+fn draw_star(x: f32, y: f32, radius: f32, color: Color) {
+    let point = |index: usize| {
+        let angle = -std::f32::consts::FRAC_PI_2 + index as f32 * std::f32::consts::PI / 5.0;
+        let reach = if index.is_multiple_of(2) { radius } else { radius * 0.45 };
+        vec2(x + angle.cos() * reach, y + angle.sin() * reach)
+    };
+    for index in 0..10 {
+        draw_triangle(vec2(x, y), point(index), point(index + 1), color);
+    }
+}
+
+// Draw `text` horizontally centered on the screen
+fn draw_centered_text(text: &str, y: f32, font_size: u16, color: Color) {
+    let width = measure_text(text, None, font_size, 1.0).width;
+    draw_text(text, (SCREEN_WIDTH - width) / 2.0, y, font_size as f32, color);
+}
+
+// The frozen field with the pause overlay, also used while an online match
+// waits for a player to reconnect
+fn draw_paused(game: &Match, subtitle: &str) {
+    // Draw game objects (frozen)
+    draw_rectangle(LEFT_PADDLE_X, game.left.y, PADDLE_WIDTH, game.left.height, WHITE);
+    draw_rectangle(RIGHT_PADDLE_X, game.right.y, PADDLE_WIDTH, game.right.height, WHITE);
+    draw_circle(game.ball.x, game.ball.y, BALL_SIZE, WHITE);
+    
+    // Draw scores
+    let score_text = format!("{}    {}", game.left_score, game.right_score);
+    let score_width = measure_text(&score_text, None, 60, 1.0).width;
+    draw_text(
+        &score_text,
+        (SCREEN_WIDTH - score_width) / 2.0,
+        100.0,
+        60.0,
+        WHITE,
+    );
+    
+    // Draw pause overlay
+    let pause_text = "PAUSED";
+    let pause_width = measure_text(pause_text, None, 60, 1.0).width;
+    draw_text(
+        pause_text,
+        (SCREEN_WIDTH - pause_width) / 2.0,
+        SCREEN_HEIGHT / 2.0,
+        60.0,
+        YELLOW,
+    );
+    
+    let resume_width = measure_text(subtitle, None, 30, 1.0).width;
+    draw_text(
+        subtitle,
+        (SCREEN_WIDTH - resume_width) / 2.0,
+        SCREEN_HEIGHT / 2.0 + 60.0,
+        30.0,
+        GRAY,
+    );
+}
+
+// Player names and pings beside the scores, rally count below the field
+fn draw_spectator_hud(spectator: &Spectator<Box<dyn Transport>>) {
+    let status = format!(
+        "SPECTATING ({:.0} s delay)  {} watching",
+        SPECTATOR_DELAY,
+        spectator.spectators()
+    );
+    draw_text(&status, 20.0, 20.0, 18.0, GRAY);
+    
+    for side in [Side::Left, Side::Right] {
+        let name = spectator.name(side);
+        let ping = format!("{} ms", spectator.ping_ms(side));
+        let name_width = measure_text(name, None, 24, 1.0).width;
+        let ping_width = measure_text(&ping, None, 16, 1.0).width;
+        let (name_x, ping_x) = match side {
+            Side::Left => (SCREEN_WIDTH / 2.0 - 70.0 - name_width, SCREEN_WIDTH / 2.0 - 70.0 - ping_width),
+            Side::Right => (SCREEN_WIDTH / 2.0 + 70.0, SCREEN_WIDTH / 2.0 + 70.0),
+        };
+        draw_text(name, name_x, 135.0, 24.0, WHITE);
+        draw_text(&ping, ping_x, 155.0, 16.0, GRAY);
+    }
+    
+    draw_centered_text(&format!("Rally: {}", spectator.rally()), SCREEN_HEIGHT - 20.0, 22, YELLOW);
+}
+
+// Value following `flag` on the command line, e.g. `--bot "python3 bot.py"`
+fn arg_value(flag: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    let index = args.iter().position(|arg| arg == flag)?;
+    args.get(index + 1).cloned()
+}
 
-    // Ball position and velocity
-    let mut ball_x = BALL_START_X;
-    let mut ball_y = BALL_START_Y;
-    let mut ball_vel_x = BALL_SPEED;
-    let mut ball_vel_y = BALL_SPEED * 0.3; // Start with some angle
-    let mut current_ball_speed = BALL_SPEED; // Track current ball speed
+// `address` with `port` appended unless it names one
+fn with_default_port(address: &str, port: u16) -> String {
+    if address.contains(':') {
+        address.to_string()
+    } else {
+        format!("{}:{}", address, port)
+    }
+}
+
+// Put the `--netsim` conditions between us and the peer, if any
+fn impair(transport: Box<dyn Transport>, conditions: Option<NetConditions>) -> Box<dyn Transport> {
+    match conditions {
+        Some(conditions) => Box::new(NetSimulator::new(transport, conditions, macroquad::rand::rand() as u64, get_time)),
+        None => transport,
+    }
+}
+
+// Map an up/down key pair to a paddle intent
+fn key_intent(up: KeyCode, down: KeyCode) -> Intent {
+    if is_key_down(up) {
+        Intent::Up
+    } else if is_key_down(down) {
+        Intent::Down
+    } else {
+        Intent::Stay
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    
+    // Headless subcommands
+    if args.first().map(String::as_str) == Some("tune") {
+        if let Err(err) = pong_rs::tune::run_cli(&args[1..]) {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if args.first().map(String::as_str) == Some("verify-replay") {
+        let Some(path) = args.get(1) else {
+            eprintln!("usage: pong_rs verify-replay FILE");
+            std::process::exit(1);
+        };
+        match Replay::load(path).map_err(|err| err.to_string()) {
+            Ok(replay) if replay.checksums.is_empty() => {
+                eprintln!("{} has no checksums to verify", path);
+                std::process::exit(1);
+            }
+            Ok(replay) => match replay.verify() {
+                Ok(game) => println!(
+                    "{} frames match, final score {}-{}",
+                    replay.actions.len(),
+                    game.left_score,
+                    game.right_score
+                ),
+                Err(report) => {
+                    print!("{}", report.to_text());
+                    std::process::exit(2);
+                }
+            },
+            Err(err) => {
+                eprintln!("could not load {}: {}", path, err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
     
-    // Paddle velocities for smooth movement
-    let mut left_paddle_vel = 0.0;
-    let mut right_paddle_vel = 0.0;
+    macroquad::Window::new("Pong", run());
+}
+
+async fn run() {
+    request_new_screen_size(SCREEN_WIDTH, SCREEN_HEIGHT);
 
-    // Player scores
-    let mut left_score = 0;
-    let mut right_score = 0;
+    // Paddles, ball and scores
+    let mut game = Match::new();
     
     // Game state
     let mut game_state = GameState::Menu;
     let mut game_mode = GameMode::TwoPlayer;
     
+    // External bot (right paddle) for GameMode::VsBot
+    let bot_command = arg_value("--bot");
+    let mut bot: Option<BotProcess> = None;
+    let mut bot_error: Option<String> = None;
+    
+    // Optional neural-network opponent loaded from `--weights <file>`
+    let mut ai_opponent = AiOpponent::Classic;
+    let mut network: Option<Mlp> = None;
+    let mut weights_error: Option<String> = None;
+    if let Some(path) = arg_value("--weights") {
+        match Mlp::load(&path) {
+            Ok(mlp) => {
+                network = Some(mlp);
+                ai_opponent = AiOpponent::Neural;
+            }
+            Err(err) => weights_error = Some(format!("Could not load {}: {}", path, err)),
+        }
+    }
+    
+    // Difficulty levels for the classic AI: tuned profiles if available
+    let profiles: Vec<AiProfile> = ai::load_profiles(AI_PROFILE_FILE)
+        .ok()
+        .filter(|profiles| !profiles.is_empty())
+        .unwrap_or_else(ai::default_profiles);
+    let mut difficulty = arg_value("--difficulty")
+        .and_then(|name| profiles.iter().position(|p| p.name == name))
+        .or_else(|| profiles.iter().position(|p| p.name == "medium"))
+        .unwrap_or(0);
+    let mut ai_player = AiController::new(profiles[difficulty].params, Side::Right);
+    
+    // Power-ups for local two-paddle matches, chosen on the mode screen
+    let mut powerups_enabled = false;
+    let mut powerups: Option<PowerUps> = None;
+    
+    // Ball spin for the same matches: a moving paddle sets the ball curving
+    let mut spin_enabled = false;
+    let mut spin: Option<Spin> = None;
+    
+    // First to N or timed, for the same matches; timed ones run a clock
+    let mut match_rule = 0; // Index into `MatchRule::PRESETS`
+    let mut match_clock: Option<MatchClock> = None;
+    
+    // Obstacle arenas for the same matches, optionally scoring broken bricks;
+    // `--arena <file>` adds a custom one and picks it
+    let mut arenas = Arena::presets();
+    let mut arena_choice = 0;
+    let mut arena_error: Option<String> = None;
+    let mut brick_points = false;
+    let mut arena_state: Option<ArenaState> = None;
+    let mut match_arena = Arena::empty(); // Layout the current match started from, for restarts
+    let mut editor_path = arg_value("--arena").unwrap_or_else(|| ARENA_DEFAULT_FILE.to_string());
+    if let Some(path) = arg_value("--arena") {
+        match Arena::load(&path) {
+            Ok(arena) => {
+                arenas.retain(|existing| existing.name != arena.name);
+                arenas.push(arena);
+                arena_choice = arenas.len() - 1;
+            }
+            Err(err) => arena_error = Some(format!("Could not load {}: {}", path, err)),
+        }
+    }
+    
+    // Arena editor, opened from the mode screen
+    let mut editor_arena = Arena::empty();
+    let mut editor_tool = EditorTool::Block;
+    let mut editor_hits = 1;
+    let mut editor_drag: Option<(f32, f32)> = None; // Where the mouse went down
+    let mut editor_message: Option<String> = None;
+    let mut editing_path = false;
+    let mut editor_testing = false; // The current match is a test-play from the editor
+    
+    // Four-player mode: the first `quad_humans` walls play from the keyboard,
+    // the AI fills in the rest
+    let mut quad = QuadMatch::default();
+    let mut quad_ai: Vec<QuadAi> = Vec::new();
+    let mut quad_humans = 2;
+    let mut quad_paused = false;
+    
+    // 2v2 doubles: humans take the back paddles first, then the front ones
+    let mut doubles = DoublesMatch::default();
+    let mut doubles_ai: Vec<DoublesAi> = Vec::new();
+    let mut doubles_humans = 2;
+    let mut doubles_paused = false;
+    let doubles_seats = [Seat::LeftBack, Seat::RightBack, Seat::LeftFront, Seat::RightFront];
+    
+    // Multi-ball: the left paddle is human, the right one too with two humans
+    let mut multiball = MultiBallMatch::default();
+    let mut multiball_ai: Option<MultiBallAi> = None;
+    let mut multiball_humans = 1;
+    let mut multiball_rule = 1; // Index into `BallRule::PRESETS`
+    let mut multiball_paused = false;
+    
+    // Wall practice: one player, one ball, and the best rally on each wall
+    let mut practice_wall = WallKind::Solid;
+    let mut practice = Practice::new(practice_wall, 1);
+    let mut practice_bests = PersonalBests::load(PRACTICE_BEST_FILE).unwrap_or_default();
+    let mut practice_new_best = false;
+    let mut practice_error: Option<String> = None;
+    let mut practice_paused = false;
+    
+    // Tournament: entrants gathered on the setup screen, then the bracket,
+    // saved after every match so it can be resumed
+    let mut tournament_entrants: Vec<Entrant> = Vec::new();
+    let mut tournament_format = Format::Single;
+    let mut tournament_name: Option<String> = None; // Name being typed for a new player
+    let mut tournament: Option<Tournament> = None;
+    let mut tournament_saved: Option<Tournament> = None; // Unfinished tournament found on disk
+    let mut tournament_error: Option<String> = None;
+    let mut tournament_players = [0, 1]; // Entrants playing the current match, left then right
+    let mut tournament_ai: [Option<AiController>; 2] = [None, None]; // Their AI, if they aren't human
+    
+    // Gauntlet: the run so far; its matches are played on the Playing screen
+    let mut gauntlet = Gauntlet::new();
+    
+    // Challenges: the bundled ones plus any `.challenge` files in
+    // `challenges/`, and the stars earned so far, which unlock the later ones
+    let mut challenges = Challenge::presets();
+    let (custom_challenges, challenge_errors) = Challenge::load_dir(CHALLENGE_DIR);
+    for challenge in custom_challenges {
+        match challenges.iter_mut().find(|existing| existing.name == challenge.name) {
+            Some(existing) => *existing = challenge,
+            None => challenges.push(challenge),
+        }
+    }
+    let mut challenge_error: Option<String> = challenge_errors.into_iter().next();
+    let mut challenge_progress = ChallengeProgress::load(CHALLENGE_PROGRESS_FILE).unwrap_or_default();
+    let mut challenge_choice: usize = 0;
+    let mut challenge_run = ChallengeRun::new(challenges[0].clone(), &profiles, 1);
+    let mut challenge_improved = false; // The last run earned more stars than before
+    let mut challenge_unlocked: Vec<String> = Vec::new(); // Challenges the last run unlocked
+    let mut challenge_paused = false;
+    
+    // Online play over UDP, through a relay room or on a match server:
+    // `--host [port]`, `--join <address>`, `--room <code>`, `--server <address>`
+    // or `--watch <address>` skip the setup screen
+    let mut net_session: Option<NetSession> = None;
+    let mut relay_pending: Option<WsTransport> = None;
+    let mut net_accumulator = 0.0;
+    let mut desync_saved: Option<(String, bool)> = None; // Report file, whether it has the peer's state
+    let mut net_error: Option<String> = None;
+    let mut net_forfeit: Option<Side> = None; // Side that left an online match for good
+    let mut net_lost = false; // Peer-to-peer match called off after the link went quiet
+    let mut net_retry_at = 0.0; // Next attempt to reach the server again after losing it
+    let mut net_port = NET_DEFAULT_PORT;
+    let mut join_address = String::new();
+    let mut room_code = String::new();
+    let mut server_address = String::new();
+    let mut player_name = arg_value("--name").unwrap_or_else(|| "Player".to_string());
+    let mut net_choice = NetChoice::Host;
+    
+    // LAN lobby on a match server: `--lobby <address>` skips the address entry
+    let mut lobby: Option<LobbyClient<Box<dyn Transport>>> = None;
+    let mut lobby_room: Option<u8> = None; // Room to go back to after its match
+    let mut lobby_selected = 0;
+    let mut editing_name = false;
+    let mut chat_typing = false;
+    let mut chat_line = String::new();
+    let net_conditions = match arg_value("--netsim").map(|spec| NetConditions::parse(&spec)) {
+        Some(Ok(conditions)) => Some(conditions),
+        Some(Err(err)) => {
+            eprintln!("Ignoring --netsim: {}", err);
+            None
+        }
+        None => None,
+    };
+    let relay_url = arg_value("--relay").unwrap_or_else(|| {
+        if cfg!(target_arch = "wasm32") {
+            String::new() // The relay that served the page
+        } else {
+            RELAY_DEFAULT_URL.to_string()
+        }
+    });
+    if std::env::args().any(|arg| arg == "--host") {
+        net_port = arg_value("--host").and_then(|port| port.parse().ok()).unwrap_or(NET_DEFAULT_PORT);
+        match UdpTransport::host(net_port) {
+            Ok(transport) => net_session = Some(NetSession::Rollback(Box::new(RollbackSession::new(impair(Box::new(transport), net_conditions), Side::Left)))),
+            Err(err) => net_error = Some(format!("Could not host: {}", err)),
+        }
+        game_mode = GameMode::Online;
+        game_state = GameState::NetSetup;
+    } else if let Some(address) = arg_value("--join") {
+        match UdpTransport::join(&address) {
+            Ok(transport) => net_session = Some(NetSession::Rollback(Box::new(RollbackSession::new(impair(Box::new(transport), net_conditions), Side::Right)))),
+            Err(err) => net_error = Some(format!("Could not join {}: {}", address, err)),
+        }
+        join_address = address;
+        net_choice = NetChoice::Join;
+        game_mode = GameMode::Online;
+        game_state = GameState::NetSetup;
+    } else if let Some(code) = arg_value("--room") {
+        match WsTransport::connect(&relay_url, &code) {
+            Ok(transport) => relay_pending = Some(transport),
+            Err(err) => net_error = Some(format!("Could not reach relay: {}", err)),
+        }
+        room_code = code.to_uppercase();
+        net_choice = NetChoice::Room;
+        game_mode = GameMode::Online;
+        game_state = GameState::NetSetup;
+    } else if let Some(address) = arg_value("--server") {
+        match UdpTransport::join(&address) {
+            Ok(transport) => {
+                let client = ServerClient::new(impair(Box::new(transport), net_conditions), &player_name);
+                net_session = Some(NetSession::Server(client));
+            }
+            Err(err) => net_error = Some(format!("Could not reach server {}: {}", address, err)),
+        }
+        server_address = address;
+        net_choice = NetChoice::Server;
+        game_mode = GameMode::Online;
+        game_state = GameState::NetSetup;
+    } else if let Some(address) = arg_value("--watch") {
+        match UdpTransport::join(&address) {
+            Ok(transport) => {
+                let spectator = Spectator::new(impair(Box::new(transport), net_conditions));
+                net_session = Some(NetSession::Spectate(spectator));
+            }
+            Err(err) => net_error = Some(format!("Could not reach server {}: {}", address, err)),
+        }
+        server_address = address;
+        net_choice = NetChoice::Watch;
+        game_mode = GameMode::Online;
+        game_state = GameState::NetSetup;
+    } else if let Some(address) = arg_value("--lobby") {
+        match UdpTransport::join(&with_default_port(&address, SERVER_DEFAULT_PORT)) {
+            Ok(transport) => lobby = Some(LobbyClient::new(impair(Box::new(transport), net_conditions), &player_name)),
+            Err(err) => net_error = Some(format!("Could not reach server {}: {}", address, err)),
+        }
+        server_address = address;
+        game_mode = GameMode::Lobby;
+        game_state = GameState::Lobby;
+    }
+    
     // Visual effects
     let mut particles: Vec<Particle> = Vec::new();
     let mut screen_shake = 0.0;
@@ -95,6 +850,7 @@ async fn main() {
 
     loop {
         clear_background(BLACK);
+        let frame_state = game_state;
         
         match game_state {
             GameState::Menu => {
@@ -153,40 +909,88 @@ async fn main() {
                 draw_text(
                     title,
                     (SCREEN_WIDTH - title_width) / 2.0,
-                    SCREEN_HEIGHT / 2.0 - 80.0,
+                    SCREEN_HEIGHT / 2.0 - 150.0,
                     40.0,
                     WHITE,
                 );
                 
                 // Draw mode options
-                let mode1_color = if matches!(game_mode, GameMode::TwoPlayer) { YELLOW } else { WHITE };
-                let mode1_text = "1: Two Player";
-                let mode1_width = measure_text(mode1_text, None, 30, 1.0).width;
-                draw_text(
-                    mode1_text,
-                    (SCREEN_WIDTH - mode1_width) / 2.0,
-                    SCREEN_HEIGHT / 2.0 - 20.0,
-                    30.0,
-                    mode1_color,
-                );
+                for (index, mode) in GameMode::ALL.iter().enumerate() {
+                    let available = *mode != GameMode::VsBot || bot_command.is_some();
+                    let color = if *mode == game_mode {
+                        YELLOW
+                    } else if available {
+                        WHITE
+                    } else {
+                        DARKGRAY
+                    };
+                    let label = match mode {
+                        GameMode::VsAI => match (ai_opponent, network.is_some()) {
+                            (AiOpponent::Classic, false) => "vs AI".to_string(),
+                            (AiOpponent::Classic, true) => "vs AI (Classic)".to_string(),
+                            (AiOpponent::Neural, _) => "vs AI (Neural Net)".to_string(),
+                        },
+                        GameMode::VsBot if !available => "vs External Bot (needs --bot)".to_string(),
+                        _ => mode.label().to_string(),
+                    };
+                    // Number keys for the first ten, arrows for the rest
+                    let text = if index < 10 { format!("{}: {}", (index + 1) % 10, label) } else { label };
+                    draw_centered_text(&text, SCREEN_HEIGHT / 2.0 - 120.0 + index as f32 * 24.0, 22, color);
+                }
+                let options_y = SCREEN_HEIGHT / 2.0 - 120.0 + GameMode::ALL.len() as f32 * 24.0;
                 
-                let mode2_color = if matches!(game_mode, GameMode::VsAI) { YELLOW } else { WHITE };
-                let mode2_text = "2: vs AI";
-                let mode2_width = measure_text(mode2_text, None, 30, 1.0).width;
-                draw_text(
-                    mode2_text,
-                    (SCREEN_WIDTH - mode2_width) / 2.0,
-                    SCREEN_HEIGHT / 2.0 + 20.0,
-                    30.0,
-                    mode2_color,
-                );
+                // Mode-specific options
+                let on_off = |enabled| if enabled { "On" } else { "Off" };
+                let rule_text = format!("T: Rule < {} >  P: Spin < {} >", MatchRule::PRESETS[match_rule].label(), on_off(spin_enabled));
+                let mut match_options = format!("U: Power-ups < {} >  A: Arena < {} >", on_off(powerups_enabled), arenas[arena_choice].name);
+                if arenas[arena_choice].has_bricks() {
+                    match_options += &format!("  K: Brick Points < {} >", on_off(brick_points));
+                }
+                if game_mode == GameMode::VsAI {
+                    let mut difficulty_text = format!("D: AI Difficulty < {} >", profiles[difficulty].name);
+                    if network.is_some() {
+                        difficulty_text += "  N: Toggle Neural Net";
+                    }
+                    draw_centered_text(&difficulty_text, options_y, 20, WHITE);
+                    draw_centered_text(&rule_text, options_y + 22.0, 20, WHITE);
+                    draw_centered_text(&match_options, options_y + 44.0, 20, WHITE);
+                } else if game_mode == GameMode::FourPlayer || game_mode == GameMode::Doubles {
+                    let humans = if game_mode == GameMode::FourPlayer { quad_humans } else { doubles_humans };
+                    let humans_text = format!("H: Human Players < {} >  D: AI Difficulty < {} >", humans, profiles[difficulty].name);
+                    draw_centered_text(&humans_text, options_y, 20, WHITE);
+                } else if game_mode == GameMode::MultiBall {
+                    let humans_text = format!("H: Human Players < {} >  D: AI Difficulty < {} >", multiball_humans, profiles[difficulty].name);
+                    draw_centered_text(&humans_text, options_y, 20, WHITE);
+                    let rule_text = format!("B: Balls < {} >", BallRule::PRESETS[multiball_rule].label());
+                    draw_centered_text(&rule_text, options_y + 22.0, 20, WHITE);
+                } else if game_mode == GameMode::Gauntlet {
+                    let rules = format!("{} opponents, first to {}, {} lives", OPPONENTS.len(), gauntlet::MATCH_POINTS, gauntlet::LIVES);
+                    draw_centered_text(&rules, options_y, 20, WHITE);
+                } else if game_mode == GameMode::Challenges {
+                    let unlocked = challenges.iter().filter(|challenge| challenge_progress.is_unlocked(challenge)).count();
+                    let progress_text = format!(
+                        "Stars: {} of {}  Unlocked: {} of {}",
+                        challenge_progress.total(),
+                        challenges.len() as u32 * MAX_STARS,
+                        unlocked,
+                        challenges.len()
+                    );
+                    draw_centered_text(&progress_text, options_y, 20, WHITE);
+                } else if game_mode == GameMode::Practice {
+                    let wall_text = format!("W: Wall < {} >  Best rally: {}", practice_wall.label(), practice_bests.get(practice_wall));
+                    draw_centered_text(&wall_text, options_y, 20, WHITE);
+                } else if game_mode.has_match_options() {
+                    draw_centered_text(&rule_text, options_y, 20, WHITE);
+                    draw_centered_text(&match_options, options_y + 22.0, 20, WHITE);
+                }
+                
+                let start_text = if game_mode.has_match_options() { "SPACE: Start Game  E: Arena Editor" } else { "SPACE: Start Game" };
                 
-                let start_text = "SPACE: Start Game";
                 let start_width = measure_text(start_text, None, 20, 1.0).width;
                 draw_text(
                     start_text,
                     (SCREEN_WIDTH - start_width) / 2.0,
-                    SCREEN_HEIGHT / 2.0 + 80.0,
+                    options_y + 70.0,
                     20.0,
                     GRAY,
                 );
@@ -196,179 +1000,704 @@ async fn main() {
                 draw_text(
                     back_text,
                     (SCREEN_WIDTH - back_width) / 2.0,
-                    SCREEN_HEIGHT / 2.0 + 110.0,
+                    options_y + 95.0,
                     20.0,
                     GRAY,
                 );
                 
+                if let Some(error) = bot_error.as_ref().or(weights_error.as_ref()).or(arena_error.as_ref()) {
+                    draw_centered_text(error, options_y + 122.0, 18, RED);
+                }
+                
                 // Handle input
-                if is_key_pressed(KeyCode::Key1) {
-                    game_mode = GameMode::TwoPlayer;
-                } else if is_key_pressed(KeyCode::Key2) {
-                    game_mode = GameMode::VsAI;
+                let selected = GameMode::ALL.iter().position(|mode| *mode == game_mode).unwrap_or(0);
+                let number_keys = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4, KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9, KeyCode::Key0];
+                let mut chosen = number_keys.iter().take(GameMode::ALL.len()).position(|key| is_key_pressed(*key));
+                if is_key_pressed(KeyCode::Up) {
+                    chosen = Some((selected + GameMode::ALL.len() - 1) % GameMode::ALL.len());
+                } else if is_key_pressed(KeyCode::Down) {
+                    chosen = Some((selected + 1) % GameMode::ALL.len());
+                }
+                if let Some(index) = chosen {
+                    let mode = GameMode::ALL[index];
+                    if mode != GameMode::VsBot || bot_command.is_some() {
+                        game_mode = mode;
+                    }
+                }
+                
+                if is_key_pressed(KeyCode::D) {
+                    difficulty = (difficulty + 1) % profiles.len();
+                } else if is_key_pressed(KeyCode::H) && game_mode == GameMode::FourPlayer {
+                    quad_humans = quad_humans % Wall::ALL.len() + 1;
+                } else if is_key_pressed(KeyCode::H) && game_mode == GameMode::Doubles {
+                    doubles_humans = doubles_humans % Seat::ALL.len() + 1;
+                } else if is_key_pressed(KeyCode::H) && game_mode == GameMode::MultiBall {
+                    multiball_humans = multiball_humans % 2 + 1;
+                } else if is_key_pressed(KeyCode::B) && game_mode == GameMode::MultiBall {
+                    multiball_rule = (multiball_rule + 1) % BallRule::PRESETS.len();
+                } else if is_key_pressed(KeyCode::W) && game_mode == GameMode::Practice {
+                    practice_wall = match practice_wall {
+                        WallKind::Solid => WallKind::Target,
+                        WallKind::Target => WallKind::Solid,
+                    };
+                } else if is_key_pressed(KeyCode::T) && game_mode.has_match_options() {
+                    match_rule = (match_rule + 1) % MatchRule::PRESETS.len();
+                } else if is_key_pressed(KeyCode::P) && game_mode.has_match_options() {
+                    spin_enabled = !spin_enabled;
+                } else if is_key_pressed(KeyCode::U) && game_mode.has_match_options() {
+                    powerups_enabled = !powerups_enabled;
+                } else if is_key_pressed(KeyCode::A) && game_mode.has_match_options() {
+                    arena_choice = (arena_choice + 1) % arenas.len();
+                } else if is_key_pressed(KeyCode::K) && game_mode.has_match_options() && arenas[arena_choice].has_bricks() {
+                    brick_points = !brick_points;
+                } else if is_key_pressed(KeyCode::E) && game_mode.has_match_options() {
+                    editor_arena = arenas[arena_choice].clone();
+                    editor_drag = None;
+                    editor_message = None;
+                    game_state = GameState::Editor;
+                } else if is_key_pressed(KeyCode::N) && network.is_some() {
+                    // Switch between the loaded network and the classic AI
+                    ai_opponent = match ai_opponent {
+                        AiOpponent::Classic => AiOpponent::Neural,
+                        AiOpponent::Neural => AiOpponent::Classic,
+                    };
                 } else if is_key_pressed(KeyCode::Space) {
-                    game_state = GameState::Playing;
+                    bot = None;
+                    bot_error = None;
+                    powerups = None;
+                    spin = None;
+                    arena_state = None;
+                    match_clock = None;
+                    editor_testing = false;
+                    if game_mode == GameMode::VsBot {
+                        let command = bot_command.as_deref().unwrap_or_default();
+                        match BotProcess::spawn(command, Side::Right, Duration::from_millis(BOT_TIMEOUT_MS)) {
+                            Ok(process) => bot = Some(process),
+                            Err(err) => bot_error = Some(format!("Could not start bot: {}", err)),
+                        }
+                    }
+                    if game_mode == GameMode::Online {
+                        net_error = None;
+                        game_state = GameState::NetSetup;
+                    } else if game_mode == GameMode::Lobby {
+                        net_error = None;
+                        game_state = GameState::Lobby;
+                    } else if game_mode == GameMode::FourPlayer {
+                        quad = QuadMatch::default();
+                        quad_ai = Wall::ALL
+                            .into_iter()
+                            .skip(quad_humans)
+                            .map(|wall| QuadAi::new(profiles[difficulty].params, wall))
+                            .collect();
+                        quad_paused = false;
+                        particles.clear();
+                        screen_shake = 0.0;
+                        score_flash_timer = 0.0;
+                        game_state = GameState::FourPlayer;
+                    } else if game_mode == GameMode::Doubles {
+                        doubles = DoublesMatch::default();
+                        doubles_ai = doubles_seats
+                            .into_iter()
+                            .skip(doubles_humans)
+                            .map(|seat| DoublesAi::new(profiles[difficulty].params, seat))
+                            .collect();
+                        doubles_paused = false;
+                        particles.clear();
+                        screen_shake = 0.0;
+                        score_flash_timer = 0.0;
+                        game_state = GameState::Doubles;
+                    } else if game_mode == GameMode::MultiBall {
+                        multiball = MultiBallMatch::new(BallRule::PRESETS[multiball_rule], WINNING_SCORE);
+                        multiball_ai = (multiball_humans < 2).then(|| MultiBallAi::new(profiles[difficulty].params, Side::Right));
+                        multiball_paused = false;
+                        particles.clear();
+                        screen_shake = 0.0;
+                        score_flash_timer = 0.0;
+                        game_state = GameState::MultiBall;
+                    } else if game_mode == GameMode::Tournament {
+                        tournament_error = None;
+                        tournament_name = None;
+                        tournament_saved = Tournament::load(TOURNAMENT_FILE).ok().filter(|saved| saved.champion().is_none());
+                        if let Some(Err(err)) = tournament_saved.as_ref().map(|saved| saved.check_profiles(&profiles)) {
+                            tournament_error = Some(format!("Can't resume {}: {}", TOURNAMENT_FILE, err));
+                            tournament_saved = None;
+                        }
+                        game_state = GameState::TournamentSetup;
+                    } else if game_mode == GameMode::Gauntlet {
+                        gauntlet = Gauntlet::new();
+                        game_state = GameState::Gauntlet;
+                    } else if game_mode == GameMode::Challenges {
+                        game_state = GameState::ChallengeSelect;
+                    } else if game_mode == GameMode::Practice {
+                        practice = Practice::new(practice_wall, macroquad::rand::rand() as u64);
+                        practice_new_best = false;
+                        practice_error = None;
+                        practice_paused = false;
+                        particles.clear();
+                        screen_shake = 0.0;
+                        game_state = GameState::Practice;
+                    } else if bot_error.is_none() {
+                        ai_player = AiController::new(profiles[difficulty].params, Side::Right);
+                        // A fresh match, whatever was played last (a tournament's, say)
+                        game.reset();
+                        match_clock = MatchRule::PRESETS[match_rule].apply(&mut game);
+                        powerups = powerups_enabled.then(|| PowerUps::new(macroquad::rand::rand() as u64));
+                        spin = spin_enabled.then(Spin::new);
+                        match_arena = arenas[arena_choice].clone();
+                        let mut arena = ArenaState::new(match_arena.clone(), brick_points);
+                        arena.serve(&mut game.ball);
+                        arena_state = Some(arena);
+                        game_state = GameState::Playing;
+                    }
                 } else if is_key_pressed(KeyCode::Escape) {
                     game_state = GameState::Menu;
                 }
             }
             
-            GameState::Playing => {
-                let dt = get_frame_time();
+            GameState::NetSetup => {
+                draw_centered_text("Online Match", SCREEN_HEIGHT / 2.0 - 120.0, 40, WHITE);
                 
-                // Handle input and update paddle velocities with immediate response + buildup
-                // Left paddle controls (W/S)
-                if is_key_down(KeyCode::W) {
-                    if left_paddle_vel > -PADDLE_BASE_SPEED {
-                        left_paddle_vel = -PADDLE_BASE_SPEED; // Immediate base speed
-                    }
-                    left_paddle_vel -= PADDLE_ACCELERATION * dt; // Add acceleration buildup
-                } else if is_key_down(KeyCode::S) {
-                    if left_paddle_vel < PADDLE_BASE_SPEED {
-                        left_paddle_vel = PADDLE_BASE_SPEED; // Immediate base speed
+                // Relay room joined: start the session on the side the relay assigned
+                if let Some(transport) = relay_pending.as_mut() {
+                    match transport.update() {
+                        RelayStatus::Joined(side) => {
+                            let transport = relay_pending.take().unwrap();
+                            net_session = Some(NetSession::Rollback(Box::new(RollbackSession::new(impair(Box::new(transport), net_conditions), side))));
+                        }
+                        RelayStatus::Full => {
+                            net_error = Some(format!("Room {} is full", room_code));
+                            relay_pending = None;
+                        }
+                        RelayStatus::Closed => {
+                            net_error = Some("Lost connection to the relay".to_string());
+                            relay_pending = None;
+                        }
+                        RelayStatus::Connecting | RelayStatus::Waiting => {}
                     }
-                    left_paddle_vel += PADDLE_ACCELERATION * dt; // Add acceleration buildup
-                } else {
-                    // Apply friction when no input
-                    left_paddle_vel *= PADDLE_FRICTION;
                 }
                 
-                // Right paddle controls (Up/Down arrows or AI)
-                match game_mode {
-                    GameMode::TwoPlayer => {
-                        if is_key_down(KeyCode::Up) {
-                            if right_paddle_vel > -PADDLE_BASE_SPEED {
-                                right_paddle_vel = -PADDLE_BASE_SPEED;
+                if net_session.is_some() || relay_pending.is_some() {
+                    // Waiting for the handshake to complete
+                    let side = net_session.as_ref().and_then(|session| session.side());
+                    let waiting = match (net_choice, side) {
+                        (NetChoice::Room, _) => format!("Room {} - waiting for opponent...", room_code),
+                        (NetChoice::Server, Some(_)) => "Joined server - waiting for opponent...".to_string(),
+                        (NetChoice::Server, None) => format!("Connecting to server {}...", server_address),
+                        (NetChoice::Watch, _) => format!("Connecting to server {} as a spectator...", server_address),
+                        (NetChoice::Host, _) => format!("Hosting on port {} - waiting for opponent...", net_port),
+                        (NetChoice::Join, _) => format!("Connecting to {}...", join_address),
+                    };
+                    draw_centered_text(&waiting, SCREEN_HEIGHT / 2.0, 24, WHITE);
+                    draw_centered_text("ESC: Cancel", SCREEN_HEIGHT / 2.0 + 60.0, 20, GRAY);
+                    
+                    if let Some(session) = net_session.as_mut() {
+                        session.poll(get_time());
+                        if session.is_running() {
+                            game = session.view(get_time(), StepEvents::default()).0;
+                            net_accumulator = 0.0;
+                            desync_saved = None;
+                            net_forfeit = None;
+                            net_lost = false;
+                            particles.clear();
+                            screen_shake = 0.0;
+                            score_flash_timer = 0.0;
+                            game_state = GameState::Playing;
+                        }
+                    }
+                    if is_key_pressed(KeyCode::Escape) {
+                        net_session = None;
+                        relay_pending = None;
+                    }
+                } else {
+                    let color = |choice| if net_choice == choice { YELLOW } else { WHITE };
+                    let cursor = if (get_time() * 2.0) as i64 % 2 == 0 { "_" } else { " " };
+                    let host_text = format!("Host a LAN match on port {}", net_port);
+                    draw_centered_text(&host_text, SCREEN_HEIGHT / 2.0 - 90.0, 28, color(NetChoice::Host));
+                    let join_cursor = if net_choice == NetChoice::Join { cursor } else { " " };
+                    let join_text = format!("Join LAN: {}{}", join_address, join_cursor);
+                    draw_centered_text(&join_text, SCREEN_HEIGHT / 2.0 - 50.0, 28, color(NetChoice::Join));
+                    let room_cursor = if net_choice == NetChoice::Room { cursor } else { " " };
+                    let room_text = format!("Relay room code: {}{}", room_code, room_cursor);
+                    draw_centered_text(&room_text, SCREEN_HEIGHT / 2.0 - 10.0, 28, color(NetChoice::Room));
+                    let server_cursor = if net_choice == NetChoice::Server { cursor } else { " " };
+                    let server_text = format!("Match server: {}{}", server_address, server_cursor);
+                    draw_centered_text(&server_text, SCREEN_HEIGHT / 2.0 + 30.0, 28, color(NetChoice::Server));
+                    let watch_cursor = if net_choice == NetChoice::Watch { cursor } else { " " };
+                    let watch_text = format!("Watch server match: {}{}", server_address, watch_cursor);
+                    draw_centered_text(&watch_text, SCREEN_HEIGHT / 2.0 + 70.0, 28, color(NetChoice::Watch));
+                    draw_centered_text(
+                        "UP/DOWN: Choose  Type address (host:port) or code  ENTER: Confirm",
+                        SCREEN_HEIGHT / 2.0 + 110.0,
+                        18,
+                        GRAY,
+                    );
+                    draw_centered_text("ESC: Back", SCREEN_HEIGHT / 2.0 + 135.0, 18, GRAY);
+                    if let Some(error) = &net_error {
+                        draw_centered_text(error, SCREEN_HEIGHT / 2.0 + 165.0, 18, RED);
+                    }
+                    
+                    // Address / room code entry
+                    let address_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '.' | ':' | '-' | '[' | ']');
+                    while let Some(c) = get_char_pressed() {
+                        match net_choice {
+                            NetChoice::Join if address_char(c) => join_address.push(c),
+                            NetChoice::Server | NetChoice::Watch if address_char(c) => server_address.push(c),
+                            NetChoice::Room if c.is_ascii_alphanumeric() && room_code.len() < 12 => {
+                                room_code.push(c.to_ascii_uppercase());
+                            }
+                            _ => {}
+                        }
+                    }
+                    if is_key_pressed(KeyCode::Backspace) {
+                        match net_choice {
+                            NetChoice::Join => join_address.pop(),
+                            NetChoice::Room => room_code.pop(),
+                            NetChoice::Server | NetChoice::Watch => server_address.pop(),
+                            NetChoice::Host => None,
+                        };
+                    }
+                    
+                    if is_key_pressed(KeyCode::Up) {
+                        net_choice = net_choice.previous();
+                    } else if is_key_pressed(KeyCode::Down) {
+                        net_choice = net_choice.next();
+                    } else if is_key_pressed(KeyCode::Enter) {
+                        net_error = None;
+                        match net_choice {
+                            NetChoice::Host => match UdpTransport::host(net_port) {
+                                Ok(transport) => {
+                                    net_session = Some(NetSession::Rollback(Box::new(RollbackSession::new(impair(Box::new(transport), net_conditions), Side::Left))));
+                                }
+                                Err(err) => net_error = Some(format!("Network error: {}", err)),
+                            },
+                            NetChoice::Join => {
+                                match UdpTransport::join(&with_default_port(&join_address, NET_DEFAULT_PORT)) {
+                                    Ok(transport) => {
+                                        net_session = Some(NetSession::Rollback(Box::new(RollbackSession::new(impair(Box::new(transport), net_conditions), Side::Right))));
+                                    }
+                                    Err(err) => net_error = Some(format!("Network error: {}", err)),
+                                }
                             }
-                            right_paddle_vel -= PADDLE_ACCELERATION * dt;
-                        } else if is_key_down(KeyCode::Down) {
-                            if right_paddle_vel < PADDLE_BASE_SPEED {
-                                right_paddle_vel = PADDLE_BASE_SPEED;
+                            NetChoice::Room if room_code.is_empty() => {
+                                net_error = Some("Enter a room code first".to_string());
+                            }
+                            NetChoice::Room => match WsTransport::connect(&relay_url, &room_code) {
+                                Ok(transport) => relay_pending = Some(transport),
+                                Err(err) => net_error = Some(format!("Could not reach relay: {}", err)),
+                            },
+                            NetChoice::Server | NetChoice::Watch => {
+                                match UdpTransport::join(&with_default_port(&server_address, SERVER_DEFAULT_PORT)) {
+                                    Ok(transport) => {
+                                        let transport = impair(Box::new(transport), net_conditions);
+                                        net_session = Some(if net_choice == NetChoice::Watch {
+                                            NetSession::Spectate(Spectator::new(transport))
+                                        } else {
+                                            NetSession::Server(ServerClient::new(transport, &player_name))
+                                        });
+                                    }
+                                    Err(err) => net_error = Some(format!("Network error: {}", err)),
+                                }
                             }
-                            right_paddle_vel += PADDLE_ACCELERATION * dt;
-                        } else {
-                            right_paddle_vel *= PADDLE_FRICTION;
                         }
+                    } else if is_key_pressed(KeyCode::Escape) {
+                        game_state = GameState::ModeSelect;
                     }
-                    GameMode::VsAI => {
-                        // Simple AI: predict where ball will be and move towards it
-                        let predicted_ball_y = if ball_vel_x > 0.0 {
-                            // Ball moving towards AI paddle
-                            let time_to_reach = (RIGHT_PADDLE_X - ball_x) / ball_vel_x.abs();
-                            ball_y + ball_vel_y * time_to_reach * AI_PREDICTION
+                }
+            }
+            
+            GameState::Lobby => {
+                draw_centered_text("LAN Lobby", 60.0, 40, WHITE);
+                let cursor = if (get_time() * 2.0) as i64 % 2 == 0 { "_" } else { " " };
+                
+                if let Some(session) = net_session.as_mut() {
+                    // Our room's match is starting
+                    draw_centered_text("Match starting...", SCREEN_HEIGHT / 2.0, 24, WHITE);
+                    session.poll(get_time());
+                    if session.is_running() {
+                        game = session.view(get_time(), StepEvents::default()).0;
+                        net_accumulator = 0.0;
+                        desync_saved = None;
+                        net_forfeit = None;
+                        net_lost = false;
+                        particles.clear();
+                        screen_shake = 0.0;
+                        score_flash_timer = 0.0;
+                        game_state = GameState::Playing;
+                    }
+                    if is_key_pressed(KeyCode::Escape) {
+                        net_session = None;
+                        lobby_room = None;
+                    }
+                } else if let Some(client) = lobby.as_mut() {
+                    client.poll(get_time());
+                    
+                    if let Some(room) = client.room().cloned() {
+                        // Seated: players, rules, chat
+                        let title = client.rooms().get(room.index as usize).map_or("Room", |r| r.name.as_str());
+                        draw_centered_text(title, 100.0, 28, YELLOW);
+                        if room.playing {
+                            draw_text("Previous match finishing...", SCREEN_WIDTH - 260.0, 100.0, 18.0, GRAY);
+                        }
+                        for (row, side) in [Side::Left, Side::Right].into_iter().enumerate() {
+                            let label = match side {
+                                Side::Left => "Left",
+                                Side::Right => "Right",
+                            };
+                            let y = 140.0 + row as f32 * 28.0;
+                            let (text, color) = match room.members.iter().position(|m| m.side == side) {
+                                Some(index) => {
+                                    let member = &room.members[index];
+                                    let you = if room.you == Some(index) { " (you)" } else { "" };
+                                    let ready = if member.ready { "READY" } else { "not ready" };
+                                    let color = if member.ready { GREEN } else { WHITE };
+                                    (format!("{}: {}{} - {}", label, member.name, you, ready), color)
+                                }
+                                None => match &room.rules.ai_fill {
+                                    Some(difficulty) => (format!("{}: AI ({})", label, difficulty), GRAY),
+                                    None => (format!("{}: (open)", label), GRAY),
+                                },
+                            };
+                            draw_text(&text, 60.0, y, 24.0, color);
+                        }
+                        let rules = client.rules();
+                        let pending = if *rules != room.rules { " (sending...)" } else { "" };
+                        draw_text(&format!("Rules: {}{}", rules.describe(), pending), 60.0, 210.0, 20.0, WHITE);
+                        
+                        // Chat log, newest at the bottom
+                        draw_rectangle_lines(50.0, 225.0, SCREEN_WIDTH - 100.0, 270.0, 1.0, DARKGRAY);
+                        let lines = client.chat();
+                        for (row, line) in lines.iter().skip(lines.len().saturating_sub(12)).enumerate() {
+                            let color = if line.starts_with('*') { GRAY } else { WHITE };
+                            draw_text(line, 60.0, 248.0 + row as f32 * 21.0, 18.0, color);
+                        }
+                        if chat_typing {
+                            draw_text(&format!("> {}{}", chat_line, cursor), 60.0, 525.0, 20.0, YELLOW);
+                            draw_centered_text("ENTER: Send  ESC: Cancel", 575.0, 18, GRAY);
                         } else {
-                            ball_y
-                        };
+                            draw_text("T: Chat", 60.0, 525.0, 20.0, GRAY);
+                            let ready = if client.is_ready() { "R: Not ready" } else { "R: Ready" };
+                            let help = format!(
+                                "LEFT/RIGHT: Side  UP/DOWN: Winning score  A: AI fill-in  {}  ESC: Leave",
+                                ready
+                            );
+                            draw_centered_text(&help, 575.0, 18, GRAY);
+                        }
                         
-                        // Add some random error to make AI beatable
-                        let target_y = predicted_ball_y + gen_range(-AI_ERROR, AI_ERROR);
-                        let paddle_center = right_paddle_y + PADDLE_HEIGHT / 2.0;
+                        if chat_typing {
+                            while let Some(c) = get_char_pressed() {
+                                if !c.is_control() && chat_line.len() + c.len_utf8() <= MAX_CHAT_LEN {
+                                    chat_line.push(c);
+                                }
+                            }
+                            if is_key_pressed(KeyCode::Backspace) {
+                                chat_line.pop();
+                            }
+                            if is_key_pressed(KeyCode::Enter) {
+                                client.say(&chat_line);
+                                chat_line.clear();
+                                chat_typing = false;
+                            } else if is_key_pressed(KeyCode::Escape) {
+                                chat_line.clear();
+                                chat_typing = false;
+                            }
+                        } else {
+                            while get_char_pressed().is_some() {} // Keys below are commands, not text
+                            let mut rules = client.rules().clone();
+                            if is_key_pressed(KeyCode::T) {
+                                chat_typing = true;
+                            } else if is_key_pressed(KeyCode::Left) {
+                                client.set_side(Side::Left);
+                            } else if is_key_pressed(KeyCode::Right) {
+                                client.set_side(Side::Right);
+                            } else if is_key_pressed(KeyCode::Up) {
+                                rules.winning_score = (rules.winning_score + 1).min(MAX_WINNING_SCORE);
+                                client.set_rules(rules);
+                            } else if is_key_pressed(KeyCode::Down) {
+                                rules.winning_score = (rules.winning_score - 1).max(1);
+                                client.set_rules(rules);
+                            } else if is_key_pressed(KeyCode::A) {
+                                // No fill-in, then each difficulty in turn
+                                let current = rules.ai_fill.as_ref().and_then(|name| profiles.iter().position(|p| &p.name == name));
+                                let next = match current {
+                                    None => 0,
+                                    Some(index) => index + 1,
+                                };
+                                rules.ai_fill = profiles.get(next).map(|profile| profile.name.clone());
+                                client.set_rules(rules);
+                            } else if is_key_pressed(KeyCode::R) {
+                                let ready = !client.is_ready();
+                                client.set_ready(ready);
+                            } else if is_key_pressed(KeyCode::Escape) {
+                                client.leave();
+                            }
+                        }
                         
-                        // Move AI paddle towards target
-                        let diff = target_y - paddle_center;
-                        if diff.abs() > 10.0 {
-                            if diff < 0.0 {
-                                right_paddle_vel = -AI_SPEED;
+                        if client.match_started() {
+                            // Same connection, now talking to the room's match
+                            lobby_room = Some(room.index);
+                            let transport = lobby.take().unwrap().into_transport();
+                            net_session = Some(NetSession::Server(ServerClient::new(transport, &player_name)));
+                        }
+                    } else {
+                        // Room list
+                        draw_centered_text(&format!("{} as {}", server_address, player_name), 100.0, 20, GRAY);
+                        let rooms = client.rooms();
+                        if rooms.is_empty() {
+                            draw_centered_text("Looking for rooms...", SCREEN_HEIGHT / 2.0, 24, WHITE);
+                        }
+                        lobby_selected = lobby_selected.min(rooms.len().saturating_sub(1));
+                        for (index, room) in rooms.iter().enumerate() {
+                            let players = if room.players.is_empty() {
+                                "(empty)".to_string()
                             } else {
-                                right_paddle_vel = AI_SPEED;
+                                room.players.join(" vs ")
+                            };
+                            let state = if room.playing {
+                                "  [playing]"
+                            } else if room.players.len() >= 2 {
+                                "  [full]"
+                            } else {
+                                ""
+                            };
+                            let color = if index == lobby_selected { YELLOW } else { WHITE };
+                            let text = format!("{}   {}{}", room.name, players, state);
+                            draw_centered_text(&text, 160.0 + index as f32 * 34.0, 26, color);
+                        }
+                        draw_centered_text("UP/DOWN: Choose  ENTER: Sit down  ESC: Disconnect", 540.0, 18, GRAY);
+                        if let Some(notice) = client.notice() {
+                            draw_centered_text(notice, 570.0, 18, RED);
+                        }
+                        
+                        if is_key_pressed(KeyCode::Up) {
+                            lobby_selected = lobby_selected.saturating_sub(1);
+                        } else if is_key_pressed(KeyCode::Down) {
+                            lobby_selected += 1;
+                        } else if is_key_pressed(KeyCode::Enter) && !rooms.is_empty() {
+                            client.enter(lobby_selected as u8);
+                            chat_typing = false;
+                            chat_line.clear();
+                        } else if is_key_pressed(KeyCode::Escape) {
+                            lobby = None;
+                        }
+                    }
+                } else {
+                    // Server address and display name
+                    let address_cursor = if editing_name { " " } else { cursor };
+                    let name_cursor = if editing_name { cursor } else { " " };
+                    let color = |selected| if selected { YELLOW } else { WHITE };
+                    let server_text = format!("Match server: {}{}", server_address, address_cursor);
+                    draw_centered_text(&server_text, SCREEN_HEIGHT / 2.0 - 40.0, 28, color(!editing_name));
+                    let name_text = format!("Your name: {}{}", player_name, name_cursor);
+                    draw_centered_text(&name_text, SCREEN_HEIGHT / 2.0, 28, color(editing_name));
+                    draw_centered_text("UP/DOWN: Choose  ENTER: Connect  ESC: Back", SCREEN_HEIGHT / 2.0 + 60.0, 18, GRAY);
+                    if let Some(error) = &net_error {
+                        draw_centered_text(error, SCREEN_HEIGHT / 2.0 + 90.0, 18, RED);
+                    }
+                    
+                    while let Some(c) = get_char_pressed() {
+                        if editing_name {
+                            if !c.is_control() && player_name.chars().count() < 16 {
+                                player_name.push(c);
                             }
+                        } else if c.is_ascii_alphanumeric() || matches!(c, '.' | ':' | '-' | '[' | ']') {
+                            server_address.push(c);
+                        }
+                    }
+                    if is_key_pressed(KeyCode::Backspace) {
+                        if editing_name {
+                            player_name.pop();
                         } else {
-                            right_paddle_vel *= 0.9; // Slow down when close to target
+                            server_address.pop();
+                        }
+                    }
+                    if is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::Down) {
+                        editing_name = !editing_name;
+                    } else if is_key_pressed(KeyCode::Enter) {
+                        net_error = None;
+                        match UdpTransport::join(&with_default_port(&server_address, SERVER_DEFAULT_PORT)) {
+                            Ok(transport) => {
+                                lobby = Some(LobbyClient::new(impair(Box::new(transport), net_conditions), &player_name));
+                                lobby_selected = 0;
+                            }
+                            Err(err) => net_error = Some(format!("Network error: {}", err)),
                         }
+                    } else if is_key_pressed(KeyCode::Escape) {
+                        game_state = GameState::ModeSelect;
                     }
                 }
+            }
+            
+            GameState::Playing => {
+                let dt = get_frame_time();
+                let mut powerup_events = PowerUpEvents::default();
+                let mut arena_events = ArenaEvents::default();
+                let mut clock_events = ClockEvents::default();
+                let mut spin_events = SpinEvents::default();
                 
-                // Clamp paddle velocities to maximum speed
-                left_paddle_vel = left_paddle_vel.clamp(-MAX_PADDLE_SPEED, MAX_PADDLE_SPEED);
-                right_paddle_vel = right_paddle_vel.clamp(-MAX_PADDLE_SPEED, MAX_PADDLE_SPEED);
-                
-                // Update paddle positions
-                left_paddle_y += left_paddle_vel * dt;
-                right_paddle_y += right_paddle_vel * dt;
-                
-                // Keep paddles within screen bounds
-                left_paddle_y = left_paddle_y.clamp(0.0, SCREEN_HEIGHT - PADDLE_HEIGHT);
-                right_paddle_y = right_paddle_y.clamp(0.0, SCREEN_HEIGHT - PADDLE_HEIGHT);
-                
-                // Stop velocity if hitting boundaries
-                if left_paddle_y <= 0.0 || left_paddle_y >= SCREEN_HEIGHT - PADDLE_HEIGHT {
-                    left_paddle_vel = 0.0;
-                }
-                if right_paddle_y <= 0.0 || right_paddle_y >= SCREEN_HEIGHT - PADDLE_HEIGHT {
-                    right_paddle_vel = 0.0;
-                }
-                
-                // Update ball position
-                ball_x += ball_vel_x * dt;
-                ball_y += ball_vel_y * dt;
+                let events = if let Some(session) = net_session.as_mut() {
+                    // Online: the session owns the fixed-step simulation
+                    // and either key set moves our paddle
+                    let intent = match key_intent(KeyCode::W, KeyCode::S) {
+                        Intent::Stay => key_intent(KeyCode::Up, KeyCode::Down),
+                        intent => intent,
+                    };
+                    let now = get_time();
+                    session.poll(now);
+                    if session.pause(now).is_some() {
+                        // A player dropped out; wait for them on the pause screen
+                        game_state = GameState::Paused;
+                    }
+                    net_accumulator = (net_accumulator + dt).min(0.25);
+                    let mut events = StepEvents::default();
+                    while net_accumulator >= NET_DT && game_state == GameState::Playing {
+                        net_accumulator -= NET_DT;
+                        events.merge(session.advance(intent, now));
+                    }
+                    let (view, events) = session.view(now, events);
+                    game = view;
+                    events
+                } else {
+                    // Reversed controls swap up and down while the power-up lasts
+                    let reverse = |side, intent| powerups.as_ref().map_or(intent, |p: &PowerUps| p.intent(side, intent));
+                    
+                    // Handle input and update paddle velocities with immediate response + buildup
+                    // Left paddle controls (W/S), unless an AI plays it in a tournament
+                    if game_mode != GameMode::Tournament || tournament_ai[0].is_none() {
+                        game.left.drive(reverse(Side::Left, key_intent(KeyCode::W, KeyCode::S)), dt);
+                    }
                 
-                // Ball collision with top and bottom walls
-                if ball_y <= BALL_SIZE || ball_y >= SCREEN_HEIGHT - BALL_SIZE {
-                    ball_vel_y = -ball_vel_y;
-                    ball_y = ball_y.clamp(BALL_SIZE, SCREEN_HEIGHT - BALL_SIZE);
-                }
+                    // Right paddle controls (Up/Down arrows or AI)
+                    match game_mode {
+                        GameMode::TwoPlayer => {
+                            game.right.drive(reverse(Side::Right, key_intent(KeyCode::Up, KeyCode::Down)), dt);
+                        }
+                        GameMode::VsAI => match (ai_opponent, &network) {
+                            (AiOpponent::Neural, Some(mlp)) => {
+                                let intent = mlp.decide(&game, Side::Right);
+                                game.right.drive(reverse(Side::Right, intent), dt);
+                            }
+                            _ => {
+                                // Add some random error to make AI beatable
+                                ai_player.drive(&mut game, dt, gen_range(-1.0, 1.0));
+                                if powerups.as_ref().is_some_and(|p| p.is_reversed(Side::Right)) {
+                                    game.right.vel = -game.right.vel;
+                                }
+                            }
+                        },
+                        GameMode::VsBot => {
+                            let intent = bot.as_mut().map_or(Intent::Stay, |bot| bot.decide(&game));
+                            game.right.drive(reverse(Side::Right, intent), dt);
+                        }
+                        GameMode::Tournament => {
+                            for (ai, side) in tournament_ai.iter_mut().zip([Side::Left, Side::Right]) {
+                                match ai {
+                                    Some(ai) => {
+                                        ai.drive(&mut game, dt, gen_range(-1.0, 1.0));
+                                        if powerups.as_ref().is_some_and(|p| p.is_reversed(side)) {
+                                            game.paddle_mut(side).vel = -game.paddle(side).vel;
+                                        }
+                                    }
+                                    None if side == Side::Right => {
+                                        game.right.drive(reverse(Side::Right, key_intent(KeyCode::Up, KeyCode::Down)), dt);
+                                    }
+                                    None => {}
+                                }
+                            }
+                        }
+                        GameMode::Gauntlet => {
+                            ai_player.drive(&mut game, dt, gen_range(-1.0, 1.0));
+                            gauntlet.tick(dt);
+                        }
+                        GameMode::Online | GameMode::Lobby => {} // Driven by the network session above
+                        GameMode::FourPlayer | GameMode::Doubles | GameMode::MultiBall | GameMode::Practice | GameMode::Challenges => {} // Played on their own screens
+                    }
                 
-                // Ball collision with left paddle
-                if ball_x - BALL_SIZE <= LEFT_PADDLE_X + PADDLE_WIDTH
-                    && ball_x + BALL_SIZE >= LEFT_PADDLE_X
-                    && ball_y + BALL_SIZE >= left_paddle_y
-                    && ball_y - BALL_SIZE <= left_paddle_y + PADDLE_HEIGHT
-                    && ball_vel_x < 0.0
-                {
-                    // Increase ball speed
-                    current_ball_speed = (current_ball_speed * BALL_SPEED_INCREASE).min(MAX_BALL_SPEED);
-                    
-                    // Calculate hit position (0.0 = top, 1.0 = bottom)
-                    let hit_pos = (ball_y - left_paddle_y) / PADDLE_HEIGHT;
+                    // Move paddles and ball, bounce and score
+                    if let Some(arena) = &arena_state {
+                        arena.guard_goals(&mut game, dt);
+                    }
+                    let mut events = match powerups.as_mut() {
+                        Some(powerups) => {
+                            let (events, extra) = powerups.step(&mut game, dt);
+                            powerup_events = extra;
+                            events
+                        }
+                        None => game.step(dt),
+                    };
                     
-                    // Factor in paddle velocity for more dynamic bounces
-                    let paddle_influence = left_paddle_vel * 0.1;
+                    // Curve the ball with any spin it carries
+                    if let Some(spin) = spin.as_mut() {
+                        spin_events = spin.step(&mut game, &events, dt);
+                    }
                     
-                    // Calculate new velocities with improved angle
-                    ball_vel_x = current_ball_speed;
-                    ball_vel_y = current_ball_speed * (hit_pos - 0.5) * 2.5 + paddle_influence;
+                    // Then bounce off the arena's obstacles
+                    if let Some(arena) = arena_state.as_mut() {
+                        arena_events = arena.step(&mut game, &events, dt);
+                        events.winner = events.winner.or(arena_events.winner);
+                    }
                     
-                    // Add screen shake and particles
-                    screen_shake = 0.15;
-                    for _ in 0..8 {
-                        particles.push(Particle::new(
-                            LEFT_PADDLE_X + PADDLE_WIDTH,
-                            ball_y,
-                            gen_range(50.0, 200.0),
-                            gen_range(-100.0, 100.0),
-                            gen_range(0.3, 0.8),
-                            gen_range(2.0, 5.0),
-                        ));
+                    // And count down a timed match
+                    if let Some(clock) = match_clock.as_mut() {
+                        clock_events = clock.step(&mut game, dt);
+                        events.winner = events.winner.or(clock_events.winner);
                     }
+                    events
+                };
+                
+                if let Some(obstacle) = arena_events.broken {
+                    let (x, y) = obstacle.center();
+                    screen_shake = 0.2;
+                    burst(&mut particles, x, y, 20, ORANGE);
+                } else if let Some(obstacle @ Obstacle::Bumper { .. }) = arena_events.bounced {
+                    let (x, y) = obstacle.center();
+                    burst(&mut particles, x, y, 6, PINK);
+                }
+                if arena_events.brick_point.is_some() {
+                    score_flash_timer = 0.5;
+                }
+                if let Some(kick) = spin_events.wall_kick {
+                    let color = if kick.abs() > 20.0 { SKYBLUE } else { GRAY };
+                    burst(&mut particles, game.ball.x, game.ball.y, 6, color);
+                }
+                if clock_events.overtime {
+                    screen_shake = 0.3;
+                    burst(&mut particles, BALL_START_X, BALL_START_Y, 24, ORANGE);
+                }
+                if arena_events.stalled {
+                    burst(&mut particles, BALL_START_X, BALL_START_Y, 12, GRAY);
                 }
                 
-                // Ball collision with right paddle
-                if ball_x + BALL_SIZE >= RIGHT_PADDLE_X
-                    && ball_x - BALL_SIZE <= RIGHT_PADDLE_X + PADDLE_WIDTH
-                    && ball_y + BALL_SIZE >= right_paddle_y
-                    && ball_y - BALL_SIZE <= right_paddle_y + PADDLE_HEIGHT
-                    && ball_vel_x > 0.0
-                {
-                    // Increase ball speed
-                    current_ball_speed = (current_ball_speed * BALL_SPEED_INCREASE).min(MAX_BALL_SPEED);
-                    
-                    // Calculate hit position (0.0 = top, 1.0 = bottom)
-                    let hit_pos = (ball_y - right_paddle_y) / PADDLE_HEIGHT;
-                    
-                    // Factor in paddle velocity for more dynamic bounces
-                    let paddle_influence = right_paddle_vel * 0.1;
-                    
-                    // Calculate new velocities with improved angle
-                    ball_vel_x = -current_ball_speed;
-                    ball_vel_y = current_ball_speed * (hit_pos - 0.5) * 2.5 + paddle_influence;
-                    
-                    // Add screen shake and particles
+                if let Some((pickup, _)) = powerup_events.collected {
+                    burst(&mut particles, pickup.x, pickup.y, 24, powerup_color(pickup.kind));
+                }
+                if let Some((side, y)) = powerup_events.shield_used {
+                    let x = match side {
+                        Side::Left => 0.0,
+                        Side::Right => SCREEN_WIDTH,
+                    };
+                    burst(&mut particles, x, y, 16, powerup_color(powerups::Kind::Shield));
+                }
+                if let Some(effect) = powerup_events.expired {
+                    let paddle = game.paddle(effect.side);
+                    let x = match effect.side {
+                        Side::Left => LEFT_PADDLE_X,
+                        Side::Right => RIGHT_PADDLE_X,
+                    };
+                    burst(&mut particles, x, paddle.y + paddle.height / 2.0, 8, GRAY);
+                }
+                
+                // Add screen shake and particles
+                if let Some((side, hit_y)) = events.paddle_hit {
                     screen_shake = 0.15;
+                    let (x, vel_x) = match side {
+                        Side::Left => (LEFT_PADDLE_X + PADDLE_WIDTH, (50.0, 200.0)),
+                        Side::Right => (RIGHT_PADDLE_X, (-200.0, -50.0)),
+                    };
                     for _ in 0..8 {
                         particles.push(Particle::new(
-                            RIGHT_PADDLE_X,
-                            ball_y,
-                            gen_range(-200.0, -50.0),
+                            x,
+                            hit_y,
+                            gen_range(vel_x.0, vel_x.1),
                             gen_range(-100.0, 100.0),
                             gen_range(0.3, 0.8),
                             gen_range(2.0, 5.0),
@@ -376,32 +1705,26 @@ async fn main() {
                     }
                 }
                 
-                // Check for scoring and reset ball
-                if ball_x < -BALL_SIZE {
-                    right_score += 1;
-                    ball_x = BALL_START_X;
-                    ball_y = BALL_START_Y;
-                    ball_vel_x = BALL_SPEED;
-                    ball_vel_y = BALL_SPEED * 0.5;
-                    current_ball_speed = BALL_SPEED; // Reset speed
+                if events.scored.is_some() {
                     score_flash_timer = 0.5; // Flash effect
                     screen_shake = 0.3; // Strong shake for scoring
-                    
-                    if right_score >= WINNING_SCORE {
-                        game_state = GameState::GameOver;
-                    }
-                } else if ball_x > SCREEN_WIDTH + BALL_SIZE {
-                    left_score += 1;
-                    ball_x = BALL_START_X;
-                    ball_y = BALL_START_Y;
-                    ball_vel_x = -BALL_SPEED;
-                    ball_vel_y = BALL_SPEED * 0.5;
-                    current_ball_speed = BALL_SPEED; // Reset speed
-                    score_flash_timer = 0.5; // Flash effect
-                    screen_shake = 0.3; // Strong shake for scoring
-                    
-                    if left_score >= WINNING_SCORE {
-                        game_state = GameState::GameOver;
+                }
+                if let Some(winner) = events.winner {
+                    if let Some(bot) = bot.as_mut() {
+                        bot.finish(winner);
+                    }
+                    if let (GameMode::Tournament, Some(tournament)) = (game_mode, tournament.as_mut()) {
+                        tournament_error = tournament
+                            .record(game.left_score, game.right_score)
+                            .err()
+                            .or_else(|| tournament.save(TOURNAMENT_FILE).err().map(|err| format!("Could not save {}: {}", TOURNAMENT_FILE, err)));
+                    }
+                    game_state = GameState::GameOver;
+                    if game_mode == GameMode::Gauntlet {
+                        // Straight to the between-rounds screen
+                        gauntlet.finish_match(game.left_score, winner == Side::Left);
+                        particles.clear();
+                        game_state = GameState::Gauntlet;
                     }
                 }
                 
@@ -430,22 +1753,48 @@ async fn main() {
                     }
                 }
                 
+                if let Some(arena) = &arena_state {
+                    draw_arena(&arena.arena, shake_x, shake_y);
+                }
+                
                 // Draw game objects with screen shake
                 draw_rectangle(
                     LEFT_PADDLE_X + shake_x,
-                    left_paddle_y + shake_y,
+                    game.left.y + shake_y,
                     PADDLE_WIDTH,
-                    PADDLE_HEIGHT,
+                    game.left.height,
                     WHITE,
                 );
                 draw_rectangle(
                     RIGHT_PADDLE_X + shake_x,
-                    right_paddle_y + shake_y,
+                    game.right.y + shake_y,
                     PADDLE_WIDTH,
-                    PADDLE_HEIGHT,
+                    game.right.height,
                     WHITE,
                 );
-                draw_circle(ball_x + shake_x, ball_y + shake_y, BALL_SIZE, WHITE);
+                draw_circle(game.ball.x + shake_x, game.ball.y + shake_y, BALL_SIZE, WHITE);
+                if let Some(spin) = &spin {
+                    draw_spin_marker(game.ball.x + shake_x, game.ball.y + shake_y, spin);
+                }
+                
+                // Draw the waiting pickup and any shields on the goal lines
+                if let Some(powerups) = &powerups {
+                    if let Some(pickup) = powerups.pickup {
+                        let pulse = 1.0 + (get_time() as f32 * 6.0).sin() * 0.1;
+                        let color = powerup_color(pickup.kind);
+                        draw_circle_lines(pickup.x + shake_x, pickup.y + shake_y, powerups::PICKUP_RADIUS * pulse, 3.0, color);
+                        draw_circle(pickup.x + shake_x, pickup.y + shake_y, powerups::PICKUP_RADIUS * 0.4, color);
+                    }
+                    for side in [Side::Left, Side::Right] {
+                        if powerups.is_active(powerups::Kind::Shield, side) {
+                            let x = match side {
+                                Side::Left => 0.0,
+                                Side::Right => SCREEN_WIDTH - 4.0,
+                            };
+                            draw_rectangle(x + shake_x, shake_y, 4.0, SCREEN_HEIGHT, powerup_color(powerups::Kind::Shield));
+                        }
+                    }
+                }
                 
                 // Draw particles
                 for particle in &particles {
@@ -453,7 +1802,7 @@ async fn main() {
                 }
                 
                 // Draw scores with flash effect
-                let score_text = format!("{}    {}", left_score, right_score);
+                let score_text = format!("{}    {}", game.left_score, game.right_score);
                 let score_width = measure_text(&score_text, None, 60, 1.0).width;
                 let score_color = if score_flash_timer > 0.0 {
                     let flash = (score_flash_timer * 10.0).sin().abs();
@@ -469,6 +1818,31 @@ async fn main() {
                     score_color,
                 );
                 
+                // Time left in a timed match
+                if let Some(timer) = &match_clock {
+                    if timer.overtime {
+                        draw_centered_text("OVERTIME - Next Point Wins", 40.0, 26, ORANGE);
+                    } else {
+                        let color = if timer.remaining < 10.0 { RED } else { WHITE };
+                        draw_centered_text(&clock(timer.remaining.ceil()), 40.0, 30, color);
+                    }
+                }
+                
+                // Active power-ups under each player's score, with the time left
+                if let Some(powerups) = &powerups {
+                    let mut rows = [0.0, 0.0];
+                    for effect in &powerups.effects {
+                        let text = format!("{} {:.0}s", effect.kind.name(), effect.remaining.ceil());
+                        let width = measure_text(&text, None, 18, 1.0).width;
+                        let (x, row) = match effect.side {
+                            Side::Left => (SCREEN_WIDTH / 2.0 - 70.0 - width, &mut rows[0]),
+                            Side::Right => (SCREEN_WIDTH / 2.0 + 70.0, &mut rows[1]),
+                        };
+                        draw_text(&text, x, 130.0 + *row, 18.0, powerup_color(effect.kind));
+                        *row += 20.0;
+                    }
+                }
+                
                 // Draw control instructions based on mode
                 match game_mode {
                     GameMode::TwoPlayer => {
@@ -477,66 +1851,160 @@ async fn main() {
                     GameMode::VsAI => {
                         draw_text("Player: W/S  P: Pause", 20.0, 20.0, 18.0, GRAY);
                     }
+                    GameMode::VsBot => {
+                        draw_text("Player: W/S  P: Pause", 20.0, 20.0, 18.0, GRAY);
+                        if bot.as_ref().is_some_and(|bot| !bot.is_alive()) {
+                            draw_text("Bot disconnected", 20.0, 40.0, 18.0, RED);
+                        }
+                    }
+                    GameMode::Tournament => {
+                        if let Some(tournament) = &tournament {
+                            let label = |index: usize, keys: &str| {
+                                let entrant = &tournament.entrants[tournament_players[index]];
+                                match entrant.controller {
+                                    Controller::Human => format!("{} ({})", entrant.name, keys),
+                                    Controller::Ai(_) => entrant.name.clone(),
+                                }
+                            };
+                            let controls = format!("{}  vs  {}  P: Pause", label(0, "W/S"), label(1, "Up/Down"));
+                            draw_text(&controls, 20.0, 20.0, 18.0, GRAY);
+                        }
+                    }
+                    GameMode::Gauntlet => {
+                        draw_text("Player: W/S  P: Pause", 20.0, 20.0, 18.0, GRAY);
+                        let stage = format!("{}  Lives: {}  {}", gauntlet.opponent(), gauntlet.lives, clock(gauntlet.elapsed));
+                        draw_text(&stage, SCREEN_WIDTH - 260.0, 20.0, 18.0, GRAY);
+                    }
+                    GameMode::FourPlayer | GameMode::Doubles | GameMode::MultiBall | GameMode::Practice | GameMode::Challenges => {} // Have their own screens and HUDs
+                    GameMode::Online | GameMode::Lobby => {
+                        if let Some(NetSession::Spectate(spectator)) = &net_session {
+                            draw_spectator_hud(spectator);
+                        } else if let Some(session) = &net_session {
+                            let side = match session.side() {
+                                Some(Side::Left) => "Left",
+                                _ => "Right",
+                            };
+                            draw_text(&format!("Online ({})  W/S or Up/Down", side), 20.0, 20.0, 18.0, GRAY);
+                            let ping = session.ping_ms().map_or("--".to_string(), |ms| format!("{:.0}", ms));
+                            draw_text(&format!("Ping: {} ms", ping), SCREEN_WIDTH - 130.0, 20.0, 18.0, GRAY);
+                            // Save the desync report, again once the peer's state arrives
+                            if let Some(report) = session.desync() {
+                                let complete = report.remote.is_some();
+                                if desync_saved.as_ref().is_none_or(|(_, saved)| complete && !saved) {
+                                    let file = match report.save() {
+                                        Ok(path) => path.display().to_string(),
+                                        Err(err) => format!("not saved ({})", err),
+                                    };
+                                    desync_saved = Some((file, complete));
+                                }
+                                if let Some((file, _)) = &desync_saved {
+                                    let text = format!("DESYNC at tick {} - report: {}", report.frame, file);
+                                    draw_text(&text, 20.0, 40.0, 18.0, RED);
+                                }
+                            }
+                            if let Some(c) = net_conditions {
+                                let simulated = format!(
+                                    "netsim {:.0}+{:.0} ms, {:.0}% loss",
+                                    c.latency * 1000.0,
+                                    c.jitter * 1000.0,
+                                    c.loss * 100.0
+                                );
+                                draw_text(&simulated, SCREEN_WIDTH - 200.0, 40.0, 16.0, ORANGE);
+                            }
+                        }
+                    }
                 }
                 
-                // Handle pause
-                if is_key_pressed(KeyCode::P) {
+                // Handle pause (not available online, the peer keeps playing)
+                if is_key_pressed(KeyCode::P) && !game_mode.is_online() {
                     game_state = GameState::Paused;
                 }
+                if editor_testing {
+                    draw_text("TAB: Back to Editor", SCREEN_WIDTH - 170.0, 20.0, 18.0, GRAY);
+                    if is_key_pressed(KeyCode::Tab) {
+                        game_state = GameState::Editor;
+                    }
+                }
             }
             
             GameState::Paused => {
-                // Draw game objects (frozen)
-                draw_rectangle(LEFT_PADDLE_X, left_paddle_y, PADDLE_WIDTH, PADDLE_HEIGHT, WHITE);
-                draw_rectangle(RIGHT_PADDLE_X, right_paddle_y, PADDLE_WIDTH, PADDLE_HEIGHT, WHITE);
-                draw_circle(ball_x, ball_y, BALL_SIZE, WHITE);
-                
-                // Draw scores
-                let score_text = format!("{}    {}", left_score, right_score);
-                let score_width = measure_text(&score_text, None, 60, 1.0).width;
-                draw_text(
-                    &score_text,
-                    (SCREEN_WIDTH - score_width) / 2.0,
-                    100.0,
-                    60.0,
-                    WHITE,
-                );
-                
-                // Draw pause overlay
-                let pause_text = "PAUSED";
-                let pause_width = measure_text(pause_text, None, 60, 1.0).width;
-                draw_text(
-                    pause_text,
-                    (SCREEN_WIDTH - pause_width) / 2.0,
-                    SCREEN_HEIGHT / 2.0,
-                    60.0,
-                    YELLOW,
-                );
-                
-                let resume_text = "Press P to Resume";
-                let resume_width = measure_text(resume_text, None, 30, 1.0).width;
-                draw_text(
-                    resume_text,
-                    (SCREEN_WIDTH - resume_width) / 2.0,
-                    SCREEN_HEIGHT / 2.0 + 60.0,
-                    30.0,
-                    GRAY,
-                );
-                
-                // Handle resume
-                if is_key_pressed(KeyCode::P) {
-                    game_state = GameState::Playing;
+                if let Some(session) = net_session.as_mut() {
+                    // Online: paused until the missing player is back, with a countdown to the forfeit
+                    let now = get_time();
+                    session.poll(now);
+                    match session.pause(now) {
+                        Some((side, remaining)) if Some(side) == session.side() => {
+                            draw_paused(&game, &format!("Connection lost - reconnecting ({:.0} s)", remaining.ceil()));
+                            // Our end dropped: rejoin from a fresh socket, the server knows our token
+                            if let NetSession::Server(client) = session {
+                                if now >= net_retry_at {
+                                    net_retry_at = now + 2.0;
+                                    if let Ok(transport) = UdpTransport::join(&with_default_port(&server_address, SERVER_DEFAULT_PORT)) {
+                                        client.reconnect(impair(Box::new(transport), net_conditions));
+                                    }
+                                }
+                            }
+                            if remaining <= 0.0 {
+                                net_forfeit = Some(side);
+                                game_state = GameState::GameOver;
+                            }
+                        }
+                        Some((_, remaining)) if matches!(session, NetSession::Rollback(_)) => {
+                            // Both peers see the other one go quiet and can't tell
+                            // whose link failed, so neither may claim the win
+                            draw_paused(&game, &format!("Connection lost - waiting {:.0} s", remaining.ceil()));
+                            // In case it was ours, the joining peer greets the host
+                            // again from a fresh socket; the host takes the new address
+                            if let NetSession::Rollback(peer) = session {
+                                if peer.side() == Side::Right && net_choice == NetChoice::Join && now >= net_retry_at {
+                                    net_retry_at = now + 2.0;
+                                    if let Ok(transport) = UdpTransport::join(&with_default_port(&join_address, NET_DEFAULT_PORT)) {
+                                        peer.reconnect(impair(Box::new(transport), net_conditions));
+                                    }
+                                }
+                            }
+                            if remaining <= 0.0 {
+                                net_lost = true;
+                                game_state = GameState::GameOver;
+                            }
+                        }
+                        Some((_, remaining)) => {
+                            // The server sends the result when the time is up
+                            draw_paused(&game, &format!("Opponent disconnected - forfeit in {:.0} s", remaining.ceil()));
+                        }
+                        None => {
+                            draw_paused(&game, "Resuming...");
+                            game_state = GameState::Playing;
+                            net_accumulator = 0.0;
+                        }
+                    }
+                } else {
+                    if let Some(arena) = &arena_state {
+                        draw_arena(&arena.arena, 0.0, 0.0);
+                    }
+                    draw_paused(&game, "Press P to Resume");
+                    
+                    // Handle resume
+                    if is_key_pressed(KeyCode::P) {
+                        game_state = GameState::Playing;
+                    }
                 }
             }
             
             GameState::GameOver => {
                 // Draw game objects (frozen)
-                draw_rectangle(LEFT_PADDLE_X, left_paddle_y, PADDLE_WIDTH, PADDLE_HEIGHT, WHITE);
-                draw_rectangle(RIGHT_PADDLE_X, right_paddle_y, PADDLE_WIDTH, PADDLE_HEIGHT, WHITE);
-                draw_circle(ball_x, ball_y, BALL_SIZE, WHITE);
+                if let Some(arena) = &arena_state {
+                    draw_arena(&arena.arena, 0.0, 0.0);
+                }
+                draw_rectangle(LEFT_PADDLE_X, game.left.y, PADDLE_WIDTH, game.left.height, WHITE);
+                draw_rectangle(RIGHT_PADDLE_X, game.right.y, PADDLE_WIDTH, game.right.height, WHITE);
+                draw_circle(game.ball.x, game.ball.y, BALL_SIZE, WHITE);
+                if let Some(spin) = &spin {
+                    draw_spin_marker(game.ball.x, game.ball.y, spin);
+                }
                 
                 // Draw scores
-                let score_text = format!("{}    {}", left_score, right_score);
+                let score_text = format!("{}    {}", game.left_score, game.right_score);
                 let score_width = measure_text(&score_text, None, 60, 1.0).width;
                 draw_text(
                     &score_text,
@@ -547,21 +2015,67 @@ async fn main() {
                 );
                 
                 // Draw winner
+                let named_winner: String;
                 let winner = match game_mode {
                     GameMode::TwoPlayer => {
-                        if left_score >= WINNING_SCORE {
+                        if game.winner() == Some(Side::Left) {
                             "Left Player Wins!"
                         } else {
                             "Right Player Wins!"
                         }
                     }
                     GameMode::VsAI => {
-                        if left_score >= WINNING_SCORE {
+                        if game.winner() == Some(Side::Left) {
                             "You Win!"
                         } else {
                             "AI Wins!"
                         }
                     }
+                    GameMode::VsBot => {
+                        if game.winner() == Some(Side::Left) {
+                            "You Win!"
+                        } else {
+                            "Bot Wins!"
+                        }
+                    }
+                    GameMode::Tournament => {
+                        let index = if game.winner() == Some(Side::Left) { 0 } else { 1 };
+                        named_winner = match &tournament {
+                            Some(tournament) => format!("{} Wins!", tournament.entrants[tournament_players[index]].name),
+                            None => "Match Over".to_string(),
+                        };
+                        &named_winner
+                    }
+                    GameMode::FourPlayer
+                    | GameMode::Doubles
+                    | GameMode::MultiBall
+                    | GameMode::Practice
+                    | GameMode::Gauntlet
+                    | GameMode::Challenges => {
+                        unreachable!("these matches end on their own screens")
+                    }
+                    GameMode::Online | GameMode::Lobby => match &net_session {
+                        Some(NetSession::Spectate(spectator)) => {
+                            draw_spectator_hud(spectator);
+                            named_winner = format!("{} Wins!", spectator.name(game.winner().unwrap_or(Side::Left)));
+                            &named_winner
+                        }
+                        session => {
+                            let local_side = session.as_ref().and_then(|session| session.side()).unwrap_or(Side::Left);
+                            let forfeit = net_forfeit.or_else(|| session.as_ref().and_then(NetSession::forfeited));
+                            if net_lost {
+                                "Connection Lost - No Result"
+                            } else if forfeit == Some(local_side) {
+                                "Connection Lost - You Forfeit"
+                            } else if forfeit.is_some() {
+                                "Opponent Left - You Win!"
+                            } else if game.winner() == Some(local_side) {
+                                "You Win!"
+                            } else {
+                                "Opponent Wins!"
+                            }
+                        }
+                    },
                 };
                 let win_width = measure_text(winner, None, 40, 1.0).width;
                 draw_text(
@@ -572,15 +2086,33 @@ async fn main() {
                     YELLOW,
                 );
                 
-                let restart_text = "Press R to Restart";
-                let restart_width = measure_text(restart_text, None, 30, 1.0).width;
-                draw_text(
-                    restart_text,
-                    (SCREEN_WIDTH - restart_width) / 2.0,
-                    SCREEN_HEIGHT / 2.0 + 60.0,
-                    30.0,
-                    GRAY,
-                );
+                // Keep answering the peer so it can confirm the result too
+                if let Some(session) = net_session.as_mut() {
+                    session.poll(get_time());
+                }
+                
+                if game_mode == GameMode::Lobby {
+                    draw_centered_text("Press L to Return to the Lobby", SCREEN_HEIGHT / 2.0 + 60.0, 30, GRAY);
+                } else if game_mode == GameMode::Tournament {
+                    draw_centered_text("Press SPACE for the Bracket", SCREEN_HEIGHT / 2.0 + 60.0, 30, GRAY);
+                    if let Some(error) = &tournament_error {
+                        draw_centered_text(error, SCREEN_HEIGHT / 2.0 + 140.0, 18, RED);
+                    }
+                    if is_key_pressed(KeyCode::Space) {
+                        particles.clear();
+                        game_state = GameState::Bracket;
+                    }
+                } else if !game_mode.is_online() {
+                    let restart_text = "Press R to Restart";
+                    let restart_width = measure_text(restart_text, None, 30, 1.0).width;
+                    draw_text(
+                        restart_text,
+                        (SCREEN_WIDTH - restart_width) / 2.0,
+                        SCREEN_HEIGHT / 2.0 + 60.0,
+                        30.0,
+                        GRAY,
+                    );
+                }
                 
                 let menu_text = "Press M to Change Mode";
                 let menu_width = measure_text(menu_text, None, 30, 1.0).width;
@@ -591,49 +2123,1150 @@ async fn main() {
                     30.0,
                     GRAY,
                 );
+                if editor_testing {
+                    draw_centered_text("Press TAB to Return to the Editor", SCREEN_HEIGHT / 2.0 + 140.0, 30, GRAY);
+                    if is_key_pressed(KeyCode::Tab) {
+                        game_state = GameState::Editor;
+                    }
+                }
                 
                 // Handle restart/menu
-                if is_key_pressed(KeyCode::R) {
-                    // Reset game
-                    left_score = 0;
-                    right_score = 0;
-                    ball_x = BALL_START_X;
-                    ball_y = BALL_START_Y;
-                    ball_vel_x = BALL_SPEED;
-                    ball_vel_y = BALL_SPEED * 0.5;
-                    current_ball_speed = BALL_SPEED;
-                    left_paddle_y = PADDLE_START_Y;
-                    right_paddle_y = PADDLE_START_Y;
-                    left_paddle_vel = 0.0;
-                    right_paddle_vel = 0.0;
+                if is_key_pressed(KeyCode::R) && !game_mode.is_online() && game_mode != GameMode::Tournament {
+                    // Give the bot a fresh process for the rematch; if it won't
+                    // start, stay here and say why
+                    bot_error = None;
+                    if game_mode == GameMode::VsBot {
+                        let command = bot_command.as_deref().unwrap_or_default();
+                        bot = None;
+                        match BotProcess::spawn(command, Side::Right, Duration::from_millis(BOT_TIMEOUT_MS)) {
+                            Ok(process) => bot = Some(process),
+                            Err(err) => bot_error = Some(format!("Could not start bot: {}", err)),
+                        }
+                    }
+                    if bot_error.is_none() {
+                        // Reset game
+                        game.reset();
+                        match_clock = MatchRule::PRESETS[match_rule].apply(&mut game);
+                        powerups = powerups_enabled.then(|| PowerUps::new(macroquad::rand::rand() as u64));
+                        spin = spin_enabled.then(Spin::new);
+                        let mut arena = ArenaState::new(match_arena.clone(), brick_points);
+                        arena.serve(&mut game.ball);
+                        arena_state = Some(arena);
+                        particles.clear();
+                        screen_shake = 0.0;
+                        score_flash_timer = 0.0;
+                        game_state = GameState::Playing;
+                    }
+                }
+                if let Some(error) = &bot_error {
+                    draw_centered_text(error, SCREEN_HEIGHT / 2.0 + 175.0, 18, RED);
+                }
+                if is_key_pressed(KeyCode::L) && game_mode == GameMode::Lobby {
+                    // Back to our seat on the same connection, ready for the next match
+                    if let (Some(NetSession::Server(client)), Some(room)) = (net_session.take(), lobby_room) {
+                        let mut client = LobbyClient::new(client.into_transport(), &player_name);
+                        client.enter(room);
+                        lobby = Some(client);
+                    }
+                    game = Match::new();
                     particles.clear();
-                    screen_shake = 0.0;
-                    score_flash_timer = 0.0;
-                    game_state = GameState::Playing;
+                    game_state = GameState::Lobby;
                 }
                 if is_key_pressed(KeyCode::M) {
                     // Reset game and go to mode select
-                    left_score = 0;
-                    right_score = 0;
-                    ball_x = BALL_START_X;
-                    ball_y = BALL_START_Y;
-                    ball_vel_x = BALL_SPEED;
-                    ball_vel_y = BALL_SPEED * 0.5;
-                    current_ball_speed = BALL_SPEED;
-                    left_paddle_y = PADDLE_START_Y;
-                    right_paddle_y = PADDLE_START_Y;
-                    left_paddle_vel = 0.0;
-                    right_paddle_vel = 0.0;
+                    game.reset();
                     particles.clear();
                     screen_shake = 0.0;
                     score_flash_timer = 0.0;
+                    net_session = None;
+                    relay_pending = None;
+                    lobby = None;
+                    lobby_room = None;
+                    game_state = GameState::ModeSelect;
+                }
+            }
+            
+            GameState::FourPlayer => {
+                let dt = get_frame_time();
+                // One key pair per seat; the top and bottom paddles take the first key for left
+                let keys = [
+                    (KeyCode::W, KeyCode::S),
+                    (KeyCode::Up, KeyCode::Down),
+                    (KeyCode::J, KeyCode::L),
+                    (KeyCode::Left, KeyCode::Right),
+                ];
+                let key_names = ["W/S", "Up/Down", "J/L", "Left/Right"];
+                let colors = [SKYBLUE, ORANGE, GREEN, PINK];
+                
+                if quad.winner().is_none() && !quad_paused {
+                    for (wall, (up, down)) in Wall::ALL.into_iter().zip(keys).take(quad_humans) {
+                        quad.paddles[wall as usize].drive(key_intent(up, down), dt);
+                    }
+                    for ai in quad_ai.iter_mut() {
+                        ai.drive(&mut quad, dt, gen_range(-1.0, 1.0));
+                    }
+                    let events = quad.step(dt);
+                    
+                    // Sparks fly back into the field
+                    if let Some((wall, x, y)) = events.paddle_hit {
+                        screen_shake = 0.15;
+                        let (vel_x, vel_y) = match wall {
+                            Wall::Left => ((50.0, 200.0), (-100.0, 100.0)),
+                            Wall::Right => ((-200.0, -50.0), (-100.0, 100.0)),
+                            Wall::Top => ((-100.0, 100.0), (50.0, 200.0)),
+                            Wall::Bottom => ((-100.0, 100.0), (-200.0, -50.0)),
+                        };
+                        for _ in 0..8 {
+                            particles.push(Particle::new(
+                                x,
+                                y,
+                                gen_range(vel_x.0, vel_x.1),
+                                gen_range(vel_y.0, vel_y.1),
+                                gen_range(0.3, 0.8),
+                                gen_range(2.0, 5.0),
+                            ));
+                        }
+                    }
+                    if events.scored.is_some() {
+                        score_flash_timer = 0.5;
+                        screen_shake = 0.3;
+                    }
+                }
+                
+                // Update visual effects
+                screen_shake = (screen_shake - dt * 10.0).max(0.0);
+                score_flash_timer = (score_flash_timer - dt).max(0.0);
+                particles.retain_mut(|particle| particle.update(dt));
+                
+                // The square field sits in the middle of the screen
+                let shake = if screen_shake > 0.0 { gen_range(-screen_shake * 10.0, screen_shake * 10.0) } else { 0.0 };
+                let origin_x = (SCREEN_WIDTH - FIELD_SIZE) / 2.0 + shake;
+                let origin_y = shake;
+                draw_rectangle_lines(origin_x, origin_y, FIELD_SIZE, FIELD_SIZE, 2.0, DARKGRAY);
+                for (index, wall) in Wall::ALL.into_iter().enumerate() {
+                    if quad.is_out(wall) {
+                        // Knocked out: the goal is walled off
+                        let (x, y, w, h) = match wall {
+                            Wall::Left => (0.0, 0.0, 6.0, FIELD_SIZE),
+                            Wall::Right => (FIELD_SIZE - 6.0, 0.0, 6.0, FIELD_SIZE),
+                            Wall::Top => (0.0, 0.0, FIELD_SIZE, 6.0),
+                            Wall::Bottom => (0.0, FIELD_SIZE - 6.0, FIELD_SIZE, 6.0),
+                        };
+                        draw_rectangle(origin_x + x, origin_y + y, w, h, GRAY);
+                    } else {
+                        let (x, y, w, h) = quad.paddle_rect(wall);
+                        draw_rectangle(origin_x + x, origin_y + y, w, h, colors[index]);
+                    }
+                }
+                draw_circle(origin_x + quad.ball.x, origin_y + quad.ball.y, BALL_SIZE, WHITE);
+                for particle in &particles {
+                    let mut particle = particle.clone();
+                    particle.x += origin_x;
+                    particle.y += origin_y;
+                    particle.draw();
+                }
+                
+                // Scoreboard in the margins: left and top on the left, right and bottom on the right
+                for (index, wall) in Wall::ALL.into_iter().enumerate() {
+                    let x = if index % 2 == 0 { 10.0 } else { SCREEN_WIDTH - (SCREEN_WIDTH - FIELD_SIZE) / 2.0 + 10.0 };
+                    let y = 40.0 + (index / 2) as f32 * 100.0;
+                    let player = if index < quad_humans { key_names[index] } else { "AI" };
+                    draw_text(&format!("{} ({})", wall.name(), player), x, y, 18.0, colors[index]);
+                    let conceded = quad.conceded[wall as usize];
+                    let (status, color) = if quad.is_out(wall) {
+                        ("OUT".to_string(), GRAY)
+                    } else if score_flash_timer > 0.0 {
+                        let flash = (score_flash_timer * 10.0).sin().abs();
+                        (format!("{} / {}", conceded, quad.point_limit), Color::new(1.0, flash, flash, 1.0))
+                    } else {
+                        (format!("{} / {}", conceded, quad.point_limit), WHITE)
+                    };
+                    draw_text(&status, x, y + 28.0, 28.0, color);
+                }
+                draw_text("Goals let in", 10.0, SCREEN_HEIGHT - 40.0, 16.0, GRAY);
+                draw_text("P: Pause", 10.0, SCREEN_HEIGHT - 20.0, 16.0, GRAY);
+                
+                if let Some(winner) = quad.winner() {
+                    let who = if (winner as usize) < quad_humans { "" } else { " (AI)" };
+                    draw_centered_text(&format!("{}{} Wins!", winner.name(), who), SCREEN_HEIGHT / 2.0, 40, YELLOW);
+                    draw_centered_text("Press R to Restart", SCREEN_HEIGHT / 2.0 + 60.0, 30, GRAY);
+                    draw_centered_text("Press M to Change Mode", SCREEN_HEIGHT / 2.0 + 100.0, 30, GRAY);
+                    if is_key_pressed(KeyCode::R) {
+                        quad = QuadMatch::default();
+                        particles.clear();
+                    } else if is_key_pressed(KeyCode::M) {
+                        particles.clear();
+                        game_state = GameState::ModeSelect;
+                    }
+                } else if quad_paused {
+                    draw_centered_text("PAUSED", SCREEN_HEIGHT / 2.0, 60, YELLOW);
+                    draw_centered_text("Press P to Resume", SCREEN_HEIGHT / 2.0 + 60.0, 30, GRAY);
+                }
+                if is_key_pressed(KeyCode::P) && quad.winner().is_none() {
+                    quad_paused = !quad_paused;
+                }
+            }
+            
+            GameState::Doubles => {
+                let dt = get_frame_time();
+                // Keys by seat, in `doubles_seats` order
+                let keys = [
+                    (KeyCode::W, KeyCode::S),
+                    (KeyCode::Up, KeyCode::Down),
+                    (KeyCode::T, KeyCode::G),
+                    (KeyCode::I, KeyCode::K),
+                ];
+                let key_names = ["W/S", "Up/Down", "T/G", "I/K"];
+                let colors = [SKYBLUE, BLUE, ORANGE, GOLD]; // By `Seat` order, one shade per team
+                let controller = |seat: Seat| {
+                    let index = doubles_seats.iter().position(|&s| s == seat).unwrap_or(0);
+                    if index < doubles_humans { key_names[index] } else { "AI" }
+                };
+                
+                if doubles.winner().is_none() && !doubles_paused {
+                    for (seat, (up, down)) in doubles_seats.into_iter().zip(keys).take(doubles_humans) {
+                        doubles.paddles[seat as usize].drive(key_intent(up, down), dt);
+                    }
+                    for ai in doubles_ai.iter_mut() {
+                        ai.drive(&mut doubles, dt, gen_range(-1.0, 1.0));
+                    }
+                    let events = doubles.step(dt);
+                    
+                    if let Some((seat, hit_y)) = events.paddle_hit {
+                        screen_shake = 0.15;
+                        let (x, vel_x) = match seat.side() {
+                            Side::Left => (seat.x() + PADDLE_WIDTH, (50.0, 200.0)),
+                            Side::Right => (seat.x(), (-200.0, -50.0)),
+                        };
+                        for _ in 0..8 {
+                            particles.push(Particle::new(
+                                x,
+                                hit_y,
+                                gen_range(vel_x.0, vel_x.1),
+                                gen_range(-100.0, 100.0),
+                                gen_range(0.3, 0.8),
+                                gen_range(2.0, 5.0),
+                            ));
+                        }
+                    }
+                    if events.scored.is_some() {
+                        score_flash_timer = 0.5;
+                        screen_shake = 0.3;
+                    }
+                }
+                
+                // Update visual effects
+                screen_shake = (screen_shake - dt * 10.0).max(0.0);
+                score_flash_timer = (score_flash_timer - dt).max(0.0);
+                particles.retain_mut(|particle| particle.update(dt));
+                let shake_x = if screen_shake > 0.0 { gen_range(-screen_shake * 10.0, screen_shake * 10.0) } else { 0.0 };
+                let shake_y = if screen_shake > 0.0 { gen_range(-screen_shake * 10.0, screen_shake * 10.0) } else { 0.0 };
+                
+                // Field: center line, four paddles, ball
+                for i in 0..20 {
+                    let y = i as f32 * 30.0 + 10.0;
+                    draw_rectangle(SCREEN_WIDTH / 2.0 - 2.0 + shake_x, y + shake_y, 4.0, 15.0, GRAY);
+                }
+                for seat in Seat::ALL {
+                    let paddle = &doubles.paddles[seat as usize];
+                    draw_rectangle(seat.x() + shake_x, paddle.y + shake_y, PADDLE_WIDTH, paddle.height, colors[seat as usize]);
+                }
+                draw_circle(doubles.ball.x + shake_x, doubles.ball.y + shake_y, BALL_SIZE, WHITE);
+                for particle in &particles {
+                    particle.draw();
+                }
+                
+                let score_text = format!("{}    {}", doubles.left_score, doubles.right_score);
+                let score_color = if score_flash_timer > 0.0 {
+                    let flash = (score_flash_timer * 10.0).sin().abs();
+                    Color::new(1.0, flash, flash, 1.0)
+                } else {
+                    WHITE
+                };
+                draw_centered_text(&score_text, 100.0, 60, score_color);
+                
+                // Who plays which paddle, with hit counts
+                for seat in Seat::ALL {
+                    let stats = doubles.stats[seat as usize];
+                    let label = format!("{} ({}): {} hits", seat.name(), controller(seat), stats.hits);
+                    let x = match seat {
+                        Seat::LeftBack => 20.0,
+                        Seat::LeftFront => 210.0,
+                        Seat::RightFront => SCREEN_WIDTH / 2.0 + 20.0,
+                        Seat::RightBack => SCREEN_WIDTH / 2.0 + 210.0,
+                    };
+                    draw_text(&label, x, SCREEN_HEIGHT - 12.0, 16.0, colors[seat as usize]);
+                }
+                draw_text("P: Pause", 20.0, 20.0, 18.0, GRAY);
+                
+                if let Some(winner) = doubles.winner() {
+                    let team = match winner {
+                        Side::Left => "Left Team Wins!",
+                        Side::Right => "Right Team Wins!",
+                    };
+                    draw_centered_text(team, SCREEN_HEIGHT / 2.0 - 80.0, 40, YELLOW);
+                    for (row, seat) in Seat::ALL.into_iter().enumerate() {
+                        let stats = doubles.stats[seat as usize];
+                        let line = format!("{:<12} {:>4} hits {:>3} points", seat.name(), stats.hits, stats.points);
+                        draw_centered_text(&line, SCREEN_HEIGHT / 2.0 - 30.0 + row as f32 * 26.0, 22, colors[seat as usize]);
+                    }
+                    draw_centered_text("Press R to Restart", SCREEN_HEIGHT / 2.0 + 100.0, 30, GRAY);
+                    draw_centered_text("Press M to Change Mode", SCREEN_HEIGHT / 2.0 + 140.0, 30, GRAY);
+                    if is_key_pressed(KeyCode::R) {
+                        doubles = DoublesMatch::default();
+                        particles.clear();
+                    } else if is_key_pressed(KeyCode::M) {
+                        particles.clear();
+                        game_state = GameState::ModeSelect;
+                    }
+                } else if doubles_paused {
+                    draw_centered_text("PAUSED", SCREEN_HEIGHT / 2.0, 60, YELLOW);
+                    draw_centered_text("Press P to Resume", SCREEN_HEIGHT / 2.0 + 60.0, 30, GRAY);
+                }
+                if is_key_pressed(KeyCode::P) && doubles.winner().is_none() {
+                    doubles_paused = !doubles_paused;
+                }
+            }
+            
+            GameState::MultiBall => {
+                let dt = get_frame_time();
+                
+                if multiball.winner().is_none() && !multiball_paused {
+                    multiball.left.drive(key_intent(KeyCode::W, KeyCode::S), dt);
+                    match multiball_ai.as_mut() {
+                        Some(ai) => ai.drive(&mut multiball, dt, gen_range(-1.0, 1.0)),
+                        None => multiball.right.drive(key_intent(KeyCode::Up, KeyCode::Down), dt),
+                    }
+                    let events = multiball.step(dt);
+                    
+                    if let Some((side, hit_y)) = events.paddle_hit {
+                        screen_shake = 0.15;
+                        let (x, vel_x) = match side {
+                            Side::Left => (LEFT_PADDLE_X + PADDLE_WIDTH, (50.0, 200.0)),
+                            Side::Right => (RIGHT_PADDLE_X, (-200.0, -50.0)),
+                        };
+                        for _ in 0..8 {
+                            particles.push(Particle::new(
+                                x,
+                                hit_y,
+                                gen_range(vel_x.0, vel_x.1),
+                                gen_range(-100.0, 100.0),
+                                gen_range(0.3, 0.8),
+                                gen_range(2.0, 5.0),
+                            ));
+                        }
+                    }
+                    if events.spawned {
+                        burst(&mut particles, BALL_START_X, BALL_START_Y, 16, YELLOW);
+                    }
+                    if events.scored.is_some() {
+                        score_flash_timer = 0.5;
+                        screen_shake = 0.3;
+                    }
+                }
+                
+                // Update visual effects
+                screen_shake = (screen_shake - dt * 10.0).max(0.0);
+                score_flash_timer = (score_flash_timer - dt).max(0.0);
+                particles.retain_mut(|particle| particle.update(dt));
+                let shake_x = if screen_shake > 0.0 { gen_range(-screen_shake * 10.0, screen_shake * 10.0) } else { 0.0 };
+                let shake_y = if screen_shake > 0.0 { gen_range(-screen_shake * 10.0, screen_shake * 10.0) } else { 0.0 };
+                
+                // Field: center line, paddles, balls (faster ones redder)
+                for i in 0..20 {
+                    let y = i as f32 * 30.0 + 10.0;
+                    draw_rectangle(SCREEN_WIDTH / 2.0 - 2.0 + shake_x, y + shake_y, 4.0, 15.0, GRAY);
+                }
+                draw_rectangle(LEFT_PADDLE_X + shake_x, multiball.left.y + shake_y, PADDLE_WIDTH, multiball.left.height, WHITE);
+                draw_rectangle(RIGHT_PADDLE_X + shake_x, multiball.right.y + shake_y, PADDLE_WIDTH, multiball.right.height, WHITE);
+                for ball in &multiball.balls {
+                    let heat = (ball.speed - BALL_SPEED) / (MAX_BALL_SPEED - BALL_SPEED);
+                    let color = Color::new(1.0, 1.0 - heat * 0.7, 1.0 - heat * 0.7, 1.0);
+                    draw_circle(ball.x + shake_x, ball.y + shake_y, BALL_SIZE, color);
+                }
+                for particle in &particles {
+                    particle.draw();
+                }
+                
+                let score_text = format!("{}    {}", multiball.left_score, multiball.right_score);
+                let score_color = if score_flash_timer > 0.0 {
+                    let flash = (score_flash_timer * 10.0).sin().abs();
+                    Color::new(1.0, flash, flash, 1.0)
+                } else {
+                    WHITE
+                };
+                draw_centered_text(&score_text, 100.0, 60, score_color);
+                
+                let controls = if multiball_ai.is_some() { "Player: W/S  P: Pause" } else { "Left: W/S  Right: Up/Down  P: Pause" };
+                draw_text(controls, 20.0, 20.0, 18.0, GRAY);
+                let balls_text = format!("Balls: {}  ({})", multiball.balls.len(), multiball.rule.label());
+                draw_centered_text(&balls_text, SCREEN_HEIGHT - 12.0, 18, GRAY);
+                
+                if let Some(winner) = multiball.winner() {
+                    let text = match (winner, multiball_ai.is_some()) {
+                        (Side::Left, true) => "You Win!",
+                        (Side::Right, true) => "AI Wins!",
+                        (Side::Left, false) => "Left Player Wins!",
+                        (Side::Right, false) => "Right Player Wins!",
+                    };
+                    draw_centered_text(text, SCREEN_HEIGHT / 2.0, 40, YELLOW);
+                    draw_centered_text("Press R to Restart", SCREEN_HEIGHT / 2.0 + 60.0, 30, GRAY);
+                    draw_centered_text("Press M to Change Mode", SCREEN_HEIGHT / 2.0 + 100.0, 30, GRAY);
+                    if is_key_pressed(KeyCode::R) {
+                        multiball = MultiBallMatch::new(multiball.rule, multiball.winning_score);
+                        particles.clear();
+                    } else if is_key_pressed(KeyCode::M) {
+                        particles.clear();
+                        game_state = GameState::ModeSelect;
+                    }
+                } else if multiball_paused {
+                    draw_centered_text("PAUSED", SCREEN_HEIGHT / 2.0, 60, YELLOW);
+                    draw_centered_text("Press P to Resume", SCREEN_HEIGHT / 2.0 + 60.0, 30, GRAY);
+                }
+                if is_key_pressed(KeyCode::P) && multiball.winner().is_none() {
+                    multiball_paused = !multiball_paused;
+                }
+            }
+            
+            GameState::TournamentSetup => {
+                draw_centered_text("Tournament", 50.0, 40, WHITE);
+                
+                // Entrants in two columns of eight
+                for (index, entrant) in tournament_entrants.iter().enumerate() {
+                    let x = if index < 8 { 120.0 } else { 440.0 };
+                    let y = 100.0 + (index % 8) as f32 * 26.0;
+                    let kind = match entrant.controller {
+                        Controller::Human => "Player",
+                        Controller::Ai(_) => "AI",
+                    };
+                    draw_text(&format!("{}. {} ({})", index + 1, entrant.name, kind), x, y, 22.0, WHITE);
+                }
+                if tournament_entrants.is_empty() {
+                    draw_centered_text("No entrants yet", 150.0, 22, DARKGRAY);
+                }
+                
+                let level = profiles[difficulty].name.as_str();
+                let options = format!("F: Format < {} >  D: AI Level < {} >", tournament_format.label(), level);
+                draw_centered_text(&options, 330.0, 20, WHITE);
+                if let Some(name) = &tournament_name {
+                    let cursor = if (get_time() * 2.0) as i64 % 2 == 0 { "_" } else { " " };
+                    draw_centered_text(&format!("Name: {}{}", name, cursor), 370.0, 28, YELLOW);
+                    draw_centered_text("ENTER: Add  ESC: Cancel", 400.0, 18, GRAY);
+                } else {
+                    draw_centered_text("N: Add Player  A: Add AI  BACKSPACE: Remove Last", 370.0, 20, GRAY);
+                    let start = format!("SPACE: Start ({}-{} entrants)  ESC: Back", MIN_ENTRANTS, MAX_ENTRANTS);
+                    draw_centered_text(&start, 395.0, 20, GRAY);
+                }
+                if let Some(saved) = &tournament_saved {
+                    let played = saved.rounds.iter().flat_map(|round| &round.games).filter(|game| game.score.is_some()).count();
+                    let resume = format!("R: Resume saved tournament ({} entrants, {} matches played)", saved.entrants.len(), played);
+                    draw_centered_text(&resume, 440.0, 20, SKYBLUE);
+                }
+                if let Some(error) = &tournament_error {
+                    draw_centered_text(error, 480.0, 18, RED);
+                }
+                
+                if let Some(name) = tournament_name.as_mut() {
+                    // Name entry
+                    while let Some(c) = get_char_pressed() {
+                        if (c.is_ascii_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.')) && name.len() < 12 {
+                            name.push(c);
+                        }
+                    }
+                    if is_key_pressed(KeyCode::Backspace) {
+                        name.pop();
+                    }
+                    if is_key_pressed(KeyCode::Enter) {
+                        let name = name.trim().to_string();
+                        if name.is_empty() {
+                            tournament_error = Some("Enter a name first".to_string());
+                        } else if tournament_entrants.iter().any(|entrant| entrant.name == name) {
+                            tournament_error = Some(format!("{} is already entered", name));
+                        } else {
+                            tournament_entrants.push(Entrant { name, controller: Controller::Human });
+                            tournament_error = None;
+                            tournament_name = None;
+                        }
+                    } else if is_key_pressed(KeyCode::Escape) {
+                        tournament_name = None;
+                    }
+                } else {
+                    while get_char_pressed().is_some() {} // Keys below are commands, not text
+                    let full = tournament_entrants.len() >= MAX_ENTRANTS;
+                    
+                    if is_key_pressed(KeyCode::N) && !full {
+                        tournament_name = Some(String::new());
+                    } else if is_key_pressed(KeyCode::A) && !full {
+                        // "Hard AI", then "Hard AI 2" and so on
+                        let base = format!("{} AI", profiles[difficulty].name);
+                        let name = (1..)
+                            .map(|n| if n == 1 { base.clone() } else { format!("{} {}", base, n) })
+                            .find(|name| tournament_entrants.iter().all(|entrant| entrant.name != *name))
+                            .unwrap_or(base);
+                        tournament_entrants.push(Entrant { name, controller: Controller::Ai(profiles[difficulty].name.clone()) });
+                    } else if is_key_pressed(KeyCode::Backspace) {
+                        tournament_entrants.pop();
+                    } else if is_key_pressed(KeyCode::F) {
+                        tournament_format = match tournament_format {
+                            Format::Single => Format::Double,
+                            Format::Double => Format::Single,
+                        };
+                    } else if is_key_pressed(KeyCode::D) {
+                        difficulty = (difficulty + 1) % profiles.len();
+                    } else if is_key_pressed(KeyCode::R) && tournament_saved.is_some() {
+                        tournament = tournament_saved.take();
+                        tournament_error = None;
+                        game_state = GameState::Bracket;
+                    } else if is_key_pressed(KeyCode::Space) {
+                        match Tournament::new(tournament_entrants.clone(), tournament_format) {
+                            Ok(new) => {
+                                tournament_error = new.save(TOURNAMENT_FILE).err().map(|err| format!("Could not save {}: {}", TOURNAMENT_FILE, err));
+                                tournament = Some(new);
+                                game_state = GameState::Bracket;
+                            }
+                            Err(err) => tournament_error = Some(format!("Can't start: {}", err)),
+                        }
+                    } else if is_key_pressed(KeyCode::Escape) {
+                        game_state = GameState::ModeSelect;
+                    }
+                }
+            }
+            
+            GameState::Bracket => {
+                if let Some(current) = tournament.as_ref() {
+                    let name = |entrant: usize| {
+                        let name = &current.entrants[entrant].name;
+                        name.chars().take(14).collect::<String>()
+                    };
+                    draw_centered_text(current.format.label(), 36.0, 28, WHITE);
+                
+                    // The latest four rounds as columns, games top to bottom
+                    let first = current.rounds.len().saturating_sub(4);
+                    for (column, round) in current.rounds.iter().enumerate().skip(first) {
+                        let x = 20.0 + (column - first) as f32 * 195.0;
+                        draw_text(&format!("Round {}", column + 1), x, 70.0, 22.0, YELLOW);
+                        let mut y = 95.0;
+                        for game in &round.games {
+                            let tag = match game.bracket {
+                                Bracket::Winners => "W",
+                                Bracket::Losers => "L",
+                                Bracket::Final => "Final",
+                            };
+                            draw_text(tag, x, y + 8.0, 14.0, DARKGRAY);
+                            let playing_next = current.current() == Some(game);
+                            draw_rectangle_lines(x + 38.0, y - 6.0, 150.0, 36.0, 1.0, if playing_next { YELLOW } else { DARKGRAY });
+                            for (slot, &entrant) in game.players.iter().enumerate() {
+                                let score = game.score.map(|(left, right)| if slot == 0 { left } else { right });
+                                let color = match game.winner() {
+                                    Some(winner) if winner != entrant => GRAY,
+                                    _ => WHITE,
+                                };
+                                let row = y + 8.0 + slot as f32 * 16.0;
+                                draw_text(&name(entrant), x + 42.0, row, 16.0, color);
+                                if let Some(score) = score {
+                                    draw_text(&score.to_string(), x + 170.0, row, 16.0, color);
+                                }
+                            }
+                            y += 42.0;
+                        }
+                        for &entrant in &round.byes {
+                            draw_text(&format!("{} (bye)", name(entrant)), x + 42.0, y + 4.0, 16.0, GRAY);
+                            y += 18.0;
+                        }
+                    }
+                
+                    if let Some(champion) = current.champion() {
+                        let text = format!("{} is the Champion!", current.entrants[champion].name);
+                        draw_centered_text(&text, SCREEN_HEIGHT - 80.0, 36, GOLD);
+                        draw_centered_text("M: Change Mode", SCREEN_HEIGHT - 40.0, 22, GRAY);
+                    } else if let Some(next) = current.current() {
+                        let next_text = format!("Next: {} vs {}", name(next.players[0]), name(next.players[1]));
+                        draw_centered_text(&next_text, SCREEN_HEIGHT - 80.0, 30, WHITE);
+                        draw_centered_text("SPACE: Play  M: Save and Leave", SCREEN_HEIGHT - 40.0, 22, GRAY);
+                    }
+                    if let Some(error) = &tournament_error {
+                        draw_centered_text(error, SCREEN_HEIGHT - 15.0, 18, RED);
+                    }
+                
+                    if let (Some(next), true) = (current.current(), is_key_pressed(KeyCode::Space)) {
+                        // The next match, with each entrant's own controller
+                        tournament_players = next.players;
+                        for (index, side) in [Side::Left, Side::Right].into_iter().enumerate() {
+                            tournament_ai[index] = match &current.entrants[next.players[index]].controller {
+                                // Checked against the profiles when it was resumed
+                                Controller::Ai(name) => profiles
+                                    .iter()
+                                    .find(|profile| profile.name == *name)
+                                    .map(|profile| AiController::new(profile.params, side)),
+                                Controller::Human => None,
+                            };
+                        }
+                        game.reset();
+                        match_clock = MatchRule::PRESETS[match_rule].apply(&mut game);
+                        powerups = powerups_enabled.then(|| PowerUps::new(macroquad::rand::rand() as u64));
+                        spin = spin_enabled.then(Spin::new);
+                        match_arena = arenas[arena_choice].clone();
+                        let mut arena = ArenaState::new(match_arena.clone(), brick_points);
+                        arena.serve(&mut game.ball);
+                        arena_state = Some(arena);
+                        particles.clear();
+                        screen_shake = 0.0;
+                        score_flash_timer = 0.0;
+                        editor_testing = false;
+                        game_state = GameState::Playing;
+                    } else if is_key_pressed(KeyCode::M) {
+                        game_state = GameState::ModeSelect;
+                    }
+                } else {
+                    game_state = GameState::ModeSelect;
+                }
+            }
+            
+            GameState::Gauntlet => {
+                draw_centered_text("Gauntlet", 60.0, 40, WHITE);
+                
+                // The ladder of opponents, beaten ones greyed out
+                for (index, name) in OPPONENTS.iter().enumerate() {
+                    let color = match index.cmp(&gauntlet.stage) {
+                        std::cmp::Ordering::Less => DARKGRAY,
+                        std::cmp::Ordering::Equal => YELLOW,
+                        std::cmp::Ordering::Greater => WHITE,
+                    };
+                    draw_text(&format!("{}. {}", index + 1, name), 60.0, 120.0 + index as f32 * 30.0, 24.0, color);
+                }
+                
+                // Run so far
+                let x = 380.0;
+                let headline = match gauntlet.result {
+                    None => format!("First to {} against each opponent", gauntlet::MATCH_POINTS),
+                    Some(MatchResult::Advanced) => format!("You beat the {}!", OPPONENTS[gauntlet.stage - 1]),
+                    Some(MatchResult::LifeLost) => format!("The {} beat you - try again", gauntlet.opponent()),
+                    Some(MatchResult::Victory) => "Gauntlet Cleared!".to_string(),
+                    Some(MatchResult::Out) => "Out of Lives".to_string(),
+                };
+                let headline_color = match gauntlet.result {
+                    Some(MatchResult::Victory) => GOLD,
+                    Some(MatchResult::LifeLost | MatchResult::Out) => RED,
+                    _ => WHITE,
+                };
+                draw_text(&headline, x, 140.0, 26.0, headline_color);
+                for life in 0..gauntlet::LIVES {
+                    let color = if life < gauntlet.lives { RED } else { DARKGRAY };
+                    draw_circle(x + 10.0 + life as f32 * 28.0, 180.0, 10.0, color);
+                }
+                draw_text(&format!("Points won: {}", gauntlet.points), x, 230.0, 24.0, WHITE);
+                draw_text(&format!("Time: {}", clock(gauntlet.elapsed)), x, 260.0, 24.0, WHITE);
+                draw_text(&format!("Score: {}", gauntlet.score()), x, 300.0, 32.0, YELLOW);
+                
+                if gauntlet.is_over() {
+                    draw_centered_text("R: Run Again  M: Change Mode", SCREEN_HEIGHT - 60.0, 24, GRAY);
+                    if is_key_pressed(KeyCode::R) {
+                        gauntlet = Gauntlet::new();
+                    }
+                } else {
+                    let next = format!("Next: {} (stage {} of {})", gauntlet.opponent(), gauntlet.stage + 1, OPPONENTS.len());
+                    draw_text(&next, x, 360.0, 24.0, WHITE);
+                    draw_centered_text("SPACE: Play  M: Change Mode", SCREEN_HEIGHT - 60.0, 24, GRAY);
+                    if is_key_pressed(KeyCode::Space) {
+                        ai_player = AiController::new(gauntlet.opponent_params(), Side::Right);
+                        game.winning_score = gauntlet::MATCH_POINTS;
+                        game.reset();
+                        powerups = None;
+                        spin = None;
+                        arena_state = None;
+                        match_clock = None;
+                        editor_testing = false;
+                        particles.clear();
+                        screen_shake = 0.0;
+                        score_flash_timer = 0.0;
+                        game_state = GameState::Playing;
+                    }
+                }
+                if is_key_pressed(KeyCode::M) {
+                    // Leave the last gauntlet match behind, short target and all
+                    game.winning_score = WINNING_SCORE;
+                    game.reset();
+                    game_state = GameState::ModeSelect;
+                }
+            }
+            
+            GameState::ChallengeSelect => {
+                draw_centered_text("Challenges", 60.0, 40, WHITE);
+                let total_text = format!("Stars: {} of {}", challenge_progress.total(), challenges.len() as u32 * MAX_STARS);
+                draw_centered_text(&total_text, 95.0, 22, GOLD);
+                
+                // The list, scrolled to keep the selection in view; locked
+                // ones say how many stars they need
+                let first = challenge_choice.saturating_sub(8);
+                for (index, challenge) in challenges.iter().enumerate().skip(first).take(9) {
+                    let y = 140.0 + (index - first) as f32 * 32.0;
+                    let unlocked = challenge_progress.is_unlocked(challenge);
+                    let color = match (index == challenge_choice, unlocked) {
+                        (true, _) => YELLOW,
+                        (false, true) => WHITE,
+                        (false, false) => DARKGRAY,
+                    };
+                    let marker = if index == challenge_choice { ">" } else { " " };
+                    draw_text(&format!("{} {}", marker, challenge.name), 120.0, y, 26.0, color);
+                    if unlocked {
+                        let earned = challenge_progress.stars(&challenge.name);
+                        for star in 0..MAX_STARS {
+                            let color = if star < earned { GOLD } else { DARKGRAY };
+                            draw_star(560.0 + star as f32 * 30.0, y - 8.0, 11.0, color);
+                        }
+                    } else {
+                        draw_text(&format!("Locked - {} stars", challenge.unlock), 520.0, y, 20.0, DARKGRAY);
+                    }
+                }
+                
+                // What the selected one asks for
+                if let Some(challenge) = challenges.get(challenge_choice) {
+                    draw_centered_text(&challenge.description, 460.0, 22, WHITE);
+                    let mut details = format!("Goal: {}", challenge.goal.label());
+                    for limit in &challenge.limits {
+                        details += &format!("  Limit: {}", limit.label());
+                    }
+                    draw_centered_text(&details, 488.0, 18, GRAY);
+                    if !challenge.stars.is_empty() {
+                        let stars = challenge.stars.iter().map(|star| star.label()).collect::<Vec<_>>().join(", ");
+                        draw_centered_text(&format!("Extra stars: {}", stars), 512.0, 18, GRAY);
+                    }
+                }
+                if let Some(error) = &challenge_error {
+                    draw_centered_text(error, 545.0, 18, RED);
+                }
+                draw_centered_text("UP/DOWN: Choose  SPACE: Play  ESC: Back", SCREEN_HEIGHT - 25.0, 20, GRAY);
+                
+                if is_key_pressed(KeyCode::Up) && !challenges.is_empty() {
+                    challenge_choice = (challenge_choice + challenges.len() - 1) % challenges.len();
+                } else if is_key_pressed(KeyCode::Down) && !challenges.is_empty() {
+                    challenge_choice = (challenge_choice + 1) % challenges.len();
+                } else if is_key_pressed(KeyCode::Space) {
+                    let chosen = challenges.get(challenge_choice).filter(|challenge| challenge_progress.is_unlocked(challenge));
+                    if let Some(Err(err)) = chosen.map(|challenge| challenge.check_profiles(&profiles)) {
+                        challenge_error = Some(err);
+                    } else if let Some(challenge) = chosen {
+                        challenge_run = ChallengeRun::new(challenge.clone(), &profiles, macroquad::rand::rand() as u64);
+                        challenge_improved = false;
+                        challenge_unlocked.clear();
+                        challenge_paused = false;
+                        particles.clear();
+                        screen_shake = 0.0;
+                        game_state = GameState::Challenge;
+                    }
+                } else if is_key_pressed(KeyCode::Escape) {
                     game_state = GameState::ModeSelect;
                 }
             }
+            
+            GameState::Challenge => {
+                let run = &mut challenge_run;
+                let dt = get_frame_time();
+                
+                if run.outcome.is_none() && !challenge_paused {
+                    run.game.left.drive(key_intent(KeyCode::W, KeyCode::S), dt);
+                    let events = run.step(dt);
+                    
+                    if let Some((side, hit_y)) = events.step.paddle_hit {
+                        screen_shake = 0.15;
+                        let (x, dir) = match side {
+                            Side::Left => (LEFT_PADDLE_X + PADDLE_WIDTH, 1.0),
+                            Side::Right => (RIGHT_PADDLE_X, -1.0),
+                        };
+                        for _ in 0..8 {
+                            particles.push(Particle::new(
+                                x,
+                                hit_y,
+                                dir * gen_range(50.0, 200.0),
+                                gen_range(-100.0, 100.0),
+                                gen_range(0.3, 0.8),
+                                gen_range(2.0, 5.0),
+                            ));
+                        }
+                    }
+                    if let Some(bank_x) = events.bank {
+                        let bank_y = if run.game.ball.y < SCREEN_HEIGHT / 2.0 { BALL_SIZE } else { SCREEN_HEIGHT - BALL_SIZE };
+                        burst(&mut particles, bank_x, bank_y, 10, SKYBLUE);
+                    }
+                    if events.step.scored.is_some() {
+                        screen_shake = 0.3;
+                    }
+                    if events.voided {
+                        burst(&mut particles, BALL_START_X, BALL_START_Y, 16, RED);
+                    }
+                    if let Some(Outcome::Passed(stars)) = events.outcome {
+                        let locked: Vec<&Challenge> = challenges.iter().filter(|challenge| !challenge_progress.is_unlocked(challenge)).collect();
+                        challenge_improved = challenge_progress.record(&run.challenge.name, stars);
+                        challenge_unlocked = locked
+                            .into_iter()
+                            .filter(|challenge| challenge_progress.is_unlocked(challenge))
+                            .map(|challenge| challenge.name.clone())
+                            .collect();
+                        if challenge_improved {
+                            challenge_error = challenge_progress
+                                .save(CHALLENGE_PROGRESS_FILE)
+                                .err()
+                                .map(|err| format!("Could not save {}: {}", CHALLENGE_PROGRESS_FILE, err));
+                        }
+                        burst(&mut particles, BALL_START_X, BALL_START_Y, 40, GOLD);
+                    }
+                }
+                
+                // Update visual effects
+                screen_shake = (screen_shake - dt * 10.0).max(0.0);
+                particles.retain_mut(|particle| particle.update(dt));
+                let shake_x = if screen_shake > 0.0 { gen_range(-screen_shake * 10.0, screen_shake * 10.0) } else { 0.0 };
+                let shake_y = if screen_shake > 0.0 { gen_range(-screen_shake * 10.0, screen_shake * 10.0) } else { 0.0 };
+                
+                // Field: the player's paddle, the wall or the opponent, and the ball
+                let game = &run.game;
+                draw_rectangle(LEFT_PADDLE_X + shake_x, game.left.y + shake_y, PADDLE_WIDTH, game.left.height, WHITE);
+                let opponent_color = if run.challenge.opponent == Opponent::Wall { GRAY } else { WHITE };
+                draw_rectangle(RIGHT_PADDLE_X + shake_x, game.right.y + shake_y, PADDLE_WIDTH, game.right.height, opponent_color);
+                let heat = (game.ball.speed - BALL_SPEED) / (MAX_BALL_SPEED - BALL_SPEED);
+                let ball_color = Color::new(1.0, 1.0 - heat * 0.7, 1.0 - heat * 0.7, 1.0);
+                draw_circle(game.ball.x + shake_x, game.ball.y + shake_y, BALL_SIZE, ball_color);
+                for particle in &particles {
+                    particle.draw();
+                }
+                
+                // Progress towards the goal, then the limits
+                let goal = run.challenge.goal;
+                let progress_text = format!("{} / {}", run.stats.get(goal.metric), goal.value);
+                draw_centered_text(&progress_text, 80.0, 50, WHITE);
+                draw_centered_text(goal.metric.label(), 108.0, 20, GRAY);
+                let limits = run
+                    .challenge
+                    .limits
+                    .iter()
+                    .map(|limit| match limit.metric {
+                        Metric::Time => format!("Time {} / {}", clock(run.stats.time), clock(limit.value)),
+                        metric => format!("{} {} / {}", metric.label(), run.stats.get(metric), limit.value),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("  ");
+                draw_centered_text(&limits, 134.0, 20, GRAY);
+                draw_text("Player: W/S  P: Pause", 20.0, 20.0, 18.0, GRAY);
+                draw_centered_text(&run.challenge.name, SCREEN_HEIGHT - 12.0, 18, GRAY);
+                
+                if let Some(outcome) = run.outcome {
+                    match outcome {
+                        Outcome::Passed(stars) => {
+                            draw_centered_text("Challenge Passed!", SCREEN_HEIGHT / 2.0 - 60.0, 50, GREEN);
+                            for star in 0..MAX_STARS {
+                                let color = if star < stars { GOLD } else { DARKGRAY };
+                                draw_star(SCREEN_WIDTH / 2.0 + (star as f32 - 1.0) * 50.0, SCREEN_HEIGHT / 2.0 - 10.0, 20.0, color);
+                            }
+                        }
+                        Outcome::Failed => {
+                            draw_centered_text("Challenge Failed", SCREEN_HEIGHT / 2.0 - 40.0, 50, RED);
+                        }
+                    }
+                    if challenge_improved {
+                        draw_centered_text("New Best!", SCREEN_HEIGHT / 2.0 + 35.0, 30, YELLOW);
+                    }
+                    if !challenge_unlocked.is_empty() {
+                        let unlocked_text = format!("Unlocked: {}", challenge_unlocked.join(", "));
+                        draw_centered_text(&unlocked_text, SCREEN_HEIGHT / 2.0 + 65.0, 24, YELLOW);
+                    }
+                    draw_centered_text("Press R to Try Again", SCREEN_HEIGHT / 2.0 + 110.0, 30, GRAY);
+                    draw_centered_text("Press SPACE for the Challenges", SCREEN_HEIGHT / 2.0 + 150.0, 30, GRAY);
+                    if let Some(error) = &challenge_error {
+                        draw_centered_text(error, SCREEN_HEIGHT / 2.0 + 185.0, 18, RED);
+                    }
+                    if is_key_pressed(KeyCode::R) {
+                        *run = ChallengeRun::new(run.challenge.clone(), &profiles, macroquad::rand::rand() as u64);
+                        challenge_improved = false;
+                        challenge_unlocked.clear();
+                        particles.clear();
+                    } else if is_key_pressed(KeyCode::Space) {
+                        particles.clear();
+                        game_state = GameState::ChallengeSelect;
+                    }
+                } else if challenge_paused {
+                    draw_centered_text("PAUSED", SCREEN_HEIGHT / 2.0, 60, YELLOW);
+                    draw_centered_text("Press P to Resume", SCREEN_HEIGHT / 2.0 + 60.0, 30, GRAY);
+                }
+                if is_key_pressed(KeyCode::P) && run.outcome.is_none() {
+                    challenge_paused = !challenge_paused;
+                }
+            }
+            
+            GameState::Practice => {
+                let dt = get_frame_time();
+                
+                if practice.lost.is_none() && !practice_paused {
+                    practice.paddle.drive(key_intent(KeyCode::W, KeyCode::S), dt);
+                    let events = practice.step(dt);
+                    
+                    if let Some(hit_y) = events.paddle_hit {
+                        screen_shake = 0.15;
+                        for _ in 0..8 {
+                            particles.push(Particle::new(
+                                LEFT_PADDLE_X + PADDLE_WIDTH,
+                                hit_y,
+                                gen_range(50.0, 200.0),
+                                gen_range(-100.0, 100.0),
+                                gen_range(0.3, 0.8),
+                                gen_range(2.0, 5.0),
+                            ));
+                        }
+                    }
+                    if let Some(hit_y) = events.wall_hit {
+                        if practice.wall == WallKind::Target {
+                            burst(&mut particles, SCREEN_WIDTH - WALL_WIDTH, hit_y, 10, GREEN);
+                        }
+                    }
+                    if let Some(rally) = events.lost {
+                        screen_shake = 0.3;
+                        practice_new_best = practice_bests.record(practice.wall, rally);
+                        if practice_new_best {
+                            practice_error = practice_bests
+                                .save(PRACTICE_BEST_FILE)
+                                .err()
+                                .map(|err| format!("Could not save {}: {}", PRACTICE_BEST_FILE, err));
+                        }
+                    }
+                }
+                
+                // Update visual effects
+                screen_shake = (screen_shake - dt * 10.0).max(0.0);
+                particles.retain_mut(|particle| particle.update(dt));
+                let shake_x = if screen_shake > 0.0 { gen_range(-screen_shake * 10.0, screen_shake * 10.0) } else { 0.0 };
+                let shake_y = if screen_shake > 0.0 { gen_range(-screen_shake * 10.0, screen_shake * 10.0) } else { 0.0 };
+                
+                // Field: the wall (and its target), paddle and ball
+                let wall_color = if practice.wall == WallKind::Target { DARKGRAY } else { GRAY };
+                draw_rectangle(SCREEN_WIDTH - WALL_WIDTH + shake_x, shake_y, WALL_WIDTH, SCREEN_HEIGHT, wall_color);
+                if practice.wall == WallKind::Target {
+                    let (top, bottom) = practice.target;
+                    draw_rectangle(SCREEN_WIDTH - WALL_WIDTH + shake_x, top + shake_y, WALL_WIDTH, bottom - top, GREEN);
+                }
+                draw_rectangle(LEFT_PADDLE_X + shake_x, practice.paddle.y + shake_y, PADDLE_WIDTH, practice.paddle.height, WHITE);
+                let heat = (practice.ball.speed - BALL_SPEED) / (MAX_BALL_SPEED - BALL_SPEED);
+                let ball_color = Color::new(1.0, 1.0 - heat * 0.7, 1.0 - heat * 0.7, 1.0);
+                draw_circle(practice.ball.x + shake_x, practice.ball.y + shake_y, BALL_SIZE, ball_color);
+                for particle in &particles {
+                    particle.draw();
+                }
+                
+                draw_centered_text(&practice.rally.to_string(), 100.0, 60, WHITE);
+                let best = practice_bests.get(practice.wall);
+                draw_centered_text(&format!("Best: {}", best), 130.0, 20, GRAY);
+                draw_text("Player: W/S  P: Pause", 20.0, 20.0, 18.0, GRAY);
+                draw_centered_text(practice.wall.label(), SCREEN_HEIGHT - 12.0, 18, GRAY);
+                
+                if let Some(rally) = practice.lost {
+                    draw_centered_text("Streak Lost", SCREEN_HEIGHT / 2.0 - 40.0, 50, RED);
+                    let rally_text = format!("Rally: {}", rally);
+                    draw_centered_text(&rally_text, SCREEN_HEIGHT / 2.0 + 10.0, 30, WHITE);
+                    if practice_new_best {
+                        draw_centered_text("New Personal Best!", SCREEN_HEIGHT / 2.0 + 45.0, 30, YELLOW);
+                    } else {
+                        let best_text = format!("Personal Best: {}", best);
+                        draw_centered_text(&best_text, SCREEN_HEIGHT / 2.0 + 45.0, 30, GRAY);
+                    }
+                    draw_centered_text("Press R to Try Again", SCREEN_HEIGHT / 2.0 + 100.0, 30, GRAY);
+                    draw_centered_text("Press M to Change Mode", SCREEN_HEIGHT / 2.0 + 140.0, 30, GRAY);
+                    if let Some(error) = &practice_error {
+                        draw_centered_text(error, SCREEN_HEIGHT / 2.0 + 180.0, 18, RED);
+                    }
+                    if is_key_pressed(KeyCode::R) {
+                        practice.restart();
+                        practice_new_best = false;
+                        particles.clear();
+                    } else if is_key_pressed(KeyCode::M) {
+                        particles.clear();
+                        game_state = GameState::ModeSelect;
+                    }
+                } else if practice_paused {
+                    draw_centered_text("PAUSED", SCREEN_HEIGHT / 2.0, 60, YELLOW);
+                    draw_centered_text("Press P to Resume", SCREEN_HEIGHT / 2.0 + 60.0, 30, GRAY);
+                }
+                if is_key_pressed(KeyCode::P) && practice.lost.is_none() {
+                    practice_paused = !practice_paused;
+                }
+            }
+            
+            GameState::Editor => {
+                let (mouse_x, mouse_y) = mouse_position();
+                let cursor = (snap(mouse_x).clamp(0.0, SCREEN_WIDTH), snap(mouse_y).clamp(0.0, SCREEN_HEIGHT));
+                
+                // Grid, arena, the paddles' home spots and the serve spots
+                for i in 0..=(SCREEN_WIDTH / (EDITOR_GRID * 5.0)) as i32 {
+                    let x = i as f32 * EDITOR_GRID * 5.0;
+                    draw_line(x, 0.0, x, SCREEN_HEIGHT, 1.0, Color::new(1.0, 1.0, 1.0, 0.08));
+                }
+                for i in 0..=(SCREEN_HEIGHT / (EDITOR_GRID * 5.0)) as i32 {
+                    let y = i as f32 * EDITOR_GRID * 5.0;
+                    draw_line(0.0, y, SCREEN_WIDTH, y, 1.0, Color::new(1.0, 1.0, 1.0, 0.08));
+                }
+                draw_arena(&editor_arena, 0.0, 0.0);
+                draw_rectangle_lines(LEFT_PADDLE_X, PADDLE_START_Y, PADDLE_WIDTH, PADDLE_HEIGHT, 2.0, DARKGRAY);
+                draw_rectangle_lines(RIGHT_PADDLE_X, PADDLE_START_Y, PADDLE_WIDTH, PADDLE_HEIGHT, 2.0, DARKGRAY);
+                if editor_arena.spawns.is_empty() {
+                    draw_circle_lines(BALL_START_X, BALL_START_Y, BALL_SIZE, 2.0, DARKGRAY);
+                }
+                for (index, &(x, y)) in editor_arena.spawns.iter().enumerate() {
+                    draw_circle_lines(x, y, BALL_SIZE, 2.0, YELLOW);
+                    draw_text(&(index + 1).to_string(), x - 4.0, y + 5.0, 18.0, YELLOW);
+                }
+                
+                // What the current drag would place
+                if let Some(start) = editor_drag {
+                    match editor_tool {
+                        EditorTool::Goal => {
+                            let x = if start.0 < SCREEN_WIDTH / 2.0 { 0.0 } else { SCREEN_WIDTH - 6.0 };
+                            let (top, bottom) = (start.1.min(cursor.1), start.1.max(cursor.1));
+                            let color = if bottom - top >= MIN_GOAL_SIZE { GREEN } else { RED };
+                            draw_rectangle(x, top, 6.0, bottom - top, color);
+                        }
+                        tool => {
+                            if let Some(obstacle) = editor_shape(tool, start, cursor, editor_hits) {
+                                draw_obstacle(&obstacle, 0.0, 0.0);
+                            }
+                        }
+                    }
+                }
+                draw_circle(cursor.0, cursor.1, 3.0, YELLOW);
+                
+                // HUD
+                let tools = EditorTool::ALL
+                    .iter()
+                    .enumerate()
+                    .map(|(index, tool)| {
+                        let marker = if *tool == editor_tool { ">" } else { " " };
+                        format!("{}{}:{}", marker, index + 1, tool.label())
+                    })
+                    .collect::<Vec<_>>()
+                    .join("  ");
+                draw_text(&tools, 20.0, 20.0, 18.0, WHITE);
+                let hits_text = format!("Brick hits: {} (UP/DOWN)", editor_hits);
+                draw_text(&hits_text, 20.0, 40.0, 18.0, GRAY);
+                let path_cursor = if editing_path && (get_time() * 2.0) as i64 % 2 == 0 { "_" } else { " " };
+                let path_text = format!("{}  File: {}{}", editor_arena.name, editor_path, path_cursor);
+                draw_text(&path_text, 20.0, SCREEN_HEIGHT - 40.0, 18.0, if editing_path { YELLOW } else { GRAY });
+                draw_text(
+                    "Drag: Place  Right click: Remove  T: Test  S: Save  L: Load  F: File  C: Clear  ESC: Done",
+                    20.0,
+                    SCREEN_HEIGHT - 16.0,
+                    18.0,
+                    GRAY,
+                );
+                if let Some(message) = &editor_message {
+                    draw_centered_text(message, 70.0, 20, YELLOW);
+                }
+                
+                if editing_path {
+                    // File name entry
+                    while let Some(c) = get_char_pressed() {
+                        if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '/' | '\\') {
+                            editor_path.push(c);
+                        }
+                    }
+                    if is_key_pressed(KeyCode::Backspace) {
+                        editor_path.pop();
+                    }
+                    if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Escape) {
+                        editing_path = false;
+                    }
+                } else {
+                    while get_char_pressed().is_some() {} // Keys below are commands, not text
+                    
+                    if is_mouse_button_pressed(MouseButton::Left) {
+                        editor_drag = Some(cursor);
+                    }
+                    if is_mouse_button_released(MouseButton::Left) {
+                        if let Some(start) = editor_drag.take() {
+                            match editor_tool {
+                                EditorTool::Spawn => editor_arena.spawns.push(cursor),
+                                EditorTool::Goal => {
+                                    let (top, bottom) = (start.1.min(cursor.1), start.1.max(cursor.1));
+                                    if bottom - top >= MIN_GOAL_SIZE {
+                                        let index = if start.0 < SCREEN_WIDTH / 2.0 { 0 } else { 1 };
+                                        editor_arena.goals[index] = (top, bottom);
+                                    } else {
+                                        editor_message = Some(format!("Goals must be at least {} high", MIN_GOAL_SIZE));
+                                    }
+                                }
+                                tool => {
+                                    if let Some(obstacle) = editor_shape(tool, start, cursor, editor_hits) {
+                                        editor_arena.obstacles.push(obstacle);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    if is_mouse_button_pressed(MouseButton::Right) && !editor_arena.remove_at(mouse_x, mouse_y) {
+                        editor_message = Some("Nothing to remove there".to_string());
+                    }
+                    
+                    let tool_keys = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4, KeyCode::Key5, KeyCode::Key6];
+                    for (tool, key) in EditorTool::ALL.into_iter().zip(tool_keys) {
+                        if is_key_pressed(key) {
+                            editor_tool = tool;
+                        }
+                    }
+                    if is_key_pressed(KeyCode::Up) {
+                        editor_hits = (editor_hits + 1).min(MAX_BRICK_HITS);
+                    } else if is_key_pressed(KeyCode::Down) {
+                        editor_hits = (editor_hits - 1).max(1);
+                    }
+                    
+                    if is_key_pressed(KeyCode::T) {
+                        // Test-play against the AI, or hot-seat from any other mode
+                        if game_mode != GameMode::VsAI {
+                            game_mode = GameMode::TwoPlayer;
+                        }
+                        bot = None;
+                        game.reset();
+                        match_clock = MatchRule::PRESETS[match_rule].apply(&mut game);
+                        ai_player = AiController::new(profiles[difficulty].params, Side::Right);
+                        powerups = powerups_enabled.then(|| PowerUps::new(macroquad::rand::rand() as u64));
+                        spin = spin_enabled.then(Spin::new);
+                        match_arena = editor_arena.clone();
+                        let mut arena = ArenaState::new(match_arena.clone(), brick_points);
+                        arena.serve(&mut game.ball);
+                        arena_state = Some(arena);
+                        particles.clear();
+                        screen_shake = 0.0;
+                        score_flash_timer = 0.0;
+                        editor_testing = true;
+                        game_state = GameState::Playing;
+                    } else if is_key_pressed(KeyCode::S) {
+                        if let Some(stem) = std::path::Path::new(&editor_path).file_stem() {
+                            editor_arena.name = stem.to_string_lossy().into_owned();
+                        }
+                        editor_message = Some(match editor_arena.save(&editor_path) {
+                            Ok(()) => format!("Saved {}", editor_path),
+                            Err(err) => format!("Could not save {}: {}", editor_path, err),
+                        });
+                    } else if is_key_pressed(KeyCode::L) {
+                        editor_message = Some(match Arena::load(&editor_path) {
+                            Ok(arena) => {
+                                editor_arena = arena;
+                                format!("Loaded {}", editor_path)
+                            }
+                            Err(err) => format!("Could not load {}: {}", editor_path, err),
+                        });
+                    } else if is_key_pressed(KeyCode::F) {
+                        editing_path = true;
+                    } else if is_key_pressed(KeyCode::C) {
+                        editor_arena = Arena { name: editor_arena.name.clone(), ..Arena::empty() };
+                    } else if is_key_pressed(KeyCode::Escape) {
+                        // Back to the mode screen with this arena selected
+                        arenas.retain(|existing| existing.name != editor_arena.name);
+                        arenas.push(editor_arena.clone());
+                        arena_choice = arenas.len() - 1;
+                        editor_drag = None;
+                        game_state = GameState::ModeSelect;
+                    }
+                }
+            }
         }
         
-        // Global exit
-        if is_key_pressed(KeyCode::Escape) {
+        // Global exit (screens with their own back action handle ESC themselves)
+        if is_key_pressed(KeyCode::Escape) && !frame_state.handles_escape() {
             break;
         }
         
@@ -0,0 +1,249 @@
+// Multi-ball: the classic two paddles against several balls at once. Each
+// ball keeps its own speed, picking up `BALL_SPEED_INCREASE` on every hit,
+// and scores on its own. Balls pass through each other.
+//
+// `BallRule` decides how many balls are in play: a fixed number that are
+// re-served as they score, or one ball that gains company on a timer or as
+// the rally grows. With the latter, balls that score leave the field and
+// only the last one is re-served.
+
+use crate::ai::{AiController, AiParams};
+use crate::constants::*;
+use crate::sim::{bounce_off_walls, hit_paddle, Ball, Paddle, Side};
+
+pub const MAX_BALLS: usize = 6;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BallRule {
+    Fixed(usize),  // Always this many balls
+    Timer(f32),    // Another ball every this many seconds
+    Rally(u32),    // Another ball every this many paddle hits without a point
+}
+
+impl BallRule {
+    // Choices offered on the mode screen
+    pub const PRESETS: [BallRule; 4] = [
+        BallRule::Fixed(2),
+        BallRule::Fixed(3),
+        BallRule::Timer(10.0),
+        BallRule::Rally(6),
+    ];
+
+    pub fn label(self) -> String {
+        match self {
+            BallRule::Fixed(count) => format!("{} balls", count),
+            BallRule::Timer(seconds) => format!("+1 ball every {:.0} s", seconds),
+            BallRule::Rally(hits) => format!("+1 ball every {} hits", hits),
+        }
+    }
+
+    fn starting_balls(self) -> usize {
+        match self {
+            BallRule::Fixed(count) => count.clamp(1, MAX_BALLS),
+            BallRule::Timer(_) | BallRule::Rally(_) => 1,
+        }
+    }
+}
+
+// Paddle hits, points and newly served balls in one `MultiBallMatch::step`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MultiBallEvents {
+    pub paddle_hit: Option<(Side, f32)>, // Side and ball Y at impact
+    pub scored: Option<Side>,            // Last point of the step; several balls may score at once
+    pub spawned: bool,
+    pub winner: Option<Side>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultiBallMatch {
+    pub left: Paddle,
+    pub right: Paddle,
+    pub balls: Vec<Ball>,
+    pub left_score: i32,
+    pub right_score: i32,
+    pub winning_score: i32,
+    pub rule: BallRule,
+    spawn_timer: f32,
+    rally: u32, // Paddle hits since the last point
+    serves: u32, // Alternates serve direction and angle
+}
+
+impl MultiBallMatch {
+    pub fn new(rule: BallRule, winning_score: i32) -> Self {
+        let mut game = Self {
+            left: Paddle::new(),
+            right: Paddle::new(),
+            balls: Vec::new(),
+            left_score: 0,
+            right_score: 0,
+            winning_score,
+            rule,
+            spawn_timer: 0.0,
+            rally: 0,
+            serves: 0,
+        };
+        for _ in 0..rule.starting_balls() {
+            game.serve();
+        }
+        game
+    }
+
+    pub fn winner(&self) -> Option<Side> {
+        if self.left_score >= self.winning_score {
+            Some(Side::Left)
+        } else if self.right_score >= self.winning_score {
+            Some(Side::Right)
+        } else {
+            None
+        }
+    }
+
+    pub fn paddle_mut(&mut self, side: Side) -> &mut Paddle {
+        match side {
+            Side::Left => &mut self.left,
+            Side::Right => &mut self.right,
+        }
+    }
+
+    // A new ball from the center, alternating sides and angles so balls
+    // served together spread out
+    fn serve(&mut self) {
+        let towards = if self.serves.is_multiple_of(2) { Side::Right } else { Side::Left };
+        let mut ball = Ball::serve(towards);
+        if self.serves % 4 >= 2 {
+            ball.vel_y = -ball.vel_y;
+        }
+        self.serves += 1;
+        self.balls.push(ball);
+    }
+
+    // Advance paddles and balls by `dt`. Paddle velocities must already
+    // reflect this frame's input (see `Paddle::drive` / `MultiBallAi::drive`).
+    pub fn step(&mut self, dt: f32) -> MultiBallEvents {
+        let mut events = MultiBallEvents::default();
+        if self.winner().is_some() {
+            return events;
+        }
+
+        self.left.integrate(dt);
+        self.right.integrate(dt);
+
+        let mut scored = Vec::new();
+        for (index, ball) in self.balls.iter_mut().enumerate() {
+            ball.x += ball.vel_x * dt;
+            ball.y += ball.vel_y * dt;
+
+            bounce_off_walls(ball);
+            if hit_paddle(ball, &self.left, LEFT_PADDLE_X, Side::Left) {
+                events.paddle_hit = Some((Side::Left, ball.y));
+                self.rally += 1;
+            }
+            if hit_paddle(ball, &self.right, RIGHT_PADDLE_X, Side::Right) {
+                events.paddle_hit = Some((Side::Right, ball.y));
+                self.rally += 1;
+            }
+
+            if ball.x < -BALL_SIZE {
+                scored.push((index, Side::Right));
+            } else if ball.x > SCREEN_WIDTH + BALL_SIZE {
+                scored.push((index, Side::Left));
+            }
+        }
+
+        // Each ball scores on its own; remove them back to front
+        for &(index, side) in scored.iter().rev() {
+            match side {
+                Side::Left => self.left_score += 1,
+                Side::Right => self.right_score += 1,
+            }
+            self.balls.remove(index);
+            events.scored = Some(side);
+        }
+        if events.scored.is_some() {
+            self.rally = 0;
+            self.spawn_timer = 0.0;
+            events.winner = self.winner();
+        }
+
+        // Top the field back up, or add a ball if the rule says so
+        let wanted = match self.rule {
+            BallRule::Fixed(_) => self.rule.starting_balls(),
+            BallRule::Timer(interval) => {
+                self.spawn_timer += dt;
+                if self.spawn_timer >= interval {
+                    self.spawn_timer = 0.0;
+                    self.balls.len() + 1
+                } else {
+                    1
+                }
+            }
+            BallRule::Rally(hits) => {
+                if events.paddle_hit.is_some() && hits > 0 && self.rally.is_multiple_of(hits) {
+                    self.balls.len() + 1
+                } else {
+                    1
+                }
+            }
+        };
+        let mut added = false;
+        while self.balls.len() < wanted.min(MAX_BALLS) && events.winner.is_none() {
+            self.serve();
+            added = true;
+        }
+        // A refill after a point is a serve, not a new ball joining the rally
+        events.spawned = added && events.scored.is_none();
+
+        events
+    }
+}
+
+impl Default for MultiBallMatch {
+    fn default() -> Self {
+        Self::new(BallRule::Fixed(2), WINNING_SCORE)
+    }
+}
+
+// Built-in AI: an `AiController` that defends against the most threatening
+// ball, the one that will reach its goal line first
+#[derive(Clone, Copy, Debug)]
+pub struct MultiBallAi {
+    pub controller: AiController,
+}
+
+impl MultiBallAi {
+    pub fn new(params: AiParams, side: Side) -> Self {
+        Self {
+            controller: AiController::new(params, side),
+        }
+    }
+
+    pub fn drive(&mut self, game: &mut MultiBallMatch, dt: f32, noise: f32) {
+        let side = self.controller.side;
+        let paddle = match side {
+            Side::Left => &mut game.left,
+            Side::Right => &mut game.right,
+        };
+        let balls = &game.balls;
+        self.controller.drive_paddle(paddle, dt, noise, |params| {
+            let threat = balls
+                .iter()
+                .filter_map(|ball| time_to_reach(ball, side).map(|time| (ball, time)))
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            match threat {
+                Some((ball, time)) => ball.y + ball.vel_y * time * params.prediction,
+                // Nothing incoming: drift back towards the middle
+                None => SCREEN_HEIGHT / 2.0,
+            }
+        });
+    }
+}
+
+// Seconds until `ball` reaches `side`'s paddle, if it's heading that way
+fn time_to_reach(ball: &Ball, side: Side) -> Option<f32> {
+    let (paddle_x, facing) = match side {
+        Side::Left => (LEFT_PADDLE_X + PADDLE_WIDTH, -1.0),
+        Side::Right => (RIGHT_PADDLE_X, 1.0),
+    };
+    let distance = facing * (paddle_x - ball.x);
+    (ball.vel_x * facing > 0.0 && distance >= 0.0).then(|| distance / ball.vel_x.abs())
+}
@@ -0,0 +1,678 @@
+// Datagram transports and the wire format shared by the networked modes.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::rc::Rc;
+use std::time::Instant;
+
+use crate::constants::NET_DISCONNECT_TIMEOUT;
+use crate::lobby::{Member, RoomSummary, Rules};
+use crate::sim::{Ball, Match, Paddle, Side};
+
+const MAGIC: u8 = 0x50; // 'P'
+const VERSION: u8 = 6;
+pub const MAX_PACKET: usize = 2048; // Receive buffer size; every packet has to fit
+
+// An unreliable, unordered datagram link to one peer
+pub trait Transport {
+    fn send(&mut self, packet: &[u8]);
+    fn recv(&mut self) -> Option<Vec<u8>>;
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn send(&mut self, packet: &[u8]) {
+        (**self).send(packet)
+    }
+
+    fn recv(&mut self) -> Option<Vec<u8>> {
+        (**self).recv()
+    }
+}
+
+// Non-blocking UDP socket talking to a single peer. A host starts without a
+// peer and adopts the address of the first datagram it receives. Once the
+// peer has gone quiet, a `Hello` from a new address takes its place, so a
+// peer whose address changed can rejoin.
+pub struct UdpTransport {
+    socket: UdpSocket,
+    peer: Option<SocketAddr>,
+    last_heard: Instant,
+    buffer: Vec<u8>,
+}
+
+impl UdpTransport {
+    pub fn host(port: u16) -> io::Result<Self> {
+        Self::bind(("0.0.0.0", port), None)
+    }
+
+    pub fn join(address: &str) -> io::Result<Self> {
+        let peer = address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no address found"))?;
+        let local: SocketAddr = if peer.is_ipv4() {
+            "0.0.0.0:0".parse().unwrap()
+        } else {
+            "[::]:0".parse().unwrap()
+        };
+        Self::bind(local, Some(peer))
+    }
+
+    fn bind(address: impl ToSocketAddrs, peer: Option<SocketAddr>) -> io::Result<Self> {
+        let socket = UdpSocket::bind(address)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            peer,
+            last_heard: Instant::now(),
+            buffer: vec![0; MAX_PACKET],
+        })
+    }
+
+    pub fn peer(&self) -> Option<SocketAddr> {
+        self.peer
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+}
+
+impl Transport for UdpTransport {
+    fn send(&mut self, packet: &[u8]) {
+        if let Some(peer) = self.peer {
+            // Datagrams are allowed to get lost; the protocol resends
+            let _ = self.socket.send_to(packet, peer);
+        }
+    }
+
+    fn recv(&mut self) -> Option<Vec<u8>> {
+        loop {
+            match self.socket.recv_from(&mut self.buffer) {
+                Ok((len, from)) => {
+                    let data = &self.buffer[..len];
+                    let quiet = self.last_heard.elapsed().as_secs_f32() > NET_DISCONNECT_TIMEOUT;
+                    match self.peer {
+                        None => self.peer = Some(from),
+                        Some(peer) if peer == from => {}
+                        Some(_) if quiet && Packet::decode(data) == Some(Packet::Hello) => self.peer = Some(from),
+                        Some(_) => continue, // Not our opponent
+                    }
+                    self.last_heard = Instant::now();
+                    return Some(data.to_vec());
+                }
+                // WouldBlock means no datagram is waiting; other errors (e.g.
+                // ICMP port unreachable while the peer starts) are transient
+                Err(_) => return None,
+            }
+        }
+    }
+}
+
+// Both ends of an in-process link, for tests and headless experiments.
+// Delivery is immediate and in order; wrap an end in a `NetSimulator` to
+// make it misbehave.
+pub struct MemoryTransport {
+    inbox: Rc<RefCell<VecDeque<Vec<u8>>>>,
+    outbox: Rc<RefCell<VecDeque<Vec<u8>>>>,
+}
+
+impl MemoryTransport {
+    pub fn pair() -> (Self, Self) {
+        let a = Rc::new(RefCell::new(VecDeque::new()));
+        let b = Rc::new(RefCell::new(VecDeque::new()));
+        (
+            Self {
+                inbox: a.clone(),
+                outbox: b.clone(),
+            },
+            Self { inbox: b, outbox: a },
+        )
+    }
+}
+
+impl Transport for MemoryTransport {
+    fn send(&mut self, packet: &[u8]) {
+        self.outbox.borrow_mut().push_back(packet.to_vec());
+    }
+
+    fn recv(&mut self) -> Option<Vec<u8>> {
+        self.inbox.borrow_mut().pop_front()
+    }
+}
+
+// Messages exchanged between two rollback peers, between the authoritative
+// server and its clients (Join onwards), or in the server's lobby (ListRooms onwards)
+#[derive(Clone, Debug, PartialEq)]
+pub enum Packet {
+    Hello,
+    Welcome,
+    Input {
+        frame: u32,        // Sender's current frame
+        ack: u32,          // Number of the receiver's inputs the sender has (contiguous)
+        start: u32,        // Frame of inputs[0]
+        inputs: Vec<u8>,   // Sender's inputs, `Intent::to_action` encoded
+        ping: f64,         // Sender's clock when sent
+        pong: f64,         // Last `ping` the sender received from us
+        checks_ack: u32,   // Number of the receiver's checksums the sender has
+        checks_start: u32, // Frame of checks[0]
+        checks: Vec<u32>,  // `Match::checksum` after each confirmed frame
+    },
+    StateDump {
+        frame: u32, // First divergent frame, state after simulating it
+        state: Match,
+    },
+    Watch,
+    Spectate {
+        tick: u32,          // Server tick of `state`, already delayed for spectators
+        state: Match,
+        rally: u32,         // Paddle hits since the last point
+        names: [String; 2], // Left, right
+        pings: [u16; 2],    // Players' round trips in milliseconds
+        spectators: u16,
+    },
+    Join {
+        name: String,
+        token: u64, // From an earlier `Accept` to take our seat back, or 0
+    },
+    Accept {
+        side: Side,
+        token: u64, // Proves the seat is ours when rejoining from a new address
+    },
+    Commands {
+        start: u32,      // Client tick of inputs[0]
+        inputs: Vec<u8>, // Inputs the server hasn't acknowledged yet
+        ping: f64,
+        rtt_ms: u16,     // Client's measured round trip, shown to spectators
+    },
+    Snapshot {
+        tick: u32, // Server tick the state was taken after
+        ack: u32,  // Number of the recipient's inputs the server has applied
+        pong: f64,
+        state: Match,
+        absent: Option<Side>, // Side the match is paused for, or that forfeited
+        resume_within: f32,   // Seconds the absent side has left to return
+    },
+    ListRooms,
+    Rooms {
+        rooms: Vec<RoomSummary>,
+    },
+    Seat {
+        room: u8,
+        name: String,
+        side: Option<Side>, // Side asked for, if any
+        ready_for: u32,     // Room version the sender is ready to play, 0 = not ready
+        rules_base: u32,    // Room version `rules` were edited from
+        rules: Rules,
+        chat_seq: u32, // Number of `chat` among the sender's messages
+        chat: String,  // Oldest unacknowledged message, or empty
+    },
+    Leave,
+    Room {
+        room: u8,
+        you: Option<u8>, // Recipient's index in `members`, None if it has no seat
+        members: Vec<Member>,
+        version: u32,
+        rules: Rules,
+        playing: bool,
+        chat_ack: u32,            // Number of the recipient's messages taken
+        chat: Vec<(u32, String)>, // Latest lines with their ids
+    },
+}
+
+impl Packet {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = vec![MAGIC, VERSION];
+        match self {
+            Packet::Hello => out.push(1),
+            Packet::Welcome => out.push(2),
+            Packet::Input {
+                frame,
+                ack,
+                start,
+                inputs,
+                ping,
+                pong,
+                checks_ack,
+                checks_start,
+                checks,
+            } => {
+                out.push(3);
+                out.extend_from_slice(&frame.to_le_bytes());
+                out.extend_from_slice(&ack.to_le_bytes());
+                out.extend_from_slice(&start.to_le_bytes());
+                out.extend_from_slice(&ping.to_le_bytes());
+                out.extend_from_slice(&pong.to_le_bytes());
+                out.push(inputs.len().min(255) as u8);
+                out.extend_from_slice(&inputs[..inputs.len().min(255)]);
+                out.extend_from_slice(&checks_ack.to_le_bytes());
+                out.extend_from_slice(&checks_start.to_le_bytes());
+                out.push(checks.len().min(255) as u8);
+                for check in &checks[..checks.len().min(255)] {
+                    out.extend_from_slice(&check.to_le_bytes());
+                }
+            }
+            Packet::StateDump { frame, state } => {
+                out.push(8);
+                out.extend_from_slice(&frame.to_le_bytes());
+                write_match(&mut out, state);
+            }
+            Packet::Watch => out.push(9),
+            Packet::Spectate {
+                tick,
+                state,
+                rally,
+                names,
+                pings,
+                spectators,
+            } => {
+                out.push(10);
+                out.extend_from_slice(&tick.to_le_bytes());
+                write_match(&mut out, state);
+                out.extend_from_slice(&rally.to_le_bytes());
+                for (name, ping) in names.iter().zip(pings) {
+                    write_str(&mut out, name);
+                    out.extend_from_slice(&ping.to_le_bytes());
+                }
+                out.extend_from_slice(&spectators.to_le_bytes());
+            }
+            Packet::Join { name, token } => {
+                out.push(4);
+                write_str(&mut out, name);
+                out.extend_from_slice(&token.to_le_bytes());
+            }
+            Packet::Accept { side, token } => {
+                out.push(5);
+                out.push(*side as u8);
+                out.extend_from_slice(&token.to_le_bytes());
+            }
+            Packet::Commands {
+                start,
+                inputs,
+                ping,
+                rtt_ms,
+            } => {
+                out.push(6);
+                out.extend_from_slice(&start.to_le_bytes());
+                out.extend_from_slice(&ping.to_le_bytes());
+                out.extend_from_slice(&rtt_ms.to_le_bytes());
+                out.push(inputs.len().min(255) as u8);
+                out.extend_from_slice(&inputs[..inputs.len().min(255)]);
+            }
+            Packet::Snapshot {
+                tick,
+                ack,
+                pong,
+                state,
+                absent,
+                resume_within,
+            } => {
+                out.push(7);
+                out.extend_from_slice(&tick.to_le_bytes());
+                out.extend_from_slice(&ack.to_le_bytes());
+                out.extend_from_slice(&pong.to_le_bytes());
+                write_match(&mut out, state);
+                write_optional_side(&mut out, *absent);
+                out.extend_from_slice(&resume_within.to_le_bytes());
+            }
+            Packet::ListRooms => out.push(11),
+            Packet::Rooms { rooms } => {
+                out.push(12);
+                out.push(rooms.len().min(255) as u8);
+                for room in rooms.iter().take(255) {
+                    write_str(&mut out, &room.name);
+                    out.push(room.playing as u8);
+                    out.push(room.players.len().min(2) as u8);
+                    for player in room.players.iter().take(2) {
+                        write_str(&mut out, player);
+                    }
+                }
+            }
+            Packet::Seat {
+                room,
+                name,
+                side,
+                ready_for,
+                rules_base,
+                rules,
+                chat_seq,
+                chat,
+            } => {
+                out.push(13);
+                out.push(*room);
+                write_str(&mut out, name);
+                write_optional_side(&mut out, *side);
+                out.extend_from_slice(&ready_for.to_le_bytes());
+                out.extend_from_slice(&rules_base.to_le_bytes());
+                write_rules(&mut out, rules);
+                out.extend_from_slice(&chat_seq.to_le_bytes());
+                write_str(&mut out, chat);
+            }
+            Packet::Leave => out.push(14),
+            Packet::Room {
+                room,
+                you,
+                members,
+                version,
+                rules,
+                playing,
+                chat_ack,
+                chat,
+            } => {
+                out.push(15);
+                out.push(*room);
+                out.push(you.unwrap_or(255));
+                out.push(members.len().min(2) as u8);
+                for member in members.iter().take(2) {
+                    write_str(&mut out, &member.name);
+                    out.push(member.side as u8);
+                    out.push(member.ready as u8);
+                }
+                out.extend_from_slice(&version.to_le_bytes());
+                write_rules(&mut out, rules);
+                out.push(*playing as u8);
+                out.extend_from_slice(&chat_ack.to_le_bytes());
+                out.push(chat.len().min(255) as u8);
+                for (id, line) in chat.iter().take(255) {
+                    out.extend_from_slice(&id.to_le_bytes());
+                    write_str(&mut out, line);
+                }
+            }
+        }
+        out
+    }
+
+    pub fn decode(data: &[u8]) -> Option<Packet> {
+        let mut reader = Reader::new(data);
+        if reader.u8()? != MAGIC || reader.u8()? != VERSION {
+            return None;
+        }
+        match reader.u8()? {
+            1 => Some(Packet::Hello),
+            2 => Some(Packet::Welcome),
+            3 => {
+                let frame = reader.u32()?;
+                let ack = reader.u32()?;
+                let start = reader.u32()?;
+                let ping = reader.f64()?;
+                let pong = reader.f64()?;
+                let count = reader.u8()? as usize;
+                let inputs = reader.bytes(count)?.to_vec();
+                let checks_ack = reader.u32()?;
+                let checks_start = reader.u32()?;
+                let count = reader.u8()? as usize;
+                let checks = (0..count).map(|_| reader.u32()).collect::<Option<_>>()?;
+                Some(Packet::Input {
+                    frame,
+                    ack,
+                    start,
+                    inputs,
+                    ping,
+                    pong,
+                    checks_ack,
+                    checks_start,
+                    checks,
+                })
+            }
+            4 => Some(Packet::Join {
+                name: reader.string()?,
+                token: reader.u64()?,
+            }),
+            5 => Some(Packet::Accept {
+                side: read_side(&mut reader)?,
+                token: reader.u64()?,
+            }),
+            6 => {
+                let start = reader.u32()?;
+                let ping = reader.f64()?;
+                let rtt_ms = reader.u16()?;
+                let count = reader.u8()? as usize;
+                let inputs = reader.bytes(count)?.to_vec();
+                Some(Packet::Commands {
+                    start,
+                    inputs,
+                    ping,
+                    rtt_ms,
+                })
+            }
+            7 => {
+                let tick = reader.u32()?;
+                let ack = reader.u32()?;
+                let pong = reader.f64()?;
+                let state = read_match(&mut reader)?;
+                Some(Packet::Snapshot {
+                    tick,
+                    ack,
+                    pong,
+                    state,
+                    absent: read_optional_side(&mut reader)?,
+                    resume_within: reader.f32()?,
+                })
+            }
+            8 => {
+                let frame = reader.u32()?;
+                let state = read_match(&mut reader)?;
+                Some(Packet::StateDump { frame, state })
+            }
+            9 => Some(Packet::Watch),
+            10 => {
+                let tick = reader.u32()?;
+                let state = read_match(&mut reader)?;
+                let rally = reader.u32()?;
+                let (left, left_ping) = (reader.string()?, reader.u16()?);
+                let (right, right_ping) = (reader.string()?, reader.u16()?);
+                let spectators = reader.u16()?;
+                Some(Packet::Spectate {
+                    tick,
+                    state,
+                    rally,
+                    names: [left, right],
+                    pings: [left_ping, right_ping],
+                    spectators,
+                })
+            }
+            11 => Some(Packet::ListRooms),
+            12 => {
+                let count = reader.u8()?;
+                let mut rooms = Vec::new();
+                for _ in 0..count {
+                    let name = reader.string()?;
+                    let playing = reader.u8()? != 0;
+                    let players = (0..reader.u8()?).map(|_| reader.string()).collect::<Option<_>>()?;
+                    rooms.push(RoomSummary { name, players, playing });
+                }
+                Some(Packet::Rooms { rooms })
+            }
+            13 => {
+                let room = reader.u8()?;
+                let name = reader.string()?;
+                let side = read_optional_side(&mut reader)?;
+                Some(Packet::Seat {
+                    room,
+                    name,
+                    side,
+                    ready_for: reader.u32()?,
+                    rules_base: reader.u32()?,
+                    rules: read_rules(&mut reader)?,
+                    chat_seq: reader.u32()?,
+                    chat: reader.string()?,
+                })
+            }
+            14 => Some(Packet::Leave),
+            15 => {
+                let room = reader.u8()?;
+                let you = Some(reader.u8()?).filter(|&you| you != 255);
+                let mut members = Vec::new();
+                for _ in 0..reader.u8()?.min(2) {
+                    let name = reader.string()?;
+                    let side = read_side(&mut reader)?;
+                    let ready = reader.u8()? != 0;
+                    members.push(Member { name, side, ready });
+                }
+                let version = reader.u32()?;
+                let rules = read_rules(&mut reader)?;
+                let playing = reader.u8()? != 0;
+                let chat_ack = reader.u32()?;
+                let chat = (0..reader.u8()?)
+                    .map(|_| Some((reader.u32()?, reader.string()?)))
+                    .collect::<Option<_>>()?;
+                Some(Packet::Room {
+                    room,
+                    you,
+                    members,
+                    version,
+                    rules,
+                    playing,
+                    chat_ack,
+                    chat,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+// Length-prefixed UTF-8, at most 255 bytes
+fn write_str(out: &mut Vec<u8>, text: &str) {
+    let mut end = text.len().min(255);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    out.push(end as u8);
+    out.extend_from_slice(&text.as_bytes()[..end]);
+}
+
+fn read_side(reader: &mut Reader) -> Option<Side> {
+    match reader.u8()? {
+        0 => Some(Side::Left),
+        1 => Some(Side::Right),
+        _ => None,
+    }
+}
+
+fn write_optional_side(out: &mut Vec<u8>, side: Option<Side>) {
+    out.push(side.map_or(2, |side| side as u8));
+}
+
+// Outer None for a malformed packet, inner None for no side
+fn read_optional_side(reader: &mut Reader) -> Option<Option<Side>> {
+    match reader.u8()? {
+        0 => Some(Some(Side::Left)),
+        1 => Some(Some(Side::Right)),
+        2 => Some(None),
+        _ => None,
+    }
+}
+
+// Winning score, then the AI fill-in difficulty or an empty string
+fn write_rules(out: &mut Vec<u8>, rules: &Rules) {
+    out.push(rules.winning_score.clamp(1, 255) as u8);
+    write_str(out, rules.ai_fill.as_deref().unwrap_or_default());
+}
+
+fn read_rules(reader: &mut Reader) -> Option<Rules> {
+    let winning_score = reader.u8()? as i32;
+    let ai_fill = Some(reader.string()?).filter(|name| !name.is_empty());
+    Some(Rules { winning_score, ai_fill })
+}
+
+fn write_match(out: &mut Vec<u8>, game: &Match) {
+    let ball = &game.ball;
+    let values = [
+        game.left.y,
+        game.left.vel,
+        game.right.y,
+        game.right.vel,
+        ball.x,
+        ball.y,
+        ball.vel_x,
+        ball.vel_y,
+        ball.speed,
+    ];
+    for value in values {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    out.extend_from_slice(&game.left_score.to_le_bytes());
+    out.extend_from_slice(&game.right_score.to_le_bytes());
+    out.push(game.winning_score.clamp(1, 255) as u8);
+}
+
+fn read_match(reader: &mut Reader) -> Option<Match> {
+    let mut v = [0.0; 9];
+    for value in v.iter_mut() {
+        *value = reader.f32()?;
+    }
+    Some(Match {
+        left: Paddle {
+            y: v[0],
+            vel: v[1],
+            ..Paddle::new()
+        },
+        right: Paddle {
+            y: v[2],
+            vel: v[3],
+            ..Paddle::new()
+        },
+        ball: Ball {
+            x: v[4],
+            y: v[5],
+            vel_x: v[6],
+            vel_y: v[7],
+            speed: v[8],
+        },
+        left_score: reader.u32()? as i32,
+        right_score: reader.u32()? as i32,
+        winning_score: reader.u8()? as i32,
+    })
+}
+
+// Little-endian cursor over a received datagram
+pub struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub fn bytes(&mut self, count: usize) -> Option<&'a [u8]> {
+        if self.data.len() < count {
+            return None;
+        }
+        let (head, tail) = self.data.split_at(count);
+        self.data = tail;
+        Some(head)
+    }
+
+    pub fn u8(&mut self) -> Option<u8> {
+        Some(self.bytes(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.bytes(2)?.try_into().ok()?))
+    }
+
+    pub fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.bytes(4)?.try_into().ok()?))
+    }
+
+    pub fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.bytes(8)?.try_into().ok()?))
+    }
+
+    pub fn f32(&mut self) -> Option<f32> {
+        Some(f32::from_le_bytes(self.bytes(4)?.try_into().ok()?))
+    }
+
+    pub fn f64(&mut self) -> Option<f64> {
+        Some(f64::from_le_bytes(self.bytes(8)?.try_into().ok()?))
+    }
+
+    pub fn string(&mut self) -> Option<String> {
+        let len = self.u8()? as usize;
+        String::from_utf8(self.bytes(len)?.to_vec()).ok()
+    }
+}
@@ -0,0 +1,158 @@
+// Bad-network simulator for testing the netcode. Wraps any `Transport` and
+// delays, drops, reorders and duplicates datagrams in both directions, so
+// one impaired peer sees the full round trip degraded. In the game:
+//
+//   pong_rs --join 10.0.0.2:7777 --netsim latency=80,jitter=20,loss=0.05,reorder=0.1
+
+use crate::net::Transport;
+use crate::sim::Rng;
+
+// How one direction of the link misbehaves
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NetConditions {
+    pub latency: f64,   // Seconds every datagram is delayed
+    pub jitter: f64,    // Up to this many extra seconds, random per datagram
+    pub loss: f32,      // Chance a datagram is dropped
+    pub reorder: f32,   // Chance a datagram is held back behind later ones
+    pub duplicate: f32, // Chance a datagram is delivered twice
+}
+
+impl NetConditions {
+    // Comma separated `key=value` list; latency and jitter in milliseconds,
+    // the rest as probabilities between 0 and 1
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut conditions = NetConditions::default();
+        for item in spec.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            let (key, value) = item
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=value, got \"{}\"", item))?;
+            let value: f64 = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid number for {}: \"{}\"", key, value))?;
+            let chance = || {
+                if (0.0..=1.0).contains(&value) {
+                    Ok(value as f32)
+                } else {
+                    Err(format!("{} must be between 0 and 1", key))
+                }
+            };
+            match key.trim() {
+                "latency" => conditions.latency = value.max(0.0) / 1000.0,
+                "jitter" => conditions.jitter = value.max(0.0) / 1000.0,
+                "loss" => conditions.loss = chance()?,
+                "reorder" => conditions.reorder = chance()?,
+                "duplicate" => conditions.duplicate = chance()?,
+                other => return Err(format!("unknown network condition \"{}\"", other)),
+            }
+        }
+        Ok(conditions)
+    }
+}
+
+struct Delayed {
+    due: f64,
+    order: u64, // Tie breaker so equal delays keep sending order
+    data: Vec<u8>,
+}
+
+pub struct NetSimulator<T: Transport> {
+    inner: T,
+    conditions: NetConditions,
+    rng: Rng,
+    clock: Box<dyn Fn() -> f64>,
+    outgoing: Vec<Delayed>,
+    incoming: Vec<Delayed>,
+    sequence: u64,
+    dropped: u32,
+}
+
+impl<T: Transport> NetSimulator<T> {
+    // `clock` returns seconds; use the game clock, or a manual one in tests
+    pub fn new(inner: T, conditions: NetConditions, seed: u64, clock: impl Fn() -> f64 + 'static) -> Self {
+        Self {
+            inner,
+            conditions,
+            rng: Rng::new(seed),
+            clock: Box::new(clock),
+            outgoing: Vec::new(),
+            incoming: Vec::new(),
+            sequence: 0,
+            dropped: 0,
+        }
+    }
+
+    pub fn conditions(&self) -> NetConditions {
+        self.conditions
+    }
+
+    // Datagrams lost so far, both directions
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    fn schedule(&mut self, queue: Direction, data: Vec<u8>, now: f64) {
+        let c = self.conditions;
+        if self.rng.next_f32() < c.loss {
+            self.dropped += 1;
+            return;
+        }
+        let copies = if self.rng.next_f32() < c.duplicate { 2 } else { 1 };
+        for _ in 0..copies {
+            let mut delay = c.latency + c.jitter * self.rng.next_f32() as f64;
+            if self.rng.next_f32() < c.reorder {
+                delay += c.latency.max(c.jitter).max(0.02); // Long enough to be overtaken
+            }
+            let delayed = Delayed {
+                due: now + delay,
+                order: self.sequence,
+                data: data.clone(),
+            };
+            self.sequence += 1;
+            match queue {
+                Direction::Outgoing => self.outgoing.push(delayed),
+                Direction::Incoming => self.incoming.push(delayed),
+            }
+        }
+    }
+
+    fn flush_outgoing(&mut self, now: f64) {
+        while let Some(data) = take_due(&mut self.outgoing, now) {
+            self.inner.send(&data);
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Direction {
+    Outgoing,
+    Incoming,
+}
+
+// Remove and return the earliest datagram that is due
+fn take_due(queue: &mut Vec<Delayed>, now: f64) -> Option<Vec<u8>> {
+    let (index, _) = queue
+        .iter()
+        .enumerate()
+        .filter(|(_, delayed)| delayed.due <= now)
+        .min_by(|(_, a), (_, b)| a.due.total_cmp(&b.due).then(a.order.cmp(&b.order)))?;
+    Some(queue.swap_remove(index).data)
+}
+
+impl<T: Transport> Transport for NetSimulator<T> {
+    fn send(&mut self, packet: &[u8]) {
+        let now = (self.clock)();
+        self.schedule(Direction::Outgoing, packet.to_vec(), now);
+        self.flush_outgoing(now);
+    }
+
+    fn recv(&mut self) -> Option<Vec<u8>> {
+        let now = (self.clock)();
+        // Sessions poll every frame, which is also when delayed sends go out
+        self.flush_outgoing(now);
+        while let Some(data) = self.inner.recv() {
+            self.schedule(Direction::Incoming, data, now);
+        }
+        take_due(&mut self.incoming, now)
+    }
+}
@@ -0,0 +1,140 @@
+// Small multilayer perceptron used as an AI opponent. Inference is plain
+// Rust over `Match::observation`, and the network outputs one score per
+// paddle action (stay, up, down); the highest score wins.
+//
+// Weights file format (plain text, `#` starts a comment):
+//
+//   pong-mlp 1
+//   layers 8 16 3        # input size, hidden sizes..., output size
+//   relu                 # activation for hidden layers: relu or tanh
+//   <weights for layer 1, row-major, `out` rows of `in` values>
+//   <biases for layer 1, `out` values>
+//   ... repeated for every layer
+//
+// Numbers may be split across lines freely.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::sim::{Intent, Match, Side, OBS_DIM};
+
+const HEADER: &str = "pong-mlp 1";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Activation {
+    Relu,
+    Tanh,
+}
+
+#[derive(Clone, Debug)]
+struct Layer {
+    inputs: usize,
+    outputs: usize,
+    weights: Vec<f32>, // outputs x inputs, row-major
+    biases: Vec<f32>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Mlp {
+    layers: Vec<Layer>,
+    activation: Activation,
+}
+
+impl Mlp {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut tokens = text
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or(""))
+            .flat_map(str::split_whitespace);
+
+        if [tokens.next(), tokens.next()] != [Some("pong-mlp"), Some("1")] {
+            return Err(format!("expected header \"{}\"", HEADER));
+        }
+        if tokens.next() != Some("layers") {
+            return Err("expected layer sizes".to_string());
+        }
+
+        // Layer sizes run until the activation keyword
+        let mut sizes = Vec::new();
+        let activation = loop {
+            match tokens.next() {
+                Some("relu") => break Activation::Relu,
+                Some("tanh") => break Activation::Tanh,
+                Some(size) => sizes.push(
+                    size.parse::<usize>()
+                        .map_err(|_| format!("bad layer size {}", size))?,
+                ),
+                None => return Err("missing activation".to_string()),
+            }
+        };
+        if sizes.len() < 2 {
+            return Err("need at least an input and an output size".to_string());
+        }
+        if sizes[0] != OBS_DIM {
+            return Err(format!("input size must be {}", OBS_DIM));
+        }
+        if sizes[sizes.len() - 1] != 3 {
+            return Err("output size must be 3 (stay, up, down)".to_string());
+        }
+
+        let mut read = |count: usize| -> Result<Vec<f32>, String> {
+            (0..count)
+                .map(|_| {
+                    let token = tokens.next().ok_or("weights file is truncated")?;
+                    token.parse().map_err(|_| format!("bad number {}", token))
+                })
+                .collect()
+        };
+
+        let mut layers = Vec::new();
+        for pair in sizes.windows(2) {
+            let (inputs, outputs) = (pair[0], pair[1]);
+            layers.push(Layer {
+                inputs,
+                outputs,
+                weights: read(inputs * outputs)?,
+                biases: read(outputs)?,
+            });
+        }
+
+        Ok(Self { layers, activation })
+    }
+
+    pub fn forward(&self, input: &[f32]) -> Vec<f32> {
+        let mut values = input.to_vec();
+        for (index, layer) in self.layers.iter().enumerate() {
+            let hidden = index + 1 < self.layers.len();
+            values = (0..layer.outputs)
+                .map(|row| {
+                    let weights = &layer.weights[row * layer.inputs..(row + 1) * layer.inputs];
+                    let sum = layer.biases[row]
+                        + weights.iter().zip(&values).map(|(w, v)| w * v).sum::<f32>();
+                    match (hidden, self.activation) {
+                        (false, _) => sum,
+                        (true, Activation::Relu) => sum.max(0.0),
+                        (true, Activation::Tanh) => sum.tanh(),
+                    }
+                })
+                .collect();
+        }
+        values
+    }
+
+    // Pick the paddle action for `side` from the current match state
+    pub fn decide(&self, game: &Match, side: Side) -> Intent {
+        let scores = self.forward(&game.observation(side));
+        let best = scores
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map_or(0, |(index, _)| index);
+        // Observations keep Y unmirrored, so up/down mean the same for both sides
+        Intent::from_action(best as u8)
+    }
+}
@@ -0,0 +1,298 @@
+// Power-ups for local matches: pickups appear around the center line and
+// go to whoever hit the ball last when it passes through one. They work
+// around `Match::step` rather than inside it.
+//
+// Paddle size is recomputed from the active effects every step, ball speed
+// changes move the ball further (or less far) along its current velocity,
+// and a sticky paddle holds the ball for a moment before sending it off
+// with the usual paddle angle.
+
+use crate::constants::*;
+use crate::sim::{aim, Intent, Match, Rng, Side, StepEvents};
+
+pub const PICKUP_RADIUS: f32 = 16.0;
+const SPAWN_INTERVAL: f32 = 6.0; // Seconds between a pickup going and the next one appearing
+const SPAWN_SPREAD_X: f32 = 80.0; // How far either side of the center line pickups appear
+const SPAWN_MARGIN_Y: f32 = 80.0; // Kept clear at the top and bottom
+const BIG_PADDLE_SCALE: f32 = 1.5;
+const SMALL_PADDLE_SCALE: f32 = 0.6;
+const FAST_BALL_SCALE: f32 = 1.4;
+const SLOW_BALL_SCALE: f32 = 0.65;
+const STICKY_HOLD: f32 = 0.5; // Seconds a sticky paddle holds the ball
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Kind {
+    BigPaddle,
+    SmallPaddle,
+    FastBall,
+    SlowBall,
+    Reverse,
+    Shield,
+    Sticky,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 7] = [
+        Kind::BigPaddle,
+        Kind::SmallPaddle,
+        Kind::FastBall,
+        Kind::SlowBall,
+        Kind::Reverse,
+        Kind::Shield,
+        Kind::Sticky,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::BigPaddle => "Big Paddle",
+            Kind::SmallPaddle => "Small Paddle",
+            Kind::FastBall => "Fast Ball",
+            Kind::SlowBall => "Slow Ball",
+            Kind::Reverse => "Reversed",
+            Kind::Shield => "Shield",
+            Kind::Sticky => "Sticky",
+        }
+    }
+
+    // Seconds the effect lasts; a shield also ends once it has saved a goal
+    pub fn duration(self) -> f32 {
+        match self {
+            Kind::BigPaddle | Kind::SmallPaddle => 10.0,
+            Kind::FastBall | Kind::SlowBall => 6.0,
+            Kind::Reverse => 5.0,
+            Kind::Shield => 12.0,
+            Kind::Sticky => 8.0,
+        }
+    }
+
+    // Hinders the collector's opponent rather than helping the collector
+    pub fn targets_opponent(self) -> bool {
+        matches!(self, Kind::SmallPaddle | Kind::Reverse)
+    }
+}
+
+// A power-up waiting on the field
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pickup {
+    pub kind: Kind,
+    pub x: f32,
+    pub y: f32,
+}
+
+// An active power-up. `side` is the player it applies to; ball speed
+// changes affect both players and are listed under the collector.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Effect {
+    pub kind: Kind,
+    pub side: Side,
+    pub remaining: f32,
+}
+
+// Pickups collected, effects running out and shield saves in one step
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PowerUpEvents {
+    pub collected: Option<(Pickup, Side)>, // Pickup and the player credited
+    pub expired: Option<Effect>,
+    pub shield_used: Option<(Side, f32)>, // Side and ball Y at the save
+}
+
+// Ball held by a sticky paddle: side, offset from the paddle top, time left
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Held {
+    side: Side,
+    offset: f32,
+    remaining: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct PowerUps {
+    pub pickup: Option<Pickup>,
+    pub effects: Vec<Effect>,
+    rng: Rng,
+    spawn_timer: f32,
+    last_hit: Option<Side>,
+    held: Option<Held>,
+}
+
+impl PowerUps {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            pickup: None,
+            effects: Vec::new(),
+            rng: Rng::new(seed),
+            spawn_timer: SPAWN_INTERVAL,
+            last_hit: None,
+            held: None,
+        }
+    }
+
+    pub fn is_active(&self, kind: Kind, side: Side) -> bool {
+        self.effects.iter().any(|effect| effect.kind == kind && effect.side == side)
+    }
+
+    pub fn is_reversed(&self, side: Side) -> bool {
+        self.is_active(Kind::Reverse, side)
+    }
+
+    // `intent` as it acts on `side`'s paddle, i.e. swapped while reversed
+    pub fn intent(&self, side: Side, intent: Intent) -> Intent {
+        if !self.is_reversed(side) {
+            return intent;
+        }
+        match intent {
+            Intent::Up => Intent::Down,
+            Intent::Down => Intent::Up,
+            Intent::Stay => Intent::Stay,
+        }
+    }
+
+    // Start (or restart) `kind` as if `collector` had picked it up
+    pub fn grant(&mut self, kind: Kind, collector: Side) {
+        let side = if kind.targets_opponent() { collector.opponent() } else { collector };
+        // Opposite ball speeds cancel out rather than stack
+        let cancels = match kind {
+            Kind::FastBall => Some(Kind::SlowBall),
+            Kind::SlowBall => Some(Kind::FastBall),
+            _ => None,
+        };
+        self.effects
+            .retain(|effect| !(effect.kind == kind && effect.side == side) && Some(effect.kind) != cancels);
+        self.effects.push(Effect {
+            kind,
+            side,
+            remaining: kind.duration(),
+        });
+    }
+
+    fn paddle_scale(&self, side: Side) -> f32 {
+        let mut scale = 1.0;
+        if self.is_active(Kind::BigPaddle, side) {
+            scale *= BIG_PADDLE_SCALE;
+        }
+        if self.is_active(Kind::SmallPaddle, side) {
+            scale *= SMALL_PADDLE_SCALE;
+        }
+        scale
+    }
+
+    fn ball_scale(&self) -> f32 {
+        if self.effects.iter().any(|effect| effect.kind == Kind::FastBall) {
+            FAST_BALL_SCALE
+        } else if self.effects.iter().any(|effect| effect.kind == Kind::SlowBall) {
+            SLOW_BALL_SCALE
+        } else {
+            1.0
+        }
+    }
+
+    // `Match::step` with the power-ups applied. Paddle velocities must
+    // already reflect this frame's input, passed through `intent`.
+    pub fn step(&mut self, game: &mut Match, dt: f32) -> (StepEvents, PowerUpEvents) {
+        let mut extra = PowerUpEvents::default();
+
+        // Tick effects, one expiry reported per step
+        for effect in self.effects.iter_mut() {
+            effect.remaining -= dt;
+        }
+        if let Some(index) = self.effects.iter().position(|effect| effect.remaining <= 0.0) {
+            extra.expired = Some(self.effects.remove(index));
+        }
+
+        // Resize paddles around their centers
+        for side in [Side::Left, Side::Right] {
+            let height = PADDLE_HEIGHT * self.paddle_scale(side);
+            let paddle = game.paddle_mut(side);
+            if paddle.height != height {
+                paddle.y += (paddle.height - height) / 2.0;
+                paddle.height = height;
+                paddle.y = paddle.y.clamp(0.0, SCREEN_HEIGHT - height);
+            }
+        }
+
+        // A shield is a wall on the goal line, good for one save
+        let ball = &mut game.ball;
+        let next_x = ball.x + ball.vel_x * dt * self.ball_scale();
+        let reaching = if next_x <= BALL_SIZE && ball.vel_x < 0.0 {
+            Some(Side::Left)
+        } else if next_x >= SCREEN_WIDTH - BALL_SIZE && ball.vel_x > 0.0 {
+            Some(Side::Right)
+        } else {
+            None
+        };
+        if let Some(side) = reaching.filter(|&side| self.is_active(Kind::Shield, side)) {
+            ball.vel_x = -ball.vel_x;
+            self.effects.retain(|effect| !(effect.kind == Kind::Shield && effect.side == side));
+            extra.shield_used = Some((side, ball.y));
+        }
+
+        // A held ball rests on the paddle face until released
+        if let Some(held) = self.held.as_mut() {
+            held.remaining -= dt;
+            game.ball.vel_x = 0.0;
+            game.ball.vel_y = 0.0;
+        }
+
+        let events = game.step(dt);
+
+        if let Some(held) = self.held {
+            let (x, dir) = match held.side {
+                Side::Left => (LEFT_PADDLE_X + PADDLE_WIDTH + BALL_SIZE, 1.0),
+                Side::Right => (RIGHT_PADDLE_X - BALL_SIZE, -1.0),
+            };
+            let paddle = *game.paddle(held.side);
+            game.ball.x = x;
+            game.ball.y = paddle.y + held.offset.min(paddle.height);
+            if held.remaining <= 0.0 {
+                aim(&mut game.ball, &paddle, dir);
+                self.held = None;
+            }
+        } else if let Some((side, hit_y)) = events.paddle_hit {
+            self.last_hit = Some(side);
+            if self.is_active(Kind::Sticky, side) {
+                self.held = Some(Held {
+                    side,
+                    offset: hit_y - game.paddle(side).y,
+                    remaining: STICKY_HOLD,
+                });
+            }
+        }
+
+        if events.scored.is_some() {
+            // The serve belongs to nobody until someone hits it
+            self.last_hit = None;
+            self.held = None;
+        } else {
+            let scale = self.ball_scale();
+            game.ball.x += game.ball.vel_x * dt * (scale - 1.0);
+            game.ball.y += game.ball.vel_y * dt * (scale - 1.0);
+        }
+
+        // Collect the pickup the ball passes through
+        if let (Some(pickup), Some(side)) = (self.pickup, self.last_hit) {
+            let dx = game.ball.x - pickup.x;
+            let dy = game.ball.y - pickup.y;
+            let reach = PICKUP_RADIUS + BALL_SIZE;
+            if dx * dx + dy * dy <= reach * reach {
+                self.grant(pickup.kind, side);
+                self.pickup = None;
+                self.spawn_timer = SPAWN_INTERVAL;
+                extra.collected = Some((pickup, side));
+            }
+        }
+
+        // Spawn the next one
+        if self.pickup.is_none() {
+            self.spawn_timer -= dt;
+            if self.spawn_timer <= 0.0 {
+                let kind = Kind::ALL[(self.rng.next_u64() % Kind::ALL.len() as u64) as usize];
+                self.pickup = Some(Pickup {
+                    kind,
+                    x: SCREEN_WIDTH / 2.0 + self.rng.range(-SPAWN_SPREAD_X, SPAWN_SPREAD_X),
+                    y: self.rng.range(SPAWN_MARGIN_Y, SCREEN_HEIGHT - SPAWN_MARGIN_Y),
+                });
+            }
+        }
+
+        (events, extra)
+    }
+}
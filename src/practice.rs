@@ -0,0 +1,213 @@
+// Practice: one player against a wall. The left paddle plays as usual and
+// the right side is a wall that returns everything, or a target zone on
+// that wall which jumps somewhere new after every return. Every paddle hit
+// speeds the ball up by `BALL_SPEED_INCREASE` and adds one to the rally;
+// the streak is lost when the ball gets past the paddle, or when it misses
+// the target.
+//
+// Personal bests are kept per wall in a small text file:
+//
+//   wall 23
+//   target 9
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::constants::*;
+use crate::sim::{bounce_off_walls, hit_paddle, Ball, Paddle, Rng, Side};
+
+pub const WALL_WIDTH: f32 = 20.0;
+pub const TARGET_SIZE: f32 = 140.0;
+const TARGET_MARGIN: f32 = 20.0; // Kept clear between the target and the top and bottom
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WallKind {
+    Solid,
+    Target,
+}
+
+impl WallKind {
+    pub const ALL: [WallKind; 2] = [WallKind::Solid, WallKind::Target];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            WallKind::Solid => "Solid wall",
+            WallKind::Target => "Target zone",
+        }
+    }
+
+    // Key in the personal best file
+    fn key(self) -> &'static str {
+        match self {
+            WallKind::Solid => "wall",
+            WallKind::Target => "target",
+        }
+    }
+}
+
+// Returns off the paddle and the wall, and the end of the streak
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PracticeEvents {
+    pub paddle_hit: Option<f32>, // Ball Y at impact
+    pub wall_hit: Option<f32>,   // Ball Y where it came back off the wall
+    pub lost: Option<u32>,       // The streak ended with this rally
+}
+
+#[derive(Clone, Debug)]
+pub struct Practice {
+    pub paddle: Paddle,
+    pub ball: Ball,
+    pub wall: WallKind,
+    pub rally: u32,         // Paddle hits since the serve
+    pub target: (f32, f32), // Top and bottom of the target zone
+    pub lost: Option<u32>,  // Rally of the streak that just ended
+    rng: Rng,
+}
+
+impl Practice {
+    pub fn new(wall: WallKind, seed: u64) -> Self {
+        let mut practice = Self {
+            paddle: Paddle::new(),
+            ball: Ball::serve(Side::Left),
+            wall,
+            rally: 0,
+            target: (0.0, SCREEN_HEIGHT),
+            lost: None,
+            rng: Rng::new(seed),
+        };
+        practice.move_target();
+        practice
+    }
+
+    // Start a new streak from the center
+    pub fn restart(&mut self) {
+        self.paddle = Paddle::new();
+        self.ball = Ball::serve(Side::Left);
+        self.rally = 0;
+        self.lost = None;
+        self.move_target();
+    }
+
+    fn move_target(&mut self) {
+        self.target = match self.wall {
+            WallKind::Solid => (0.0, SCREEN_HEIGHT),
+            WallKind::Target => {
+                let top = self.rng.range(TARGET_MARGIN, SCREEN_HEIGHT - TARGET_MARGIN - TARGET_SIZE);
+                (top, top + TARGET_SIZE)
+            }
+        };
+    }
+
+    // Advance the paddle and ball by `dt`. The paddle velocity must already
+    // reflect this frame's input (see `Paddle::drive`). Nothing moves once
+    // the streak is lost, until `restart`.
+    pub fn step(&mut self, dt: f32) -> PracticeEvents {
+        let mut events = PracticeEvents::default();
+        if self.lost.is_some() {
+            return events;
+        }
+
+        self.paddle.integrate(dt);
+        let ball = &mut self.ball;
+        ball.x += ball.vel_x * dt;
+        ball.y += ball.vel_y * dt;
+
+        bounce_off_walls(ball);
+        if hit_paddle(ball, &self.paddle, LEFT_PADDLE_X, Side::Left) {
+            self.rally += 1;
+            events.paddle_hit = Some(ball.y);
+        }
+
+        // The wall sends the ball straight back at the same speed
+        let wall_x = SCREEN_WIDTH - WALL_WIDTH;
+        if ball.x + BALL_SIZE >= wall_x && ball.vel_x > 0.0 {
+            let (top, bottom) = self.target;
+            if ball.y < top || ball.y > bottom {
+                self.lost = Some(self.rally);
+            } else {
+                ball.vel_x = -ball.vel_x;
+                ball.x = wall_x - BALL_SIZE;
+                events.wall_hit = Some(ball.y);
+            }
+        }
+        if ball.x < -BALL_SIZE {
+            self.lost = Some(self.rally);
+        }
+        if events.wall_hit.is_some() {
+            self.move_target();
+        }
+        events.lost = self.lost;
+        events
+    }
+}
+
+// Best rally on each wall; zero until one is played
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PersonalBests {
+    pub solid: u32,
+    pub target: u32,
+}
+
+impl PersonalBests {
+    pub fn get(&self, wall: WallKind) -> u32 {
+        match wall {
+            WallKind::Solid => self.solid,
+            WallKind::Target => self.target,
+        }
+    }
+
+    // Record a finished streak; true if it beat the old best
+    pub fn record(&mut self, wall: WallKind, rally: u32) -> bool {
+        let best = match wall {
+            WallKind::Solid => &mut self.solid,
+            WallKind::Target => &mut self.target,
+        };
+        if rally > *best {
+            *best = rally;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut bests = PersonalBests::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once(char::is_whitespace)
+                .ok_or(format!("line {}: expected key and value", number + 1))?;
+            let value: u32 = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("line {}: bad rally {}", number + 1, value.trim()))?;
+            match WallKind::ALL.into_iter().find(|wall| wall.key() == key) {
+                Some(wall) => {
+                    bests.record(wall, value);
+                }
+                None => return Err(format!("line {}: unknown wall {}", number + 1, key)),
+            }
+        }
+        Ok(bests)
+    }
+
+    pub fn to_text(&self) -> String {
+        WallKind::ALL
+            .iter()
+            .map(|&wall| format!("{} {}\n", wall.key(), self.get(wall)))
+            .collect()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+}
@@ -0,0 +1,132 @@
+// The pyo3 0.22 proc macros trip this lint on every fallible method
+#![allow(clippy::useless_conversion)]
+
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::batch::{step_match, Opponent};
+use crate::constants::WINNING_SCORE;
+use crate::replay::Replay;
+use crate::sim::{Match, Rng, Side, OBS_DIM};
+
+fn parse_side(side: &str) -> PyResult<Side> {
+    match side {
+        "left" => Ok(Side::Left),
+        "right" => Ok(Side::Right),
+        _ => Err(PyValueError::new_err("side must be \"left\" or \"right\"")),
+    }
+}
+
+// One headless match stepped with a fixed time step. Actions use the
+// flat-buffer encoding: 0 = stay, 1 = up, 2 = down.
+#[pyclass(name = "Match", module = "pong_rs")]
+struct PyMatch {
+    game: Match,
+    rng: Rng,
+    replay: Replay,
+}
+
+#[pymethods]
+impl PyMatch {
+    #[new]
+    #[pyo3(signature = (seed = 0, dt = 1.0 / 60.0, vs_ai = true))]
+    fn new(seed: u64, dt: f32, vs_ai: bool) -> Self {
+        let opponent = if vs_ai {
+            Opponent::BuiltinAi
+        } else {
+            Opponent::Agent
+        };
+        Self {
+            game: Match::new(),
+            rng: Rng::new(seed),
+            replay: Replay::new(seed, dt, opponent),
+        }
+    }
+
+    // Start over with the same seed; returns the left player's observation
+    fn reset(&mut self) -> Vec<f32> {
+        self.game = Match::new();
+        self.rng = Rng::new(self.replay.seed);
+        self.replay.actions.clear();
+        self.replay.checksums.clear();
+        self.game.observation(Side::Left).to_vec()
+    }
+
+    // Returns (left observation, left reward, done)
+    #[pyo3(signature = (left, right = 0))]
+    fn step(&mut self, left: u8, right: u8) -> PyResult<(Vec<f32>, f32, bool)> {
+        if self.game.winner().is_some() {
+            return Err(PyValueError::new_err("match is over, call reset()"));
+        }
+        let actions = [left, right];
+        let events = step_match(
+            &mut self.game,
+            &mut self.rng,
+            actions,
+            self.replay.dt,
+            self.replay.opponent,
+        );
+        self.replay.record(actions, &self.game);
+
+        let reward = match events.scored {
+            Some(Side::Left) => 1.0,
+            Some(Side::Right) => -1.0,
+            None => 0.0,
+        };
+        Ok((
+            self.game.observation(Side::Left).to_vec(),
+            reward,
+            events.winner.is_some(),
+        ))
+    }
+
+    #[pyo3(signature = (side = "left"))]
+    fn observation(&self, side: &str) -> PyResult<Vec<f32>> {
+        Ok(self.game.observation(parse_side(side)?).to_vec())
+    }
+
+    #[getter]
+    fn left_score(&self) -> i32 {
+        self.game.left_score
+    }
+
+    #[getter]
+    fn right_score(&self) -> i32 {
+        self.game.right_score
+    }
+
+    #[getter]
+    fn tick(&self) -> usize {
+        self.replay.actions.len()
+    }
+
+    // Raw state: (ball_x, ball_y, ball_vel_x, ball_vel_y, left_y, right_y)
+    fn state(&self) -> (f32, f32, f32, f32, f32, f32) {
+        let g = &self.game;
+        (g.ball.x, g.ball.y, g.ball.vel_x, g.ball.vel_y, g.left.y, g.right.y)
+    }
+
+    fn save_replay(&self, path: &str) -> PyResult<()> {
+        self.replay
+            .save(path)
+            .map_err(|e| PyIOError::new_err(e.to_string()))
+    }
+}
+
+// Re-simulate a saved replay and return the final (left_score, right_score).
+// Raises ValueError with a desync report if a frame's checksum doesn't match.
+#[pyfunction]
+fn replay_scores(path: &str) -> PyResult<(i32, i32)> {
+    let replay = Replay::load(path).map_err(|e| PyIOError::new_err(e.to_string()))?;
+    let game = replay.verify().map_err(|report| PyValueError::new_err(report.to_text()))?;
+    Ok((game.left_score, game.right_score))
+}
+
+#[pymodule]
+fn pong_rs(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyMatch>()?;
+    m.add_function(wrap_pyfunction!(replay_scores, m)?)?;
+    m.add("OBS_DIM", OBS_DIM)?;
+    m.add("WINNING_SCORE", WINNING_SCORE)?;
+    Ok(())
+}
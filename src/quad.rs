@@ -0,0 +1,255 @@
+// Four-player free-for-all on a square field: instead of bouncing off the
+// top and bottom walls, the ball meets a paddle on every wall, each guarding
+// the goal behind it. Letting the ball through costs a point; whoever
+// reaches the point limit is out and their wall turns solid. The last player
+// standing wins.
+//
+// Vertical and horizontal paddles share the same physics: collisions are
+// done in each wall's frame, where the ball's axes are swapped for the top
+// and bottom walls so they behave like the left and right ones.
+
+use crate::ai::{AiController, AiParams};
+use crate::constants::*;
+use crate::sim::{hit_paddle, Ball, Paddle, Side};
+
+pub const FIELD_SIZE: f32 = SCREEN_HEIGHT; // Width and height of the square field
+const NEAR_X: f32 = PADDLE_MARGIN; // Left and top paddles, in the wall's frame
+const FAR_X: f32 = FIELD_SIZE - PADDLE_MARGIN - PADDLE_WIDTH; // Right and bottom paddles
+// Paddles stop short of the corners so they never overlap each other
+const TRAVEL_MIN: f32 = PADDLE_MARGIN + PADDLE_WIDTH;
+const TRAVEL_MAX: f32 = FIELD_SIZE - TRAVEL_MIN - PADDLE_HEIGHT;
+const MIN_WALL_ANGLE: f32 = 0.3; // Least sideways speed after a solid wall, so the ball can't bounce straight forever
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Wall {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl Wall {
+    pub const ALL: [Wall; 4] = [Wall::Left, Wall::Right, Wall::Top, Wall::Bottom];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Wall::Left => "Left",
+            Wall::Right => "Right",
+            Wall::Top => "Top",
+            Wall::Bottom => "Bottom",
+        }
+    }
+
+    // Top and bottom paddles move sideways; `Intent::Up` moves them left
+    pub fn is_horizontal(self) -> bool {
+        matches!(self, Wall::Top | Wall::Bottom)
+    }
+
+    // At the low end of its axis, i.e. the ball leaves through it moving in -x (-y)
+    fn is_near(self) -> bool {
+        matches!(self, Wall::Left | Wall::Top)
+    }
+}
+
+// The ball as seen from `wall`: x across the wall, y along it
+fn to_frame(ball: &Ball, wall: Wall) -> Ball {
+    if wall.is_horizontal() {
+        Ball {
+            x: ball.y,
+            y: ball.x,
+            vel_x: ball.vel_y,
+            vel_y: ball.vel_x,
+            speed: ball.speed,
+        }
+    } else {
+        *ball
+    }
+}
+
+// Paddle hits, goals and knock-outs in one `QuadMatch::step`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct QuadEvents {
+    pub paddle_hit: Option<(Wall, f32, f32)>, // Wall and ball position at impact
+    pub scored: Option<Wall>,                 // Wall the ball went through
+    pub eliminated: Option<Wall>,
+    pub winner: Option<Wall>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuadMatch {
+    pub paddles: [Paddle; 4], // By `Wall` order; `y` is the position along the wall
+    pub ball: Ball,           // In field coordinates, (0, 0) being the top left corner
+    pub conceded: [i32; 4],   // Goals let in, by `Wall` order
+    pub point_limit: i32,     // Conceding this many knocks a player out
+    serves: usize,            // Rotates the serve between the remaining players
+}
+
+impl QuadMatch {
+    pub fn new(point_limit: i32) -> Self {
+        let mut game = Self {
+            paddles: [Paddle::new(); 4],
+            ball: Ball::new(),
+            conceded: [0; 4],
+            point_limit,
+            serves: 0,
+        };
+        game.serve();
+        game
+    }
+
+    pub fn is_out(&self, wall: Wall) -> bool {
+        self.conceded[wall as usize] >= self.point_limit
+    }
+
+    // Players still in, in `Wall` order
+    pub fn standing(&self) -> impl Iterator<Item = Wall> + '_ {
+        Wall::ALL.into_iter().filter(|&wall| !self.is_out(wall))
+    }
+
+    pub fn winner(&self) -> Option<Wall> {
+        let mut standing = self.standing();
+        match (standing.next(), standing.next()) {
+            (Some(wall), None) => Some(wall),
+            _ => None,
+        }
+    }
+
+    // Paddle rectangle (x, y, width, height) in field coordinates
+    pub fn paddle_rect(&self, wall: Wall) -> (f32, f32, f32, f32) {
+        let along = self.paddles[wall as usize].y;
+        let across = if wall.is_near() { NEAR_X } else { FAR_X };
+        if wall.is_horizontal() {
+            (along, across, PADDLE_HEIGHT, PADDLE_WIDTH)
+        } else {
+            (across, along, PADDLE_WIDTH, PADDLE_HEIGHT)
+        }
+    }
+
+    // From the center towards the next player in the rotation, at an angle
+    fn serve(&mut self) {
+        let standing: Vec<Wall> = self.standing().collect();
+        if standing.is_empty() {
+            return;
+        }
+        let towards = standing[self.serves % standing.len()];
+        self.serves += 1;
+        let served = Ball {
+            x: FIELD_SIZE / 2.0,
+            y: FIELD_SIZE / 2.0,
+            vel_x: if towards.is_near() { -BALL_SPEED } else { BALL_SPEED },
+            vel_y: BALL_SPEED * 0.5,
+            speed: BALL_SPEED,
+        };
+        self.ball = to_frame(&served, towards);
+    }
+
+    // Advance paddles and ball by `dt`. Paddle velocities must already
+    // reflect this frame's input (see `Paddle::drive` / `QuadAi::drive`).
+    pub fn step(&mut self, dt: f32) -> QuadEvents {
+        let mut events = QuadEvents::default();
+        if self.winner().is_some() {
+            return events;
+        }
+
+        for paddle in self.paddles.iter_mut() {
+            paddle.integrate(dt);
+            paddle.y = paddle.y.clamp(TRAVEL_MIN, TRAVEL_MAX);
+            if paddle.y <= TRAVEL_MIN || paddle.y >= TRAVEL_MAX {
+                paddle.vel = 0.0;
+            }
+        }
+
+        self.ball.x += self.ball.vel_x * dt;
+        self.ball.y += self.ball.vel_y * dt;
+
+        for wall in Wall::ALL {
+            let mut ball = to_frame(&self.ball, wall);
+            let near = wall.is_near();
+            let towards = if near { ball.vel_x < 0.0 } else { ball.vel_x > 0.0 };
+            if !towards {
+                continue;
+            }
+
+            if self.is_out(wall) {
+                // Solid wall: reflect, with enough sideways speed to keep moving on
+                let hit = if near { ball.x <= BALL_SIZE } else { ball.x >= FIELD_SIZE - BALL_SIZE };
+                if hit {
+                    ball.vel_x = -ball.vel_x;
+                    ball.x = ball.x.clamp(BALL_SIZE, FIELD_SIZE - BALL_SIZE);
+                    let least = ball.speed * MIN_WALL_ANGLE;
+                    if ball.vel_y.abs() < least {
+                        ball.vel_y = if ball.vel_y < 0.0 { -least } else { least };
+                    }
+                }
+                self.ball = to_frame(&ball, wall);
+                continue;
+            }
+
+            // Paddle face, as in `Match::step` with a near wall on the left
+            let (paddle_x, side) = if near { (NEAR_X, Side::Left) } else { (FAR_X, Side::Right) };
+            if hit_paddle(&mut ball, &self.paddles[wall as usize], paddle_x, side) {
+                self.ball = to_frame(&ball, wall);
+                events.paddle_hit = Some((wall, self.ball.x, self.ball.y));
+                continue;
+            }
+
+            let through = if near { ball.x < -BALL_SIZE } else { ball.x > FIELD_SIZE + BALL_SIZE };
+            if through {
+                self.conceded[wall as usize] += 1;
+                events.scored = Some(wall);
+                if self.is_out(wall) {
+                    events.eliminated = Some(wall);
+                }
+                events.winner = self.winner();
+                self.serve();
+                break;
+            }
+        }
+
+        events
+    }
+}
+
+impl Default for QuadMatch {
+    fn default() -> Self {
+        Self::new(FOUR_PLAYER_POINT_LIMIT)
+    }
+}
+
+// Built-in AI for an empty seat: an `AiController` aiming along its wall
+#[derive(Clone, Copy, Debug)]
+pub struct QuadAi {
+    pub wall: Wall,
+    pub controller: AiController,
+}
+
+impl QuadAi {
+    pub fn new(params: AiParams, wall: Wall) -> Self {
+        // In its wall's frame a near paddle plays the left side
+        let side = if wall.is_near() { Side::Left } else { Side::Right };
+        Self {
+            wall,
+            controller: AiController::new(params, side),
+        }
+    }
+
+    pub fn drive(&mut self, game: &mut QuadMatch, dt: f32, noise: f32) {
+        // Where along our wall the ball will arrive, or where it is now
+        // while it's heading elsewhere
+        let ball = to_frame(&game.ball, self.wall);
+        let (paddle_x, facing) = if self.wall.is_near() {
+            (NEAR_X + PADDLE_WIDTH, -1.0)
+        } else {
+            (FAR_X, 1.0)
+        };
+        let paddle = &mut game.paddles[self.wall as usize];
+        self.controller.drive_paddle(paddle, dt, noise, |params| {
+            if ball.vel_x * facing > 0.0 {
+                let time_to_reach = facing * (paddle_x - ball.x) / ball.vel_x.abs();
+                ball.y + ball.vel_y * time_to_reach * params.prediction
+            } else {
+                ball.y
+            }
+        });
+    }
+}
@@ -0,0 +1,157 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::batch::{step_match, Opponent};
+use crate::desync::DesyncReport;
+use crate::sim::{Match, Rng};
+
+const HEADER: &str = "pong-replay 2";
+const HEADER_V1: &str = "pong-replay 1"; // Same, without checksums
+
+// Everything needed to re-simulate a headless match exactly: the RNG seed,
+// the fixed time step and the actions applied on every frame, plus the
+// checksum of the state each frame produced when it was recorded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub dt: f32,
+    pub opponent: Opponent,
+    pub actions: Vec<[u8; 2]>,
+    pub checksums: Vec<u32>, // Empty for replays recorded without them
+}
+
+impl Replay {
+    pub fn new(seed: u64, dt: f32, opponent: Opponent) -> Self {
+        Self {
+            seed,
+            dt,
+            opponent,
+            actions: Vec::new(),
+            checksums: Vec::new(),
+        }
+    }
+
+    // `after` is the state the frame produced
+    pub fn record(&mut self, actions: [u8; 2], after: &Match) {
+        self.actions.push(actions);
+        self.checksums.push(after.checksum());
+    }
+
+    // Re-run the recorded frames and return the resulting match state
+    pub fn simulate(&self) -> Match {
+        let mut game = Match::new();
+        let mut rng = Rng::new(self.seed);
+        for &actions in &self.actions {
+            step_match(&mut game, &mut rng, actions, self.dt, self.opponent);
+        }
+        game
+    }
+
+    // Re-simulate and compare every frame against the recorded checksums.
+    // Fails with a report at the first frame that came out differently.
+    pub fn verify(&self) -> Result<Match, Box<DesyncReport>> {
+        let mut game = Match::new();
+        let mut rng = Rng::new(self.seed);
+        for (frame, &actions) in self.actions.iter().enumerate() {
+            step_match(&mut game, &mut rng, actions, self.dt, self.opponent);
+            if let Some(&expected) = self.checksums.get(frame) {
+                if game.checksum() != expected {
+                    return Err(Box::new(DesyncReport {
+                        frame: frame as u32,
+                        local: game,
+                        local_checksum: game.checksum(),
+                        remote: None,
+                        remote_checksum: expected,
+                        remote_label: "recording".to_string(),
+                    }));
+                }
+            }
+        }
+        Ok(game)
+    }
+
+    // Plain-text format: a short header followed by one "left right checksum"
+    // line per frame
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        let opponent = match self.opponent {
+            Opponent::Agent => "agent",
+            Opponent::BuiltinAi => "ai",
+        };
+        let _ = writeln!(out, "{}", HEADER);
+        let _ = writeln!(out, "seed {}", self.seed);
+        let _ = writeln!(out, "dt {:?}", self.dt);
+        let _ = writeln!(out, "opponent {}", opponent);
+        let _ = writeln!(out, "frames {}", self.actions.len());
+        for (frame, [left, right]) in self.actions.iter().enumerate() {
+            let _ = match self.checksums.get(frame) {
+                Some(checksum) => writeln!(out, "{} {} {:08x}", left, right, checksum),
+                None => writeln!(out, "{} {}", left, right),
+            };
+        }
+        out
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+        let version = lines.next().map(str::trim);
+        if version != Some(HEADER) && version != Some(HEADER_V1) {
+            return Err("not a pong replay".to_string());
+        }
+
+        let mut field = |name: &str| -> Result<String, String> {
+            let line = lines.next().ok_or(format!("missing {}", name))?;
+            line.strip_prefix(name)
+                .map(|v| v.trim().to_string())
+                .ok_or(format!("expected {}", name))
+        };
+        let seed = field("seed")?.parse().map_err(|_| "bad seed")?;
+        let dt = field("dt")?.parse().map_err(|_| "bad dt")?;
+        let opponent = match field("opponent")?.as_str() {
+            "agent" => Opponent::Agent,
+            "ai" => Opponent::BuiltinAi,
+            other => return Err(format!("unknown opponent {}", other)),
+        };
+        let frames: usize = field("frames")?.parse().map_err(|_| "bad frame count")?;
+
+        let mut actions = Vec::with_capacity(frames);
+        let mut checksums = Vec::with_capacity(frames);
+        for line in lines.take(frames) {
+            let mut parts = line.split_whitespace();
+            let mut action = || parts.next().and_then(|part| part.parse::<u8>().ok());
+            match (action(), action()) {
+                (Some(left), Some(right)) => actions.push([left, right]),
+                _ => return Err(format!("bad frame line: {}", line)),
+            }
+            if let Some(checksum) = parts.next() {
+                let checksum = u32::from_str_radix(checksum, 16).map_err(|_| format!("bad checksum: {}", line))?;
+                checksums.push(checksum);
+            }
+        }
+        if actions.len() != frames {
+            return Err("replay is truncated".to_string());
+        }
+        if !checksums.is_empty() && checksums.len() != frames {
+            return Err("replay has checksums for only some frames".to_string());
+        }
+
+        Ok(Self {
+            seed,
+            dt,
+            opponent,
+            actions,
+            checksums,
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::from_text(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
@@ -0,0 +1,398 @@
+// GGPO-style rollback session for two peers. Both sides run the same
+// fixed-step simulation; local input is delayed by a few frames, missing
+// remote input is predicted, and when the real input arrives and differs the
+// session rewinds to that frame and re-simulates. Peers also exchange the
+// checksum of every confirmed frame and report the first one that differs.
+
+use crate::batch::{step_match, Opponent};
+use crate::constants::*;
+use crate::desync::DesyncReport;
+use crate::net::{Packet, Transport};
+use crate::sim::{Intent, Match, Rng, Side, StepEvents};
+
+// Frames of history kept for rewinding; must exceed NET_MAX_PREDICTION
+const RING: usize = 128;
+const HELLO_INTERVAL: f64 = 0.2;
+const RESEND_INTERVAL: f64 = 1.0 / 30.0;
+const MAX_INPUTS_PER_PACKET: u32 = 64;
+const CHECK_RING: usize = 1024; // Confirmed frames kept for checksum comparison
+const MAX_CHECKS_PER_PACKET: u32 = 32;
+
+pub const NET_DT: f32 = 1.0 / NET_TICK_RATE;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SessionStatus {
+    Connecting,
+    Running,
+}
+
+pub struct RollbackSession<T: Transport> {
+    transport: T,
+    side: Side,
+    status: SessionStatus,
+    game: Match, // State before `frame`
+    frame: u32,  // Next frame to simulate
+    rng: Rng,
+    states: Vec<Match>, // State before each frame in the ring
+    local: Vec<u8>,
+    local_count: u32, // Local inputs known for frames 0..local_count
+    remote: Vec<u8>,
+    remote_count: u32, // Remote inputs known for frames 0..remote_count
+    used: Vec<u8>,     // Remote input each simulated frame actually used
+    remote_frame: u32,
+    remote_ack: u32, // How many of our inputs the peer has
+    last_remote_ping: f64,
+    rtt: Option<f64>,
+    last_send: f64,
+    last_hello: f64,
+    last_receive: f64,
+    rejoining: bool, // Greeting the host again from a new link
+    rollbacks: u32,
+    checks: Vec<u32>,       // Checksum after each confirmed frame
+    confirmed: Vec<Match>,  // State after each confirmed frame, for desync dumps
+    checked: u32,           // Frames 0..checked are confirmed and checksummed
+    remote_checks: Vec<u32>,
+    remote_checks_count: u32,
+    remote_checks_ack: u32, // How many of our checksums the peer has
+    verified: u32,          // Frames whose checksums were compared
+    remote_dump: Option<(u32, Match)>,
+    desync: Option<DesyncReport>,
+}
+
+impl<T: Transport> RollbackSession<T> {
+    // Host plays the left paddle, the joining peer the right one
+    pub fn new(transport: T, side: Side) -> Self {
+        let game = Match::new();
+        let mut session = Self {
+            transport,
+            side,
+            status: SessionStatus::Connecting,
+            game,
+            frame: 0,
+            rng: Rng::new(0),
+            states: vec![game; RING],
+            local: vec![0; RING],
+            local_count: 0,
+            remote: vec![0; RING],
+            remote_count: 0,
+            used: vec![0; RING],
+            remote_frame: 0,
+            remote_ack: 0,
+            last_remote_ping: 0.0,
+            rtt: None,
+            last_send: f64::NEG_INFINITY,
+            last_hello: f64::NEG_INFINITY,
+            last_receive: 0.0,
+            rejoining: false,
+            rollbacks: 0,
+            checks: vec![0; CHECK_RING],
+            confirmed: vec![game; CHECK_RING],
+            checked: 0,
+            remote_checks: vec![0; CHECK_RING],
+            remote_checks_count: 0,
+            remote_checks_ack: 0,
+            verified: 0,
+            remote_dump: None,
+            desync: None,
+        };
+        // The first frames run with neutral input while the delay fills up
+        for _ in 0..NET_INPUT_DELAY {
+            session.push_local(0);
+        }
+        session
+    }
+
+    pub fn side(&self) -> Side {
+        self.side
+    }
+
+    pub fn status(&self) -> SessionStatus {
+        self.status
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.status == SessionStatus::Running
+    }
+
+    // Current (possibly predicted) state
+    pub fn state(&self) -> &Match {
+        &self.game
+    }
+
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    // Latest state computed only from inputs both peers agree on
+    pub fn confirmed_state(&self) -> &Match {
+        if self.remote_count < self.frame {
+            &self.states[self.remote_count as usize % RING]
+        } else {
+            &self.game
+        }
+    }
+
+    pub fn ping_ms(&self) -> Option<f32> {
+        self.rtt.map(|rtt| (rtt * 1000.0) as f32)
+    }
+
+    // How many times the session had to rewind so far
+    pub fn rollbacks(&self) -> u32 {
+        self.rollbacks
+    }
+
+    // Set once a confirmed frame's checksum differs from the peer's
+    pub fn desync(&self) -> Option<&DesyncReport> {
+        self.desync.as_ref()
+    }
+
+    // Seconds since the peer was last heard from
+    pub fn silence(&self, now: f64) -> f64 {
+        now - self.last_receive
+    }
+
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    // Carry on over a new link after ours dropped, e.g. from a fresh socket.
+    // The joining peer greets the host again until it hears back, so the host
+    // can take the new address.
+    pub fn reconnect(&mut self, transport: T) {
+        self.transport = transport;
+        self.last_hello = f64::NEG_INFINITY;
+        self.rejoining = self.side == Side::Right;
+    }
+
+    // Handle handshake and incoming inputs; call every frame, even when not advancing
+    pub fn poll(&mut self, now: f64) {
+        if (self.status == SessionStatus::Connecting || self.rejoining)
+            && self.side == Side::Right
+            && now - self.last_hello >= HELLO_INTERVAL
+        {
+            self.transport.send(&Packet::Hello.encode());
+            self.last_hello = now;
+        }
+
+        let mut rollback_to: Option<u32> = None;
+        while let Some(data) = self.transport.recv() {
+            let Some(packet) = Packet::decode(&data) else {
+                continue;
+            };
+            self.last_receive = now;
+            self.rejoining = false;
+            match packet {
+                // Host: greet (again, in case the welcome got lost)
+                Packet::Hello if self.side == Side::Left => {
+                    self.transport.send(&Packet::Welcome.encode());
+                    self.status = SessionStatus::Running;
+                }
+                Packet::Welcome if self.side == Side::Right => {
+                    self.status = SessionStatus::Running;
+                }
+                Packet::Input {
+                    frame,
+                    ack,
+                    start,
+                    inputs,
+                    ping,
+                    pong,
+                    checks_ack,
+                    checks_start,
+                    checks,
+                } => {
+                    self.status = SessionStatus::Running;
+                    self.remote_frame = self.remote_frame.max(frame);
+                    self.remote_ack = self.remote_ack.max(ack);
+                    self.last_remote_ping = self.last_remote_ping.max(ping);
+                    if pong > 0.0 {
+                        let sample = now - pong;
+                        self.rtt = Some(match self.rtt {
+                            Some(rtt) => rtt * 0.9 + sample * 0.1,
+                            None => sample,
+                        });
+                    }
+
+                    // Inputs are resent from our ack onwards, so only accept
+                    // the next contiguous ones
+                    for (offset, &input) in inputs.iter().enumerate() {
+                        let f = start + offset as u32;
+                        if f != self.remote_count {
+                            continue;
+                        }
+                        self.remote[f as usize % RING] = input;
+                        self.remote_count += 1;
+                        if f < self.frame && self.used[f as usize % RING] != input {
+                            rollback_to = Some(rollback_to.map_or(f, |r| r.min(f)));
+                        }
+                    }
+
+                    self.remote_checks_ack = self.remote_checks_ack.max(checks_ack);
+                    for (offset, &check) in checks.iter().enumerate() {
+                        let f = checks_start + offset as u32;
+                        if f == self.remote_checks_count {
+                            self.remote_checks[f as usize % CHECK_RING] = check;
+                            self.remote_checks_count += 1;
+                        }
+                    }
+                }
+                Packet::StateDump { frame, state } => {
+                    self.remote_dump = Some((frame, state));
+                    if let Some(report) = self.desync.as_mut().filter(|report| report.frame == frame) {
+                        report.remote = Some(state);
+                    }
+                }
+                _ => {} // Greetings for the other side, or authoritative server messages
+            }
+        }
+
+        if let Some(frame) = rollback_to {
+            self.rewind(frame);
+        }
+        self.update_checks();
+
+        if self.is_running() && now - self.last_send >= RESEND_INTERVAL {
+            self.send_inputs(now);
+        }
+    }
+
+    // Add this frame's local input and simulate one frame. Returns None when
+    // the session has to wait for the peer (not connected, too far ahead).
+    pub fn advance(&mut self, intent: Intent, now: f64) -> Option<StepEvents> {
+        if !self.is_running() {
+            return None;
+        }
+
+        // Don't outrun the remote inputs by more than we can roll back
+        if self.frame >= self.remote_count + NET_MAX_PREDICTION {
+            return None;
+        }
+
+        // Time sync: let the peer catch up if we are clearly ahead of it
+        let rtt_frames = self.rtt.unwrap_or(0.0) * NET_TICK_RATE as f64;
+        let remote_now = self.remote_frame as f64 + rtt_frames / 2.0;
+        if self.frame as f64 - remote_now > 2.0 && self.frame.is_multiple_of(3) {
+            return None;
+        }
+
+        self.push_local(intent.to_action());
+        let events = self.simulate();
+        self.update_checks();
+        self.send_inputs(now);
+        Some(events)
+    }
+
+    fn push_local(&mut self, action: u8) {
+        self.local[self.local_count as usize % RING] = action;
+        self.local_count += 1;
+    }
+
+    fn predicted_remote(&self) -> u8 {
+        // Assume the peer keeps doing what it did last
+        if self.remote_count == 0 {
+            0
+        } else {
+            self.remote[(self.remote_count - 1) as usize % RING]
+        }
+    }
+
+    // Simulate `self.frame` and move on to the next one
+    fn simulate(&mut self) -> StepEvents {
+        let f = self.frame as usize % RING;
+        self.states[f] = self.game;
+
+        let remote = if self.frame < self.remote_count {
+            self.remote[f]
+        } else {
+            self.predicted_remote()
+        };
+        self.used[f] = remote;
+
+        let local = self.local[f];
+        let actions = match self.side {
+            Side::Left => [local, remote],
+            Side::Right => [remote, local],
+        };
+        let events = step_match(&mut self.game, &mut self.rng, actions, NET_DT, Opponent::Agent);
+        self.frame += 1;
+        events
+    }
+
+    // Restore the state before `frame` and re-simulate up to the present
+    fn rewind(&mut self, frame: u32) {
+        let present = self.frame;
+        self.game = self.states[frame as usize % RING];
+        self.frame = frame;
+        while self.frame < present {
+            self.simulate();
+        }
+        self.rollbacks += 1;
+    }
+
+    // Checksum newly confirmed frames and compare them with the peer's
+    fn update_checks(&mut self) {
+        while self.checked < self.remote_count.min(self.frame) {
+            let f = self.checked;
+            // The state after frame f is the state before f + 1
+            let state = if f + 1 == self.frame {
+                self.game
+            } else {
+                self.states[(f + 1) as usize % RING]
+            };
+            self.checks[f as usize % CHECK_RING] = state.checksum();
+            self.confirmed[f as usize % CHECK_RING] = state;
+            self.checked += 1;
+        }
+
+        while self.desync.is_none() && self.verified < self.checked.min(self.remote_checks_count) {
+            let f = self.verified;
+            let (local, remote) = (self.checks[f as usize % CHECK_RING], self.remote_checks[f as usize % CHECK_RING]);
+            if local != remote {
+                self.desync = Some(DesyncReport {
+                    frame: f,
+                    local: self.confirmed[f as usize % CHECK_RING],
+                    local_checksum: local,
+                    remote: self.remote_dump.filter(|&(frame, _)| frame == f).map(|(_, state)| state),
+                    remote_checksum: remote,
+                    remote_label: "peer".to_string(),
+                });
+            }
+            self.verified += 1;
+        }
+    }
+
+    fn send_inputs(&mut self, now: f64) {
+        let start = self.remote_ack.max(self.local_count.saturating_sub(MAX_INPUTS_PER_PACKET));
+        let inputs = (start..self.local_count)
+            .map(|f| self.local[f as usize % RING])
+            .collect();
+        let checks_start = self
+            .remote_checks_ack
+            .max(self.checked.saturating_sub(MAX_CHECKS_PER_PACKET));
+        let checks = (checks_start..self.checked)
+            .map(|f| self.checks[f as usize % CHECK_RING])
+            .collect();
+        let packet = Packet::Input {
+            frame: self.frame,
+            ack: self.remote_count,
+            start,
+            inputs,
+            ping: now,
+            pong: self.last_remote_ping,
+            checks_ack: self.remote_checks_count,
+            checks_start,
+            checks,
+        };
+        self.transport.send(&packet.encode());
+
+        // Keep offering our side of the divergence until the session ends
+        if let Some(report) = &self.desync {
+            let dump = Packet::StateDump {
+                frame: report.frame,
+                state: report.local,
+            };
+            self.transport.send(&dump.encode());
+        }
+        self.last_send = now;
+    }
+}
@@ -0,0 +1,405 @@
+use crate::ai::AiParams;
+use crate::constants::*;
+
+// Length of the observation vector returned by `Match::observation`
+pub const OBS_DIM: usize = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    pub fn opponent(self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+}
+
+// What a controller wants its paddle to do this frame
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Intent {
+    #[default]
+    Stay,
+    Up,
+    Down,
+}
+
+impl Intent {
+    // Action encoding used by flat buffers: 0 = stay, 1 = up, 2 = down
+    pub fn from_action(action: u8) -> Intent {
+        match action {
+            1 => Intent::Up,
+            2 => Intent::Down,
+            _ => Intent::Stay,
+        }
+    }
+
+    pub fn to_action(self) -> u8 {
+        match self {
+            Intent::Stay => 0,
+            Intent::Up => 1,
+            Intent::Down => 2,
+        }
+    }
+}
+
+// Small deterministic RNG (splitmix64) so headless matches are reproducible
+#[derive(Clone, Copy, Debug)]
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform value in [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    pub fn range(&mut self, low: f32, high: f32) -> f32 {
+        low + (high - low) * self.next_f32()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Paddle {
+    pub y: f32,
+    pub vel: f32,
+    pub height: f32, // `PADDLE_HEIGHT` unless a power-up changed it
+}
+
+impl Paddle {
+    pub fn new() -> Self {
+        Self {
+            y: PADDLE_START_Y,
+            vel: 0.0,
+            height: PADDLE_HEIGHT,
+        }
+    }
+
+    // Human-style control: immediate base speed plus acceleration buildup
+    pub fn drive(&mut self, intent: Intent, dt: f32) {
+        match intent {
+            Intent::Up => {
+                if self.vel > -PADDLE_BASE_SPEED {
+                    self.vel = -PADDLE_BASE_SPEED; // Immediate base speed
+                }
+                self.vel -= PADDLE_ACCELERATION * dt; // Add acceleration buildup
+            }
+            Intent::Down => {
+                if self.vel < PADDLE_BASE_SPEED {
+                    self.vel = PADDLE_BASE_SPEED; // Immediate base speed
+                }
+                self.vel += PADDLE_ACCELERATION * dt; // Add acceleration buildup
+            }
+            Intent::Stay => {
+                // Apply friction when no input
+                self.vel *= PADDLE_FRICTION;
+            }
+        }
+    }
+
+    // Move by the current velocity; `Match::step` does this for both paddles
+    pub fn integrate(&mut self, dt: f32) {
+        // Clamp paddle velocity to maximum speed
+        self.vel = self.vel.clamp(-MAX_PADDLE_SPEED, MAX_PADDLE_SPEED);
+
+        // Update position and keep paddle within screen bounds
+        self.y += self.vel * dt;
+        self.y = self.y.clamp(0.0, SCREEN_HEIGHT - self.height);
+
+        // Stop velocity if hitting boundaries
+        if self.y <= 0.0 || self.y >= SCREEN_HEIGHT - self.height {
+            self.vel = 0.0;
+        }
+    }
+}
+
+impl Default for Paddle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ball {
+    pub x: f32,
+    pub y: f32,
+    pub vel_x: f32,
+    pub vel_y: f32,
+    pub speed: f32, // Current speed, grows with each paddle hit
+}
+
+impl Ball {
+    // Opening serve of a fresh match
+    pub fn new() -> Self {
+        Self {
+            x: BALL_START_X,
+            y: BALL_START_Y,
+            vel_x: BALL_SPEED,
+            vel_y: BALL_SPEED * 0.3, // Start with some angle
+            speed: BALL_SPEED,
+        }
+    }
+
+    // Serve from the center towards `towards` after a point or restart
+    pub fn serve(towards: Side) -> Self {
+        Self {
+            x: BALL_START_X,
+            y: BALL_START_Y,
+            vel_x: match towards {
+                Side::Left => -BALL_SPEED,
+                Side::Right => BALL_SPEED,
+            },
+            vel_y: BALL_SPEED * 0.5,
+            speed: BALL_SPEED,
+        }
+    }
+}
+
+impl Default for Ball {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Things that happened during a step, for effects and rewards
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StepEvents {
+    pub paddle_hit: Option<(Side, f32)>, // Side and ball Y at impact
+    pub scored: Option<Side>,
+    pub winner: Option<Side>,
+}
+
+impl StepEvents {
+    // Combine the events of several steps (later steps win)
+    pub fn merge(&mut self, other: StepEvents) {
+        self.paddle_hit = other.paddle_hit.or(self.paddle_hit);
+        self.scored = other.scored.or(self.scored);
+        self.winner = other.winner.or(self.winner);
+    }
+}
+
+// Complete state of one match: paddles, ball and scores
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Match {
+    pub left: Paddle,
+    pub right: Paddle,
+    pub ball: Ball,
+    pub left_score: i32,
+    pub right_score: i32,
+    pub winning_score: i32, // Points needed to win, `WINNING_SCORE` unless agreed otherwise
+}
+
+impl Match {
+    pub fn new() -> Self {
+        Self {
+            left: Paddle::new(),
+            right: Paddle::new(),
+            ball: Ball::new(),
+            left_score: 0,
+            right_score: 0,
+            winning_score: WINNING_SCORE,
+        }
+    }
+
+    // Reset scores and positions for a rematch under the same rules
+    pub fn reset(&mut self) {
+        *self = Self {
+            ball: Ball::serve(Side::Right),
+            winning_score: self.winning_score,
+            ..Self::new()
+        };
+    }
+
+    pub fn paddle(&self, side: Side) -> &Paddle {
+        match side {
+            Side::Left => &self.left,
+            Side::Right => &self.right,
+        }
+    }
+
+    pub fn paddle_mut(&mut self, side: Side) -> &mut Paddle {
+        match side {
+            Side::Left => &mut self.left,
+            Side::Right => &mut self.right,
+        }
+    }
+
+    pub fn winner(&self) -> Option<Side> {
+        if self.left_score >= self.winning_score {
+            Some(Side::Left)
+        } else if self.right_score >= self.winning_score {
+            Some(Side::Right)
+        } else {
+            None
+        }
+    }
+
+    // Let the built-in AI steer `side` towards the ball.
+    // `jitter` is the random positioning error that makes the AI beatable.
+    pub fn ai_drive(&mut self, side: Side, jitter: f32) {
+        let ai = AiParams::classic();
+        let target_y = ai.predict(&self.ball, side) + jitter;
+        ai.steer(self.paddle_mut(side), target_y);
+    }
+
+    // Advance paddles and ball by `dt`. Paddle velocities must already
+    // reflect this frame's input (see `Paddle::drive` / `Match::ai_drive`).
+    pub fn step(&mut self, dt: f32) -> StepEvents {
+        let mut events = StepEvents::default();
+
+        self.left.integrate(dt);
+        self.right.integrate(dt);
+
+        let ball = &mut self.ball;
+
+        // Update ball position
+        ball.x += ball.vel_x * dt;
+        ball.y += ball.vel_y * dt;
+
+        bounce_off_walls(ball);
+
+        // Ball collision with the paddles
+        if hit_paddle(ball, &self.left, LEFT_PADDLE_X, Side::Left) {
+            events.paddle_hit = Some((Side::Left, ball.y));
+        }
+        if hit_paddle(ball, &self.right, RIGHT_PADDLE_X, Side::Right) {
+            events.paddle_hit = Some((Side::Right, ball.y));
+        }
+
+        // Check for scoring and reset ball
+        if ball.x < -BALL_SIZE {
+            self.right_score += 1;
+            *ball = Ball::serve(Side::Right);
+            events.scored = Some(Side::Right);
+        } else if ball.x > SCREEN_WIDTH + BALL_SIZE {
+            self.left_score += 1;
+            *ball = Ball::serve(Side::Left);
+            events.scored = Some(Side::Left);
+        }
+
+        if events.scored.is_some() {
+            events.winner = self.winner();
+        }
+
+        events
+    }
+
+    // Normalized state as seen by `side`, mirrored so that the observer
+    // always defends the left edge.
+    pub fn observation(&self, side: Side) -> [f32; OBS_DIM] {
+        let (own, opp, ball_x, vel_x) = match side {
+            Side::Left => (&self.left, &self.right, self.ball.x, self.ball.vel_x),
+            Side::Right => (
+                &self.right,
+                &self.left,
+                SCREEN_WIDTH - self.ball.x,
+                -self.ball.vel_x,
+            ),
+        };
+        [
+            ball_x / SCREEN_WIDTH,
+            self.ball.y / SCREEN_HEIGHT,
+            vel_x / MAX_BALL_SPEED,
+            self.ball.vel_y / MAX_BALL_SPEED,
+            own.y / SCREEN_HEIGHT,
+            own.vel / MAX_PADDLE_SPEED,
+            opp.y / SCREEN_HEIGHT,
+            opp.vel / MAX_PADDLE_SPEED,
+        ]
+    }
+
+    // FNV-1a over the exact bits of every field, so peers and replays can
+    // cheaply compare states tick by tick
+    pub fn checksum(&self) -> u32 {
+        let ball = &self.ball;
+        let words = [
+            self.left.y.to_bits(),
+            self.left.vel.to_bits(),
+            self.right.y.to_bits(),
+            self.right.vel.to_bits(),
+            self.left.height.to_bits(),
+            self.right.height.to_bits(),
+            ball.x.to_bits(),
+            ball.y.to_bits(),
+            ball.vel_x.to_bits(),
+            ball.vel_y.to_bits(),
+            ball.speed.to_bits(),
+            self.left_score as u32,
+            self.right_score as u32,
+        ];
+        let mut hash: u32 = 0x811c_9dc5;
+        for byte in words.iter().flat_map(|word| word.to_le_bytes()) {
+            hash ^= byte as u32;
+            hash = hash.wrapping_mul(0x0100_0193);
+        }
+        hash
+    }
+}
+
+impl Default for Match {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Ball collision with the top and bottom walls
+pub(crate) fn bounce_off_walls(ball: &mut Ball) {
+    if ball.y <= BALL_SIZE || ball.y >= SCREEN_HEIGHT - BALL_SIZE {
+        ball.vel_y = -ball.vel_y;
+        ball.y = ball.y.clamp(BALL_SIZE, SCREEN_HEIGHT - BALL_SIZE);
+    }
+}
+
+// Bounce the ball off `side`'s paddle, standing at `paddle_x`, if it's
+// heading that way and touching it; true on a hit
+pub(crate) fn hit_paddle(ball: &mut Ball, paddle: &Paddle, paddle_x: f32, side: Side) -> bool {
+    let (incoming, dir) = match side {
+        Side::Left => (ball.vel_x < 0.0, 1.0),
+        Side::Right => (ball.vel_x > 0.0, -1.0),
+    };
+    let hit = incoming
+        && ball.x - BALL_SIZE <= paddle_x + PADDLE_WIDTH
+        && ball.x + BALL_SIZE >= paddle_x
+        && ball.y + BALL_SIZE >= paddle.y
+        && ball.y - BALL_SIZE <= paddle.y + paddle.height;
+    if hit {
+        bounce(ball, paddle, dir);
+    }
+    hit
+}
+
+// Reflect the ball off `paddle`; `dir` is the new horizontal direction
+pub(crate) fn bounce(ball: &mut Ball, paddle: &Paddle, dir: f32) {
+    // Increase ball speed
+    ball.speed = (ball.speed * BALL_SPEED_INCREASE).min(MAX_BALL_SPEED);
+    aim(ball, paddle, dir);
+}
+
+// Send the ball off `paddle` at its current speed, angled by where it sits
+// on the paddle and by the paddle's motion
+pub(crate) fn aim(ball: &mut Ball, paddle: &Paddle, dir: f32) {
+    // Calculate hit position (0.0 = top, 1.0 = bottom)
+    let hit_pos = (ball.y - paddle.y) / paddle.height;
+
+    // Factor in paddle velocity for more dynamic bounces
+    let paddle_influence = paddle.vel * 0.1;
+
+    // Calculate new velocities with improved angle
+    ball.vel_x = dir * ball.speed;
+    ball.vel_y = ball.speed * (hit_pos - 0.5) * 2.5 + paddle_influence;
+}
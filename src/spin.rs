@@ -0,0 +1,86 @@
+// Ball spin for local matches. A paddle that's moving when it hits the ball
+// sets it spinning, on top of the usual paddle angle. The spin then curves
+// the ball's flight the other way from the paddle's motion (the Magnus
+// effect), bites when the ball bounces off the top or bottom wall, and dies
+// away over time. Applied after `Match::step`, like power-ups.
+//
+// Spin is the speed of the ball's surface in pixels per second, positive
+// for counterclockwise on screen. A paddle moving down brushes the ball's
+// left (or right) side downwards, so the left paddle spins it
+// counterclockwise and the right paddle clockwise.
+//
+// The curve only bends the vertical velocity, so the ball keeps its pace
+// towards the goals. A wall bounce is the other way round: the spinning
+// surface grips the wall and speeds the ball up or slows it down along it,
+// which changes the bounce angle and uses up some of the spin.
+
+use crate::constants::*;
+use crate::sim::{Match, Side, StepEvents};
+
+const SPIN_TRANSFER: f32 = 1.0; // Spin per unit of paddle speed at impact
+const MAGNUS: f32 = 0.0005; // Sideways acceleration per unit of spin and of ball speed
+const SPIN_DECAY: f32 = 0.5; // Rate the spin dies away at, per second
+const WALL_GRIP: f32 = 0.25; // Share of the spin turned into speed along a wall
+const WALL_SPIN_KEEP: f32 = 0.5; // Share of the spin left after a wall bounce
+const MIN_HORIZONTAL: f32 = 0.5; // Least horizontal speed after a wall bounce, as a share of the ball speed
+
+// Things spin did during a step, for effects
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SpinEvents {
+    pub imparted: Option<f32>,  // A paddle hit set this spin
+    pub wall_kick: Option<f32>, // A wall bounce changed the horizontal speed by this much
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Spin {
+    pub spin: f32,  // Surface speed, counterclockwise positive
+    pub angle: f32, // Where the ball's marker points, in radians, for drawing
+}
+
+impl Spin {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Call after `Match::step` with the events it returned
+    pub fn step(&mut self, game: &mut Match, events: &StepEvents, dt: f32) -> SpinEvents {
+        let mut spin_events = SpinEvents::default();
+        let ball = &mut game.ball;
+
+        if events.scored.is_some() {
+            self.spin = 0.0;
+            return spin_events;
+        }
+
+        if let Some((side, _)) = events.paddle_hit {
+            let (paddle, dir) = match side {
+                Side::Left => (&game.left, 1.0),
+                Side::Right => (&game.right, -1.0),
+            };
+            self.spin = dir * paddle.vel * SPIN_TRANSFER;
+            spin_events.imparted = Some(self.spin);
+        } else {
+            // `Match::step` leaves a ball that just bounced exactly on the
+            // wall's edge, heading away from it
+            let top = ball.y == BALL_SIZE && ball.vel_y > 0.0;
+            let bottom = ball.y == SCREEN_HEIGHT - BALL_SIZE && ball.vel_y < 0.0;
+            if (top || bottom) && self.spin != 0.0 {
+                // The top of a counterclockwise ball moves left, so the wall
+                // pushes it right; the bottom is the other way round
+                let kick = if top { WALL_GRIP * self.spin } else { -WALL_GRIP * self.spin };
+                let (before, direction) = (ball.vel_x, ball.vel_x.signum());
+                let kicked = ball.vel_x + kick;
+                let least = ball.speed * MIN_HORIZONTAL;
+                ball.vel_x = if kicked * direction >= least { kicked } else { direction * least };
+                self.spin *= WALL_SPIN_KEEP;
+                spin_events.wall_kick = Some(ball.vel_x - before);
+            }
+        }
+
+        // Curve away from the spin, then let it die down
+        ball.vel_y -= MAGNUS * self.spin * ball.vel_x * dt;
+        self.spin *= (-SPIN_DECAY * dt).exp();
+        self.angle = (self.angle - self.spin / BALL_SIZE * dt) % std::f32::consts::TAU;
+        spin_events
+    }
+}
@@ -0,0 +1,88 @@
+// Match rules for local matches: the classic first to N points, or a
+// timed match where whoever leads when the clock runs out wins. A tie at
+// the end goes to sudden-death overtime: the next point wins.
+//
+// The clock works through `Match::winning_score` rather than changing the
+// simulation: a timed match has no score limit, and when time is up the
+// limit drops to the leader's score (or one past the tie), so
+// `Match::winner` and the usual end-of-match handling just work.
+
+use crate::constants::*;
+use crate::sim::{Match, Side};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MatchRule {
+    FirstTo(i32), // First to this many points
+    Timed(f32),   // Seconds of play, then overtime on a tie
+}
+
+impl MatchRule {
+    // Choices offered on the mode screen
+    pub const PRESETS: [MatchRule; 4] = [
+        MatchRule::FirstTo(WINNING_SCORE),
+        MatchRule::Timed(60.0),
+        MatchRule::Timed(120.0),
+        MatchRule::Timed(180.0),
+    ];
+
+    pub fn label(self) -> String {
+        match self {
+            MatchRule::FirstTo(points) => format!("First to {}", points),
+            MatchRule::Timed(seconds) => format!("{:.0} min timed", seconds / 60.0),
+        }
+    }
+
+    // Set up a fresh `game` under this rule; timed matches get the clock
+    // to step alongside it
+    pub fn apply(self, game: &mut Match) -> Option<MatchClock> {
+        match self {
+            MatchRule::FirstTo(points) => {
+                game.winning_score = points;
+                None
+            }
+            MatchRule::Timed(seconds) => {
+                game.winning_score = i32::MAX;
+                Some(MatchClock { remaining: seconds, overtime: false })
+            }
+        }
+    }
+}
+
+// Things the clock did during a step
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ClockEvents {
+    pub overtime: bool,       // Time ran out on a tie
+    pub winner: Option<Side>, // Time ran out with someone ahead
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MatchClock {
+    pub remaining: f32, // Seconds left in regulation time
+    pub overtime: bool, // Sudden death: the next point wins
+}
+
+impl MatchClock {
+    // Count down `dt` of play; call after `Match::step` while the match
+    // is still on
+    pub fn step(&mut self, game: &mut Match, dt: f32) -> ClockEvents {
+        let mut events = ClockEvents::default();
+        if self.overtime || game.winner().is_some() {
+            return events;
+        }
+        self.remaining = (self.remaining - dt).max(0.0);
+        if self.remaining > 0.0 {
+            return events;
+        }
+
+        let leader = game.left_score.max(game.right_score);
+        if game.left_score == game.right_score {
+            self.overtime = true;
+            game.winning_score = leader + 1;
+            events.overtime = true;
+        } else {
+            game.winning_score = leader;
+            events.winner = game.winner();
+        }
+        events
+    }
+}
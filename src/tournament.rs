@@ -0,0 +1,363 @@
+// Tournaments: 4 to 16 entrants, local players or AI, in a single- or
+// double-elimination bracket. Matches are played one at a time; the first
+// entrant of a game takes the left paddle.
+//
+// The bracket is built a round at a time. The first winners round is
+// seeded the usual way (1 v 16, 8 v 9, ...) with byes for the top seeds
+// when the field isn't a power of two, and later winners rounds pair the
+// winners of neighbouring games. With double elimination, losing a winners
+// game drops the player into the losers bracket, where an odd player out
+// gets a bye (whoever has had the fewest). The last player in each bracket
+// meet in the final; if the winners bracket player loses it, a second
+// final decides the champion.
+//
+// A tournament is saved as its entrants and the results so far, and loading
+// it plays the results back through the same bracket:
+//
+//   pong-tournament 1
+//   format double
+//   entrant human Alice
+//   entrant ai hard Hard AI     # AI profile, then the name
+//   result 6 3                  # left and right score of the next game
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::ai::AiProfile;
+
+pub const MIN_ENTRANTS: usize = 4;
+pub const MAX_ENTRANTS: usize = 16;
+const HEADER: &str = "pong-tournament 1";
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Controller {
+    Human,
+    Ai(String), // Name of the AI profile it plays with
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entrant {
+    pub name: String,
+    pub controller: Controller,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    Single,
+    Double,
+}
+
+impl Format {
+    pub fn label(self) -> &'static str {
+        match self {
+            Format::Single => "Single elimination",
+            Format::Double => "Double elimination",
+        }
+    }
+
+    // Losses that knock a player out
+    fn lives(self) -> u32 {
+        match self {
+            Format::Single => 1,
+            Format::Double => 2,
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Format::Single => "single",
+            Format::Double => "double",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bracket {
+    Winners,
+    Losers,
+    Final,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Game {
+    pub bracket: Bracket,
+    pub players: [usize; 2],       // Entrant indices, left paddle first
+    pub score: Option<(i32, i32)>, // Once played
+}
+
+impl Game {
+    pub fn winner(&self) -> Option<usize> {
+        self.score.map(|(left, right)| if left > right { self.players[0] } else { self.players[1] })
+    }
+
+    pub fn loser(&self) -> Option<usize> {
+        self.score.map(|(left, right)| if left > right { self.players[1] } else { self.players[0] })
+    }
+}
+
+// Where a player in a bracket line comes from
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Slot {
+    Game(usize), // Winner of this game of the round
+    Bye(usize),  // This entrant, without playing
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Round {
+    pub games: Vec<Game>,
+    pub byes: Vec<usize>, // Entrants who go through without playing
+    winners: Vec<Slot>,   // The next winners bracket line, in order
+    losers: Vec<Slot>,    // The next losers bracket line, before this round's drops
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tournament {
+    pub entrants: Vec<Entrant>,
+    pub format: Format,
+    pub rounds: Vec<Round>,
+    losses: Vec<u32>,
+    byes: Vec<u32>,
+    champion: Option<usize>,
+}
+
+impl Tournament {
+    pub fn new(entrants: Vec<Entrant>, format: Format) -> Result<Self, String> {
+        if !(MIN_ENTRANTS..=MAX_ENTRANTS).contains(&entrants.len()) {
+            return Err(format!("a tournament needs {} to {} entrants", MIN_ENTRANTS, MAX_ENTRANTS));
+        }
+        if entrants.iter().any(|entrant| entrant.name.trim().is_empty()) {
+            return Err("every entrant needs a name".to_string());
+        }
+        let count = entrants.len();
+        let mut tournament = Self {
+            entrants,
+            format,
+            rounds: Vec::new(),
+            losses: vec![0; count],
+            byes: vec![0; count],
+            champion: None,
+        };
+        tournament.seed();
+        Ok(tournament)
+    }
+
+    // Fails on an AI entrant whose profile isn't among `profiles`
+    pub fn check_profiles(&self, profiles: &[AiProfile]) -> Result<(), String> {
+        for entrant in &self.entrants {
+            if let Controller::Ai(name) = &entrant.controller {
+                if !profiles.iter().any(|profile| profile.name == *name) {
+                    return Err(format!("{} plays unknown AI profile \"{}\"", entrant.name, name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn champion(&self) -> Option<usize> {
+        self.champion
+    }
+
+    pub fn is_out(&self, entrant: usize) -> bool {
+        self.losses[entrant] >= self.format.lives()
+    }
+
+    // The next game to play, if the tournament isn't over
+    pub fn current(&self) -> Option<&Game> {
+        self.rounds.last()?.games.iter().find(|game| game.score.is_none())
+    }
+
+    // Record the score of the current game and move the bracket on
+    pub fn record(&mut self, left_score: i32, right_score: i32) -> Result<(), String> {
+        if left_score == right_score {
+            return Err("a game needs a winner".to_string());
+        }
+        let round = self.rounds.last_mut().ok_or("the tournament is over")?;
+        let game = round
+            .games
+            .iter_mut()
+            .find(|game| game.score.is_none())
+            .ok_or("the tournament is over")?;
+        game.score = Some((left_score, right_score));
+        if let Some(loser) = game.loser() {
+            self.losses[loser] += 1;
+        }
+        if round.games.iter().all(|game| game.score.is_some()) {
+            self.advance();
+        }
+        Ok(())
+    }
+
+    // First winners round: seeds in bracket order, byes for the top seeds
+    fn seed(&mut self) {
+        let count = self.entrants.len();
+        let mut order = vec![0];
+        while order.len() < count {
+            let size = order.len() * 2;
+            order = order.iter().flat_map(|&seed| [seed, size - 1 - seed]).collect();
+        }
+        let mut round = Round { games: Vec::new(), byes: Vec::new(), winners: Vec::new(), losers: Vec::new() };
+        for pair in order.chunks(2) {
+            if pair[1] >= count {
+                round.byes.push(pair[0]);
+                self.byes[pair[0]] += 1;
+                round.winners.push(Slot::Bye(pair[0]));
+            } else {
+                round.winners.push(Slot::Game(round.games.len()));
+                round.games.push(Game { bracket: Bracket::Winners, players: [pair[0], pair[1]], score: None });
+            }
+        }
+        self.rounds.push(round);
+    }
+
+    // Build the next round from the finished one, or crown the champion
+    fn advance(&mut self) {
+        let Some(round) = self.rounds.last() else {
+            return;
+        };
+        let through = |slot: &Slot| match *slot {
+            Slot::Game(index) => round.games[index].winner().unwrap_or_default(),
+            Slot::Bye(entrant) => entrant,
+        };
+        // Everyone still in, in bracket order: winners line, finalists,
+        // losers line, then the players who just dropped out of the winners
+        let mut line: Vec<usize> = round.winners.iter().map(through).collect();
+        for game in round.games.iter().filter(|game| game.bracket == Bracket::Final) {
+            line.extend(game.winner().into_iter().chain(game.loser()));
+        }
+        line.extend(round.losers.iter().map(through));
+        for game in round.games.iter().filter(|game| game.bracket == Bracket::Winners) {
+            line.extend(game.loser());
+        }
+        line.retain(|&entrant| !self.is_out(entrant));
+        let (winners, losers): (Vec<usize>, Vec<usize>) = line.into_iter().partition(|&entrant| self.losses[entrant] == 0);
+
+        let mut next = Round { games: Vec::new(), byes: Vec::new(), winners: Vec::new(), losers: Vec::new() };
+        match (winners.len(), losers.len()) {
+            (1, 0) => self.champion = Some(winners[0]),
+            (0, 1) => self.champion = Some(losers[0]),
+            (1, 1) => {
+                next.games.push(Game { bracket: Bracket::Final, players: [winners[0], losers[0]], score: None });
+            }
+            (0, 2) => {
+                next.games.push(Game { bracket: Bracket::Final, players: [losers[0], losers[1]], score: None });
+            }
+            _ => {
+                next.winners = self.pair(&mut next.games, &mut next.byes, Bracket::Winners, &winners);
+                next.losers = self.pair(&mut next.games, &mut next.byes, Bracket::Losers, &losers);
+            }
+        }
+        if self.champion.is_none() {
+            self.rounds.push(next);
+        }
+    }
+
+    // Pair a bracket line into games, neighbours against each other. An odd
+    // player out gets a bye; a lone player waits for the other bracket.
+    fn pair(&mut self, games: &mut Vec<Game>, byes: &mut Vec<usize>, bracket: Bracket, line: &[usize]) -> Vec<Slot> {
+        if line.len() < 2 {
+            return line.iter().map(|&entrant| Slot::Bye(entrant)).collect();
+        }
+        let sitting_out = (line.len() % 2 == 1).then(|| {
+            let fewest = line.iter().map(|&entrant| self.byes[entrant]).min().unwrap_or_default();
+            let entrant = line.iter().copied().find(|&entrant| self.byes[entrant] == fewest).unwrap_or(line[0]);
+            self.byes[entrant] += 1;
+            byes.push(entrant);
+            entrant
+        });
+        let mut slots = Vec::new();
+        let mut waiting = None;
+        for &entrant in line {
+            if Some(entrant) == sitting_out {
+                slots.push(Slot::Bye(entrant));
+            } else if let Some(opponent) = waiting.take() {
+                slots.push(Slot::Game(games.len()));
+                games.push(Game { bracket, players: [opponent, entrant], score: None });
+            } else {
+                waiting = Some(entrant);
+            }
+        }
+        slots
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(number, line)| (number + 1, line.split('#').next().unwrap_or("").trim()))
+            .filter(|(_, line)| !line.is_empty());
+        if lines.next().map(|(_, line)| line) != Some(HEADER) {
+            return Err(format!("expected header \"{}\"", HEADER));
+        }
+
+        let mut format = Format::Single;
+        let mut entrants = Vec::new();
+        let mut results = Vec::new();
+        for (number, line) in lines {
+            let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let rest = rest.trim();
+            match keyword {
+                "format" => {
+                    format = [Format::Single, Format::Double]
+                        .into_iter()
+                        .find(|format| format.key() == rest)
+                        .ok_or(format!("line {}: format must be single or double", number))?;
+                }
+                "entrant" => {
+                    let bad = || format!("line {}: expected \"entrant human <name>\" or \"entrant ai <profile> <name>\"", number);
+                    let (kind, rest) = rest.split_once(char::is_whitespace).ok_or_else(bad)?;
+                    let (controller, name) = match kind {
+                        "human" => (Controller::Human, rest),
+                        "ai" => {
+                            let (profile, name) = rest.trim().split_once(char::is_whitespace).ok_or_else(bad)?;
+                            (Controller::Ai(profile.to_string()), name)
+                        }
+                        _ => return Err(bad()),
+                    };
+                    entrants.push(Entrant { name: name.trim().to_string(), controller });
+                }
+                "result" => {
+                    let scores = rest
+                        .split_whitespace()
+                        .map(|word| word.parse::<i32>().ok())
+                        .collect::<Option<Vec<i32>>>()
+                        .filter(|scores| scores.len() == 2)
+                        .ok_or(format!("line {}: result takes two scores", number))?;
+                    results.push((number, scores[0], scores[1]));
+                }
+                other => return Err(format!("line {}: unknown item {}", number, other)),
+            }
+        }
+
+        let mut tournament = Tournament::new(entrants, format)?;
+        for (number, left, right) in results {
+            tournament.record(left, right).map_err(|err| format!("line {}: {}", number, err))?;
+        }
+        Ok(tournament)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = format!("{}\nformat {}\n", HEADER, self.format.key());
+        for entrant in &self.entrants {
+            out += &match &entrant.controller {
+                Controller::Human => format!("entrant human {}\n", entrant.name),
+                Controller::Ai(profile) => format!("entrant ai {} {}\n", profile, entrant.name),
+            };
+        }
+        for game in self.rounds.iter().flat_map(|round| &round.games) {
+            if let Some((left, right)) = game.score {
+                out += &format!("result {} {}\n", left, right);
+            }
+        }
+        out
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+}
@@ -0,0 +1,313 @@
+// Genetic search over `AiParams`. Candidates are scored by headless matches
+// against fixed reference bots, and the best set for each target win rate is
+// written out as an AI profile (see `ai::save_profiles`).
+
+use std::thread;
+
+use crate::ai::{save_profiles, AiController, AiParams, AiProfile};
+use crate::constants::*;
+use crate::sim::{Intent, Match, Rng, Side};
+
+const DT: f32 = 1.0 / 60.0;
+const MAX_MATCH_SECONDS: f32 = 180.0; // Unfinished matches are decided on points
+
+// Search space: (min, max) for speed, prediction, error, reaction_time
+const BOUNDS: [(f32, f32); 4] = [(100.0, 500.0), (0.0, 1.0), (0.0, 80.0), (0.0, 0.5)];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Reference {
+    Tracker, // Presses towards the ball's height like a simple human
+    Classic, // The original built-in AI
+}
+
+pub const REFERENCES: [Reference; 2] = [Reference::Tracker, Reference::Classic];
+
+#[derive(Clone, Debug)]
+pub struct TuneConfig {
+    pub targets: Vec<(String, f32)>, // Profile name and target win rate
+    pub population: usize,
+    pub generations: usize,
+    pub matches: usize, // Matches per reference bot for each evaluation
+    pub seed: u64,
+    pub threads: usize,
+}
+
+impl Default for TuneConfig {
+    fn default() -> Self {
+        Self {
+            targets: vec![
+                ("easy".to_string(), 0.25),
+                ("medium".to_string(), 0.5),
+                ("hard".to_string(), 0.75),
+            ],
+            population: 24,
+            generations: 30,
+            matches: 6,
+            seed: 1,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+}
+
+// How a candidate did against the reference bots
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Evaluation {
+    pub win_rate: f32,
+    pub point_share: f32,
+}
+
+impl Evaluation {
+    fn fitness(&self, target: f32) -> f32 {
+        -(self.win_rate - target).abs() - 0.25 * (self.point_share - target).abs()
+    }
+}
+
+fn to_genes(params: &AiParams) -> [f32; 4] {
+    [params.speed, params.prediction, params.error, params.reaction_time]
+}
+
+fn from_genes(genes: [f32; 4]) -> AiParams {
+    let g: Vec<f32> = genes
+        .iter()
+        .zip(BOUNDS)
+        .map(|(value, (min, max))| value.clamp(min, max))
+        .collect();
+    AiParams {
+        speed: g[0],
+        prediction: g[1],
+        error: g[2],
+        reaction_time: g[3],
+    }
+}
+
+fn gaussian(rng: &mut Rng) -> f32 {
+    // Box-Muller transform
+    let u1 = rng.next_f32().max(1e-7);
+    let u2 = rng.next_f32();
+    (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos()
+}
+
+// Play one headless match with the candidate on the right; returns the
+// candidate's points, the reference's points and the candidate's result
+// (1 = win, 0.5 = draw on time, 0 = loss).
+pub fn play(candidate: AiParams, reference: Reference, seed: u64) -> (i32, i32, f32) {
+    let mut game = Match::new();
+    let mut rng = Rng::new(seed);
+    let mut ai = AiController::new(candidate, Side::Right);
+    let mut opponent = AiController::new(AiParams::classic(), Side::Left);
+
+    let mut time = 0.0;
+    while game.winner().is_none() && time < MAX_MATCH_SECONDS {
+        match reference {
+            Reference::Tracker => {
+                let center = game.left.y + PADDLE_HEIGHT / 2.0;
+                let intent = if game.ball.y < center - 10.0 {
+                    Intent::Up
+                } else if game.ball.y > center + 10.0 {
+                    Intent::Down
+                } else {
+                    Intent::Stay
+                };
+                game.left.drive(intent, DT);
+            }
+            Reference::Classic => opponent.drive(&mut game, DT, rng.range(-1.0, 1.0)),
+        }
+        ai.drive(&mut game, DT, rng.range(-1.0, 1.0));
+        game.step(DT);
+        time += DT;
+    }
+
+    let result = match game.right_score.cmp(&game.left_score) {
+        std::cmp::Ordering::Greater => 1.0,
+        std::cmp::Ordering::Equal => 0.5,
+        std::cmp::Ordering::Less => 0.0,
+    };
+    (game.right_score, game.left_score, result)
+}
+
+pub fn evaluate(params: AiParams, matches: usize, seed: u64) -> Evaluation {
+    let mut results = 0.0;
+    let mut won = 0;
+    let mut lost = 0;
+    let mut played = 0;
+    for (r, &reference) in REFERENCES.iter().enumerate() {
+        for m in 0..matches {
+            let match_seed = seed ^ ((r * 1000 + m) as u64).wrapping_mul(0x9E37_79B9);
+            let (ours, theirs, result) = play(params, reference, match_seed);
+            won += ours;
+            lost += theirs;
+            results += result;
+            played += 1;
+        }
+    }
+    Evaluation {
+        win_rate: results / played.max(1) as f32,
+        point_share: won as f32 / (won + lost).max(1) as f32,
+    }
+}
+
+fn evaluate_all(population: &[AiParams], config: &TuneConfig, seed: u64) -> Vec<Evaluation> {
+    let chunk = population.len().div_ceil(config.threads.max(1)).max(1);
+    thread::scope(|scope| {
+        let handles: Vec<_> = population
+            .chunks(chunk)
+            .map(|candidates| {
+                scope.spawn(move || {
+                    candidates
+                        .iter()
+                        .map(|&params| evaluate(params, config.matches, seed))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("evaluation thread panicked"))
+            .collect()
+    })
+}
+
+// Run the genetic search for one target win rate
+pub fn search(target: f32, config: &TuneConfig, mut progress: impl FnMut(usize, f32, &Evaluation)) -> (AiParams, Evaluation) {
+    let mut rng = Rng::new(config.seed ^ target.to_bits() as u64);
+
+    // Start from the classic AI plus random candidates across the bounds
+    let mut population: Vec<AiParams> = vec![AiParams::classic()];
+    while population.len() < config.population.max(2) {
+        let genes = BOUNDS.map(|(min, max)| rng.range(min, max));
+        population.push(from_genes(genes));
+    }
+
+    let mut best = (population[0], Evaluation::default(), f32::NEG_INFINITY);
+    for generation in 0..config.generations.max(1) {
+        // Evaluate every generation on the same seeds so scores are comparable
+        let evaluations = evaluate_all(&population, config, config.seed);
+        let mut ranked: Vec<(AiParams, Evaluation, f32)> = population
+            .iter()
+            .zip(&evaluations)
+            .map(|(&params, &eval)| (params, eval, eval.fitness(target)))
+            .collect();
+        ranked.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+        if ranked[0].2 > best.2 {
+            best = ranked[0];
+        }
+        progress(generation, best.2, &best.1);
+
+        // Next generation: keep the elite, breed the rest by tournament
+        let mut next: Vec<AiParams> = ranked.iter().take(2).map(|r| r.0).collect();
+        while next.len() < population.len() {
+            let mut pick = || {
+                let a = &ranked[(rng.next_u64() % ranked.len() as u64) as usize];
+                let b = &ranked[(rng.next_u64() % ranked.len() as u64) as usize];
+                if a.2 >= b.2 {
+                    to_genes(&a.0)
+                } else {
+                    to_genes(&b.0)
+                }
+            };
+            let (mother, father) = (pick(), pick());
+            let mut child = [0.0; 4];
+            for i in 0..4 {
+                let mix = rng.range(-0.25, 1.25); // Blend crossover
+                child[i] = mother[i] + (father[i] - mother[i]) * mix;
+                if rng.next_f32() < 0.3 {
+                    let (min, max) = BOUNDS[i];
+                    child[i] += gaussian(&mut rng) * (max - min) * 0.1;
+                }
+            }
+            next.push(from_genes(child));
+        }
+        population = next;
+    }
+
+    (best.0, best.1)
+}
+
+pub fn tune(config: &TuneConfig) -> Vec<AiProfile> {
+    config
+        .targets
+        .iter()
+        .map(|(name, target)| {
+            println!("Tuning \"{}\" for a {:.0}% win rate", name, target * 100.0);
+            let (params, eval) = search(*target, config, |generation, fitness, eval| {
+                println!(
+                    "  generation {:>3}: fitness {:.3}  win rate {:.2}  point share {:.2}",
+                    generation + 1,
+                    fitness,
+                    eval.win_rate,
+                    eval.point_share
+                );
+            });
+            AiProfile {
+                name: name.clone(),
+                params,
+                win_rate: Some(eval.win_rate),
+            }
+        })
+        .collect()
+}
+
+// Parse `--targets`: comma-separated `name=rate` pairs or bare rates. Bare
+// rates keep the default easy/medium/hard names when there are as many of
+// them, since the game picks profiles by name; otherwise they're `win25`
+// and so on.
+pub fn parse_targets(text: &str) -> Result<Vec<(String, f32)>, String> {
+    let defaults = TuneConfig::default().targets;
+    let entries: Vec<&str> = text.split(',').map(str::trim).collect();
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let (name, rate) = match entry.split_once('=') {
+                Some((name, rate)) => (Some(name.trim().to_string()), rate.trim()),
+                None => (None, *entry),
+            };
+            let rate: f32 = rate.parse().map_err(|_| format!("bad target win rate {}", rate))?;
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(format!("target win rate {} is not between 0 and 1", rate));
+            }
+            let name = match name {
+                Some(name) if name.is_empty() => return Err(format!("missing profile name in {}", entry)),
+                Some(name) => name,
+                None if entries.len() == defaults.len() => defaults[index].0.clone(),
+                None => format!("win{:.0}", rate * 100.0),
+            };
+            Ok((name, rate))
+        })
+        .collect()
+}
+
+fn usage() -> String {
+    "usage: pong_rs tune [--out FILE] [--targets easy=0.25,medium=0.5,hard=0.75] [--generations N] \
+     [--population N] [--matches N] [--seed N]"
+        .to_string()
+}
+
+// Entry point for `pong_rs tune ...`; `args` excludes the subcommand itself
+pub fn run_cli(args: &[String]) -> Result<(), String> {
+    let mut config = TuneConfig::default();
+    let mut out = AI_PROFILE_FILE.to_string();
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().cloned().ok_or_else(usage);
+        let number = |v: String| v.parse::<usize>().map_err(|_| usage());
+        match flag.as_str() {
+            "--out" => out = value()?,
+            "--generations" => config.generations = number(value()?)?,
+            "--population" => config.population = number(value()?)?,
+            "--matches" => config.matches = number(value()?)?,
+            "--seed" => config.seed = number(value()?)? as u64,
+            "--targets" => {
+                config.targets = parse_targets(&value()?).map_err(|e| format!("{}\n{}", e, usage()))?;
+            }
+            _ => return Err(usage()),
+        }
+    }
+
+    let profiles = tune(&config);
+    save_profiles(&out, &profiles).map_err(|e| format!("could not write {}: {}", out, e))?;
+    println!("Wrote {} profiles to {}", profiles.len(), out);
+    Ok(())
+}
@@ -0,0 +1,609 @@
+// Minimal WebSocket support for the relay server and its clients: the
+// opening handshake, frame encoding and a client `Transport`. Only what the
+// relay needs is implemented (no extensions); fragmented messages are
+// joined back together on receipt, but we always send whole ones.
+
+use crate::net::Transport;
+use crate::sim::Side;
+
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+pub const OP_CONTINUATION: u8 = 0x0;
+pub const OP_TEXT: u8 = 0x1;
+pub const OP_BINARY: u8 = 0x2;
+pub const OP_CLOSE: u8 = 0x8;
+pub const OP_PING: u8 = 0x9;
+pub const OP_PONG: u8 = 0xA;
+
+// Largest frame or reassembled message accepted; game packets are far smaller
+pub const MAX_MESSAGE: usize = 64 * 1024;
+// Unparsed bytes held per connection: a whole frame, its header and a read's worth
+pub const MAX_BUFFERED: usize = MAX_MESSAGE + 16 + 4096;
+// Close status for a peer that went over those limits
+pub const CLOSE_TOO_BIG: u16 = 1009;
+
+// A frame or message over `MAX_MESSAGE`; close with `CLOSE_TOO_BIG`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TooBig;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub opcode: u8,
+    pub payload: Vec<u8>,
+    pub fin: bool, // Last frame of its message
+}
+
+// Encode a single final frame; clients must mask, servers must not
+pub fn encode_frame(opcode: u8, payload: &[u8], mask: Option<[u8; 4]>) -> Vec<u8> {
+    encode_fragment(opcode, payload, true, mask)
+}
+
+// Encode one frame of a message: the first carries the opcode, the rest
+// OP_CONTINUATION, and only the last has `fin` set
+pub fn encode_fragment(opcode: u8, payload: &[u8], fin: bool, mask: Option<[u8; 4]>) -> Vec<u8> {
+    let mut out = vec![if fin { 0x80 } else { 0 } | opcode];
+    let mask_bit = if mask.is_some() { 0x80 } else { 0 };
+    match payload.len() {
+        len if len < 126 => out.push(mask_bit | len as u8),
+        len if len <= u16::MAX as usize => {
+            out.push(mask_bit | 126);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            out.push(mask_bit | 127);
+            out.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    match mask {
+        Some(key) => {
+            out.extend_from_slice(&key);
+            out.extend(payload.iter().enumerate().map(|(i, b)| b ^ key[i % 4]));
+        }
+        None => out.extend_from_slice(payload),
+    }
+    out
+}
+
+// Decode one frame from the front of `data`; returns the frame and the
+// number of bytes it used, or None if more data is needed. A length over
+// `MAX_MESSAGE` fails as soon as the header is in.
+pub fn decode_frame(data: &[u8]) -> Result<Option<(Frame, usize)>, TooBig> {
+    let Some((length, offset)) = frame_length(data) else {
+        return Ok(None);
+    };
+    if length > MAX_MESSAGE as u64 {
+        return Err(TooBig);
+    }
+    Ok(frame_body(data, length as usize, offset))
+}
+
+// The payload length and where the mask (if any) starts
+fn frame_length(data: &[u8]) -> Option<(u64, usize)> {
+    match *data.get(1)? & 0x7F {
+        126 => Some((u16::from_be_bytes(data.get(2..4)?.try_into().ok()?) as u64, 4)),
+        127 => Some((u64::from_be_bytes(data.get(2..10)?.try_into().ok()?), 10)),
+        len => Some((len as u64, 2)),
+    }
+}
+
+fn frame_body(data: &[u8], len: usize, mut offset: usize) -> Option<(Frame, usize)> {
+    let fin = data[0] & 0x80 != 0;
+    let opcode = data[0] & 0x0F;
+    let masked = data[1] & 0x80 != 0;
+    let mask = if masked {
+        let key = data.get(offset..offset + 4)?;
+        offset += 4;
+        Some([key[0], key[1], key[2], key[3]])
+    } else {
+        None
+    };
+    let body = data.get(offset..offset + len)?;
+    let payload = match mask {
+        Some(key) => body.iter().enumerate().map(|(i, b)| b ^ key[i % 4]).collect(),
+        None => body.to_vec(),
+    };
+    Some((Frame { opcode, payload, fin }, offset + len))
+}
+
+// Joins fragmented messages back together. Control frames may come between
+// the fragments and are passed straight through.
+#[derive(Default)]
+pub struct Reassembler {
+    partial: Option<Frame>,
+}
+
+impl Reassembler {
+    // Feed frames in order; returns a frame once it completes a message.
+    // Messages may not grow past `MAX_MESSAGE` either.
+    pub fn push(&mut self, frame: Frame) -> Result<Option<Frame>, TooBig> {
+        if frame.opcode & 0x8 != 0 {
+            return Ok(Some(frame));
+        }
+        match (self.partial.take(), frame.opcode) {
+            (Some(mut partial), OP_CONTINUATION) => {
+                if partial.payload.len() + frame.payload.len() > MAX_MESSAGE {
+                    return Err(TooBig);
+                }
+                partial.payload.extend_from_slice(&frame.payload);
+                partial.fin = frame.fin;
+                if partial.fin {
+                    return Ok(Some(partial));
+                }
+                self.partial = Some(partial);
+                Ok(None)
+            }
+            // Nothing to continue; a broken peer, so drop it
+            (None, OP_CONTINUATION) => Ok(None),
+            // A new message abandons an unfinished one
+            _ if frame.fin => Ok(Some(frame)),
+            _ => {
+                self.partial = Some(frame);
+                Ok(None)
+            }
+        }
+    }
+}
+
+// Value for the server's Sec-WebSocket-Accept header
+pub fn accept_key(client_key: &str) -> String {
+    base64(&sha1(format!("{}{}", client_key.trim(), GUID).as_bytes()))
+}
+
+// Look up an HTTP header (case-insensitive) in a raw request/response head
+pub fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+    })
+}
+
+pub fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for chunk in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for i in 0..16 {
+            w[i] = u32::from_be_bytes(chunk[i * 4..i * 4 + 4].try_into().unwrap());
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut out = [0u8; 20];
+    for (i, word) in h.iter().enumerate() {
+        out[i * 4..i * 4 + 4].copy_from_slice(&word.to_be_bytes());
+    }
+    out
+}
+
+pub fn base64(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(TABLE[(n >> (18 - i * 6)) as usize & 63] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+// Relay control messages (text frames). Game data travels in binary frames
+// and is forwarded untouched to the other member of the room.
+//
+//   client -> relay: "join <ROOM>"
+//   relay -> client: "joined left" / "joined right", "full", "peer-joined", "peer-left"
+pub fn parse_side(word: &str) -> Option<Side> {
+    match word {
+        "left" => Some(Side::Left),
+        "right" => Some(Side::Right),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RelayStatus {
+    Connecting,
+    Waiting,       // In the room, waiting for the side assignment
+    Joined(Side),  // Playing this side
+    Full,          // Room already has two players
+    Closed,
+}
+
+// WebSocket client for the relay, usable as a `Transport` once joined.
+// Desktop builds use a TCP socket; the web build goes through the
+// browser's WebSocket via `web/ws_plugin.js`.
+pub struct WsTransport {
+    socket: platform::Socket,
+    status: RelayStatus,
+    room: String,
+    join_sent: bool,
+    peer_present: bool,
+}
+
+impl WsTransport {
+    // `url` like "ws://127.0.0.1:8080/ws"; an empty URL on the web means
+    // "the server this page was loaded from"
+    pub fn connect(url: &str, room: &str) -> Result<Self, String> {
+        Ok(Self {
+            socket: platform::Socket::connect(url)?,
+            status: RelayStatus::Connecting,
+            room: room.trim().to_uppercase(),
+            join_sent: false,
+            peer_present: false,
+        })
+    }
+
+    pub fn status(&self) -> RelayStatus {
+        self.status
+    }
+
+    pub fn room(&self) -> &str {
+        &self.room
+    }
+
+    // True while the other player is connected to the room
+    pub fn peer_present(&self) -> bool {
+        self.peer_present
+    }
+
+    // Process control messages; returns the next binary payload if one arrived
+    fn pump(&mut self) -> Option<Vec<u8>> {
+        if !self.join_sent && self.socket.is_open() {
+            let join = format!("join {}", self.room);
+            self.socket.send(OP_TEXT, join.as_bytes());
+            self.join_sent = true;
+            self.status = RelayStatus::Waiting;
+        }
+        if self.socket.is_closed() {
+            self.status = RelayStatus::Closed;
+        }
+
+        while let Some(frame) = self.socket.recv() {
+            match frame.opcode {
+                OP_BINARY => return Some(frame.payload),
+                OP_TEXT => {
+                    let text = String::from_utf8_lossy(&frame.payload);
+                    let mut words = text.split_whitespace();
+                    match (words.next(), words.next()) {
+                        (Some("joined"), Some(side)) => {
+                            if let Some(side) = parse_side(side) {
+                                self.status = RelayStatus::Joined(side);
+                                // The right side always joins an occupied room
+                                self.peer_present = side == Side::Right;
+                            }
+                        }
+                        (Some("full"), _) => self.status = RelayStatus::Full,
+                        (Some("peer-joined"), _) => self.peer_present = true,
+                        (Some("peer-left"), _) => self.peer_present = false,
+                        _ => {}
+                    }
+                }
+                OP_CLOSE => self.status = RelayStatus::Closed,
+                _ => {}
+            }
+        }
+        None
+    }
+
+    // Drive the connection without consuming game data (e.g. while joining)
+    pub fn update(&mut self) -> RelayStatus {
+        if let Some(payload) = self.pump() {
+            self.socket.unread(payload);
+        }
+        self.status
+    }
+}
+
+impl Transport for WsTransport {
+    fn send(&mut self, packet: &[u8]) {
+        if matches!(self.status, RelayStatus::Joined(_)) {
+            self.socket.send(OP_BINARY, packet);
+        }
+    }
+
+    fn recv(&mut self) -> Option<Vec<u8>> {
+        if let Some(payload) = self.socket.take_unread() {
+            return Some(payload);
+        }
+        self.pump()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod platform {
+    use std::collections::VecDeque;
+    use std::io::{ErrorKind, Read, Write};
+    use std::net::{TcpStream, ToSocketAddrs};
+    use std::time::Duration;
+
+    use super::{
+        decode_frame, encode_frame, header, Frame, Reassembler, CLOSE_TOO_BIG, MAX_BUFFERED, OP_CLOSE, OP_PING, OP_PONG,
+    };
+
+    pub struct Socket {
+        stream: TcpStream,
+        incoming: Vec<u8>,
+        messages: Reassembler,
+        outgoing: Vec<u8>,
+        unread: VecDeque<Vec<u8>>,
+        closed: bool,
+        mask_seed: u32,
+    }
+
+    impl Socket {
+        pub fn connect(url: &str) -> Result<Self, String> {
+            let rest = url
+                .strip_prefix("ws://")
+                .ok_or("only ws:// URLs are supported on desktop")?;
+            let (host, path) = match rest.find('/') {
+                Some(index) => (&rest[..index], &rest[index..]),
+                None => (rest, "/"),
+            };
+            let address = host
+                .to_socket_addrs()
+                .map_err(|e| e.to_string())?
+                .next()
+                .ok_or("could not resolve relay address")?;
+            let mut stream = TcpStream::connect_timeout(&address, Duration::from_secs(3))
+                .map_err(|e| e.to_string())?;
+            stream
+                .set_read_timeout(Some(Duration::from_secs(3)))
+                .map_err(|e| e.to_string())?;
+
+            let key = super::base64(&rand_bytes(address.port() as u32));
+            let request = format!(
+                "GET {} HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+                 Sec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n\r\n",
+                path, host, key
+            );
+            stream.write_all(request.as_bytes()).map_err(|e| e.to_string())?;
+
+            // Read the response head; anything after it is already frame data
+            let mut response = Vec::new();
+            let mut buffer = [0u8; 1024];
+            let head_end = loop {
+                let read = stream.read(&mut buffer).map_err(|e| e.to_string())?;
+                if read == 0 {
+                    return Err("relay closed the connection".to_string());
+                }
+                response.extend_from_slice(&buffer[..read]);
+                if let Some(end) = response.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break end + 4;
+                }
+            };
+            let head = String::from_utf8_lossy(&response[..head_end]).to_string();
+            if !head.starts_with("HTTP/1.1 101")
+                || header(&head, "Sec-WebSocket-Accept") != Some(super::accept_key(&key).as_str())
+            {
+                return Err("relay refused the WebSocket upgrade".to_string());
+            }
+            stream.set_nonblocking(true).map_err(|e| e.to_string())?;
+
+            Ok(Self {
+                stream,
+                incoming: response[head_end..].to_vec(),
+                messages: Reassembler::default(),
+                outgoing: Vec::new(),
+                unread: VecDeque::new(),
+                closed: false,
+                mask_seed: address.port() as u32 ^ 0x9E37_79B9,
+            })
+        }
+
+        pub fn is_open(&self) -> bool {
+            !self.closed
+        }
+
+        pub fn is_closed(&self) -> bool {
+            self.closed
+        }
+
+        pub fn send(&mut self, opcode: u8, payload: &[u8]) {
+            self.mask_seed = self.mask_seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let frame = encode_frame(opcode, payload, Some(self.mask_seed.to_le_bytes()));
+            self.outgoing.extend_from_slice(&frame);
+            self.flush();
+        }
+
+        fn flush(&mut self) {
+            while !self.outgoing.is_empty() {
+                match self.stream.write(&self.outgoing) {
+                    Ok(0) => {
+                        self.closed = true;
+                        return;
+                    }
+                    Ok(written) => {
+                        self.outgoing.drain(..written);
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => return,
+                    Err(_) => {
+                        self.closed = true;
+                        return;
+                    }
+                }
+            }
+        }
+
+        pub fn recv(&mut self) -> Option<Frame> {
+            self.flush();
+            let mut buffer = [0u8; 4096];
+            loop {
+                match self.stream.read(&mut buffer) {
+                    Ok(0) => {
+                        self.closed = true;
+                        break;
+                    }
+                    Ok(read) => {
+                        self.incoming.extend_from_slice(&buffer[..read]);
+                        // Enough for a whole frame; the rest waits in the socket
+                        if self.incoming.len() >= MAX_BUFFERED {
+                            break;
+                        }
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                    Err(_) => {
+                        self.closed = true;
+                        break;
+                    }
+                }
+            }
+
+            loop {
+                let (frame, used) = match decode_frame(&self.incoming) {
+                    Ok(Some(decoded)) => decoded,
+                    Ok(None) => return None,
+                    Err(_) => return self.close_too_big(),
+                };
+                self.incoming.drain(..used);
+                let frame = match self.messages.push(frame) {
+                    Ok(Some(frame)) => frame,
+                    Ok(None) => continue,
+                    Err(_) => return self.close_too_big(),
+                };
+                match frame.opcode {
+                    OP_PING => self.send(OP_PONG, &frame.payload),
+                    OP_CLOSE => {
+                        self.closed = true;
+                        return Some(frame);
+                    }
+                    _ => return Some(frame),
+                }
+            }
+        }
+
+        pub fn unread(&mut self, payload: Vec<u8>) {
+            self.unread.push_back(payload);
+        }
+
+        pub fn take_unread(&mut self) -> Option<Vec<u8>> {
+            self.unread.pop_front()
+        }
+
+        fn close_too_big(&mut self) -> Option<Frame> {
+            self.send(OP_CLOSE, &CLOSE_TOO_BIG.to_be_bytes());
+            self.closed = true;
+            None
+        }
+    }
+
+    // Handshake key material; it only has to differ between connections
+    fn rand_bytes(salt: u32) -> [u8; 16] {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        let mut state = nanos ^ ((salt as u64) << 32);
+        let mut out = [0u8; 16];
+        for byte in out.iter_mut() {
+            state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            *byte = (state >> 56) as u8;
+        }
+        out
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod platform {
+    use std::collections::VecDeque;
+
+    use super::{Frame, OP_BINARY, OP_TEXT};
+
+    // Implemented in web/ws_plugin.js
+    extern "C" {
+        fn pong_ws_connect(url: *const u8, url_len: usize) -> i32;
+        fn pong_ws_state(id: i32) -> i32; // 0 connecting, 1 open, 2 closed
+        fn pong_ws_send(id: i32, data: *const u8, len: usize, binary: i32);
+        fn pong_ws_next_len(id: i32) -> i32; // -1 when nothing is queued
+        fn pong_ws_next_is_text(id: i32) -> i32;
+        fn pong_ws_take(id: i32, data: *mut u8, len: usize);
+    }
+
+    pub struct Socket {
+        id: i32,
+        unread: VecDeque<Vec<u8>>,
+    }
+
+    impl Socket {
+        pub fn connect(url: &str) -> Result<Self, String> {
+            let id = unsafe { pong_ws_connect(url.as_ptr(), url.len()) };
+            if id < 0 {
+                return Err("WebSocket support is not available".to_string());
+            }
+            Ok(Self {
+                id,
+                unread: VecDeque::new(),
+            })
+        }
+
+        pub fn is_open(&self) -> bool {
+            unsafe { pong_ws_state(self.id) == 1 }
+        }
+
+        pub fn is_closed(&self) -> bool {
+            unsafe { pong_ws_state(self.id) == 2 }
+        }
+
+        pub fn send(&mut self, opcode: u8, payload: &[u8]) {
+            let binary = (opcode == OP_BINARY) as i32;
+            unsafe { pong_ws_send(self.id, payload.as_ptr(), payload.len(), binary) }
+        }
+
+        pub fn recv(&mut self) -> Option<Frame> {
+            let len = unsafe { pong_ws_next_len(self.id) };
+            if len < 0 {
+                return None;
+            }
+            let text = unsafe { pong_ws_next_is_text(self.id) } != 0;
+            let mut payload = vec![0u8; len as usize];
+            unsafe { pong_ws_take(self.id, payload.as_mut_ptr(), payload.len()) };
+            Some(Frame {
+                opcode: if text { OP_TEXT } else { OP_BINARY },
+                payload,
+                fin: true,
+            })
+        }
+
+        pub fn unread(&mut self, payload: Vec<u8>) {
+            self.unread.push_back(payload);
+        }
+
+        pub fn take_unread(&mut self) -> Option<Vec<u8>> {
+            self.unread.pop_front()
+        }
+    }
+}
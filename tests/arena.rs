@@ -0,0 +1,168 @@
+// Arenas: circle-vs-shape bounces off every obstacle type, bricks break and
+// optionally score, and matches on the built-in layouts still finish.
+
+use pong_rs::arena::{Arena, ArenaState, Obstacle};
+use pong_rs::constants::*;
+use pong_rs::sim::{Ball, Match, Rng, Side, StepEvents};
+
+const DT: f32 = 1.0 / 60.0;
+
+fn ball(x: f32, y: f32, vel_x: f32, vel_y: f32) -> Ball {
+    Ball { x, y, vel_x, vel_y, speed: BALL_SPEED }
+}
+
+// A match with both paddles parked out of the way
+fn open_field(ball: Ball) -> Match {
+    let mut game = Match::new();
+    game.left.y = SCREEN_HEIGHT;
+    game.right.y = SCREEN_HEIGHT;
+    game.ball = ball;
+    game
+}
+
+fn arena(obstacles: Vec<Obstacle>, brick_points: bool) -> ArenaState {
+    ArenaState::new(Arena { name: "Test".to_string(), obstacles, ..Arena::empty() }, brick_points)
+}
+
+// Steps the match and the arena until the ball first touches an obstacle
+fn first_bounce(state: &mut ArenaState, game: &mut Match) -> Option<Obstacle> {
+    (0..60 * 3).find_map(|_| {
+        let events = game.step(DT);
+        state.step(game, &events, DT).bounced
+    })
+}
+
+#[test]
+fn blocks_reflect_off_the_face_that_was_hit() {
+    let block = Obstacle::Block { x: 500.0, y: 250.0, w: 20.0, h: 100.0 };
+    let mut state = arena(vec![block], false);
+    let mut game = open_field(ball(400.0, 300.0, BALL_SPEED, 40.0));
+    assert_eq!(first_bounce(&mut state, &mut game), Some(block));
+    assert!(game.ball.vel_x < 0.0);
+    assert!(game.ball.vel_y > 0.0);
+    assert!(game.ball.x + BALL_SIZE <= 500.0 + 0.01);
+
+    // Top face: only the vertical speed flips
+    let mut game = open_field(ball(510.0, 200.0, 100.0, BALL_SPEED));
+    first_bounce(&mut state, &mut game).expect("no bounce");
+    assert!(game.ball.vel_y < 0.0);
+    assert!(game.ball.vel_x > 0.0);
+}
+
+#[test]
+fn bumpers_reflect_radially_and_kick_the_ball() {
+    let bumper = Obstacle::Bumper { x: 500.0, y: 300.0, radius: 30.0 };
+    let mut state = arena(vec![bumper], false);
+    // Straight at the center: straight back, faster
+    let mut game = open_field(ball(400.0, 300.0, BALL_SPEED, 0.0));
+    first_bounce(&mut state, &mut game).expect("no bounce");
+    assert!(game.ball.vel_x < -BALL_SPEED);
+    assert!(game.ball.speed > BALL_SPEED);
+    let (dx, dy) = (game.ball.x - 500.0, game.ball.y - 300.0);
+    assert!(dx.hypot(dy) >= 30.0 + BALL_SIZE - 0.01);
+
+    // Off center, above: deflected upwards
+    let mut game = open_field(ball(400.0, 280.0, BALL_SPEED, 0.0));
+    first_bounce(&mut state, &mut game).expect("no bounce");
+    assert!(game.ball.vel_y < 0.0);
+}
+
+#[test]
+fn deflectors_turn_the_ball_but_keep_it_moving_across() {
+    let deflector = Obstacle::Deflector { x1: 480.0, y1: 240.0, x2: 540.0, y2: 360.0 };
+    let mut state = arena(vec![deflector], false);
+    let mut game = open_field(ball(400.0, 300.0, BALL_SPEED, 0.0));
+    let speed = BALL_SPEED;
+    first_bounce(&mut state, &mut game).expect("no bounce");
+    assert!(game.ball.vel_x < 0.0);
+    assert!(game.ball.vel_y.abs() > 1.0);
+    assert!(game.ball.vel_x.abs() >= 0.3 * speed - 0.01);
+    assert!((game.ball.vel_x.hypot(game.ball.vel_y) - speed).abs() < 0.1);
+}
+
+#[test]
+fn bricks_break_and_score_for_the_last_hitter() {
+    let brick = Obstacle::Brick { x: 500.0, y: 250.0, w: 20.0, h: 100.0, hits: 2 };
+    let center = PADDLE_START_Y + PADDLE_HEIGHT / 2.0;
+
+    for brick_points in [false, true] {
+        let mut state = arena(vec![brick], brick_points);
+        let mut game = Match::new();
+        game.left_score = game.winning_score - 1;
+        // The left paddle sends it to the brick, and gets it back twice over
+        game.ball = ball(100.0, center, -BALL_SPEED, 0.0);
+        let mut broken = None;
+        for _ in 0..60 * 6 {
+            let events = game.step(DT);
+            assert_eq!(events.scored, None);
+            let arena_events = state.step(&mut game, &events, DT);
+            if arena_events.broken.is_some() {
+                broken = Some(arena_events);
+                break;
+            }
+        }
+        let broken = broken.expect("brick did not break");
+        assert!(state.arena.obstacles.is_empty());
+        if brick_points {
+            assert_eq!(broken.brick_point, Some(Side::Left));
+            assert_eq!(broken.winner, Some(Side::Left));
+        } else {
+            assert_eq!(broken.brick_point, None);
+            assert_eq!(game.left_score, game.winning_score - 1);
+        }
+    }
+
+    // Nobody has touched a fresh serve: no point
+    let mut state = arena(vec![brick], true);
+    let mut game = open_field(ball(400.0, 300.0, BALL_SPEED, 0.0));
+    state.arena.obstacles[0] = Obstacle::Brick { x: 500.0, y: 250.0, w: 20.0, h: 100.0, hits: 1 };
+    let events = (0..60).map(|_| {
+        let events = game.step(DT);
+        state.step(&mut game, &events, DT)
+    });
+    assert!(events.filter_map(|events| events.broken.map(|_| events.brick_point)).all(|point| point.is_none()));
+    assert_eq!((game.left_score, game.right_score), (0, 0));
+}
+
+#[test]
+fn matches_on_every_preset_finish() {
+    for preset in Arena::presets() {
+        // The serve spot is clear
+        let mut state = arena(preset.obstacles.clone(), true);
+        let mut still = Match::new();
+        still.ball = ball(BALL_START_X, BALL_START_Y, 0.0, 0.0);
+        assert_eq!(state.step(&mut still, &StepEvents::default(), DT).bounced, None, "{}", preset.name);
+
+        let mut state = ArenaState::new(preset.clone(), true);
+        let mut game = Match::new();
+        let mut rng = Rng::new(11);
+        let winner = (0..60 * 60 * 10).find_map(|_| {
+            game.ai_drive(Side::Left, rng.range(-40.0, 40.0));
+            game.ai_drive(Side::Right, rng.range(-40.0, 40.0));
+            let events = game.step(DT);
+            let arena_events = state.step(&mut game, &events, DT);
+            events.winner.or(arena_events.winner)
+        });
+        assert!(winner.is_some(), "match in {} did not finish", preset.name);
+        assert!(game.ball.x.is_finite() && game.ball.y.is_finite());
+    }
+}
+
+#[test]
+fn a_trapped_ball_is_served_again() {
+    // Two full-height walls the ball can never leave
+    let walls = vec![
+        Obstacle::Block { x: 300.0, y: 0.0, w: 20.0, h: SCREEN_HEIGHT },
+        Obstacle::Block { x: 480.0, y: 0.0, w: 20.0, h: SCREEN_HEIGHT },
+    ];
+    let mut state = arena(walls, false);
+    let mut game = open_field(ball(400.0, 300.0, BALL_SPEED, 60.0));
+    let stalled = (0..60 * 10).position(|_| {
+        let events = game.step(DT);
+        state.step(&mut game, &events, DT).stalled
+    });
+    let steps = stalled.expect("ball was never served again") as f32;
+    assert!((7.5..8.5).contains(&(steps * DT)));
+    assert_eq!((game.ball.x, game.ball.y), (BALL_START_X, BALL_START_Y));
+    assert_eq!((game.left_score, game.right_score), (0, 0));
+}
@@ -0,0 +1,109 @@
+// Arena files: the plain-text format round-trips and rejects bad lines, and
+// the goals and spawn points it describes shape the match.
+
+use pong_rs::arena::{Arena, ArenaState, Obstacle};
+use pong_rs::constants::*;
+use pong_rs::sim::{Ball, Match, Side};
+
+const DT: f32 = 1.0 / 60.0;
+
+const FORTRESS: &str = "
+pong-arena 1
+name Fortress   # comments and blank lines are fine
+
+goal left 200 400
+spawn 400 150
+spawn 400 450
+block 390 0 20 90
+brick 300 100 20 60 2
+bumper 400 300 30
+deflector 200 120 280 170
+";
+
+// Steps the arena around the match, goal walls included, until a point
+fn play_until_scored(state: &mut ArenaState, game: &mut Match) -> Option<Side> {
+    (0..60 * 3).find_map(|_| {
+        state.guard_goals(game, DT);
+        let events = game.step(DT);
+        state.step(game, &events, DT);
+        events.scored
+    })
+}
+
+#[test]
+fn arena_text_round_trips() {
+    let arena = Arena::parse(FORTRESS).expect("valid arena");
+    assert_eq!(arena.name, "Fortress");
+    assert_eq!(arena.goals, [(200.0, 400.0), (0.0, SCREEN_HEIGHT)]);
+    assert_eq!(arena.spawns, vec![(400.0, 150.0), (400.0, 450.0)]);
+    assert_eq!(arena.obstacles.len(), 4);
+    assert_eq!(arena.obstacles[1], Obstacle::Brick { x: 300.0, y: 100.0, w: 20.0, h: 60.0, hits: 2 });
+    assert_eq!(Arena::parse(&arena.to_text()), Ok(arena));
+
+    for preset in Arena::presets() {
+        assert_eq!(Arena::parse(&preset.to_text()).as_ref(), Ok(&preset));
+    }
+}
+
+#[test]
+fn bad_arena_files_are_rejected_with_the_line() {
+    let error = |body: &str| Arena::parse(&format!("pong-arena 1\nname Bad\n{}\n", body)).unwrap_err();
+    assert!(Arena::parse("name Missing header\n").unwrap_err().contains("header"));
+    assert!(error("wall 1 2 3 4").contains("line 3: unknown item wall"));
+    assert!(error("block 1 2 3").contains("line 3: block takes 4 numbers"));
+    assert!(error("bumper 1 two 3").contains("expected numbers"));
+    assert!(error("brick 1 2 3 4 0").contains("at least 1 hit"));
+    assert!(error("goal left 100 120").contains("at least"));
+    assert!(error("goal middle 0 600").contains("left or right"));
+}
+
+#[test]
+fn narrowed_goals_score_only_inside_the_opening() {
+    let arena = Arena { goals: [(200.0, 400.0), (0.0, SCREEN_HEIGHT)], ..Arena::empty() };
+    for (y, scores) in [(100.0, false), (300.0, true), (450.0, false)] {
+        let mut state = ArenaState::new(arena.clone(), false);
+        let mut game = Match::new();
+        game.left.y = if y < 200.0 { SCREEN_HEIGHT } else { 0.0 }; // Out of the way
+        game.ball = Ball { x: 200.0, y, vel_x: -BALL_SPEED * 2.0, vel_y: 0.0, speed: BALL_SPEED * 2.0 };
+        let scored = play_until_scored(&mut state, &mut game);
+        if scores {
+            assert_eq!(scored, Some(Side::Right));
+        } else {
+            // Bounced off the end wall and went on to the other, full-height goal
+            assert_eq!(scored, Some(Side::Left), "ball at y {}", y);
+        }
+    }
+}
+
+#[test]
+fn serves_use_the_spawn_points_in_turn() {
+    let arena = Arena::parse(FORTRESS).unwrap();
+    let mut state = ArenaState::new(Arena { obstacles: Vec::new(), ..arena }, false);
+    let mut game = Match::new();
+    state.serve(&mut game.ball);
+    assert_eq!((game.ball.x, game.ball.y), (400.0, 150.0));
+
+    let mut spots = Vec::new();
+    for _ in 0..3 {
+        game.left.y = SCREEN_HEIGHT;
+        game.right.y = SCREEN_HEIGHT;
+        game.ball = Ball { x: SCREEN_WIDTH - 100.0, y: 300.0, vel_x: BALL_SPEED, vel_y: 0.0, speed: BALL_SPEED };
+        play_until_scored(&mut state, &mut game).expect("no point");
+        spots.push((game.ball.x, game.ball.y));
+    }
+    assert_eq!(spots, vec![(400.0, 450.0), (400.0, 150.0), (400.0, 450.0)]);
+}
+
+#[test]
+fn remove_at_takes_the_newest_item_under_the_point() {
+    let mut arena = Arena::parse(FORTRESS).unwrap();
+    arena.obstacles.push(Obstacle::Block { x: 380.0, y: 280.0, w: 40.0, h: 40.0 });
+    assert!(arena.remove_at(400.0, 300.0));
+    assert_eq!(arena.obstacles.len(), 4);
+    assert!(arena.obstacles.contains(&Obstacle::Bumper { x: 400.0, y: 300.0, radius: 30.0 }));
+    assert!(arena.remove_at(402.0, 152.0));
+    assert_eq!(arena.spawns, vec![(400.0, 450.0)]);
+    assert!(arena.remove_at(240.0, 145.0)); // On the deflector
+    assert!(!arena.remove_at(600.0, 500.0));
+    assert_eq!(arena.obstacles.len(), 3);
+}
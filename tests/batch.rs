@@ -0,0 +1,56 @@
+// Batched matches: every lane must follow the single-match physics exactly,
+// and splitting the lanes over threads must not change a thing.
+
+use pong_rs::batch::{step_match, BatchEnv, Opponent};
+use pong_rs::sim::{Match, Rng};
+
+const DT: f32 = 1.0 / 60.0;
+const LANES: usize = 16;
+
+// Pseudo-random actions for every lane, two per lane
+fn actions(rng: &mut Rng) -> Vec<u8> {
+    (0..LANES * 2).map(|_| (rng.next_u64() % 3) as u8).collect()
+}
+
+#[test]
+fn lanes_match_single_matches_step_for_step() {
+    let mut env = BatchEnv::new(LANES, DT, Opponent::Agent, 7);
+    let mut matches = vec![Match::new(); LANES];
+    let mut unused = Rng::new(0); // Only the built-in AI draws from it
+    let mut action_rng = Rng::new(99);
+    let mut finished = 0;
+
+    for _ in 0..60 * 120 {
+        let actions = actions(&mut action_rng);
+        env.step(&actions);
+        for (i, single) in matches.iter_mut().enumerate() {
+            let events = step_match(single, &mut unused, [actions[i * 2], actions[i * 2 + 1]], DT, Opponent::Agent);
+            if events.winner.is_some() {
+                *single = Match::new();
+                finished += 1;
+                assert_eq!(env.dones()[i], 1);
+            }
+            assert_eq!(env.get(i).checksum(), single.checksum(), "lane {} diverged", i);
+        }
+    }
+    assert!(finished > 0, "no match finished, so resets went untested");
+}
+
+#[test]
+fn threaded_steps_give_the_same_buffers() {
+    let mut single = BatchEnv::new(LANES, DT, Opponent::BuiltinAi, 3);
+    let mut threaded = BatchEnv::new(LANES, DT, Opponent::BuiltinAi, 3).with_threads(4);
+    let mut action_rng = Rng::new(5);
+
+    for _ in 0..60 * 30 {
+        let actions = actions(&mut action_rng);
+        single.step(&actions);
+        threaded.step(&actions);
+        assert_eq!(single.observations(), threaded.observations());
+        assert_eq!(single.rewards(), threaded.rewards());
+        assert_eq!(single.dones(), threaded.dones());
+    }
+    for i in 0..LANES {
+        assert_eq!(single.get(i), threaded.get(i));
+    }
+}
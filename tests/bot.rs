@@ -0,0 +1,94 @@
+// External bots: reply lines are parsed leniently, the state message is
+// from the bot's side of the table, and a slow or silent bot never holds
+// up the game.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use pong_rs::bot::{parse_reply, state_message, BotProcess};
+use pong_rs::constants::*;
+use pong_rs::sim::{Intent, Match, Side};
+
+#[test]
+fn replies_parse_as_json_or_bare_words() {
+    assert_eq!(parse_reply(r#"{"move":"up"}"#), Some((None, Intent::Up)));
+    assert_eq!(parse_reply(r#"  { "tick" : 42, "move" : "down" }  "#), Some((Some(42), Intent::Down)));
+    assert_eq!(parse_reply("stay\n"), Some((None, Intent::Stay)));
+    assert_eq!(parse_reply("down"), Some((None, Intent::Down)));
+
+    for bad in ["", "left", "UP", r#"{"move":"left"}"#, r#"{"mov":"up"}"#, r#"{"move":up}"#, r#"{"move":"up"#] {
+        assert_eq!(parse_reply(bad), None, "{:?} parsed", bad);
+    }
+}
+
+#[test]
+fn state_message_is_from_the_bots_side() {
+    let mut game = Match::new();
+    game.left.y = 10.0;
+    game.right.y = 250.0;
+    game.right.vel = -30.0;
+    game.left_score = 4;
+    game.right_score = 1;
+
+    let message = state_message(&game, Side::Right, 7);
+    assert!(message.starts_with(r#"{"type":"state","tick":7,"#));
+    assert!(message.contains(&format!(r#""you":{{"x":{:.2},"y":250.00,"vel":-30.00}}"#, RIGHT_PADDLE_X)));
+    assert!(message.contains(&format!(r#""opponent":{{"x":{:.2},"y":10.00,"#, LEFT_PADDLE_X)));
+    assert!(message.ends_with(r#""score":{"you":1,"opponent":4}}"#));
+    assert!(!message.contains('\n'));
+
+    let left = state_message(&game, Side::Left, 7);
+    assert!(left.contains(r#""score":{"you":4,"opponent":1}"#));
+}
+
+#[cfg(unix)]
+#[test]
+fn silent_bots_never_block_and_their_moves_time_out() {
+    let game = Match::new();
+
+    // Never answers: the paddle stays put, and asking doesn't wait
+    let mut silent = BotProcess::spawn("cat > /dev/null", Side::Right, Duration::from_millis(50)).unwrap();
+    let start = Instant::now();
+    for _ in 0..10 {
+        assert_eq!(silent.decide(&game), Intent::Stay);
+    }
+    assert!(start.elapsed() < Duration::from_millis(50));
+
+    // Answers once, then goes quiet: the move holds until the timeout
+    let mut once = BotProcess::spawn("read line; echo up; cat > /dev/null", Side::Right, Duration::from_millis(200)).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while once.decide(&game) != Intent::Up {
+        assert!(Instant::now() < deadline, "no move from the bot");
+        thread::sleep(Duration::from_millis(5));
+    }
+    thread::sleep(Duration::from_millis(300));
+    assert_eq!(once.decide(&game), Intent::Stay);
+    assert!(once.is_alive());
+}
+
+#[cfg(unix)]
+#[test]
+fn a_bot_that_never_reads_cannot_freeze_the_game() {
+    // Its stdin pipe fills after a few hundred states; asking must not block
+    let game = Match::new();
+    let mut stuck = BotProcess::spawn("sleep 100", Side::Right, Duration::from_millis(50)).unwrap();
+    let start = Instant::now();
+    for _ in 0..5000 {
+        assert_eq!(stuck.decide(&game), Intent::Stay);
+    }
+    assert!(start.elapsed() < Duration::from_secs(1), "took {:?}", start.elapsed());
+    assert!(stuck.is_alive());
+}
+
+#[cfg(unix)]
+#[test]
+fn a_bot_that_exits_is_reported_dead() {
+    let game = Match::new();
+    let mut quitter = BotProcess::spawn("true", Side::Right, Duration::from_millis(50)).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while quitter.is_alive() {
+        assert!(Instant::now() < deadline, "bot still alive");
+        assert_eq!(quitter.decide(&game), Intent::Stay);
+        thread::sleep(Duration::from_millis(5));
+    }
+}
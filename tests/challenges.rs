@@ -0,0 +1,148 @@
+// Challenges: files describe the setup, goal, limits and star targets; a
+// run is passed or failed from the results so far, and stars earned unlock
+// the later challenges.
+
+use pong_rs::ai::default_profiles;
+use pong_rs::challenge::{Challenge, ChallengeProgress, ChallengeRun, Metric, Outcome, MAX_STARS};
+use pong_rs::constants::*;
+use pong_rs::sim::Side;
+
+const DT: f32 = 1.0 / 60.0;
+
+fn challenge(text: &str) -> Challenge {
+    Challenge::parse(&format!("pong-challenge 1\nname Test\n{}", text)).unwrap()
+}
+
+fn play(run: &mut ChallengeRun, seconds: f32) -> Option<Outcome> {
+    (0..(seconds / DT) as usize).find_map(|_| run.step(DT).outcome)
+}
+
+#[test]
+fn bundled_challenges_round_trip_and_unlock_in_order() {
+    let presets = Challenge::presets();
+    assert!(presets.len() >= 5);
+    assert_eq!(presets[0].unlock, 0);
+    for pair in presets.windows(2) {
+        assert!(pair[0].unlock <= pair[1].unlock);
+    }
+    for preset in &presets {
+        assert_eq!(&Challenge::parse(&preset.to_text()).unwrap(), preset);
+        assert_eq!(preset.check_profiles(&default_profiles()), Ok(()));
+    }
+    // AI opponents are profiles by name
+    let unknown = challenge("opponent ai impossible\ngoal points 1\n");
+    assert!(unknown.check_profiles(&default_profiles()).unwrap_err().contains("impossible"));
+
+    let speed_demon = presets.iter().find(|preset| preset.name == "Speed Demon").unwrap();
+    assert_eq!(speed_demon.ball_speed, MAX_BALL_SPEED);
+    assert_eq!(speed_demon.goal.metric, Metric::Returns);
+}
+
+#[test]
+fn bad_challenge_files_are_rejected_with_the_line() {
+    let bad = [
+        ("pong-challenge 1\ngoal points 3\n", "needs a name"),
+        ("pong-challenge 1\nname X\n", "needs a goal"),
+        ("pong-challenge 1\nname X\ngoal time 30\n", "line 3"),
+        ("pong-challenge 1\nname X\ngoal points 3\nlimit returns 4\n", "line 4"),
+        ("pong-challenge 1\nname X\ngoal points 3\npaddle 5\n", "line 4"),
+        ("pong-challenge 1\nname X\ngoal points 3\nstar time 9\nstar time 8\nstar time 7\n", "line 6"),
+        ("pong-arena 1\nname X\n", "header"),
+    ];
+    for (text, expected) in bad {
+        let err = Challenge::parse(text).unwrap_err();
+        assert!(err.contains(expected), "{:?} gave {:?}", text, err);
+    }
+}
+
+#[test]
+fn the_wall_returns_every_shot_until_the_goal_is_met() {
+    // A full-height paddle can't miss
+    let challenge = challenge("opponent wall\nball_speed 500\npaddle 600\ngoal returns 3\nlimit conceded 0\nstar conceded 0\n");
+    let mut run = ChallengeRun::new(challenge, &default_profiles(), 1);
+    assert_eq!(run.game.ball.speed, MAX_BALL_SPEED);
+    assert_eq!(run.game.ball.vel_x, -MAX_BALL_SPEED);
+
+    let mut wall_hits = 0;
+    let outcome = (0..60 * 20).find_map(|_| {
+        let before = run.game.ball;
+        let events = run.step(DT);
+        if events.step.paddle_hit.is_some_and(|(side, _)| side == Side::Right) {
+            wall_hits += 1;
+            assert_eq!(run.game.ball.vel_x, -before.vel_x);
+            assert_eq!(run.game.ball.speed, before.speed);
+        }
+        events.outcome
+    });
+    assert_eq!(outcome, Some(Outcome::Passed(2)));
+    assert_eq!(run.stats.returns, 3);
+    assert!(wall_hits >= 2);
+    assert_eq!(run.step(DT).outcome, None); // Nothing moves once it's over
+}
+
+#[test]
+fn exceeding_a_limit_fails_the_challenge() {
+    let mut run = ChallengeRun::new(challenge("opponent wall\npaddle 20\ngoal returns 50\nlimit conceded 1\n"), &default_profiles(), 1);
+    run.game.left.y = 0.0; // Well out of the way
+    assert_eq!(play(&mut run, 30.0), Some(Outcome::Failed));
+    assert_eq!(run.stats.conceded, 2);
+
+    let mut run = ChallengeRun::new(challenge("opponent wall\npaddle 600\ngoal returns 500\nlimit time 2\n"), &default_profiles(), 1);
+    assert_eq!(play(&mut run, 5.0), Some(Outcome::Failed));
+    assert!(run.stats.time > 2.0 && run.stats.time < 2.1);
+}
+
+#[test]
+fn only_bank_shots_count_when_bank_only() {
+    let challenge = challenge("opponent ai easy\nopponent_paddle 20\nbank_only\ngoal points 2\n");
+    assert_eq!(challenge.goal.metric, Metric::Banks);
+    let mut run = ChallengeRun::new(challenge, &default_profiles(), 1);
+
+    // Straight past the opponent: the point is taken back
+    run.game.right.y = 0.0;
+    run.game.ball.x = SCREEN_WIDTH - 10.0;
+    run.game.ball.y = SCREEN_HEIGHT / 2.0;
+    run.game.ball.vel_x = 400.0;
+    run.game.ball.vel_y = 0.0;
+    let voided = (0..60).map(|_| run.step(DT)).find(|events| events.step.scored.is_some()).unwrap();
+    assert!(voided.voided);
+    assert_eq!((run.game.left_score, run.stats.points), (0, 0));
+
+    // Off the player's paddle, then off the top wall and in
+    run.game.ball.x = LEFT_PADDLE_X + PADDLE_WIDTH + BALL_SIZE + 1.0;
+    run.game.ball.y = run.game.left.y + run.game.left.height / 2.0;
+    run.game.ball.vel_x = -BALL_SPEED;
+    run.game.ball.vel_y = 0.0;
+    assert!(run.step(DT).step.paddle_hit.is_some());
+    run.game.right.y = SCREEN_HEIGHT - run.game.right.height;
+    run.game.ball.x = 700.0;
+    run.game.ball.y = BALL_SIZE + 5.0;
+    run.game.ball.vel_x = 400.0;
+    run.game.ball.vel_y = -300.0;
+    let mut banked = false;
+    let scored = (0..60).map(|_| run.step(DT)).find(|events| {
+        banked |= events.bank.is_some();
+        events.step.scored.is_some()
+    });
+    assert!(banked);
+    assert!(!scored.unwrap().voided);
+    assert_eq!((run.game.left_score, run.stats.points, run.stats.banks), (1, 1, 1));
+}
+
+#[test]
+fn progress_keeps_the_best_stars_and_unlocks_by_total() {
+    let mut progress = ChallengeProgress::default();
+    let locked = challenge("unlock 4\ngoal points 1\n");
+    assert!(!progress.is_unlocked(&locked));
+
+    assert!(progress.record("Warm Up", 2));
+    assert!(!progress.record("Warm Up", 1));
+    assert!(progress.record("Speed Demon", 9));
+    assert_eq!(progress.stars("Speed Demon"), MAX_STARS);
+    assert_eq!(progress.total(), 2 + MAX_STARS);
+    assert!(progress.is_unlocked(&locked));
+
+    let text = progress.to_text();
+    assert_eq!(ChallengeProgress::parse(&text).unwrap(), progress);
+    assert!(ChallengeProgress::parse("two Warm Up\n").is_err());
+}
@@ -0,0 +1,88 @@
+// Checksum exchange and desync reports: a corrupted input must be caught at
+// exactly the tick it changed the state, with both states in the report.
+
+use pong_rs::batch::{step_match, Opponent};
+use pong_rs::net::{MemoryTransport, Packet, Transport};
+use pong_rs::replay::Replay;
+use pong_rs::rollback::{RollbackSession, NET_DT};
+use pong_rs::sim::{Intent, Match, Rng, Side};
+
+const CORRUPT_FRAME: u32 = 30;
+
+// Rewrites one input on its way out, as a buggy or hostile network might
+struct Corrupting {
+    inner: MemoryTransport,
+    done: bool,
+}
+
+impl Transport for Corrupting {
+    fn send(&mut self, packet: &[u8]) {
+        if let Some(Packet::Input { start, mut inputs, frame, ack, ping, pong, checks_ack, checks_start, checks }) =
+            Packet::decode(packet)
+        {
+            if !self.done && start <= CORRUPT_FRAME && CORRUPT_FRAME < start + inputs.len() as u32 {
+                let input = &mut inputs[(CORRUPT_FRAME - start) as usize];
+                *input = (*input + 1) % 3;
+                self.done = true;
+                let packet = Packet::Input { start, inputs, frame, ack, ping, pong, checks_ack, checks_start, checks };
+                self.inner.send(&packet.encode());
+                return;
+            }
+        }
+        self.inner.send(packet);
+    }
+
+    fn recv(&mut self) -> Option<Vec<u8>> {
+        self.inner.recv()
+    }
+}
+
+#[test]
+fn rollback_peers_report_the_first_divergent_tick() {
+    let (a, b) = MemoryTransport::pair();
+    let a: Box<dyn Transport> = Box::new(Corrupting { inner: a, done: false });
+    let b: Box<dyn Transport> = Box::new(b);
+    let mut peers = [RollbackSession::new(a, Side::Left), RollbackSession::new(b, Side::Right)];
+
+    for tick in 0..600 {
+        let now = tick as f64 * NET_DT as f64;
+        for peer in peers.iter_mut() {
+            peer.poll(now);
+            peer.advance(Intent::Stay, now);
+        }
+        if peers.iter().all(|peer| peer.desync().is_some_and(|report| report.remote.is_some())) {
+            break;
+        }
+    }
+
+    let left = peers[0].desync().expect("left peer missed the desync");
+    let right = peers[1].desync().expect("right peer missed the desync");
+    assert_eq!(left.frame, CORRUPT_FRAME);
+    assert_eq!(right.frame, CORRUPT_FRAME);
+    assert_ne!(left.local, right.local);
+    assert_eq!(left.remote, Some(right.local));
+    assert_eq!(right.remote, Some(left.local));
+    assert!(left.to_text().contains("<--"));
+}
+
+#[test]
+fn replays_pinpoint_the_frame_that_came_out_differently() {
+    let mut replay = Replay::new(7, NET_DT, Opponent::BuiltinAi);
+    let mut game = Match::new();
+    let mut rng = Rng::new(replay.seed);
+    for frame in 0..300u32 {
+        let actions = [(frame / 20 % 3) as u8, 0];
+        step_match(&mut game, &mut rng, actions, replay.dt, replay.opponent);
+        replay.record(actions, &game);
+    }
+
+    let loaded = Replay::from_text(&replay.to_text()).unwrap();
+    assert_eq!(loaded.verify().ok(), Some(game));
+
+    let mut tampered = loaded.clone();
+    tampered.actions[150][0] = (tampered.actions[150][0] + 1) % 3;
+    let report = tampered.verify().unwrap_err();
+    assert_eq!(report.frame, 150);
+    assert_eq!(report.remote_checksum, replay.checksums[150]);
+    assert_ne!(report.local_checksum, report.remote_checksum);
+}
@@ -0,0 +1,99 @@
+// 2v2 doubles: front paddles play incoming balls first, back paddles cover
+// behind them, returns pass through teammates and hits are credited per seat.
+
+use pong_rs::ai;
+use pong_rs::constants::*;
+use pong_rs::doubles::{DoublesAi, DoublesMatch, Seat};
+use pong_rs::sim::{Ball, Rng, Side};
+
+const DT: f32 = 1.0 / 60.0;
+
+// Ball flying straight across at height `y`
+fn flying(towards: Side, y: f32) -> Ball {
+    Ball {
+        x: SCREEN_WIDTH / 2.0,
+        y,
+        vel_x: match towards {
+            Side::Left => -BALL_SPEED,
+            Side::Right => BALL_SPEED,
+        },
+        vel_y: 0.0,
+        speed: BALL_SPEED,
+    }
+}
+
+// The seat of the next paddle hit within 5 s
+fn next_hit(game: &mut DoublesMatch) -> Option<Seat> {
+    (0..60 * 5).find_map(|_| game.step(DT).paddle_hit.map(|(seat, _)| seat))
+}
+
+#[test]
+fn the_front_paddle_plays_first_and_the_back_one_covers() {
+    for side in [Side::Left, Side::Right] {
+        let (front, back) = match side {
+            Side::Left => (Seat::LeftFront, Seat::LeftBack),
+            Side::Right => (Seat::RightFront, Seat::RightBack),
+        };
+        let mut game = DoublesMatch::default();
+        game.ball = flying(side, SCREEN_HEIGHT / 2.0);
+        assert_eq!(next_hit(&mut game), Some(front));
+
+        // Front paddle out of the way: the back one still gets it
+        let mut game = DoublesMatch::default();
+        game.paddles[front as usize].y = 0.0;
+        game.ball = flying(side, SCREEN_HEIGHT / 2.0);
+        assert_eq!(next_hit(&mut game), Some(back));
+        assert_eq!(game.stats[back as usize].hits, 1);
+        assert_eq!(game.stats[front as usize].hits, 0);
+    }
+}
+
+#[test]
+fn returns_pass_through_teammates() {
+    // The left back hits it and it flies right through the left front paddle,
+    // which sits at the same height
+    let mut game = DoublesMatch::default();
+    game.ball = flying(Side::Right, SCREEN_HEIGHT / 2.0);
+    game.ball.x = Seat::LeftBack.x() + 40.0;
+    let hits: Vec<Seat> = (0..2).filter_map(|_| next_hit(&mut game)).collect();
+    assert_eq!(hits.first(), Some(&Seat::RightFront));
+}
+
+#[test]
+fn points_are_credited_to_the_last_touch() {
+    // Left front returns a ball that both right paddles miss
+    let mut game = DoublesMatch::default();
+    game.paddles[Seat::RightFront as usize].y = 0.0;
+    game.paddles[Seat::RightBack as usize].y = 0.0;
+    game.ball = flying(Side::Left, SCREEN_HEIGHT - 100.0);
+    game.paddles[Seat::LeftFront as usize].y = SCREEN_HEIGHT - 100.0 - PADDLE_HEIGHT / 2.0;
+    let scored = (0..60 * 10).find_map(|_| game.step(DT).scored);
+    assert_eq!(scored, Some(Side::Left));
+    assert_eq!(game.stats[Seat::LeftFront as usize].points, 1);
+    assert_eq!(game.stats[Seat::LeftFront as usize].hits, 1);
+}
+
+#[test]
+fn four_ai_players_finish_a_match() {
+    let profiles = ai::default_profiles();
+    let mut players: Vec<DoublesAi> = Seat::ALL
+        .into_iter()
+        .map(|seat| DoublesAi::new(profiles[seat as usize % profiles.len()].params, seat))
+        .collect();
+    let mut rng = Rng::new(3);
+    let mut game = DoublesMatch::default();
+    let winner = (0..60 * 60 * 20).find_map(|_| {
+        for player in players.iter_mut() {
+            let noise = rng.range(-1.0, 1.0);
+            player.drive(&mut game, DT, noise);
+        }
+        game.step(DT).winner
+    });
+
+    let winner = winner.expect("match did not finish");
+    assert_eq!(game.winner(), Some(winner));
+    let points: u32 = game.stats.iter().map(|stats| stats.points).sum();
+    assert!(points as i32 <= game.left_score + game.right_score);
+    let fronts = [Seat::LeftFront, Seat::RightFront].map(|seat| game.stats[seat as usize].hits);
+    assert!(fronts.iter().all(|&hits| hits > 0), "front paddles played: {:?}", game.stats);
+}
@@ -0,0 +1,123 @@
+// Four-player mode: horizontal paddles behave like the classic ones, goals
+// count against the wall they went through, and eliminated walls turn solid.
+
+use pong_rs::ai::{self, AiParams};
+use pong_rs::constants::*;
+use pong_rs::quad::{QuadAi, QuadEvents, QuadMatch, Wall, FIELD_SIZE};
+use pong_rs::sim::{Ball, Intent, Rng};
+
+const DT: f32 = 1.0 / 60.0;
+
+// Ball heading straight for the middle of `wall`
+fn aimed_at(wall: Wall) -> Ball {
+    let (vel_x, vel_y) = match wall {
+        Wall::Left => (-BALL_SPEED, 0.0),
+        Wall::Right => (BALL_SPEED, 0.0),
+        Wall::Top => (0.0, -BALL_SPEED),
+        Wall::Bottom => (0.0, BALL_SPEED),
+    };
+    Ball {
+        x: FIELD_SIZE / 2.0,
+        y: FIELD_SIZE / 2.0,
+        vel_x,
+        vel_y,
+        speed: BALL_SPEED,
+    }
+}
+
+// Steps until `event` returns something, for at most 5 s
+fn step_until<T>(game: &mut QuadMatch, mut event: impl FnMut(QuadEvents) -> Option<T>) -> Option<T> {
+    (0..60 * 5).find_map(|_| event(game.step(DT)))
+}
+
+#[test]
+fn every_wall_returns_a_centered_ball() {
+    for wall in Wall::ALL {
+        let mut game = QuadMatch::new(FOUR_PLAYER_POINT_LIMIT);
+        game.ball = aimed_at(wall);
+        let hit = step_until(&mut game, |events| events.paddle_hit.map(|(hit, _, _)| hit));
+        assert_eq!(hit, Some(wall));
+        assert_eq!(game.conceded, [0; 4]);
+    }
+}
+
+#[test]
+fn paddles_stop_dead_short_of_the_corners() {
+    let mut game = QuadMatch::new(FOUR_PLAYER_POINT_LIMIT);
+    game.paddles[Wall::Top as usize].vel = -MAX_PADDLE_SPEED;
+    game.paddles[Wall::Bottom as usize].vel = MAX_PADDLE_SPEED;
+    for _ in 0..60 {
+        game.step(DT);
+    }
+    let (top, bottom) = (game.paddles[Wall::Top as usize], game.paddles[Wall::Bottom as usize]);
+    assert_eq!((top.vel, bottom.vel), (0.0, 0.0));
+    assert!(top.y > PADDLE_MARGIN && bottom.y + bottom.height < FIELD_SIZE - PADDLE_MARGIN);
+
+    // Pushing into the limit doesn't build up speed either
+    let top = &mut game.paddles[Wall::Top as usize];
+    top.drive(Intent::Up, DT);
+    game.step(DT);
+    assert_eq!(game.paddles[Wall::Top as usize].vel, 0.0);
+}
+
+#[test]
+fn goals_count_against_the_wall_and_knock_players_out() {
+    let mut game = QuadMatch::new(2);
+    // Top paddle as far right as it goes, the ball passes left of it
+    game.paddles[Wall::Top as usize].y = FIELD_SIZE;
+    for goal in 1..=2 {
+        game.ball = aimed_at(Wall::Top);
+        game.ball.x = FIELD_SIZE / 2.0 + PADDLE_HEIGHT / 2.0;
+        assert_eq!(step_until(&mut game, |events| events.scored), Some(Wall::Top));
+        assert_eq!(game.conceded[Wall::Top as usize], goal);
+    }
+    assert!(game.is_out(Wall::Top));
+    assert_eq!(game.standing().count(), 3);
+
+    // The top wall is solid now and sends the ball back at an angle
+    game.ball = aimed_at(Wall::Top);
+    for _ in 0..90 {
+        assert_eq!(game.step(DT).scored, None);
+    }
+    assert!(game.ball.vel_y > 0.0);
+    assert!(game.ball.vel_x.abs() > 0.0);
+    assert_eq!(game.conceded[Wall::Top as usize], 2);
+}
+
+#[test]
+fn ai_players_play_down_to_one_winner() {
+    let profiles = ai::default_profiles();
+    let params: Vec<AiParams> = profiles.iter().map(|profile| profile.params).collect();
+    let mut ais: Vec<QuadAi> = Wall::ALL
+        .into_iter()
+        .enumerate()
+        .map(|(index, wall)| QuadAi::new(params[index % params.len()], wall))
+        .collect();
+    let mut rng = Rng::new(7);
+    let mut game = QuadMatch::new(3);
+    let mut eliminated = Vec::new();
+    let mut winner = None;
+    for _ in 0..60 * 60 * 20 {
+        for ai in ais.iter_mut() {
+            let noise = rng.range(-1.0, 1.0);
+            ai.drive(&mut game, DT, noise);
+        }
+        let events = game.step(DT);
+        if let Some(wall) = events.eliminated {
+            eliminated.push(wall);
+        }
+        if events.winner.is_some() {
+            winner = events.winner;
+            break;
+        }
+    }
+
+    let winner = winner.expect("match did not finish");
+    assert_eq!(eliminated.len(), 3);
+    assert!(!eliminated.contains(&winner));
+    assert_eq!(game.winner(), Some(winner));
+    assert!(game.conceded[winner as usize] < 3);
+    for wall in eliminated {
+        assert_eq!(game.conceded[wall as usize], 3, "{} kept conceding after going out", wall.name());
+    }
+}
@@ -0,0 +1,88 @@
+// Gauntlet: opponents get sharper stage by stage, losses cost lives, and
+// the score rewards points, opponents beaten and quick wins.
+
+use pong_rs::ai::{AiController, AiParams};
+use pong_rs::gauntlet::{Gauntlet, MatchResult, LIVES, MATCH_POINTS, OPPONENTS};
+use pong_rs::sim::{Match, Rng, Side};
+
+const DT: f32 = 1.0 / 60.0;
+
+#[test]
+fn each_opponent_is_sharper_than_the_last() {
+    let mut gauntlet = Gauntlet::new();
+    let mut previous: Option<AiParams> = None;
+    for _ in 0..OPPONENTS.len() {
+        let params = gauntlet.opponent_params();
+        if let Some(previous) = previous {
+            assert!(params.speed > previous.speed);
+            assert!(params.prediction > previous.prediction);
+            assert!(params.error < previous.error);
+            assert!(params.reaction_time < previous.reaction_time || params.reaction_time == 0.0);
+        }
+        previous = Some(params);
+        gauntlet.finish_match(MATCH_POINTS, true);
+    }
+    assert_eq!(gauntlet.result, Some(MatchResult::Victory));
+    assert!(gauntlet.is_over());
+}
+
+#[test]
+fn losing_costs_a_life_and_replays_the_same_opponent() {
+    let mut gauntlet = Gauntlet::new();
+    assert_eq!(gauntlet.finish_match(MATCH_POINTS, true), MatchResult::Advanced);
+    let opponent = gauntlet.opponent();
+    for life in (1..LIVES).rev() {
+        assert_eq!(gauntlet.finish_match(1, false), MatchResult::LifeLost);
+        assert_eq!(gauntlet.lives, life);
+        assert_eq!(gauntlet.opponent(), opponent);
+        assert!(!gauntlet.is_over());
+    }
+    assert_eq!(gauntlet.finish_match(0, false), MatchResult::Out);
+    assert!(gauntlet.is_over());
+    assert_eq!(gauntlet.points, MATCH_POINTS as u32 + LIVES - 1);
+}
+
+#[test]
+fn quicker_wins_score_more() {
+    let run = |seconds: f32| {
+        let mut gauntlet = Gauntlet::new();
+        for _ in 0..(seconds / DT) as usize {
+            gauntlet.tick(DT);
+        }
+        gauntlet.finish_match(MATCH_POINTS, true);
+        gauntlet
+    };
+    let (quick, slow, slower) = (run(20.0), run(50.0), run(300.0));
+    assert!(quick.score() > slow.score());
+    assert!(slow.score() > slower.score());
+    assert!((quick.elapsed - 20.0).abs() < 0.1);
+    assert_eq!(quick.match_time, 0.0);
+
+    // Past the par time only the points and the stage count; a pointless
+    // loss counts for nothing
+    let mut lost = Gauntlet::new();
+    lost.finish_match(0, false);
+    assert_eq!(lost.score(), 0);
+    assert_eq!(run(600.0).score(), slower.score());
+}
+
+#[test]
+fn matches_against_every_opponent_finish_at_three() {
+    let mut gauntlet = Gauntlet::new();
+    let mut rng = Rng::new(3);
+    while !gauntlet.is_over() {
+        let mut player = AiController::new(AiParams::classic(), Side::Left);
+        let mut opponent = AiController::new(gauntlet.opponent_params(), Side::Right);
+        let mut game = Match { winning_score: MATCH_POINTS, ..Match::new() };
+        let winner = (0..60 * 60 * 10).find_map(|_| {
+            player.drive(&mut game, DT, rng.range(-1.0, 1.0));
+            opponent.drive(&mut game, DT, rng.range(-1.0, 1.0));
+            gauntlet.tick(DT);
+            game.step(DT).winner
+        });
+        let winner = winner.expect("match did not finish");
+        assert_eq!(game.left_score.max(game.right_score), MATCH_POINTS);
+        gauntlet.finish_match(game.left_score, winner == Side::Left);
+    }
+    assert!(gauntlet.elapsed > 0.0);
+}
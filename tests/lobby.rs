@@ -0,0 +1,360 @@
+// LAN lobby: seats, rule changes, ready checks and chat over lossy links,
+// then the room's match on the same connections and back to the lobby.
+// Players who drop out mid-match rejoin it from a new address.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use pong_rs::ai;
+use pong_rs::authority::ServerClient;
+use pong_rs::constants::*;
+use pong_rs::lobby::{LobbyClient, LobbyServer, Rules};
+use pong_rs::net::{MemoryTransport, Packet, Transport, MAX_PACKET};
+use pong_rs::netsim::{NetConditions, NetSimulator};
+use pong_rs::rollback::NET_DT;
+use pong_rs::sim::{Intent, Match, Side};
+
+type Link = NetSimulator<MemoryTransport>;
+
+fn lossy() -> NetConditions {
+    NetConditions::parse("latency=30,jitter=20,loss=0.3,duplicate=0.1,reorder=0.1").unwrap()
+}
+
+// A player who is either in the lobby or in the room's match
+enum Player {
+    Lobby(LobbyClient<Link>),
+    Playing(ServerClient<Link>),
+}
+
+struct Harness {
+    clock: Rc<Cell<f64>>,
+    tick: u32,
+    server: LobbyServer,
+    links: Vec<Link>,
+}
+
+impl Harness {
+    fn new(rooms: usize) -> Self {
+        Self {
+            clock: Rc::new(Cell::new(0.0)),
+            tick: 0,
+            server: LobbyServer::new(rooms, ai::default_profiles()),
+            links: Vec::new(),
+        }
+    }
+
+    fn connect(&mut self, name: &str) -> Player {
+        Player::Lobby(LobbyClient::new(self.link(), name))
+    }
+
+    // A new connection; returns the client's end
+    fn link(&mut self) -> Link {
+        let (a, b) = MemoryTransport::pair();
+        let seed = self.links.len() as u64 * 2 + 1;
+        let (clock_a, clock_b) = (self.clock.clone(), self.clock.clone());
+        self.links.push(NetSimulator::new(a, lossy(), seed, move || clock_a.get()));
+        NetSimulator::new(b, lossy(), seed + 1, move || clock_b.get())
+    }
+
+    // One server tick; players in a match follow the ball
+    fn step(&mut self, players: &mut [Player]) {
+        let now = self.tick as f64 * NET_DT as f64;
+        self.clock.set(now);
+        for player in players.iter_mut() {
+            match player {
+                Player::Lobby(client) => client.poll(now),
+                Player::Playing(client) => {
+                    client.poll(now);
+                    let (view, _) = client.view(now);
+                    let intent = client.side().map_or(Intent::Stay, |side| follow(&view, side));
+                    client.advance(intent, now);
+                }
+            }
+        }
+        for (connection, link) in self.links.iter_mut().enumerate() {
+            while let Some(data) = link.recv() {
+                self.server.receive(connection, &data);
+            }
+        }
+        self.server.tick();
+        for (connection, packet) in self.server.take_outgoing() {
+            self.links[connection].send(&packet);
+        }
+        self.tick += 1;
+    }
+
+    fn wait(&mut self, players: &mut [Player], seconds: f32) {
+        for _ in 0..(seconds / NET_DT) as u32 {
+            self.step(players);
+        }
+    }
+
+    fn run_until(&mut self, players: &mut [Player], seconds: f32, mut done: impl FnMut(&[Player]) -> bool) {
+        for _ in 0..(seconds / NET_DT) as u32 {
+            self.step(players);
+            if done(players) {
+                return;
+            }
+        }
+        panic!("timed out after {} s", seconds);
+    }
+}
+
+fn follow(game: &Match, side: Side) -> Intent {
+    let center = game.paddle(side).y + PADDLE_HEIGHT / 2.0;
+    if game.ball.y < center - 15.0 {
+        Intent::Up
+    } else if game.ball.y > center + 15.0 {
+        Intent::Down
+    } else {
+        Intent::Stay
+    }
+}
+
+fn lobby(player: &mut Player) -> &mut LobbyClient<Link> {
+    match player {
+        Player::Lobby(client) => client,
+        Player::Playing(_) => panic!("player is in a match"),
+    }
+}
+
+// Hand every lobby client whose match started over to a `ServerClient`
+fn start_matches(players: &mut [Player], names: &[&str]) {
+    for (player, name) in players.iter_mut().zip(names) {
+        if matches!(player, Player::Lobby(client) if client.match_started()) {
+            let Player::Lobby(client) = std::mem::replace(player, Player::Lobby(LobbyClient::new(dummy(), ""))) else {
+                unreachable!()
+            };
+            *player = Player::Playing(ServerClient::new(client.into_transport(), name));
+        }
+    }
+}
+
+fn dummy() -> Link {
+    NetSimulator::new(MemoryTransport::pair().0, NetConditions::default(), 0, || 0.0)
+}
+
+#[test]
+fn players_agree_on_rules_chat_ready_up_and_play() {
+    let mut harness = Harness::new(2);
+    let mut players = [harness.connect("Ada"), harness.connect("Bob")];
+
+    // Both see the room list and sit at the second table; Bob asks for the left side
+    harness.run_until(&mut players, 5.0, |players| {
+        players.iter().all(|p| matches!(p, Player::Lobby(c) if c.rooms().len() == 2))
+    });
+    lobby(&mut players[0]).enter(1);
+    harness.run_until(&mut players, 5.0, |players| matches!(&players[0], Player::Lobby(c) if c.room().is_some()));
+    lobby(&mut players[1]).enter(1);
+    lobby(&mut players[1]).set_side(Side::Left);
+    harness.run_until(&mut players, 5.0, |players| {
+        players.iter().all(|p| matches!(p, Player::Lobby(c) if c.room().is_some_and(|room| room.members.len() == 2)))
+    });
+    let room = lobby(&mut players[1]).room().unwrap().clone();
+    assert_eq!(room.members[0].side, Side::Right, "Ada had no preference and moved over");
+    assert_eq!(room.members[1].side, Side::Left);
+
+    // Ada readies, then Bob changes the rules, which needs everyone to agree again
+    lobby(&mut players[0]).set_ready(true);
+    harness.run_until(&mut players, 5.0, |players| {
+        matches!(&players[1], Player::Lobby(c) if c.room().is_some_and(|room| room.members[0].ready))
+    });
+    let rules = Rules {
+        winning_score: 2,
+        ai_fill: None,
+    };
+    lobby(&mut players[1]).set_rules(rules.clone());
+    harness.run_until(&mut players, 5.0, |players| {
+        matches!(&players[0], Player::Lobby(c) if c.room().is_some_and(|room| room.rules == rules))
+    });
+    assert!(!lobby(&mut players[0]).is_ready(), "rule change resets readiness");
+    assert_eq!(lobby(&mut players[0]).rules(), &rules);
+
+    // Chat arrives exactly once and in order despite loss and duplication
+    for i in 0..5 {
+        lobby(&mut players[0]).say(&format!("message {}", i));
+    }
+    lobby(&mut players[1]).say("hi");
+    harness.run_until(&mut players, 10.0, |players| {
+        players.iter().all(|p| matches!(p, Player::Lobby(c) if c.chat().iter().filter(|line| !line.starts_with('*')).count() == 6))
+    });
+    for player in players.iter_mut() {
+        let said: Vec<&String> = lobby(player).chat().iter().filter(|line| line.starts_with("Ada:")).collect();
+        let expected: Vec<String> = (0..5).map(|i| format!("Ada: message {}", i)).collect();
+        assert_eq!(said, expected.iter().collect::<Vec<_>>());
+    }
+    assert!(lobby(&mut players[0]).chat().contains(&"Bob: hi".to_string()));
+
+    // Nothing starts until both are ready
+    lobby(&mut players[0]).set_ready(true);
+    harness.wait(&mut players, 2.0);
+    assert!(harness.server.game(1).is_none());
+    lobby(&mut players[1]).set_ready(true);
+    harness.run_until(&mut players, 5.0, |players| players.iter().all(|p| matches!(p, Player::Lobby(c) if c.match_started())));
+
+    // Play the match to two points on the same connections
+    start_matches(&mut players, &["Ada", "Bob"]);
+    let game = *harness.server.game(1).expect("match started");
+    assert_eq!(game.winning_score, 2);
+    harness.run_until(&mut players, 120.0, |players| {
+        players.iter().all(|p| matches!(p, Player::Playing(c) if c.server_state().is_some_and(|s| s.winner().is_some())))
+    });
+    let Player::Playing(bob) = &players[1] else { unreachable!() };
+    let result = *bob.server_state().unwrap();
+    assert_eq!(result.left_score.max(result.right_score), 2);
+    assert_eq!(bob.side(), Some(Side::Left));
+
+    // Back to the lobby: same seats, not ready, room open again
+    for player in players.iter_mut() {
+        let Player::Playing(client) = std::mem::replace(player, Player::Lobby(LobbyClient::new(dummy(), ""))) else {
+            unreachable!()
+        };
+        let mut client = LobbyClient::new(client.into_transport(), "");
+        client.enter(1);
+        *player = Player::Lobby(client);
+    }
+    harness.run_until(&mut players, 10.0, |players| {
+        players
+            .iter()
+            .all(|p| matches!(p, Player::Lobby(c) if c.room().is_some_and(|room| !room.playing && room.members.len() == 2)))
+    });
+    assert!(harness.server.game(1).is_none());
+    assert!(players.iter_mut().all(|p| !lobby(p).match_started()));
+}
+
+#[test]
+fn a_lone_player_can_play_the_ai_fill_in() {
+    let mut harness = Harness::new(1);
+    let mut players = [harness.connect("Cy")];
+    lobby(&mut players[0]).enter(0);
+    harness.run_until(&mut players, 5.0, |players| matches!(&players[0], Player::Lobby(c) if c.room().is_some()));
+
+    // Ready alone without a fill-in: keeps waiting for an opponent
+    lobby(&mut players[0]).set_ready(true);
+    harness.wait(&mut players, 2.0);
+    assert!(harness.server.game(0).is_none());
+
+    let rules = Rules {
+        winning_score: 1,
+        ai_fill: Some("hard".to_string()),
+    };
+    lobby(&mut players[0]).set_rules(rules);
+    harness.run_until(&mut players, 5.0, |players| {
+        matches!(&players[0], Player::Lobby(c) if c.room().is_some_and(|room| room.rules.ai_fill.is_some()))
+    });
+    lobby(&mut players[0]).set_ready(true);
+    harness.run_until(&mut players, 5.0, |players| matches!(&players[0], Player::Lobby(c) if c.match_started()));
+    start_matches(&mut players, &["Cy"]);
+    harness.run_until(&mut players, 60.0, |players| {
+        matches!(&players[0], Player::Playing(c) if c.server_state().is_some_and(|s| s.winner().is_some()))
+    });
+}
+
+#[test]
+fn a_full_chat_history_still_fits_a_packet() {
+    // Widest names and messages there are, sent raw so only the server trims them
+    let mut server = LobbyServer::new(1, ai::default_profiles());
+    let wide = "\u{1D11E}";
+    for chat_seq in 1..=10 {
+        for connection in 0..2 {
+            let seat = Packet::Seat {
+                room: 0,
+                name: wide.repeat(40),
+                side: None,
+                ready_for: 0,
+                rules_base: 0,
+                rules: Rules::default(),
+                chat_seq,
+                chat: format!("\u{7}{}\r\n{}", wide.repeat(40), wide.repeat(40)),
+            };
+            server.receive(connection, &seat.encode());
+        }
+    }
+    server.tick();
+
+    let outgoing = server.take_outgoing();
+    let (_, largest) = outgoing.iter().max_by_key(|(_, data)| data.len()).unwrap();
+    assert!(largest.len() <= MAX_PACKET, "{} bytes", largest.len());
+    let Some(Packet::Room { chat, .. }) = Packet::decode(largest) else {
+        panic!("not a room update");
+    };
+    assert_eq!(chat.len(), 8);
+    for (_, line) in &chat {
+        assert!(line.starts_with(&wide.repeat(16)), "{:?}", line);
+        assert!(!line.contains(char::is_control), "{:?}", line);
+    }
+}
+
+#[test]
+fn connections_are_forgotten_once_nothing_uses_them() {
+    let mut harness = Harness::new(1);
+    let mut players = [harness.connect("Ada"), harness.connect("Bob")];
+    lobby(&mut players[0]).enter(0);
+    harness.run_until(&mut players, 5.0, |players| matches!(&players[0], Player::Lobby(c) if c.room().is_some()));
+    assert!(harness.server.knows(0));
+    assert!(!harness.server.knows(1), "browsing the rooms holds nothing");
+
+    // Ada goes quiet and loses the seat, and with it the connection
+    harness.wait(&mut players[1..], 12.0);
+    assert!(!harness.server.knows(0));
+}
+
+#[test]
+fn a_dropped_player_rejoins_the_room_match_from_a_new_address() {
+    let mut harness = Harness::new(1);
+    let mut players = [harness.connect("Ada"), harness.connect("Bob")];
+    for player in players.iter_mut() {
+        lobby(player).enter(0);
+    }
+    harness.run_until(&mut players, 5.0, |players| {
+        players.iter().all(|p| matches!(p, Player::Lobby(c) if c.room().is_some_and(|room| room.members.len() == 2)))
+    });
+    for player in players.iter_mut() {
+        lobby(player).set_ready(true);
+    }
+    harness.run_until(&mut players, 5.0, |players| players.iter().all(|p| matches!(p, Player::Lobby(c) if c.match_started())));
+    start_matches(&mut players, &["Ada", "Bob"]);
+    harness.wait(&mut players, 3.0);
+
+    // Bob's Wi-Fi drops and the old address goes dark; the match freezes
+    harness.links[1] = dummy();
+    harness.wait(&mut players[..1], NET_DISCONNECT_TIMEOUT + 1.0);
+    let frozen = *harness.server.game(0).expect("match still on");
+    harness.wait(&mut players[..1], 3.0);
+    assert_eq!(*harness.server.game(0).unwrap(), frozen);
+
+    // Bob rejoins from a new address and play picks up from the frozen state
+    let link = harness.link();
+    let Player::Playing(bob) = &mut players[1] else { unreachable!() };
+    bob.reconnect(link);
+    for _ in 0..(5.0 / NET_DT) as u32 {
+        harness.step(&mut players);
+        if *harness.server.game(0).unwrap() != frozen {
+            break;
+        }
+    }
+    assert_ne!(*harness.server.game(0).unwrap(), frozen, "match did not resume");
+    // The old address's id is free for someone else
+    assert!(!harness.server.knows(1));
+    assert!(harness.server.knows(2));
+    harness.run_until(&mut players, 120.0, |players| {
+        players.iter().all(|p| matches!(p, Player::Playing(c) if c.server_state().is_some_and(|s| s.winner().is_some())))
+    });
+    let Player::Playing(ada) = &players[0] else { unreachable!() };
+    assert_eq!(ada.forfeited(), None);
+
+    // Bob's seat in the lobby moved to the new address too
+    for player in players.iter_mut() {
+        let Player::Playing(client) = std::mem::replace(player, Player::Lobby(LobbyClient::new(dummy(), ""))) else {
+            unreachable!()
+        };
+        let mut client = LobbyClient::new(client.into_transport(), "");
+        client.enter(0);
+        *player = Player::Lobby(client);
+    }
+    harness.run_until(&mut players, 10.0, |players| {
+        players
+            .iter()
+            .all(|p| matches!(p, Player::Lobby(c) if c.room().is_some_and(|room| !room.playing && room.members.len() == 2)))
+    });
+}
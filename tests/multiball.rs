@@ -0,0 +1,116 @@
+// Multi-ball: every ball speeds up and scores on its own, the ball rules
+// add balls as promised, and the AI defends against the nearest threat.
+
+use pong_rs::ai::AiParams;
+use pong_rs::constants::*;
+use pong_rs::multiball::{BallRule, MultiBallAi, MultiBallMatch};
+use pong_rs::sim::{Ball, Rng, Side};
+
+const DT: f32 = 1.0 / 60.0;
+
+// Ball flying straight across from `x` at height `y`
+fn flying(towards: Side, x: f32, y: f32) -> Ball {
+    Ball {
+        x,
+        y,
+        vel_x: match towards {
+            Side::Left => -BALL_SPEED,
+            Side::Right => BALL_SPEED,
+        },
+        vel_y: 0.0,
+        speed: BALL_SPEED,
+    }
+}
+
+#[test]
+fn balls_score_independently_and_fixed_counts_are_refilled() {
+    let mut game = MultiBallMatch::new(BallRule::Fixed(2), WINNING_SCORE);
+    game.left.y = SCREEN_HEIGHT; // Both paddles out of the way
+    game.right.y = SCREEN_HEIGHT;
+    game.balls = vec![
+        flying(Side::Left, 60.0, 100.0),
+        flying(Side::Right, SCREEN_WIDTH - 60.0, 100.0),
+    ];
+    for _ in 0..30 {
+        game.step(DT);
+    }
+    assert_eq!((game.left_score, game.right_score), (1, 1));
+    assert_eq!(game.balls.len(), 2);
+}
+
+#[test]
+fn each_ball_keeps_its_own_speed() {
+    let mut game = MultiBallMatch::new(BallRule::Fixed(2), WINNING_SCORE);
+    let center = PADDLE_START_Y + PADDLE_HEIGHT / 2.0;
+    game.balls = vec![
+        flying(Side::Left, 100.0, center),
+        flying(Side::Right, SCREEN_WIDTH / 2.0 - 100.0, 40.0),
+    ];
+    let hit = (0..60).find_map(|_| game.step(DT).paddle_hit);
+    assert_eq!(hit.map(|(side, _)| side), Some(Side::Left));
+    assert_eq!(game.balls[0].speed, BALL_SPEED * BALL_SPEED_INCREASE);
+    assert_eq!(game.balls[1].speed, BALL_SPEED);
+}
+
+#[test]
+fn timer_and_rally_rules_add_balls() {
+    // A ball bouncing between the paddle centers, so no point resets the timer
+    let center = PADDLE_START_Y + PADDLE_HEIGHT / 2.0;
+    let mut game = MultiBallMatch::new(BallRule::Timer(2.0), WINNING_SCORE);
+    assert_eq!(game.balls.len(), 1);
+    game.balls = vec![flying(Side::Left, 100.0, center)];
+    let spawned = (0..60 * 3).position(|_| game.step(DT).spawned);
+    let steps = spawned.expect("no ball was added");
+    assert!((115..125).contains(&steps), "added after {} steps", steps);
+    assert_eq!(game.balls.len(), 2);
+
+    // The same ball, two hits
+    let mut game = MultiBallMatch::new(BallRule::Rally(2), WINNING_SCORE);
+    game.balls = vec![flying(Side::Left, 100.0, center)];
+    let mut hits = 0;
+    while hits < 2 {
+        let events = game.step(DT);
+        hits += events.paddle_hit.is_some() as u32;
+        assert!(events.scored.is_none());
+        assert_eq!(events.spawned, hits == 2 && events.paddle_hit.is_some());
+    }
+    assert_eq!(game.balls.len(), 2);
+}
+
+#[test]
+fn ai_defends_against_the_ball_that_arrives_first() {
+    let params = AiParams {
+        reaction_time: 0.0,
+        error: 0.0,
+        ..AiParams::classic()
+    };
+    let mut ai = MultiBallAi::new(params, Side::Right);
+    let mut game = MultiBallMatch::new(BallRule::Fixed(3), WINNING_SCORE);
+    game.balls = vec![
+        flying(Side::Right, SCREEN_WIDTH / 2.0, SCREEN_HEIGHT - 60.0), // Further away
+        flying(Side::Right, SCREEN_WIDTH - 250.0, 60.0),               // The threat
+        flying(Side::Left, SCREEN_WIDTH - 100.0, SCREEN_HEIGHT / 2.0), // Heading away
+    ];
+    for _ in 0..30 {
+        ai.drive(&mut game, DT, 0.0);
+        game.step(DT);
+    }
+    assert!(game.right.y < PADDLE_START_Y - 50.0, "paddle at {}", game.right.y);
+}
+
+#[test]
+fn ai_players_finish_a_match() {
+    let params = AiParams::classic();
+    let mut players = [MultiBallAi::new(params, Side::Left), MultiBallAi::new(params, Side::Right)];
+    let mut rng = Rng::new(5);
+    let mut game = MultiBallMatch::new(BallRule::Rally(4), WINNING_SCORE);
+    let winner = (0..60 * 60 * 10).find_map(|_| {
+        for player in players.iter_mut() {
+            let noise = rng.range(-1.0, 1.0);
+            player.drive(&mut game, DT, noise);
+        }
+        game.step(DT).winner
+    });
+    assert_eq!(winner.expect("match did not finish"), game.winner().unwrap());
+    assert!(game.left_score.max(game.right_score) == WINNING_SCORE);
+}
@@ -0,0 +1,416 @@
+// Scripted online matches over an in-memory link with simulated bad network
+// conditions. Whatever the network does, both ends must agree on the result.
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use pong_rs::authority::{AuthorityServer, ServerClient, Spectator};
+use pong_rs::constants::*;
+use pong_rs::net::{MemoryTransport, Packet, Transport, UdpTransport};
+use pong_rs::netsim::{NetConditions, NetSimulator};
+use pong_rs::rollback::{RollbackSession, NET_DT};
+use pong_rs::sim::{Intent, Match, Side};
+
+const MAX_TICKS: u32 = 60 * 60 * 10;
+
+fn bad_network() -> NetConditions {
+    NetConditions::parse("latency=60,jitter=40,loss=0.15,reorder=0.1,duplicate=0.05").unwrap()
+}
+
+// Follows the ball, but hesitates now and then so input changes often and
+// the other side's predictions are regularly wrong
+fn script(game: &Match, side: Side, tick: u32) -> Intent {
+    if (tick / 9 + side as u32).is_multiple_of(4) {
+        return Intent::Stay;
+    }
+    let center = game.paddle(side).y + PADDLE_HEIGHT / 2.0;
+    if game.ball.y < center - 15.0 {
+        Intent::Up
+    } else if game.ball.y > center + 15.0 {
+        Intent::Down
+    } else {
+        Intent::Stay
+    }
+}
+
+type Impaired = NetSimulator<MemoryTransport>;
+
+// A link whose both ends see `conditions` in each direction, on a shared manual clock
+fn impaired_link(conditions: NetConditions, seed: u64, clock: &Rc<Cell<f64>>) -> (Impaired, Impaired) {
+    let (a, b) = MemoryTransport::pair();
+    let (clock_a, clock_b) = (clock.clone(), clock.clone());
+    (
+        NetSimulator::new(a, conditions, seed, move || clock_a.get()),
+        NetSimulator::new(b, conditions, seed + 1, move || clock_b.get()),
+    )
+}
+
+fn scores(game: &Match) -> (i32, i32) {
+    (game.left_score, game.right_score)
+}
+
+fn play_rollback(conditions: NetConditions, seed: u64) -> ((i32, i32), (i32, i32), u32) {
+    let clock = Rc::new(Cell::new(0.0));
+    let (a, b) = impaired_link(conditions, seed, &clock);
+    let mut peers = [RollbackSession::new(a, Side::Left), RollbackSession::new(b, Side::Right)];
+    let mut results: [Option<(i32, i32)>; 2] = [None, None];
+
+    for tick in 0..MAX_TICKS {
+        let now = tick as f64 * NET_DT as f64;
+        clock.set(now);
+        for (peer, result) in peers.iter_mut().zip(results.iter_mut()) {
+            peer.poll(now);
+            let intent = script(peer.state(), peer.side(), tick);
+            peer.advance(intent, now);
+            // Record the first confirmed result; the simulation keeps running after it
+            if result.is_none() && peer.confirmed_state().winner().is_some() {
+                *result = Some(scores(peer.confirmed_state()));
+            }
+        }
+        assert!(peers.iter().all(|peer| peer.desync().is_none()), "checksums diverged at tick {}", tick);
+        if let [Some(left), Some(right)] = results {
+            return (left, right, peers[0].rollbacks() + peers[1].rollbacks());
+        }
+    }
+    panic!("rollback match did not finish: {:?}", results);
+}
+
+#[test]
+fn rollback_peers_agree_on_a_perfect_network() {
+    let (left, right, _) = play_rollback(NetConditions::default(), 1);
+    assert_eq!(left, right);
+}
+
+#[test]
+fn rollback_peers_agree_under_bad_conditions() {
+    for seed in [3, 17] {
+        let (left, right, rollbacks) = play_rollback(bad_network(), seed);
+        assert_eq!(left, right, "seed {}", seed);
+        assert!(left.0 == WINNING_SCORE || left.1 == WINNING_SCORE);
+        assert!(rollbacks > 0, "the network should have forced rollbacks");
+    }
+}
+
+#[test]
+fn server_clients_and_spectators_agree_under_bad_conditions() {
+    const SPECTATOR_JOINS: u32 = 60 * 10;
+
+    let clock = Rc::new(Cell::new(0.0));
+    let (server_left, client_left) = impaired_link(bad_network(), 5, &clock);
+    let (server_right, client_right) = impaired_link(bad_network(), 9, &clock);
+    let (server_watch, client_watch) = impaired_link(bad_network(), 13, &clock);
+    let mut links = [server_left, server_right, server_watch];
+    let mut clients = [ServerClient::new(client_left, "Ada"), ServerClient::new(client_right, "Bob")];
+    let mut spectator = Spectator::new(client_watch);
+    let mut spectator_caught_up = None;
+    let mut server = AuthorityServer::new();
+
+    // Play until the server has a winner, then give the final (and for the
+    // spectator, delayed) snapshots time to arrive
+    let mut finished_at = None;
+    for tick in 0..MAX_TICKS {
+        let now = tick as f64 * NET_DT as f64;
+        clock.set(now);
+        for client in clients.iter_mut() {
+            client.poll(now);
+            let (view, _) = client.view(now);
+            let intent = client.side().map_or(Intent::Stay, |side| script(&view, side, tick));
+            client.advance(intent, now);
+        }
+        if tick >= SPECTATOR_JOINS {
+            spectator.poll(now);
+            spectator.view(now);
+            if spectator.is_running() && spectator_caught_up.is_none() {
+                spectator_caught_up = Some(tick);
+            }
+        }
+        for (connection, link) in links.iter_mut().enumerate() {
+            while let Some(data) = link.recv() {
+                server.receive(connection, &data);
+            }
+        }
+        server.tick();
+        for (connection, packet) in server.take_outgoing() {
+            links[connection].send(&packet);
+        }
+
+        if server.state().winner().is_some() && finished_at.is_none() {
+            finished_at = Some(tick);
+        }
+        if finished_at.is_some_and(|finished| tick as f32 > finished as f32 + (SPECTATOR_DELAY + 2.0) / NET_DT) {
+            break;
+        }
+    }
+
+    assert!(server.state().winner().is_some(), "server match did not finish");
+    for client in &clients {
+        let state = client.server_state().expect("client never got a snapshot");
+        assert_eq!(scores(state), scores(server.state()));
+    }
+
+    // The late spectator starts from a snapshot instead of the beginning
+    let caught_up = spectator_caught_up.expect("spectator never got a snapshot");
+    assert!(caught_up < SPECTATOR_JOINS + 60, "spectator took {} ticks to catch up", caught_up - SPECTATOR_JOINS);
+    let now = clock.get();
+    assert_eq!(scores(&spectator.view(now).0), scores(server.state()));
+    let mut names = [spectator.name(Side::Left), spectator.name(Side::Right)];
+    names.sort();
+    assert_eq!(names, ["Ada", "Bob"]);
+    assert_eq!(spectator.spectators(), 1);
+}
+
+// Two server clients whose connections can drop out, like laptops on flaky Wi-Fi
+struct Table {
+    clock: Rc<Cell<f64>>,
+    tick: u32,
+    server: AuthorityServer,
+    links: Vec<Option<Impaired>>, // Server ends by connection id; `None` once dropped
+    clients: [ServerClient<Impaired>; 2],
+    online: [bool; 2],
+}
+
+impl Table {
+    fn new() -> Self {
+        let clock = Rc::new(Cell::new(0.0));
+        let (server_left, client_left) = impaired_link(bad_network(), 21, &clock);
+        let (server_right, client_right) = impaired_link(bad_network(), 23, &clock);
+        Self {
+            clock,
+            tick: 0,
+            server: AuthorityServer::new(),
+            links: vec![Some(server_left), Some(server_right)],
+            clients: [ServerClient::new(client_left, "Ada"), ServerClient::new(client_right, "Bob")],
+            online: [true, true],
+        }
+    }
+
+    fn now(&self) -> f64 {
+        self.tick as f64 * NET_DT as f64
+    }
+
+    fn drop_connection(&mut self, player: usize, connection: usize) {
+        self.online[player] = false;
+        self.links[connection] = None;
+    }
+
+    // Rejoin over a new link, i.e. from a new address
+    fn reconnect(&mut self, player: usize) {
+        let (server_end, client_end) = impaired_link(bad_network(), 31 + self.links.len() as u64 * 2, &self.clock);
+        self.links.push(Some(server_end));
+        self.clients[player].reconnect(client_end);
+        self.online[player] = true;
+    }
+
+    // Players stop moving while the match is paused, as the game does
+    fn step(&mut self) {
+        let now = self.now();
+        self.clock.set(now);
+        for (client, _) in self.clients.iter_mut().zip(self.online).filter(|(_, online)| *online) {
+            client.poll(now);
+            let (view, _) = client.view(now);
+            if client.pause(now).is_none() {
+                let intent = client.side().map_or(Intent::Stay, |side| script(&view, side, self.tick));
+                client.advance(intent, now);
+            }
+        }
+        for (connection, link) in self.links.iter_mut().enumerate() {
+            while let Some(data) = link.as_mut().and_then(|link| link.recv()) {
+                self.server.receive(connection, &data);
+            }
+        }
+        self.server.tick();
+        for (connection, packet) in self.server.take_outgoing() {
+            if let Some(link) = self.links[connection].as_mut() {
+                link.send(&packet);
+            }
+        }
+        self.tick += 1;
+    }
+
+    fn run(&mut self, seconds: f32) {
+        for _ in 0..(seconds / NET_DT) as u32 {
+            self.step();
+        }
+    }
+}
+
+#[test]
+fn a_dropped_player_rejoins_and_the_match_resumes_where_it_paused() {
+    let mut table = Table::new();
+    table.run(5.0);
+    assert!(table.server.is_running());
+    let bob = table.clients[1].side().unwrap();
+
+    // Bob's Wi-Fi drops: the match freezes once Bob has been quiet for a moment
+    table.drop_connection(1, 1);
+    table.run(NET_DISCONNECT_TIMEOUT + 0.5);
+    assert_eq!(table.server.absent(), Some(bob));
+    let frozen = *table.server.state();
+    table.run(5.0);
+    assert_eq!(*table.server.state(), frozen, "no steps while paused");
+    let now = table.now();
+    let (absent, remaining) = table.clients[0].pause(now).expect("Ada sees the pause");
+    assert_eq!(absent, bob);
+    assert!(remaining < NET_RECONNECT_TIMEOUT - 4.0 && remaining > 0.0, "countdown at {}", remaining);
+
+    // Back from a new address: Bob takes the seat back and play continues
+    // from the exact frozen state
+    table.reconnect(1);
+    assert_eq!(table.clients[1].pause(now).map(|(side, _)| side), Some(bob), "Bob knows the connection was lost");
+    let mut resumed = false;
+    for _ in 0..(5.0 / NET_DT) as u32 {
+        if table.server.absent().is_some() {
+            assert_eq!(*table.server.state(), frozen);
+        }
+        table.step();
+        if table.server.absent().is_none() {
+            resumed = true;
+            break;
+        }
+    }
+    assert!(resumed, "match did not resume");
+    assert_eq!(table.server.player_side(2), Some(bob));
+
+    for _ in 0..MAX_TICKS {
+        table.step();
+        if table.server.state().winner().is_some() {
+            break;
+        }
+    }
+    assert!(table.server.state().winner().is_some(), "match did not finish");
+    assert_eq!(table.server.forfeit(), None);
+    table.run(2.0);
+    for client in &table.clients {
+        assert_eq!(scores(client.server_state().unwrap()), scores(table.server.state()));
+    }
+}
+
+// Real sockets on the loopback interface, on the wall clock, stepping every
+// peer in `active` and letting the others fall silent
+fn run_udp(peers: &mut [RollbackSession<UdpTransport>; 2], active: &[bool; 2], clock: &Instant, seconds: f64) {
+    let until = clock.elapsed().as_secs_f64() + seconds;
+    while clock.elapsed().as_secs_f64() < until {
+        let now = clock.elapsed().as_secs_f64();
+        for (peer, &active) in peers.iter_mut().zip(active) {
+            if active {
+                peer.poll(now);
+                let intent = script(peer.state(), peer.side(), peer.frame());
+                peer.advance(intent, now);
+            }
+        }
+        thread::sleep(Duration::from_secs_f32(NET_DT));
+    }
+}
+
+#[test]
+fn a_rollback_peer_rejoins_from_a_new_address() {
+    let host = UdpTransport::host(0).unwrap();
+    let address = format!("127.0.0.1:{}", host.local_addr().unwrap().port());
+    let mut peers = [
+        RollbackSession::new(host, Side::Left),
+        RollbackSession::new(UdpTransport::join(&address).unwrap(), Side::Right),
+    ];
+    let clock = Instant::now();
+    run_udp(&mut peers, &[true, true], &clock, 0.5);
+    assert!(peers.iter().all(|peer| peer.is_running() && peer.frame() > 10));
+    let joined_from = peers[0].transport_mut().peer();
+
+    // A stranger can't take the seat while the peer is still talking
+    let mut stranger = UdpTransport::join(&address).unwrap();
+    stranger.send(&Packet::Hello.encode());
+    run_udp(&mut peers, &[true, true], &clock, 0.2);
+    assert_eq!(peers[0].transport_mut().peer(), joined_from);
+
+    // The joiner's link dies; the host pauses
+    run_udp(&mut peers, &[true, false], &clock, NET_DISCONNECT_TIMEOUT as f64 + 0.3);
+    let frozen = peers[0].frame();
+    assert!(peers[0].silence(clock.elapsed().as_secs_f64()) > NET_DISCONNECT_TIMEOUT as f64);
+
+    // Back from a fresh socket: the host takes the new address and play goes on
+    let socket = UdpTransport::join(&address).unwrap();
+    let rejoined_from = socket.local_addr().unwrap().port();
+    peers[1].reconnect(socket);
+    run_udp(&mut peers, &[true, true], &clock, 1.0);
+    assert_eq!(peers[0].transport_mut().peer().map(|peer| peer.port()), Some(rejoined_from));
+    assert!(peers[0].frame() > frozen + 20, "host stuck at frame {}", peers[0].frame());
+    assert!(peers.iter().all(|peer| peer.desync().is_none()));
+}
+
+// Seats two players on a fresh server and returns their rejoin tokens
+fn tokens() -> [u64; 2] {
+    let mut server = AuthorityServer::new();
+    let mut tokens = [0; 2];
+    for connection in 0..2 {
+        server.receive(connection, &Packet::Join { name: "Ada".to_string(), token: 0 }.encode());
+        for (_, data) in server.take_outgoing() {
+            if let Some(Packet::Accept { side, token }) = Packet::decode(&data) {
+                tokens[side as usize] = token;
+            }
+        }
+    }
+    tokens
+}
+
+#[test]
+fn rejoin_tokens_differ_between_seats_and_servers() {
+    let [a, b] = tokens();
+    let [c, d] = tokens();
+    assert!(a != 0 && b != 0 && c != 0 && d != 0);
+    assert!(a != b && c != d && a != c && b != d);
+    // All 64 bits are used
+    assert!([a, b, c, d].iter().any(|&token| token > u32::MAX as u64));
+}
+
+#[test]
+fn a_player_who_does_not_come_back_forfeits() {
+    let mut table = Table::new();
+    table.run(3.0);
+    let bob = table.clients[1].side().unwrap();
+    table.drop_connection(1, 1);
+    table.run(NET_DISCONNECT_TIMEOUT + NET_RECONNECT_TIMEOUT + 2.0);
+
+    assert_eq!(table.server.forfeit(), Some(bob));
+    assert!(table.server.is_over());
+    let ada = &table.clients[0];
+    assert_eq!(ada.forfeited(), Some(bob));
+    let state = ada.server_state().unwrap();
+    assert_eq!(state.winner(), Some(bob.opponent()));
+    assert_eq!(scores(state), scores(table.server.state()));
+    assert_eq!(ada.pause(table.now()), None);
+}
+
+#[test]
+fn simulator_drops_delays_and_keeps_order_without_jitter() {
+    let clock = Rc::new(Cell::new(0.0));
+    let conditions = NetConditions::parse("latency=100").unwrap();
+    let (mut a, mut b) = impaired_link(conditions, 1, &clock);
+    for i in 0..5u8 {
+        a.send(&[i]);
+    }
+    assert_eq!(b.recv(), None);
+    // 100 ms out of `a` (delayed sends leave when `a` is polled), then 100 ms into `b`
+    clock.set(0.1);
+    assert_eq!(a.recv(), None);
+    assert_eq!(b.recv(), None);
+    clock.set(0.2);
+    let received: Vec<u8> = std::iter::from_fn(|| b.recv()).map(|data| data[0]).collect();
+    assert_eq!(received, vec![0, 1, 2, 3, 4]);
+
+    let lossy = NetConditions::parse("loss=1").unwrap();
+    let (mut a, mut b) = impaired_link(lossy, 1, &clock);
+    a.send(&[1]);
+    assert_eq!(b.recv(), None);
+}
+
+#[test]
+fn conditions_parse_from_the_command_line_format() {
+    let conditions = NetConditions::parse("latency=80, jitter=20,loss=0.05").unwrap();
+    assert_eq!(conditions.latency, 0.08);
+    assert_eq!(conditions.jitter, 0.02);
+    assert_eq!(conditions.loss, 0.05);
+    assert!(NetConditions::parse("loss=2").is_err());
+    assert!(NetConditions::parse("speed=3").is_err());
+    assert!(NetConditions::parse("latency").is_err());
+}
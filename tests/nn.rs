@@ -0,0 +1,75 @@
+// Neural-net opponent: weights files parse strictly, and a forward pass
+// with hand-picked weights gives the scores worked out by hand.
+
+use pong_rs::nn::Mlp;
+use pong_rs::sim::{Intent, Match, Side, OBS_DIM};
+
+// 8 inputs -> 2 hidden -> 3 outputs. The first hidden unit copies input 0,
+// the second is 0.5 minus input 1.
+const SMALL: &str = "\
+pong-mlp 1
+layers 8 2 3   # input, hidden, output
+ACTIVATION
+1 0 0 0 0 0 0 0
+0 -1 0 0 0 0 0 0
+0 0.5          # hidden biases
+1 1  2 0  0 3
+0.5 0 -1
+";
+
+fn small(activation: &str) -> Mlp {
+    Mlp::parse(&SMALL.replace("ACTIVATION", activation)).unwrap()
+}
+
+fn input(first: f32, second: f32) -> Vec<f32> {
+    let mut input = vec![0.0; OBS_DIM];
+    input[0] = first;
+    input[1] = second;
+    input
+}
+
+#[test]
+fn known_weights_give_known_scores() {
+    // relu: hidden = [2, 0], outputs = [2 + 0 + 0.5, 4, 0 - 1]
+    assert_eq!(small("relu").forward(&input(2.0, 1.0)), vec![2.5, 4.0, -1.0]);
+    // A positive second hidden unit reaches the last output
+    assert_eq!(small("relu").forward(&input(0.0, -1.0)), vec![2.0, 0.0, 3.5]);
+
+    let tanh = small("tanh").forward(&input(2.0, 1.0));
+    let (a, b) = (2.0f32.tanh(), (-0.5f32).tanh());
+    let expected = [a + b + 0.5, 2.0 * a, 3.0 * b - 1.0];
+    for (got, want) in tanh.iter().zip(expected) {
+        assert!((got - want).abs() < 1e-6, "{:?} vs {:?}", tanh, expected);
+    }
+}
+
+#[test]
+fn the_highest_score_picks_the_move() {
+    // No weights, only output biases, so the observation doesn't matter
+    let zeros = vec!["0"; OBS_DIM * 3].join(" ");
+    for (biases, intent) in [("1 0 0", Intent::Stay), ("0 1 0", Intent::Up), ("0 0 1", Intent::Down)] {
+        let net = Mlp::parse(&format!("pong-mlp 1\nlayers 8 3\nrelu\n{}\n{}\n", zeros, biases)).unwrap();
+        assert_eq!(net.decide(&Match::new(), Side::Left), intent);
+        assert_eq!(net.decide(&Match::new(), Side::Right), intent);
+    }
+}
+
+#[test]
+fn bad_weights_files_are_rejected() {
+    let good = SMALL.replace("ACTIVATION", "relu");
+    let bad = [
+        (good.replace("pong-mlp 1", "pong-mlp 2"), "header"),
+        (good.replace("layers 8 2 3", "layers 7 2 3"), "input size"),
+        (good.replace("layers 8 2 3", "layers 8 2 4"), "output size"),
+        (good.replace("layers 8 2 3", "layers 8"), "at least"),
+        (good.replace("layers 8 2 3", "layers 8 two 3"), "bad layer size"),
+        (good.replace("relu", "sigmoid"), "bad layer size"),
+        (good.replace("relu\n", ""), "bad layer size"),
+        (good.replace("0.5 0 -1", "0.5 0 x"), "bad number x"),
+        (good.replace("0.5 0 -1", "0.5 0"), "truncated"),
+    ];
+    for (text, expected) in bad {
+        let err = Mlp::parse(&text).unwrap_err();
+        assert!(err.contains(expected), "{:?} gave {:?}", text, err);
+    }
+}
@@ -0,0 +1,164 @@
+// Power-ups: pickups go to the last player to hit the ball, effects change
+// the paddles or ball for their duration and then wear off.
+
+use pong_rs::constants::*;
+use pong_rs::powerups::{Kind, Pickup, PowerUps};
+use pong_rs::sim::{Ball, Intent, Match, Side};
+
+const DT: f32 = 1.0 / 60.0;
+
+// Ball flying straight across at the height of the paddles' centers
+fn flying(towards: Side, x: f32) -> Ball {
+    Ball {
+        x,
+        y: PADDLE_START_Y + PADDLE_HEIGHT / 2.0,
+        vel_x: match towards {
+            Side::Left => -BALL_SPEED,
+            Side::Right => BALL_SPEED,
+        },
+        vel_y: 0.0,
+        speed: BALL_SPEED,
+    }
+}
+
+// Center of the field, on the ball's path
+fn pickup(kind: Kind) -> Option<Pickup> {
+    Some(Pickup {
+        kind,
+        x: SCREEN_WIDTH / 2.0,
+        y: PADDLE_START_Y + PADDLE_HEIGHT / 2.0,
+    })
+}
+
+#[test]
+fn the_last_hitter_collects_and_the_effect_wears_off() {
+    let mut powerups = PowerUps::new(1);
+    let mut game = Match::new();
+    powerups.pickup = pickup(Kind::BigPaddle);
+    game.ball = flying(Side::Left, SCREEN_WIDTH / 2.0 - 100.0);
+
+    let collected = (0..60 * 5).find_map(|_| powerups.step(&mut game, DT).1.collected);
+    let (pickup, side) = collected.expect("pickup was not collected");
+    assert_eq!((pickup.kind, side), (Kind::BigPaddle, Side::Left));
+    assert!(powerups.pickup.is_none());
+
+    powerups.step(&mut game, DT);
+    assert!(game.left.height > PADDLE_HEIGHT);
+    assert_eq!(game.right.height, PADDLE_HEIGHT);
+
+    let steps = (Kind::BigPaddle.duration() / DT) as usize + 2;
+    let expired = (0..steps).find_map(|_| powerups.step(&mut game, DT).1.expired);
+    assert_eq!(expired.map(|effect| effect.kind), Some(Kind::BigPaddle));
+    powerups.step(&mut game, DT);
+    assert_eq!(game.left.height, PADDLE_HEIGHT);
+}
+
+#[test]
+fn a_serve_nobody_has_touched_collects_nothing() {
+    let mut powerups = PowerUps::new(1);
+    let mut game = Match::new();
+    powerups.pickup = pickup(Kind::FastBall);
+    game.ball = flying(Side::Right, SCREEN_WIDTH / 2.0 - 100.0);
+    for _ in 0..30 {
+        assert_eq!(powerups.step(&mut game, DT).1.collected, None);
+    }
+    assert_eq!(powerups.pickup, pickup(Kind::FastBall));
+}
+
+#[test]
+fn opponent_power_ups_hit_the_other_side() {
+    let mut powerups = PowerUps::new(1);
+    let mut game = Match::new();
+    powerups.grant(Kind::SmallPaddle, Side::Left);
+    powerups.grant(Kind::Reverse, Side::Left);
+    powerups.step(&mut game, DT);
+    assert!(game.right.height < PADDLE_HEIGHT);
+    assert_eq!(game.left.height, PADDLE_HEIGHT);
+    assert_eq!(powerups.intent(Side::Right, Intent::Up), Intent::Down);
+    assert_eq!(powerups.intent(Side::Left, Intent::Up), Intent::Up);
+}
+
+#[test]
+fn a_shield_saves_one_goal() {
+    let mut powerups = PowerUps::new(1);
+    let mut game = Match::new();
+    game.left.y = SCREEN_HEIGHT; // Out of the way
+    powerups.grant(Kind::Shield, Side::Left);
+    game.ball = flying(Side::Left, 100.0);
+
+    let saved = (0..60 * 2).find_map(|_| {
+        let (events, extra) = powerups.step(&mut game, DT);
+        assert_eq!(events.scored, None);
+        extra.shield_used
+    });
+    assert_eq!(saved.map(|(side, _)| side), Some(Side::Left));
+    assert!(game.ball.vel_x > 0.0);
+    assert!(!powerups.is_active(Kind::Shield, Side::Left));
+
+    game.ball = flying(Side::Left, 100.0);
+    let scored = (0..60 * 2).find_map(|_| powerups.step(&mut game, DT).0.scored);
+    assert_eq!(scored, Some(Side::Right));
+}
+
+#[test]
+fn a_sticky_paddle_holds_the_ball_then_returns_it() {
+    let mut powerups = PowerUps::new(1);
+    let mut game = Match::new();
+    powerups.grant(Kind::Sticky, Side::Right);
+    game.ball = flying(Side::Right, SCREEN_WIDTH - 150.0);
+    (0..60 * 2)
+        .find_map(|_| powerups.step(&mut game, DT).0.paddle_hit)
+        .expect("no paddle hit");
+
+    // Held against the paddle while it moves
+    for _ in 0..10 {
+        game.right.drive(Intent::Down, DT);
+        powerups.step(&mut game, DT);
+        assert_eq!(game.ball.x, RIGHT_PADDLE_X - BALL_SIZE);
+        assert!(game.ball.y >= game.right.y && game.ball.y <= game.right.y + game.right.height);
+    }
+    for _ in 0..60 {
+        game.right.drive(Intent::Stay, DT);
+        powerups.step(&mut game, DT);
+    }
+    assert!(game.ball.vel_x < 0.0);
+    assert!(game.ball.x < RIGHT_PADDLE_X - BALL_SIZE * 2.0);
+}
+
+#[test]
+fn ball_speed_power_ups_change_the_distance_covered() {
+    let distance = |kind: Option<Kind>| {
+        let mut powerups = PowerUps::new(1);
+        let mut game = Match::new();
+        if let Some(kind) = kind {
+            powerups.grant(kind, Side::Left);
+        }
+        game.ball = flying(Side::Right, SCREEN_WIDTH / 2.0 - 100.0);
+        for _ in 0..30 {
+            powerups.step(&mut game, DT);
+        }
+        game.ball.x
+    };
+    let normal = distance(None);
+    assert!(distance(Some(Kind::FastBall)) > normal);
+    assert!(distance(Some(Kind::SlowBall)) < normal);
+}
+
+#[test]
+fn pickups_keep_spawning_near_the_center() {
+    let mut powerups = PowerUps::new(9);
+    let mut game = Match::new();
+    let mut kinds = Vec::new();
+    for _ in 0..20 {
+        let spawned = (0..60 * 10).find_map(|_| {
+            powerups.step(&mut game, DT);
+            powerups.pickup
+        });
+        let pickup = spawned.expect("no pickup spawned");
+        assert!((pickup.x - SCREEN_WIDTH / 2.0).abs() <= 100.0);
+        assert!(pickup.y > 0.0 && pickup.y < SCREEN_HEIGHT);
+        kinds.push(pickup.kind);
+        powerups.pickup = None;
+    }
+    assert!(kinds.iter().any(|&kind| kind != kinds[0]));
+}
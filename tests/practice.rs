@@ -0,0 +1,85 @@
+// Wall practice: the wall returns the ball, paddle hits build the rally and
+// speed the ball up, and the streak ends with a miss; personal bests keep
+// the longest rally on each wall.
+
+use pong_rs::constants::*;
+use pong_rs::practice::{PersonalBests, Practice, WallKind, WALL_WIDTH};
+use pong_rs::sim::Ball;
+
+const DT: f32 = 1.0 / 60.0;
+
+// Ball flying straight at the wall at the height of the paddle's center
+fn at_the_wall(y: f32) -> Ball {
+    Ball { x: SCREEN_WIDTH / 2.0, y, vel_x: BALL_SPEED, vel_y: 0.0, speed: BALL_SPEED }
+}
+
+#[test]
+fn the_wall_returns_the_ball_and_paddle_hits_build_the_rally() {
+    let mut practice = Practice::new(WallKind::Solid, 1);
+    practice.ball = at_the_wall(PADDLE_START_Y + PADDLE_HEIGHT / 2.0);
+    let mut wall_hits = 0;
+    for _ in 0..60 * 20 {
+        let events = practice.step(DT);
+        assert_eq!(events.lost, None);
+        if events.wall_hit.is_some() {
+            wall_hits += 1;
+            assert!(practice.ball.x + BALL_SIZE <= SCREEN_WIDTH - WALL_WIDTH);
+        }
+    }
+    assert!(wall_hits >= 3);
+    assert!(practice.rally >= 3);
+    assert!(practice.ball.speed > BALL_SPEED);
+    let expected = (BALL_SPEED * BALL_SPEED_INCREASE.powi(practice.rally as i32)).min(MAX_BALL_SPEED);
+    assert!((practice.ball.speed - expected).abs() < 0.01);
+}
+
+#[test]
+fn a_miss_loses_the_streak_and_freezes_play() {
+    let mut practice = Practice::new(WallKind::Solid, 1);
+    practice.ball = at_the_wall(PADDLE_START_Y + PADDLE_HEIGHT / 2.0);
+    (0..60 * 5).find_map(|_| practice.step(DT).paddle_hit).expect("no paddle hit");
+    practice.paddle.y = 0.0; // Out of the way of the return
+    let lost = (0..60 * 10).find_map(|_| practice.step(DT).lost);
+    assert_eq!(lost, Some(1));
+
+    let frozen = practice.ball;
+    assert_eq!(practice.step(DT), Default::default());
+    assert_eq!(practice.ball, frozen);
+
+    practice.restart();
+    assert_eq!((practice.rally, practice.lost), (0, None));
+    assert_eq!((practice.ball.x, practice.ball.y), (BALL_START_X, BALL_START_Y));
+}
+
+#[test]
+fn target_walls_only_return_balls_inside_the_target() {
+    let mut practice = Practice::new(WallKind::Target, 3);
+    let (top, bottom) = practice.target;
+    assert!(top >= 0.0 && bottom <= SCREEN_HEIGHT && bottom > top);
+
+    practice.ball = at_the_wall((top + bottom) / 2.0);
+    let hit = (0..60 * 5).find_map(|_| practice.step(DT).wall_hit);
+    assert!(hit.is_some());
+    assert_ne!(practice.target, (top, bottom), "the target did not move");
+
+    let (top, bottom) = practice.target;
+    let outside = if top > SCREEN_HEIGHT / 2.0 { top - 40.0 } else { bottom + 40.0 };
+    practice.ball = at_the_wall(outside);
+    let lost = (0..60 * 5).find_map(|_| practice.step(DT).lost);
+    assert_eq!(lost, Some(0));
+}
+
+#[test]
+fn personal_bests_keep_the_longest_rally_per_wall() {
+    let mut bests = PersonalBests::default();
+    assert!(bests.record(WallKind::Solid, 12));
+    assert!(!bests.record(WallKind::Solid, 7));
+    assert!(bests.record(WallKind::Target, 3));
+    assert_eq!((bests.get(WallKind::Solid), bests.get(WallKind::Target)), (12, 3));
+    assert_eq!(PersonalBests::parse(&bests.to_text()), Ok(bests));
+
+    let parsed = PersonalBests::parse("# comment\n\nwall 5\n").unwrap();
+    assert_eq!((parsed.solid, parsed.target), (5, 0));
+    assert!(PersonalBests::parse("ceiling 4").unwrap_err().contains("unknown wall"));
+    assert!(PersonalBests::parse("wall lots").unwrap_err().contains("line 1"));
+}
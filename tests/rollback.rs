@@ -0,0 +1,169 @@
+// Rollback internals: every packet survives the wire format, and a wrong
+// prediction of the peer's input is rewound and re-simulated exactly.
+
+use pong_rs::batch::{step_match, Opponent};
+use pong_rs::constants::*;
+use pong_rs::lobby::{Member, RoomSummary, Rules};
+use pong_rs::net::{MemoryTransport, Packet, Transport};
+use pong_rs::rollback::{RollbackSession, NET_DT};
+use pong_rs::sim::{Intent, Match, Rng, Side};
+
+fn mid_rally() -> Match {
+    let mut game = Match::new();
+    for _ in 0..40 {
+        game.step(NET_DT);
+    }
+    game.left.vel = -MAX_PADDLE_SPEED;
+    game.right_score = 3;
+    game
+}
+
+fn every_packet() -> Vec<Packet> {
+    let state = mid_rally();
+    let rules = Rules {
+        winning_score: 9,
+        ai_fill: Some("hard".to_string()),
+    };
+    vec![
+        Packet::Hello,
+        Packet::Welcome,
+        Packet::Input {
+            frame: 120,
+            ack: 117,
+            start: 110,
+            inputs: vec![0, 1, 2, 2, 1],
+            ping: 12.5,
+            pong: 12.25,
+            checks_ack: 100,
+            checks_start: 98,
+            checks: vec![0xdead_beef, 7],
+        },
+        Packet::StateDump { frame: 42, state },
+        Packet::Watch,
+        Packet::Spectate {
+            tick: 900,
+            state,
+            rally: 4,
+            names: ["Ann".to_string(), "Bo".to_string()],
+            pings: [35, 180],
+            spectators: 2,
+        },
+        Packet::Join {
+            name: "Ann".to_string(),
+            token: 0x1234_5678,
+        },
+        Packet::Accept {
+            side: Side::Right,
+            token: 0x1234_5678,
+        },
+        Packet::Commands {
+            start: 30,
+            inputs: vec![2, 2, 0],
+            ping: 0.5,
+            rtt_ms: 64,
+        },
+        Packet::Snapshot {
+            tick: 31,
+            ack: 29,
+            pong: 0.5,
+            state,
+            absent: Some(Side::Left),
+            resume_within: 12.5,
+        },
+        Packet::ListRooms,
+        Packet::Rooms {
+            rooms: vec![
+                RoomSummary { name: "Room 1".to_string(), players: vec!["Ann".to_string()], playing: false },
+                RoomSummary { name: "Room 2".to_string(), players: vec![], playing: true },
+            ],
+        },
+        Packet::Seat {
+            room: 1,
+            name: "Bo".to_string(),
+            side: None,
+            ready_for: 3,
+            rules_base: 2,
+            rules: rules.clone(),
+            chat_seq: 5,
+            chat: "gg".to_string(),
+        },
+        Packet::Leave,
+        Packet::Room {
+            room: 1,
+            you: Some(0),
+            members: vec![
+                Member { name: "Ann".to_string(), side: Side::Left, ready: true },
+                Member { name: "Bo".to_string(), side: Side::Right, ready: false },
+            ],
+            version: 3,
+            rules,
+            playing: false,
+            chat_ack: 5,
+            chat: vec![(1, "hi".to_string()), (2, "gg".to_string())],
+        },
+    ]
+}
+
+#[test]
+fn every_packet_survives_encode_and_decode() {
+    for packet in every_packet() {
+        let data = packet.encode();
+        assert_eq!(Packet::decode(&data).as_ref(), Some(&packet));
+        // A cut-off datagram is dropped rather than misread
+        assert_eq!(Packet::decode(&data[..data.len() - 1]), None, "{:?}", packet);
+    }
+    assert_eq!(Packet::decode(&[]), None);
+}
+
+#[test]
+fn a_wrong_prediction_is_rewound_and_resimulated() {
+    let (transport, mut peer) = MemoryTransport::pair();
+    let mut session = RollbackSession::new(transport, Side::Left);
+    peer.send(&Packet::Hello.encode());
+    session.poll(0.0);
+    assert!(session.is_running());
+
+    // Run ahead of the peer's inputs, predicting it keeps still. It claims
+    // to be as far along, so time sync doesn't hold us back.
+    let frames = NET_MAX_PREDICTION;
+    let input = |inputs: Vec<u8>| Packet::Input {
+        frame: frames,
+        ack: 0,
+        start: 0,
+        inputs,
+        ping: 0.0,
+        pong: 0.0,
+        checks_ack: 0,
+        checks_start: 0,
+        checks: vec![],
+    };
+    peer.send(&input(vec![]).encode());
+    session.poll(0.0);
+    for frame in 0..frames {
+        assert!(session.advance(Intent::Stay, frame as f64 * NET_DT as f64).is_some());
+    }
+    assert_eq!(session.frame(), frames);
+    assert_eq!(session.state().right.y, Match::new().right.y);
+
+    // The peer actually started moving down at frame 3
+    let inputs: Vec<u8> = (0..frames).map(|frame| if frame < 3 { 0 } else { 2 }).collect();
+    peer.send(&input(inputs.clone()).encode());
+    session.poll(frames as f64 * NET_DT as f64);
+    assert_eq!(session.rollbacks(), 1);
+    assert_eq!(session.frame(), frames);
+
+    // Same as simulating the real inputs from the start
+    let mut expected = Match::new();
+    let mut rng = Rng::new(0);
+    for &remote in &inputs {
+        step_match(&mut expected, &mut rng, [0, remote], NET_DT, Opponent::Agent);
+    }
+    assert_eq!(session.state(), &expected);
+    assert!(session.state().right.y > Match::new().right.y);
+    assert_eq!(session.confirmed_state(), &expected);
+
+    // Resending the same inputs changes nothing
+    peer.send(&input(inputs).encode());
+    session.poll(frames as f64 * NET_DT as f64);
+    assert_eq!(session.rollbacks(), 1);
+}
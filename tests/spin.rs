@@ -0,0 +1,91 @@
+// Ball spin: a moving paddle sets the ball spinning, the spin curves its
+// flight against the paddle's motion, changes how it comes off the walls,
+// and dies away.
+
+use pong_rs::constants::*;
+use pong_rs::sim::{Ball, Match, Side, StepEvents};
+use pong_rs::spin::Spin;
+
+const DT: f32 = 1.0 / 60.0;
+
+// Ball about to meet the left paddle while it moves down at full speed
+fn hit_off_moving_paddle() -> (Match, Spin) {
+    let mut game = Match::new();
+    game.left.vel = MAX_PADDLE_SPEED;
+    let y = game.left.y + game.left.height / 2.0;
+    game.ball = Ball { x: LEFT_PADDLE_X + PADDLE_WIDTH + BALL_SIZE + 2.0, y, vel_x: -BALL_SPEED, vel_y: 0.0, speed: BALL_SPEED };
+    let mut spin = Spin::new();
+    let events = game.step(DT);
+    assert!(events.paddle_hit.is_some());
+    let imparted = spin.step(&mut game, &events, DT).imparted;
+    assert!(imparted.is_some_and(|imparted| imparted > 0.0));
+    (game, spin)
+}
+
+#[test]
+fn spin_curves_the_ball_against_the_paddle_motion_and_decays() {
+    let (mut game, mut spin) = hit_off_moving_paddle();
+    let mut plain = game;
+    let start = spin.spin;
+    let angle = spin.angle;
+
+    for _ in 0..60 {
+        let events = game.step(DT);
+        spin.step(&mut game, &events, DT);
+        plain.step(DT);
+    }
+    // The paddle went down, so the ball bends up compared with no spin
+    assert!(game.ball.vel_y < plain.ball.vel_y);
+    assert!(game.ball.y < plain.ball.y);
+    assert_eq!(game.ball.vel_x, plain.ball.vel_x);
+    assert!(spin.spin > 0.0 && spin.spin < start * 0.7);
+    assert!(spin.angle != angle);
+}
+
+#[test]
+fn wall_bounces_change_the_angle_and_use_up_spin() {
+    for (y, vel_y) in [(BALL_SIZE + 1.0, -BALL_SPEED), (SCREEN_HEIGHT - BALL_SIZE - 1.0, BALL_SPEED)] {
+        let mut game = Match::new();
+        game.ball = Ball { x: SCREEN_WIDTH / 2.0, y, vel_x: BALL_SPEED, vel_y, speed: BALL_SPEED };
+        let mut spin = Spin { spin: 200.0, angle: 0.0 };
+        let events = game.step(DT);
+        let kick = spin.step(&mut game, &events, DT).wall_kick.expect("no wall bounce");
+        assert!(spin.spin < 101.0);
+
+        // Counterclockwise spin speeds the ball up off the top wall and
+        // slows it down off the bottom one
+        if y < SCREEN_HEIGHT / 2.0 {
+            assert!(kick > 0.0 && game.ball.vel_x > BALL_SPEED);
+        } else {
+            assert!(kick < 0.0 && game.ball.vel_x < BALL_SPEED);
+        }
+        assert!(game.ball.vel_x >= BALL_SPEED * 0.5);
+    }
+}
+
+#[test]
+fn heavy_spin_never_turns_the_ball_around_on_a_wall() {
+    let mut game = Match::new();
+    game.ball = Ball { x: SCREEN_WIDTH / 2.0, y: SCREEN_HEIGHT - BALL_SIZE - 1.0, vel_x: BALL_SPEED, vel_y: BALL_SPEED, speed: BALL_SPEED };
+    let mut spin = Spin { spin: 5000.0, angle: 0.0 };
+    let events = game.step(DT);
+    spin.step(&mut game, &events, DT);
+    assert_eq!(game.ball.vel_x, BALL_SPEED * 0.5);
+}
+
+#[test]
+fn a_point_clears_the_spin() {
+    let (mut game, mut spin) = hit_off_moving_paddle();
+    game.ball.x = SCREEN_WIDTH + BALL_SIZE;
+    let events = game.step(DT);
+    assert!(events.scored.is_some());
+    spin.step(&mut game, &events, DT);
+    assert_eq!(spin.spin, 0.0);
+
+    // And a still paddle puts none on
+    let mut still = Spin { spin: 300.0, angle: 0.0 };
+    let hit = StepEvents { paddle_hit: Some((Side::Left, game.left.y)), ..Default::default() };
+    game.left.vel = 0.0;
+    still.step(&mut game, &hit, DT);
+    assert_eq!(still.spin, 0.0);
+}
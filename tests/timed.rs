@@ -0,0 +1,69 @@
+// Timed matches: the leader wins when time runs out, a tie goes to
+// sudden-death overtime, and first-to-N matches keep their score limit.
+
+use pong_rs::constants::*;
+use pong_rs::sim::{Match, Side};
+use pong_rs::timed::MatchRule;
+
+const DT: f32 = 1.0 / 60.0;
+
+#[test]
+fn first_to_sets_the_score_limit() {
+    let mut game = Match::new();
+    game.winning_score = 3;
+    assert_eq!(MatchRule::PRESETS[0], MatchRule::FirstTo(WINNING_SCORE));
+    assert!(MatchRule::PRESETS[0].apply(&mut game).is_none());
+    assert_eq!(game.winning_score, WINNING_SCORE);
+    assert_eq!(MatchRule::PRESETS[0].label(), format!("First to {}", WINNING_SCORE));
+}
+
+#[test]
+fn leader_wins_when_time_runs_out() {
+    let mut game = Match::new();
+    let mut clock = MatchRule::Timed(2.0).apply(&mut game).expect("timed matches have a clock");
+    assert_eq!(MatchRule::Timed(120.0).label(), "2 min timed");
+
+    // No score limit while the clock runs
+    game.left_score = WINNING_SCORE + 4;
+    game.right_score = 2;
+    assert_eq!(game.winner(), None);
+    assert_eq!(clock.step(&mut game, 1.0).winner, None);
+
+    let events = clock.step(&mut game, 1.5);
+    assert_eq!(events.winner, Some(Side::Left));
+    assert!(!events.overtime);
+    assert_eq!(clock.remaining, 0.0);
+    assert_eq!(game.winner(), Some(Side::Left));
+}
+
+#[test]
+fn tie_goes_to_overtime_and_the_next_point_wins() {
+    let mut game = Match::new();
+    let mut clock = MatchRule::Timed(1.0).apply(&mut game).unwrap();
+    game.left_score = 3;
+    game.right_score = 3;
+
+    let mut overtime = 0;
+    for _ in 0..120 {
+        let events = clock.step(&mut game, DT);
+        assert_eq!(events.winner, None);
+        overtime += events.overtime as u32;
+    }
+    assert_eq!(overtime, 1);
+    assert!(clock.overtime);
+    assert_eq!(game.winner(), None);
+
+    game.right_score += 1;
+    assert_eq!(game.winner(), Some(Side::Right));
+}
+
+#[test]
+fn clock_stops_once_the_match_is_won() {
+    let mut game = Match::new();
+    let mut clock = MatchRule::Timed(60.0).apply(&mut game).unwrap();
+    game.winning_score = 1;
+    game.left_score = 1;
+    let events = clock.step(&mut game, 90.0);
+    assert_eq!(events, Default::default());
+    assert_eq!(clock.remaining, 60.0);
+}
@@ -0,0 +1,117 @@
+// Tournaments: brackets are seeded with byes for the top seeds, every
+// format finishes with one champion, double elimination needs two losses,
+// and a saved tournament resumes where it left off.
+
+use pong_rs::ai;
+use pong_rs::tournament::{Bracket, Controller, Entrant, Format, Tournament};
+
+fn entrants(count: usize) -> Vec<Entrant> {
+    let profiles = ai::default_profiles();
+    (0..count)
+        .map(|index| Entrant {
+            name: format!("Player {}", index + 1),
+            controller: if index % 2 == 0 {
+                Controller::Human
+            } else {
+                Controller::Ai(profiles[index % profiles.len()].name.clone())
+            },
+        })
+        .collect()
+}
+
+// Plays the tournament out with `winner` picking who wins each game
+fn play_out(tournament: &mut Tournament, mut winner: impl FnMut([usize; 2]) -> usize) -> usize {
+    let mut games = 0;
+    while let Some(game) = tournament.current().copied() {
+        let left_wins = winner(game.players) == game.players[0];
+        let (left, right) = if left_wins { (6, 2) } else { (3, 6) };
+        tournament.record(left, right).unwrap();
+        games += 1;
+        assert!(games < 100, "tournament never ended");
+    }
+    games
+}
+
+#[test]
+fn first_rounds_are_seeded_with_byes_for_the_top_seeds() {
+    let tournament = Tournament::new(entrants(6), Format::Single).unwrap();
+    let first = &tournament.rounds[0];
+    assert_eq!(first.byes, vec![0, 1]);
+    let pairs: Vec<[usize; 2]> = first.games.iter().map(|game| game.players).collect();
+    assert_eq!(pairs, vec![[3, 4], [2, 5]]);
+
+    let tournament = Tournament::new(entrants(8), Format::Single).unwrap();
+    let pairs: Vec<[usize; 2]> = tournament.rounds[0].games.iter().map(|game| game.players).collect();
+    assert_eq!(pairs, vec![[0, 7], [3, 4], [1, 6], [2, 5]]);
+
+    assert!(Tournament::new(entrants(3), Format::Single).is_err());
+    assert!(Tournament::new(entrants(17), Format::Double).is_err());
+}
+
+#[test]
+fn every_field_size_finishes_with_one_champion() {
+    for count in 4..=16 {
+        for format in [Format::Single, Format::Double] {
+            let mut tournament = Tournament::new(entrants(count), format).unwrap();
+            let games = play_out(&mut tournament, |players| players[0].min(players[1]));
+            assert_eq!(tournament.champion(), Some(0), "{} entrants, {:?}", count, format);
+            let out = (0..count).filter(|&entrant| tournament.is_out(entrant)).count();
+            assert_eq!(out, count - 1);
+            // Every game knocks out one player, or costs a double elimination life
+            match format {
+                Format::Single => assert_eq!(games, count - 1),
+                Format::Double => assert_eq!(games, 2 * (count - 1)),
+            }
+        }
+    }
+}
+
+#[test]
+fn double_elimination_takes_two_losses_and_can_reset_the_final() {
+    let mut tournament = Tournament::new(entrants(8), Format::Double).unwrap();
+    // The top seed loses its first game, then wins everything; otherwise
+    // the better seed wins
+    let mut first_game = true;
+    play_out(&mut tournament, |players| {
+        let best = players[0].min(players[1]);
+        if best == 0 && first_game {
+            first_game = false;
+            players[0].max(players[1])
+        } else {
+            best
+        }
+    });
+    assert_eq!(tournament.champion(), Some(0));
+    let finals: Vec<_> = tournament
+        .rounds
+        .iter()
+        .flat_map(|round| &round.games)
+        .filter(|game| game.bracket == Bracket::Final)
+        .collect();
+    assert_eq!(finals.len(), 2, "the final was not played again");
+    assert!(tournament.rounds.iter().flat_map(|round| &round.games).any(|game| game.bracket == Bracket::Losers && game.players.contains(&0)));
+}
+
+#[test]
+fn saved_tournaments_resume_where_they_left_off() {
+    let mut tournament = Tournament::new(entrants(5), Format::Double).unwrap();
+    for _ in 0..4 {
+        tournament.record(6, 4).unwrap();
+    }
+    let text = tournament.to_text();
+    let resumed = Tournament::parse(&text).unwrap();
+    assert_eq!(resumed, tournament);
+    assert_eq!(resumed.current(), tournament.current());
+    assert!(text.contains("entrant ai medium Player 2"));
+    assert_eq!(resumed.check_profiles(&ai::default_profiles()), Ok(()));
+
+    // AI entrants refer to profiles by name, which have to exist
+    let unknown = text.replace("entrant ai medium", "entrant ai impossible");
+    let err = Tournament::parse(&unknown).unwrap().check_profiles(&ai::default_profiles()).unwrap_err();
+    assert!(err.contains("Player 2") && err.contains("impossible"), "{}", err);
+
+    assert!(Tournament::parse("pong-tournament 1\nformat triple\n").unwrap_err().contains("line 2"));
+    assert!(Tournament::parse("pong-tournament 1\nentrant robot X\n").unwrap_err().contains("entrant human"));
+    let mut tied = Tournament::new(entrants(4), Format::Single).unwrap();
+    assert!(tied.record(3, 3).is_err());
+}
@@ -0,0 +1,36 @@
+// AI tuning output: `--targets` names the profiles the game looks up, and
+// profile files read back exactly what was written.
+
+use pong_rs::ai::{default_profiles, parse_profiles, profiles_to_text, AiProfile};
+use pong_rs::tune::parse_targets;
+
+#[test]
+fn bare_targets_keep_the_difficulty_names_when_the_count_matches() {
+    let names = |targets: Vec<(String, f32)>| targets.into_iter().map(|(name, _)| name).collect::<Vec<_>>();
+    assert_eq!(names(parse_targets("0.2, 0.5, 0.8").unwrap()), ["easy", "medium", "hard"]);
+    assert_eq!(names(parse_targets("0.25,0.75").unwrap()), ["win25", "win75"]);
+    assert_eq!(
+        parse_targets("novice=0.1,easy=0.25,medium=0.5,hard=0.75").unwrap(),
+        [("novice".to_string(), 0.1), ("easy".to_string(), 0.25), ("medium".to_string(), 0.5), ("hard".to_string(), 0.75)]
+    );
+
+    for bad in ["0.5,x", "easy=", "=0.5", "1.5", ""] {
+        assert!(parse_targets(bad).is_err(), "{:?} was accepted", bad);
+    }
+}
+
+#[test]
+fn profiles_round_trip_through_text() {
+    let mut profiles = default_profiles();
+    profiles.push(AiProfile {
+        name: "tuned".to_string(),
+        win_rate: Some(0.4375),
+        ..profiles[1].clone()
+    });
+    profiles[0].params.reaction_time = 0.123;
+    assert_eq!(parse_profiles(&profiles_to_text(&profiles)).unwrap(), profiles);
+
+    assert!(parse_profiles("speed = 200\n").is_err());
+    assert!(parse_profiles("[easy]\nspeed 200\n").is_err());
+    assert!(parse_profiles("[easy]\nluck = 1\n").is_err());
+}
@@ -0,0 +1,86 @@
+// WebSocket handshake and framing as used by the relay: the accept key from
+// RFC 6455, masked client frames, and messages split over several frames.
+
+use pong_rs::ws::{
+    accept_key, decode_frame, encode_fragment, encode_frame, header, Frame, Reassembler, TooBig, MAX_MESSAGE, OP_BINARY,
+    OP_CONTINUATION, OP_PING, OP_TEXT,
+};
+
+#[test]
+fn accept_key_matches_the_rfc_example() {
+    assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+
+    let head = "GET /ws HTTP/1.1\r\nHost: example\r\nsec-websocket-key:  dGhlIHNhbXBsZSBub25jZQ== \r\n\r\n";
+    assert_eq!(header(head, "Sec-WebSocket-Key"), Some("dGhlIHNhbXBsZSBub25jZQ=="));
+    assert_eq!(header(head, "Upgrade"), None);
+}
+
+#[test]
+fn masked_frames_round_trip_at_every_length_encoding() {
+    let mask = [0x37, 0xfa, 0x21, 0x3d];
+    // The RFC's masked "Hello"
+    assert_eq!(
+        encode_frame(OP_TEXT, b"Hello", Some(mask)),
+        [0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58]
+    );
+
+    for len in [0, 5, 125, 126, 300, 65535, MAX_MESSAGE] {
+        let payload: Vec<u8> = (0..len).map(|i| (i * 7) as u8).collect();
+        for mask in [Some(mask), None] {
+            let data = encode_frame(OP_BINARY, &payload, mask);
+            let expected = Frame { opcode: OP_BINARY, payload: payload.clone(), fin: true };
+            assert_eq!(decode_frame(&data), Ok(Some((expected, data.len()))), "length {}", len);
+            // Not all there yet
+            assert_eq!(decode_frame(&data[..data.len() - 1]), Ok(None));
+        }
+    }
+}
+
+#[test]
+fn fragmented_messages_are_joined_around_control_frames() {
+    let mask = Some([1, 2, 3, 4]);
+    let mut data = encode_fragment(OP_TEXT, b"join ", false, mask);
+    data.extend(encode_frame(OP_PING, b"?", mask));
+    data.extend(encode_fragment(OP_CONTINUATION, b"AB", false, mask));
+    data.extend(encode_fragment(OP_CONTINUATION, b"CD", true, mask));
+    data.extend(encode_frame(OP_BINARY, &[9, 9], mask));
+
+    let mut messages = Reassembler::default();
+    let mut received = Vec::new();
+    let mut rest = &data[..];
+    while let Some((frame, used)) = decode_frame(rest).unwrap() {
+        rest = &rest[used..];
+        received.extend(messages.push(frame).unwrap());
+    }
+    assert!(rest.is_empty());
+    assert_eq!(
+        received,
+        [
+            Frame { opcode: OP_PING, payload: b"?".to_vec(), fin: true },
+            Frame { opcode: OP_TEXT, payload: b"join ABCD".to_vec(), fin: true },
+            Frame { opcode: OP_BINARY, payload: vec![9, 9], fin: true },
+        ]
+    );
+
+    // A stray continuation is dropped
+    let (stray, _) = decode_frame(&encode_fragment(OP_CONTINUATION, b"x", true, None)).unwrap().unwrap();
+    assert_eq!(messages.push(stray), Ok(None));
+}
+
+#[test]
+fn oversized_frames_and_messages_are_refused() {
+    // The length header alone is enough to refuse, with no payload sent
+    let mut huge = vec![0x82, 0xFF];
+    huge.extend_from_slice(&u64::MAX.to_be_bytes());
+    assert_eq!(decode_frame(&huge), Err(TooBig));
+    let just_over = encode_frame(OP_BINARY, &vec![0; MAX_MESSAGE + 1], None);
+    assert_eq!(decode_frame(&just_over[..10]), Err(TooBig));
+
+    // Fragments that each fit can't add up to more either
+    let mut messages = Reassembler::default();
+    let chunk = vec![7; MAX_MESSAGE / 2];
+    let fragment = |opcode, fin| decode_frame(&encode_fragment(opcode, &chunk, fin, None)).unwrap().unwrap().0;
+    assert_eq!(messages.push(fragment(OP_BINARY, false)), Ok(None));
+    assert_eq!(messages.push(fragment(OP_CONTINUATION, false)), Ok(None));
+    assert_eq!(messages.push(fragment(OP_CONTINUATION, true)), Err(TooBig));
+}
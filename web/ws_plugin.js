@@ -0,0 +1,81 @@
+// WebSocket bridge used by the relay client in src/ws.rs (web build only).
+// Each socket queues incoming messages until the game polls them.
+(function () {
+    "use strict";
+
+    const sockets = [];
+
+    function relayUrl(url) {
+        if (url.length > 0) {
+            return url;
+        }
+        // Default to the relay that served this page
+        const scheme = window.location.protocol === "https:" ? "wss://" : "ws://";
+        return scheme + window.location.host + "/ws";
+    }
+
+    function register(importObject) {
+        importObject.env.pong_ws_connect = function (ptr, len) {
+            const bytes = new Uint8Array(wasm_memory.buffer, ptr, len);
+            const url = relayUrl(new TextDecoder().decode(bytes));
+            const entry = { socket: null, queue: [], closed: false };
+            try {
+                entry.socket = new WebSocket(url);
+            } catch (e) {
+                console.error("WebSocket connect failed:", e);
+                return -1;
+            }
+            entry.socket.binaryType = "arraybuffer";
+            entry.socket.onmessage = function (event) {
+                if (typeof event.data === "string") {
+                    entry.queue.push({ text: true, data: new TextEncoder().encode(event.data) });
+                } else {
+                    entry.queue.push({ text: false, data: new Uint8Array(event.data) });
+                }
+            };
+            entry.socket.onclose = function () {
+                entry.closed = true;
+            };
+            sockets.push(entry);
+            return sockets.length - 1;
+        };
+
+        importObject.env.pong_ws_state = function (id) {
+            const entry = sockets[id];
+            if (!entry || entry.closed) {
+                return 2;
+            }
+            return entry.socket.readyState === WebSocket.OPEN ? 1 : 0;
+        };
+
+        importObject.env.pong_ws_send = function (id, ptr, len, binary) {
+            const entry = sockets[id];
+            if (!entry || entry.socket.readyState !== WebSocket.OPEN) {
+                return;
+            }
+            const bytes = new Uint8Array(wasm_memory.buffer, ptr, len).slice();
+            entry.socket.send(binary ? bytes : new TextDecoder().decode(bytes));
+        };
+
+        importObject.env.pong_ws_next_len = function (id) {
+            const entry = sockets[id];
+            return entry && entry.queue.length > 0 ? entry.queue[0].data.length : -1;
+        };
+
+        importObject.env.pong_ws_next_is_text = function (id) {
+            const entry = sockets[id];
+            return entry && entry.queue.length > 0 && entry.queue[0].text ? 1 : 0;
+        };
+
+        importObject.env.pong_ws_take = function (id, ptr, len) {
+            const entry = sockets[id];
+            if (!entry || entry.queue.length === 0) {
+                return;
+            }
+            const message = entry.queue.shift();
+            new Uint8Array(wasm_memory.buffer, ptr, len).set(message.data.subarray(0, len));
+        };
+    }
+
+    miniquad_add_plugin({ register_plugin: register, version: 1, name: "pong_ws" });
+})();